@@ -1,7 +1,7 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use lkr_core::{KeyKind, KeyStore, KeychainStore, mask_value};
+use lkr_core::{KeyKind, KeyStore, default_store, mask_value};
 use serde::{Deserialize, Serialize};
 use zeroize::Zeroize;
 
@@ -18,12 +18,19 @@ struct SetKeyRequest {
     value: String,
     kind: String,
     force: bool,
+    /// Trim surrounding whitespace from `value` (default); false stores it verbatim
+    #[serde(default = "default_trim")]
+    trim: bool,
+}
+
+fn default_trim() -> bool {
+    true
 }
 
 /// IPC Security: returns ONLY masked_value — raw key NEVER crosses the IPC boundary.
 #[tauri::command]
 fn get_key(name: String) -> Result<GetKeyResponse, String> {
-    let store = KeychainStore::new();
+    let store = default_store().map_err(|e| e.to_string())?;
     let (value, kind) = store.get(&name).map_err(|e| e.to_string())?;
     Ok(GetKeyResponse {
         name,
@@ -36,18 +43,26 @@ fn get_key(name: String) -> Result<GetKeyResponse, String> {
 /// from lingering in process memory. See docs/SECURITY.md for the full threat model.
 #[tauri::command]
 fn set_key(mut req: SetKeyRequest) -> Result<String, String> {
-    let store = KeychainStore::new();
     let kind = match req.kind.as_str() {
         "runtime" => KeyKind::Runtime,
         "admin" => KeyKind::Admin,
+        "service" => KeyKind::Service,
         other => {
             req.value.zeroize();
-            return Err(format!("Invalid kind '{}'. Must be 'runtime' or 'admin'.", other));
+            return Err(format!(
+                "Invalid kind '{}'. Must be 'runtime', 'admin' or 'service'.",
+                other
+            ));
         }
     };
     let name = req.name.clone();
-    let result = store
-        .set(&req.name, req.value.trim(), kind, req.force)
+    let value = if req.trim {
+        req.value.trim()
+    } else {
+        req.value.as_str()
+    };
+    let result = default_store()
+        .and_then(|store| store.set(&req.name, value, kind, req.force))
         .map_err(|e| e.to_string());
 
     // Zeroize raw key value before returning (regardless of success/error)
@@ -59,13 +74,59 @@ fn set_key(mut req: SetKeyRequest) -> Result<String, String> {
 /// IPC Security: KeyEntry contains only masked_value — no raw values exposed.
 #[tauri::command]
 fn list_keys(include_admin: bool) -> Result<Vec<lkr_core::KeyEntry>, String> {
-    let store = KeychainStore::new();
+    let store = default_store().map_err(|e| e.to_string())?;
     store.list(include_admin).map_err(|e| e.to_string())
 }
 
+/// Rename a key without the raw value ever crossing the IPC boundary.
+///
+/// Both names are validated by `KeyStore::rename` — the frontend's own
+/// validation is a convenience, not a guarantee.
+#[tauri::command]
+fn rename_key(old_name: String, new_name: String) -> Result<String, String> {
+    let store = default_store().map_err(|e| e.to_string())?;
+    store
+        .rename(&old_name, &new_name, false)
+        .map_err(|e| rename_error_message(&e))?;
+    Ok(format!("Renamed {} to {}", old_name, new_name))
+}
+
+/// User-readable messages for the errors a rename can hit.
+fn rename_error_message(e: &lkr_core::Error) -> String {
+    match e {
+        lkr_core::Error::KeyNotFound { name } => format!("Key '{}' does not exist.", name),
+        lkr_core::Error::KeyAlreadyExists { name } => format!(
+            "Key '{}' already exists. Remove it first or choose another name.",
+            name
+        ),
+        lkr_core::Error::InvalidKeyName { name, reason } => {
+            format!("'{}' is not a valid key name. {}", name, reason)
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Server-side search for the GUI (called on debounced keypress).
+/// `pattern` is a glob (`openai:*`) or a plain substring.
+///
+/// IPC Security: KeyEntry contains only masked_value — no raw values exposed.
+#[tauri::command]
+fn search_keys(pattern: String, include_admin: bool) -> Result<Vec<lkr_core::KeyEntry>, String> {
+    let store = default_store().map_err(|e| e.to_string())?;
+    store
+        .search(&pattern, include_admin)
+        .map_err(|e| e.to_string())
+}
+
 fn main() {
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![get_key, set_key, list_keys])
+        .invoke_handler(tauri::generate_handler![
+            get_key,
+            set_key,
+            list_keys,
+            search_keys,
+            rename_key
+        ])
         .run(tauri::generate_context!())
         .expect("error while running LLM Key Ring");
 }
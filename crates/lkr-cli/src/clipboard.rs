@@ -0,0 +1,207 @@
+//! Clipboard access for `lkr get`, behind a trait so the selection
+//! plumbing can be tested without a display server.
+
+/// Which system selection to target. X11/Wayland have both; macOS and
+/// Windows only have the clipboard, so `Primary` falls back to it there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub(crate) enum Selection {
+    /// Regular clipboard (Ctrl-V / Cmd-V)
+    #[default]
+    Clipboard,
+    /// X11/Wayland primary selection (middle-click paste)
+    Primary,
+}
+
+pub(crate) trait Clipboard {
+    fn set_text(&mut self, selection: Selection, text: &str) -> Result<(), String>;
+
+    /// Clear `selection` after `seconds`, unless its content changed meanwhile.
+    fn schedule_clear(&mut self, selection: Selection, seconds: u32);
+}
+
+/// Copy `text` to `selection`, and schedule the auto-clear on the same
+/// selection. Returns the copy error, if any (no clear is scheduled then).
+pub(crate) fn copy_with_auto_clear(
+    clipboard: &mut impl Clipboard,
+    selection: Selection,
+    text: &str,
+    clear_after: Option<u32>,
+) -> Result<(), String> {
+    clipboard.set_text(selection, text)?;
+    if let Some(secs) = clear_after {
+        clipboard.schedule_clear(selection, secs);
+    }
+    Ok(())
+}
+
+/// The real system clipboard via `arboard`.
+pub(crate) struct SystemClipboard;
+
+impl Clipboard for SystemClipboard {
+    fn set_text(&mut self, selection: Selection, text: &str) -> Result<(), String> {
+        let mut cb = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            use arboard::{LinuxClipboardKind, SetExtLinux};
+            let kind = match selection {
+                Selection::Clipboard => LinuxClipboardKind::Clipboard,
+                Selection::Primary => LinuxClipboardKind::Primary,
+            };
+            cb.set()
+                .clipboard(kind)
+                .text(text)
+                .map_err(|e| e.to_string())
+        }
+
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        {
+            // Single clipboard on this platform
+            let _ = selection;
+            cb.set_text(text).map_err(|e| e.to_string())
+        }
+    }
+
+    /// Spawn a detached background process that clears the selection after `seconds`.
+    ///
+    /// Uses SHA-256 hash comparison to avoid clearing if the user copied something else.
+    /// The raw key value is never passed as a process argument (prevents `ps` exposure).
+    fn schedule_clear(&mut self, selection: Selection, seconds: u32) {
+        let (paste, copy) = clear_commands(selection);
+
+        // Capture SHA-256 hash of current clipboard content
+        let hash_output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!(
+                "{} 2>/dev/null | shasum -a 256 | cut -d' ' -f1",
+                paste
+            ))
+            .output();
+
+        let expected_hash = match hash_output {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            Err(_) => return, // Can't hash — skip auto-clear silently
+        };
+
+        if expected_hash.is_empty() {
+            return;
+        }
+
+        // Spawn detached process: sleep → compare hash → clear if unchanged
+        let script = format!(
+            "sleep {} && current=$({} 2>/dev/null | shasum -a 256 | cut -d' ' -f1) && \
+             [ \"$current\" = \"{}\" ] && printf '' | {}",
+            seconds, paste, expected_hash, copy
+        );
+
+        let _ = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&script)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn(); // Detach — orphaned child survives parent exit
+    }
+}
+
+/// Programs the auto-clear in [`SystemClipboard::schedule_clear`] runs for
+/// `selection`. Without any one of them it silently does nothing.
+pub(crate) fn clear_tools(selection: Selection) -> Vec<&'static str> {
+    let (paste, copy) = clear_commands(selection);
+    let mut tools = vec!["sh", "shasum"];
+    for command in [paste, copy] {
+        let program = command.split_whitespace().next().unwrap_or(command);
+        if !tools.contains(&program) {
+            tools.push(program);
+        }
+    }
+    tools
+}
+
+/// Of the auto-clear tools for `selection`, those `is_available` rejects.
+pub(crate) fn missing_clear_tools(
+    selection: Selection,
+    is_available: impl Fn(&str) -> bool,
+) -> Vec<&'static str> {
+    clear_tools(selection)
+        .into_iter()
+        .filter(|tool| !is_available(tool))
+        .collect()
+}
+
+/// Whether `program` is a file in one of the `PATH` directories.
+pub(crate) fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+/// Shell commands that read and write `selection`: (paste, copy).
+fn clear_commands(selection: Selection) -> (&'static str, &'static str) {
+    if cfg!(target_os = "macos") {
+        ("pbpaste", "pbcopy")
+    } else {
+        match selection {
+            Selection::Clipboard => (
+                "xclip -o -selection clipboard",
+                "xclip -i -selection clipboard",
+            ),
+            Selection::Primary => ("xclip -o -selection primary", "xclip -i -selection primary"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records which selection each call targeted.
+    #[derive(Default)]
+    struct FakeClipboard {
+        copied: Vec<(Selection, String)>,
+        cleared: Vec<(Selection, u32)>,
+        fail: bool,
+    }
+
+    impl Clipboard for FakeClipboard {
+        fn set_text(&mut self, selection: Selection, text: &str) -> Result<(), String> {
+            if self.fail {
+                return Err("no display".to_string());
+            }
+            self.copied.push((selection, text.to_string()));
+            Ok(())
+        }
+
+        fn schedule_clear(&mut self, selection: Selection, seconds: u32) {
+            self.cleared.push((selection, seconds));
+        }
+    }
+
+    #[test]
+    fn test_auto_clear_targets_same_selection() {
+        for selection in [Selection::Clipboard, Selection::Primary] {
+            let mut cb = FakeClipboard::default();
+            copy_with_auto_clear(&mut cb, selection, "sk-test", Some(30)).unwrap();
+            assert_eq!(cb.copied, vec![(selection, "sk-test".to_string())]);
+            assert_eq!(cb.cleared, vec![(selection, 30)]);
+        }
+    }
+
+    #[test]
+    fn test_no_clear_when_disabled_or_copy_fails() {
+        let mut cb = FakeClipboard::default();
+        copy_with_auto_clear(&mut cb, Selection::Primary, "sk-test", None).unwrap();
+        assert!(cb.cleared.is_empty());
+
+        let mut cb = FakeClipboard {
+            fail: true,
+            ..Default::default()
+        };
+        assert!(copy_with_auto_clear(&mut cb, Selection::Primary, "sk-test", Some(30)).is_err());
+        assert!(cb.cleared.is_empty());
+    }
+
+    #[test]
+    fn test_selection_default_is_clipboard() {
+        assert_eq!(Selection::default(), Selection::Clipboard);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::cmd::import::read_input;
+use lkr_core::{BatchFormat, BatchResult, BatchStatus, KeyStore};
+use std::path::Path;
+
+/// `lkr batch-set --file FILE`: store every key in a JSON or TOML file
+/// (`-` reads stdin). Unlike `set-many`, a bad entry is reported and the
+/// rest are still stored; the command fails if any entry did.
+pub(crate) fn cmd_batch_set(
+    store: &impl KeyStore,
+    file: &str,
+    force: bool,
+) -> lkr_core::Result<()> {
+    let content = read_input(file)?;
+    let text = std::str::from_utf8(&content)
+        .map_err(|_| lkr_core::Error::Usage(format!("'{}' is not UTF-8 text", file)))?;
+    let format = BatchFormat::detect(Path::new(file), text);
+    let entries = lkr_core::parse_batch_file(text, format)?;
+    if entries.is_empty() {
+        eprintln!("Nothing to store.");
+        return Ok(());
+    }
+
+    let result = lkr_core::set_batch(store, &entries, force);
+    print_table(&result);
+    if file != "-" {
+        eprintln!(
+            "Key file is plain text: delete {} once the keys are stored.",
+            file
+        );
+    }
+
+    let failed = result.errors().len();
+    if failed > 0 {
+        return Err(lkr_core::Error::Usage(format!(
+            "{} of {} entries failed",
+            failed,
+            result.entries.len()
+        )));
+    }
+    Ok(())
+}
+
+fn print_table(result: &BatchResult) {
+    println!("  {:<28} Status", "Name");
+    println!("  {}", "-".repeat(50));
+    for (name, status) in &result.entries {
+        println!("  {:<28} {}", name, status_text(status));
+    }
+    println!(
+        "\n  {} stored, {} replaced, {} skipped, {} failed",
+        result.count(|s| *s == BatchStatus::Stored),
+        result.count(|s| *s == BatchStatus::Replaced),
+        result.count(|s| *s == BatchStatus::Skipped),
+        result.errors().len()
+    );
+}
+
+fn status_text(status: &BatchStatus) -> String {
+    match status {
+        BatchStatus::Stored => "stored".to_string(),
+        BatchStatus::Replaced => "replaced".to_string(),
+        BatchStatus::Skipped => "skipped (exists; --force to overwrite)".to_string(),
+        BatchStatus::Failed(reason) => format!("FAILED: {}", reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::keymanager::MockStore;
+
+    #[test]
+    fn test_batch_set_stores_good_entries_and_fails_on_bad() {
+        let path = std::env::temp_dir().join(format!("lkr-batch-set-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "[[key]]\nname = \"openai:prod\"\nvalue = \"sk-1\"\n\n\
+             [[key]]\nname = \"bad name\"\nvalue = \"sk-2\"\n",
+        )
+        .unwrap();
+        let store = MockStore::new();
+
+        let err = cmd_batch_set(&store, path.to_str().unwrap(), false).unwrap_err();
+        assert!(err.to_string().contains("1 of 2 entries failed"));
+        assert_eq!(&*store.get("openai:prod").unwrap().0, "sk-1");
+        // lkr never touches the key file
+        assert!(path.exists());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,136 @@
+use crate::util::{format_age, format_until};
+use chrono::{DateTime, Utc};
+use lkr_core::{KeyEntry, KeyStore};
+
+/// How far ahead `lkr list` flags a key as expiring soon.
+pub(crate) const EXPIRING_SOON: chrono::Duration = chrono::Duration::hours(48);
+
+/// Keys that need attention, by name, with when they expire(d).
+#[derive(Debug, Default)]
+struct ExpiryReport {
+    expired: Vec<(String, DateTime<Utc>)>,
+    expiring: Vec<(String, DateTime<Utc>)>,
+}
+
+/// `lkr check-expiry`: report every key (admin included) that has expired
+/// or expires within `within`, and fail if there is any. For CI and
+/// pre-commit hooks; values are never read.
+pub(crate) fn cmd_check_expiry(
+    store: &impl KeyStore,
+    within: std::time::Duration,
+    json: bool,
+) -> lkr_core::Result<()> {
+    let window = chrono::Duration::from_std(within).unwrap_or(chrono::Duration::MAX);
+    let now = Utc::now();
+    let report = check(&store.list(true)?, now, window);
+
+    if json {
+        let keys = |list: &[(String, DateTime<Utc>)]| -> Vec<serde_json::Value> {
+            list.iter()
+                .map(|(name, ts)| serde_json::json!({"name": name, "expires_at": ts}))
+                .collect()
+        };
+        let out = serde_json::json!({
+            "expired": keys(&report.expired),
+            "expiring": keys(&report.expiring),
+        });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap());
+    } else {
+        for (name, ts) in &report.expired {
+            println!("  EXPIRED   {:<24} {}", name, format_age(*ts, now));
+        }
+        for (name, ts) in &report.expiring {
+            println!("  EXPIRING  {:<24} {}", name, format_until(*ts, now));
+        }
+    }
+
+    if report.expired.is_empty() && report.expiring.is_empty() {
+        if !json {
+            eprintln!("No keys expired or expiring soon.");
+        }
+        return Ok(());
+    }
+    Err(lkr_core::Error::Usage(format!(
+        "{} key(s) expired, {} expiring soon. Run `lkr rotate NAME` to renew them.",
+        report.expired.len(),
+        report.expiring.len()
+    )))
+}
+
+fn check(entries: &[KeyEntry], now: DateTime<Utc>, window: chrono::Duration) -> ExpiryReport {
+    let mut report = ExpiryReport::default();
+    for entry in entries {
+        let Some(ts) = entry.expires_at else {
+            continue;
+        };
+        if entry.is_expired(now) {
+            report.expired.push((entry.name.clone(), ts));
+        } else if entry.expires_within(now, window) {
+            report.expiring.push((entry.name.clone(), ts));
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::keymanager::MockStore;
+    use lkr_core::{KeyKind, MetadataUpdate};
+
+    #[test]
+    fn test_check_sorts_expired_expiring_and_fine_keys() {
+        let store = MockStore::new();
+        let now = Utc::now();
+        for (name, offset) in [
+            ("aws:gone", Some(chrono::Duration::hours(-1))),
+            ("aws:soon", Some(chrono::Duration::hours(5))),
+            ("aws:later", Some(chrono::Duration::days(30))),
+            ("openai:prod", None),
+        ] {
+            store.set(name, "sk-1", KeyKind::Runtime, false).unwrap();
+            if let Some(offset) = offset {
+                store
+                    .update_metadata(name, &MetadataUpdate::Expiry(Some(now + offset)))
+                    .unwrap();
+            }
+        }
+        // Admin keys are checked too
+        store
+            .set("aws:admin", "sk-2", KeyKind::Admin, false)
+            .unwrap();
+        store
+            .update_metadata(
+                "aws:admin",
+                &MetadataUpdate::Expiry(Some(now - chrono::Duration::days(2))),
+            )
+            .unwrap();
+
+        let report = check(&store.list(true).unwrap(), now, EXPIRING_SOON);
+        let names = |list: &[(String, DateTime<Utc>)]| {
+            list.iter().map(|(n, _)| n.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(&report.expired), ["aws:admin", "aws:gone"]);
+        assert_eq!(names(&report.expiring), ["aws:soon"]);
+
+        let wide = check(&store.list(true).unwrap(), now, chrono::Duration::days(60));
+        assert_eq!(names(&wide.expiring), ["aws:later", "aws:soon"]);
+    }
+
+    #[test]
+    fn test_check_expiry_passes_with_nothing_due() {
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        assert!(cmd_check_expiry(&store, std::time::Duration::from_secs(3600), true).is_ok());
+
+        store
+            .update_metadata(
+                "openai:prod",
+                &MetadataUpdate::Expiry(Some(Utc::now() + chrono::Duration::minutes(30))),
+            )
+            .unwrap();
+        assert!(cmd_check_expiry(&store, std::time::Duration::from_secs(3600), true).is_err());
+    }
+}
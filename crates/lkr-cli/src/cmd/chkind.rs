@@ -0,0 +1,78 @@
+use crate::prompt::confirm_admin;
+use lkr_core::{KeyKind, KeyStore};
+
+/// `lkr chkind NAME --kind KIND`: change a key's kind in place. Promoting
+/// to admin needs the typed confirmation unless `force`.
+pub(crate) fn cmd_chkind(
+    store: &impl KeyStore,
+    name: &str,
+    kind: &str,
+    force: bool,
+) -> lkr_core::Result<()> {
+    let kind: KeyKind = kind
+        .parse()
+        .map_err(|reason| lkr_core::Error::InvalidKeyName {
+            name: name.to_string(),
+            reason,
+        })?;
+    // Metadata only: the value isn't needed, and expired keys can change too
+    let current = store.get_with_metadata(name)?.kind;
+    if current == kind {
+        eprintln!("{} is already {}", name, kind);
+        return Ok(());
+    }
+    if kind == KeyKind::Admin && !force && !confirm_admin("store", name) {
+        eprintln!("Cancelled.");
+        return Ok(());
+    }
+
+    store.with_locked(|s| s.set_kind(name, kind))?;
+    eprintln!("Changed {} from {} to {}", name, current, kind);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::keymanager::MockStore;
+
+    #[test]
+    fn test_chkind_changes_kind_only() {
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+
+        cmd_chkind(&store, "openai:prod", "service", false).unwrap();
+        let (value, kind) = store.get("openai:prod").unwrap();
+        assert_eq!((value.as_str(), kind), ("sk-1", KeyKind::Service));
+
+        // Promotion with --force skips the typed confirmation
+        cmd_chkind(&store, "openai:prod", "admin", true).unwrap();
+        assert_eq!(store.get("openai:prod").unwrap().1, KeyKind::Admin);
+
+        assert!(cmd_chkind(&store, "openai:prod", "superuser", true).is_err());
+        assert!(cmd_chkind(&store, "openai:missing", "runtime", true).is_err());
+    }
+
+    #[test]
+    fn test_chkind_works_on_expired_key() {
+        let store = MockStore::new();
+        store
+            .set("openai:old", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        store
+            .update_metadata(
+                "openai:old",
+                &lkr_core::MetadataUpdate::Expiry(Some(
+                    chrono::Utc::now() - chrono::Duration::hours(1),
+                )),
+            )
+            .unwrap();
+
+        cmd_chkind(&store, "openai:old", "service", false).unwrap();
+        let entry = store.get_with_metadata("openai:old").unwrap();
+        assert_eq!(entry.kind, KeyKind::Service);
+        assert!(entry.expires_at.is_some());
+    }
+}
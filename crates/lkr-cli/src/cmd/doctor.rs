@@ -0,0 +1,221 @@
+use crate::clipboard::{self, Selection};
+use lkr_core::{EnvMappingConflict, KeychainStore, UnlistedItem};
+
+/// Outcome of a single doctor check.
+struct Finding {
+    /// Stable identifier for `--json` consumers; never reworded
+    id: &'static str,
+    check: &'static str,
+    problems: Vec<String>,
+    ok_detail: String,
+}
+
+/// `lkr doctor`: run configuration health checks and report problems.
+pub(crate) fn cmd_doctor(json: bool) -> lkr_core::Result<()> {
+    report(&config_findings(), json)
+}
+
+/// `lkr doctor --keychain`: the configuration checks plus checks that need
+/// the unlocked Keychain.
+pub(crate) fn cmd_doctor_keychain(store: &KeychainStore, json: bool) -> lkr_core::Result<()> {
+    let mut findings = config_findings();
+    findings.push(check_list_consistency(store.unlisted_items()?));
+    report(&findings, json)
+}
+
+fn config_findings() -> Vec<Finding> {
+    vec![
+        check_env_mappings(&lkr_core::builtin_env_mapping_conflicts()),
+        check_clipboard_clear(
+            &clipboard::clear_tools(Selection::Clipboard),
+            &clipboard::missing_clear_tools(Selection::Clipboard, clipboard::on_path),
+        ),
+    ]
+}
+
+fn report(findings: &[Finding], json: bool) -> lkr_core::Result<()> {
+    let problem_count: usize = findings.iter().map(|f| f.problems.len()).sum();
+
+    if json {
+        let report = json_report(findings);
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        print_findings(findings);
+    }
+
+    if problem_count > 0 {
+        return Err(lkr_core::Error::Usage(format!(
+            "doctor found {} problem(s)",
+            problem_count
+        )));
+    }
+    Ok(())
+}
+
+fn print_findings(findings: &[Finding]) {
+    println!();
+    for finding in findings {
+        if finding.problems.is_empty() {
+            println!("  ✓ {}: {}", finding.check, finding.ok_detail);
+            continue;
+        }
+        println!("  ⚠ {}:", finding.check);
+        for problem in &finding.problems {
+            println!("      {}", problem);
+        }
+    }
+    println!();
+}
+
+/// `{"ok", "checks": [{"check", "status", "detail"}]}` where `status` is
+/// `"ok"` or `"warn"`. Details name env vars and providers, never values.
+fn json_report(findings: &[Finding]) -> serde_json::Value {
+    let checks: Vec<_> = findings
+        .iter()
+        .map(|f| {
+            let (status, detail) = if f.problems.is_empty() {
+                ("ok", f.ok_detail.clone())
+            } else {
+                ("warn", f.problems.join("; "))
+            };
+            serde_json::json!({"check": f.id, "status": status, "detail": detail})
+        })
+        .collect();
+    serde_json::json!({
+        "ok": findings.iter().all(|f| f.problems.is_empty()),
+        "checks": checks,
+    })
+}
+
+/// Env vars claimed by several providers make `.env` auto-detection ambiguous.
+fn check_env_mappings(conflicts: &[EnvMappingConflict]) -> Finding {
+    Finding {
+        id: "env_mappings",
+        check: "Env var mappings",
+        problems: conflicts
+            .iter()
+            .map(|c| {
+                format!(
+                    "{} is mapped by multiple providers: {}",
+                    c.env_var,
+                    c.providers.join(", ")
+                )
+            })
+            .collect(),
+        ok_detail: "no conflicting env var → provider mappings".to_string(),
+    }
+}
+
+/// The clipboard auto-clear shells out to these tools and gives up silently
+/// without them, leaving keys copied by `lkr get` in the clipboard.
+fn check_clipboard_clear(tools: &[&str], missing: &[&str]) -> Finding {
+    let problems = if missing.is_empty() {
+        Vec::new()
+    } else {
+        vec![format!(
+            "{} not found on PATH: `lkr get` would copy keys without ever clearing them \
+             (use --plain or lkr exec instead)",
+            missing.join(", ")
+        )]
+    };
+    Finding {
+        id: "clipboard_clear",
+        check: "Clipboard auto-clear",
+        problems,
+        ok_detail: format!("{} available", tools.join(", ")),
+    }
+}
+
+/// Keychain items `list` skips (corrupted payloads, invalid names) are
+/// otherwise invisible: they never show up anywhere.
+fn check_list_consistency(unlisted: Vec<UnlistedItem>) -> Finding {
+    Finding {
+        id: "list_consistency",
+        check: "Keychain items vs list",
+        problems: unlisted
+            .into_iter()
+            .map(|u| {
+                format!(
+                    "{} is in the Keychain but not in `lkr list`: {}",
+                    u.account, u.reason
+                )
+            })
+            .collect(),
+        ok_detail: "every Keychain item appears in `lkr list`".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_mapping_conflict_listed() {
+        let conflicts = lkr_core::env_mapping_conflicts(&[
+            ("LLM_API_KEY", "openai"),
+            ("LLM_API_KEY", "anthropic"),
+        ]);
+        let finding = check_env_mappings(&conflicts);
+        assert_eq!(
+            finding.problems,
+            vec!["LLM_API_KEY is mapped by multiple providers: openai, anthropic"]
+        );
+        assert!(check_env_mappings(&[]).problems.is_empty());
+    }
+
+    #[test]
+    fn test_clipboard_clear_warns_when_tools_are_absent() {
+        let tools = clipboard::clear_tools(Selection::Clipboard);
+        let missing = clipboard::missing_clear_tools(Selection::Clipboard, |_| false);
+        assert_eq!(missing, tools);
+        let finding = check_clipboard_clear(&tools, &missing);
+        assert_eq!(finding.problems.len(), 1);
+        assert!(finding.problems[0].contains("shasum"));
+
+        let missing = clipboard::missing_clear_tools(Selection::Clipboard, |t| t != "shasum");
+        assert_eq!(missing, ["shasum"]);
+
+        let none = clipboard::missing_clear_tools(Selection::Clipboard, |_| true);
+        assert!(check_clipboard_clear(&tools, &none).problems.is_empty());
+    }
+
+    #[test]
+    fn test_list_consistency_reports_divergence() {
+        let items = vec![
+            (
+                "openai:prod".to_string(),
+                Ok(br#"{"value":"sk-1","kind":"runtime"}"#.to_vec()),
+            ),
+            ("openai:broken".to_string(), Ok(b"garbage".to_vec())),
+        ];
+        let listed = std::collections::HashSet::from(["openai:prod".to_string()]);
+        let finding = check_list_consistency(lkr_core::find_unlisted(items, &listed));
+        assert_eq!(
+            finding.problems,
+            vec![
+                "openai:broken is in the Keychain but not in `lkr list`: \
+                 payload is not a valid lkr entry"
+            ]
+        );
+        assert!(check_list_consistency(Vec::new()).problems.is_empty());
+    }
+
+    #[test]
+    fn test_json_report_statuses_and_overall_flag() {
+        let conflicts =
+            lkr_core::env_mapping_conflicts(&[("LLM_API_KEY", "openai"), ("LLM_API_KEY", "groq")]);
+        let report = json_report(&[check_env_mappings(&conflicts)]);
+        assert_eq!(report["ok"], false);
+        assert_eq!(report["checks"][0]["check"], "env_mappings");
+        assert_eq!(report["checks"][0]["status"], "warn");
+
+        let report = json_report(&[check_env_mappings(&[])]);
+        assert_eq!(report["ok"], true);
+        let checks = report["checks"].as_array().unwrap();
+        assert_eq!(checks.len(), 1);
+        for check in checks {
+            assert!(["ok", "warn"].contains(&check["status"].as_str().unwrap()));
+            assert!(check["detail"].is_string());
+        }
+    }
+}
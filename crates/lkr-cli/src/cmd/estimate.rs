@@ -0,0 +1,39 @@
+use lkr_core::PriceTable;
+
+/// `lkr estimate`: projected cost of a run from token counts.
+pub(crate) fn cmd_estimate(
+    provider: &str,
+    model: &str,
+    input_tokens: u64,
+    output_tokens: u64,
+    json: bool,
+) -> lkr_core::Result<()> {
+    let table = PriceTable::load(&PriceTable::default_path()?)?;
+    let price = table.find(&provider.to_lowercase(), model)?;
+    let report = lkr_core::estimate_cost(price, input_tokens, output_tokens);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return Ok(());
+    }
+
+    println!("\n  {} {} — estimate", report.provider, price.model);
+    println!("  {}", "-".repeat(50));
+    for item in &report.line_items {
+        println!(
+            "    {:<30} {}",
+            item.description,
+            lkr_core::format_cost(item.cost_cents)
+        );
+    }
+    println!(
+        "  {:<32} {}",
+        "Total",
+        lkr_core::format_cost(report.total_cost_cents)
+    );
+    println!(
+        "\n  Prices: ${:.2} / ${:.2} per 1M input / output tokens\n",
+        price.input_usd_per_mtok, price.output_usd_per_mtok
+    );
+    Ok(())
+}
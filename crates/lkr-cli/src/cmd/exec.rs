@@ -1,11 +1,51 @@
 use lkr_core::{KeyKind, KeyStatus, KeyStore};
+use std::ffi::OsString;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::{Duration, Instant};
+
+/// Flags for `lkr exec`, bundled so new options don't grow the signature.
+#[derive(Default)]
+pub(crate) struct ExecOptions {
+    /// Explicit key names (`-k`); `@tag` stands for every runtime key with that tag
+    pub keys: Vec<String>,
+    /// Inject every runtime key whose name starts with any of these (OR-combined)
+    pub key_prefixes: Vec<String>,
+    /// Prepended to each derived env var name (e.g. `APP_` → `APP_OPENAI_API_KEY`)
+    pub env_prefix: Option<String>,
+    /// Inject every admin key instead of runtime keys (needs `i_understand`)
+    pub only_admin: bool,
+    /// Explicit acknowledgement of the risk of `only_admin`
+    pub i_understand: bool,
+    /// Also inject service keys (never injected otherwise)
+    pub include_service: bool,
+    /// Don't warn when an injected var overrides one from the parent env
+    pub no_warn_conflicts: bool,
+    /// Abort instead of overriding vars already set in the parent env
+    pub fail_on_conflict: bool,
+    /// Abort on a key with an empty value instead of skipping it
+    pub strict: bool,
+    /// Keys that must end up injected, whatever selected them, or nothing runs
+    pub fail_if_missing: Vec<String>,
+    /// Stop the child after this long: SIGTERM, then SIGKILL after `grace_period`
+    pub timeout: Option<Duration>,
+    /// Time between SIGTERM and SIGKILL when `timeout` expires
+    pub grace_period: Duration,
+    /// Start the child with an empty environment (plus `inherit_patterns`)
+    pub no_inherit_env: bool,
+    /// Parent env vars kept under `no_inherit_env`, as globs (e.g. `LC_*`)
+    pub inherit_patterns: Vec<String>,
+    /// Run the child in its own process group and signal the whole group
+    pub process_group: bool,
+    pub verbose: bool,
+}
 
 pub(crate) fn cmd_exec(
     store: &impl KeyStore,
-    keys: &[String],
     command: &[String],
+    opts: &ExecOptions,
     stdout_is_tty: bool,
-    verbose: bool,
 ) -> lkr_core::Result<()> {
     if command.is_empty() {
         return Err(lkr_core::Error::Usage(
@@ -13,47 +53,51 @@ pub(crate) fn cmd_exec(
         ));
     }
 
-    // Collect keys to inject
-    let entries: Vec<(String, lkr_core::Zeroizing<String>)> = if keys.is_empty() {
-        // No -k flags: inject all runtime keys
-        let listed = store.list(false)?;
-        let mut blocked: Vec<String> = Vec::new();
-        let mut pairs = Vec::new();
-        for entry in &listed {
-            if entry.status == KeyStatus::AclBlocked {
-                blocked.push(entry.name.clone());
-                continue;
-            }
-            // entry.status == Ok means data was already read by list(),
-            // but we still need the raw value. Re-fetch via get().
-            if let Ok((value, _kind)) = store.get(&entry.name) {
-                pairs.push((lkr_core::key_to_env_var(&entry.name), value));
-            }
+    let injection = collect_entries(store, opts)?;
+    ensure_required(store, &injection, opts)?;
+    if !injection.skipped_empty.is_empty() {
+        eprintln!(
+            "Warning: {} key(s) skipped (empty value): {}",
+            injection.skipped_empty.len(),
+            injection.skipped_empty.join(", ")
+        );
+    }
+    if !injection.skipped_expired.is_empty() {
+        eprintln!(
+            "Warning: {} key(s) skipped (expired): {}. Renew with `lkr rotate NAME`.",
+            injection.skipped_expired.len(),
+            injection.skipped_expired.join(", ")
+        );
+    }
+    // Under --no-inherit-env only vars kept by a pattern can conflict
+    let conflicts = env_conflicts(&injection, |var| {
+        if opts.no_inherit_env && !matches_any(&opts.inherit_patterns, var) {
+            return None;
         }
-        if !blocked.is_empty() {
+        std::env::var(var).ok().map(lkr_core::Zeroizing::new)
+    });
+    if !opts.no_warn_conflicts {
+        for conflict in &conflicts {
             eprintln!(
-                "⚠ {} key(s) skipped (ACL mismatch): {}",
-                blocked.len(),
-                blocked.join(", ")
+                "Warning: overriding {} (was {} from parent env, now {} from Keychain)",
+                conflict.env_var, conflict.old_masked, conflict.new_masked
             );
-            eprintln!("  Run `lkr harden` to fix ACL for these keys.");
-        }
-        pairs
-    } else {
-        // Specific keys requested — admin keys are rejected (SECURITY.md T7)
-        let mut pairs = Vec::new();
-        for key_name in keys {
-            let (value, kind) = store.get(key_name)?;
-            if kind == KeyKind::Admin {
-                return Err(lkr_core::Error::Usage(format!(
-                    "admin key \"{}\" cannot be used with exec. Use runtime keys only.",
-                    key_name
-                )));
-            }
-            pairs.push((lkr_core::key_to_env_var(key_name), value));
         }
-        pairs
-    };
+    }
+    if opts.fail_on_conflict && !conflicts.is_empty() {
+        let names: Vec<&str> = conflicts.iter().map(|c| c.env_var.as_str()).collect();
+        return Err(lkr_core::Error::Usage(format!(
+            "{} env var(s) already set in the parent environment: {}. \
+             Unset them or drop --fail-on-conflict.",
+            names.len(),
+            names.join(", ")
+        )));
+    }
+
+    let Injection {
+        entries, endpoints, ..
+    } = injection;
+    let verbose = opts.verbose;
 
     // v0.2.0 stderr output rules:
     //   TTY + no --verbose   → silent
@@ -65,6 +109,9 @@ pub(crate) fn cmd_exec(
         for (env_var, _) in &entries {
             eprintln!("  {}", env_var);
         }
+        for (env_var, _) in &endpoints {
+            eprintln!("  {} (endpoint)", env_var);
+        }
     };
 
     if entries.is_empty() {
@@ -86,15 +133,1017 @@ pub(crate) fn cmd_exec(
     let mut child = std::process::Command::new(&command[0]);
     child.args(&command[1..]);
 
-    // Inject keys as environment variables
+    if opts.no_inherit_env {
+        child.env_clear();
+        child.envs(inherited_vars(std::env::vars_os(), &opts.inherit_patterns));
+    }
+
+    // Inject keys as environment variables (after inherited vars, so a
+    // Keychain key wins over a same-named var kept by --inherit-pattern)
     for (env_var, value) in &entries {
         child.env(env_var, &**value);
     }
+    for (env_var, url) in &endpoints {
+        child.env(env_var, url);
+    }
+
+    // Hand the terminal to the child's group only if we hold it ourselves
+    let tty = if opts.process_group {
+        foreground_terminal()
+    } else {
+        None
+    };
+    if opts.process_group {
+        child.process_group(0);
+        if let Some(fd) = tty {
+            // SAFETY: take_terminal only makes async-signal-safe calls
+            unsafe { child.pre_exec(move || take_terminal(fd)) };
+        }
+    }
 
-    let status = child.status().map_err(|e| {
+    let spawn_err = |e: std::io::Error| {
         lkr_core::Error::Usage(format!("Failed to execute '{}': {}", command[0], e))
-    })?;
+    };
+    let mut child = child.spawn().map_err(spawn_err)?;
+    if opts.process_group {
+        forward_signals_to_group(child.id());
+    }
+    let code = match opts.timeout {
+        None => {
+            let wait_err = |e: std::io::Error| {
+                lkr_core::Error::Usage(format!("Failed to wait for child: {}", e))
+            };
+            let status = match tty {
+                Some(fd) => wait_job(child.id(), fd, true).map(|s| s.expect("blocking wait")),
+                None => child.wait(),
+            };
+            exit_code(status.map_err(wait_err)?)
+        }
+        Some(timeout) => {
+            run_with_timeout(child, timeout, opts.grace_period, opts.process_group, tty)?
+        }
+    };
+    if let Some(fd) = tty {
+        take_terminal(fd).ok();
+    }
 
     // Propagate child exit code
-    std::process::exit(status.code().unwrap_or(1));
+    std::process::exit(code);
+}
+
+/// Parent env vars that `--no-inherit-env` keeps: those whose name matches
+/// one of `patterns`. Names that aren't valid UTF-8 never match.
+fn inherited_vars(
+    parent: impl IntoIterator<Item = (OsString, OsString)>,
+    patterns: &[String],
+) -> Vec<(OsString, OsString)> {
+    parent
+        .into_iter()
+        .filter(|(name, _)| name.to_str().is_some_and(|n| matches_any(patterns, n)))
+        .collect()
+}
+
+fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|p| lkr_core::glob_match(p, name))
+}
+
+/// Exit code for a finished child: its own code, or 128 + signal number
+/// when a signal ended it (the shell convention).
+fn exit_code(status: ExitStatus) -> i32 {
+    status
+        .code()
+        .or_else(|| status.signal().map(|sig| 128 + sig))
+        .unwrap_or(1)
+}
+
+/// Process group `forward_signal` relays to; 0 while there is none.
+static CHILD_PGID: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn forward_signal(sig: libc::c_int) {
+    let pgid = CHILD_PGID.load(Ordering::SeqCst);
+    if pgid > 0 {
+        // SAFETY: killpg(2) is async-signal-safe
+        unsafe { libc::killpg(pgid, sig) };
+    }
+}
+
+/// `--process-group`: relay SIGINT, SIGTERM and SIGHUP sent to lkr to the
+/// child's whole group, so grandchildren are stopped too. lkr itself keeps
+/// running until the child exits, and exits with its code.
+fn forward_signals_to_group(pgid: u32) {
+    CHILD_PGID.store(pgid as i32, Ordering::SeqCst);
+    for sig in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+        // SAFETY: forward_signal only touches an atomic and calls killpg
+        unsafe {
+            libc::signal(
+                sig,
+                forward_signal as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            )
+        };
+    }
+}
+
+/// A descriptor for lkr's controlling terminal, opened via `/dev/tty`, if
+/// lkr's group is its foreground group. Going through `/dev/tty` rather
+/// than stdin means a piped stdin doesn't leave the child in a background
+/// group, where touching the terminal would stop it with SIGTTIN/SIGTTOU.
+fn foreground_terminal() -> Option<libc::c_int> {
+    // SAFETY: open(2), tcgetpgrp(3), getpgrp(2) and close(2) on a
+    // descriptor we own; the path is a NUL-terminated literal
+    unsafe {
+        let fd = libc::open(
+            c"/dev/tty".as_ptr(),
+            libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC,
+        );
+        if fd < 0 {
+            return None;
+        }
+        if libc::tcgetpgrp(fd) == libc::getpgrp() {
+            Some(fd)
+        } else {
+            libc::close(fd);
+            None
+        }
+    }
+}
+
+/// Make the calling process's group the foreground group of terminal `fd`,
+/// as a shell does for a job. Run in the child before exec, so Ctrl+C
+/// reaches its whole group and it can read the terminal; run in lkr
+/// afterwards to take the terminal back. SIGTTOU is ignored meanwhile, as
+/// a background group may not otherwise change the foreground group.
+fn take_terminal(fd: libc::c_int) -> std::io::Result<()> {
+    // SAFETY: signal(2), tcsetpgrp(3) and getpgrp(2) are async-signal-safe
+    unsafe {
+        let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+        let result = libc::tcsetpgrp(fd, libc::getpgrp());
+        libc::signal(libc::SIGTTOU, previous);
+        if result != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Hand terminal `fd` to process group `pgid`, ignoring SIGTTOU as in
+/// [`take_terminal`].
+fn give_terminal(fd: libc::c_int, pgid: libc::pid_t) {
+    // SAFETY: signal(2) and tcsetpgrp(3) have no memory-safety preconditions
+    unsafe {
+        let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+        libc::tcsetpgrp(fd, pgid);
+        libc::signal(libc::SIGTTOU, previous);
+    }
+}
+
+/// Wait for the foreground job led by `pid` the way a shell does. When the
+/// child stops (Ctrl+Z, or SIGTTIN/SIGTTOU), lkr takes terminal `fd` back
+/// and stops itself, so the shell sees the whole job stopped; once resumed
+/// (`fg`), it hands the terminal back and continues the child's group.
+/// Without `block`, returns `None` while the child is still running.
+fn wait_job(pid: u32, fd: libc::c_int, block: bool) -> std::io::Result<Option<ExitStatus>> {
+    let pid = pid as libc::pid_t;
+    let flags = libc::WUNTRACED | if block { 0 } else { libc::WNOHANG };
+    loop {
+        let mut status = 0;
+        // SAFETY: waitpid(2) on our own unreaped child with a valid out pointer
+        let reaped = unsafe { libc::waitpid(pid, &mut status, flags) };
+        if reaped < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err);
+        }
+        if reaped == 0 {
+            return Ok(None);
+        }
+        if !libc::WIFSTOPPED(status) {
+            return Ok(Some(ExitStatus::from_raw(status)));
+        }
+        take_terminal(fd).ok();
+        // SAFETY: kill(2)/killpg(2) have no memory-safety preconditions;
+        // SIGSTOP returns once the shell continues lkr
+        unsafe { libc::kill(libc::getpid(), libc::SIGSTOP) };
+        give_terminal(fd, pid);
+        // SAFETY: as above; `pid` leads the child's own process group
+        unsafe { libc::killpg(pid, libc::SIGCONT) };
+    }
+}
+
+/// Wait up to `limit` for `child` to exit, handling job control through
+/// `tty` as [`wait_job`] does.
+fn wait_for(
+    child: &mut Child,
+    limit: Duration,
+    tty: Option<libc::c_int>,
+) -> std::io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + limit;
+    loop {
+        let status = match tty {
+            Some(fd) => wait_job(child.id(), fd, false)?,
+            None => child.try_wait()?,
+        };
+        if let Some(status) = status {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// How long to wait for the child to be reaped after SIGKILL.
+const KILL_WAIT: Duration = Duration::from_secs(2);
+
+/// `--timeout`: wait for the child; on expiry send SIGTERM, give it
+/// `grace` to clean up, then SIGKILL. With `group` the signals go to the
+/// child's whole process group; `tty` is the terminal handed to that group,
+/// if any. Returns the code lkr should exit with.
+fn run_with_timeout(
+    mut child: Child,
+    timeout: Duration,
+    grace: Duration,
+    group: bool,
+    tty: Option<libc::c_int>,
+) -> lkr_core::Result<i32> {
+    let wait_err =
+        |e: std::io::Error| lkr_core::Error::Usage(format!("Failed to wait for child: {}", e));
+    if let Some(status) = wait_for(&mut child, timeout, tty).map_err(wait_err)? {
+        return Ok(exit_code(status));
+    }
+
+    let pid = child.id();
+    eprintln!(
+        "lkr: timeout after {}s; sending SIGTERM to {}",
+        timeout.as_secs_f64(),
+        pid
+    );
+    let signal = |sig: libc::c_int| {
+        // SAFETY: kill(2)/killpg(2) have no memory-safety preconditions;
+        // `pid` is our own child (and, with `group`, its process group),
+        // which has not been reaped yet (try_wait returned None)
+        unsafe {
+            if group {
+                libc::killpg(pid as libc::pid_t, sig);
+            } else {
+                libc::kill(pid as libc::pid_t, sig);
+            }
+        }
+    };
+    signal(libc::SIGTERM);
+    if let Some(status) = wait_for(&mut child, grace, tty).map_err(wait_err)? {
+        eprintln!("lkr: {} exited after SIGTERM", pid);
+        return Ok(exit_code(status));
+    }
+
+    eprintln!(
+        "lkr: {} still running after {}s grace period; sending SIGKILL",
+        pid,
+        grace.as_secs_f64()
+    );
+    if group {
+        signal(libc::SIGKILL);
+    } else {
+        child.kill().map_err(wait_err)?;
+    }
+    if wait_for(&mut child, KILL_WAIT, tty)
+        .map_err(wait_err)?
+        .is_none()
+    {
+        eprintln!("lkr: {} did not exit after SIGKILL", pid);
+    }
+    Ok(128 + libc::SIGKILL)
+}
+
+/// Env vars for the child process.
+struct Injection {
+    /// Names of the injected keys
+    names: Vec<String>,
+    /// `(env var name, value)` per injected key
+    entries: Vec<(String, lkr_core::Zeroizing<String>)>,
+    /// `(env var name, URL)` for injected keys with a custom endpoint
+    endpoints: Vec<(String, String)>,
+    /// Keys left out because their stored value is empty (legacy data)
+    skipped_empty: Vec<String>,
+    /// Matching keys left out because they have expired
+    skipped_expired: Vec<String>,
+}
+
+/// Resolve the keys to inject.
+///
+/// No `-k` and no `--key-prefix`: all runtime keys. Otherwise the explicit
+/// keys (admin keys rejected) plus every runtime key matching a prefix.
+/// Keys stored with `--endpoint` also export it (e.g. `OPENAI_BASE_URL`).
+/// Keys with an empty value are skipped, or abort with `strict`.
+fn collect_entries(store: &impl KeyStore, opts: &ExecOptions) -> lkr_core::Result<Injection> {
+    let prefix = opts.env_prefix.as_deref().unwrap_or("");
+    let env_name = |key_name: &str| format!("{}{}", prefix, lkr_core::key_to_env_var(key_name));
+    let endpoint_name =
+        |key_name: &str| format!("{}{}", prefix, lkr_core::key_to_endpoint_env_var(key_name));
+
+    let mut pairs = Vec::new();
+    let mut endpoints = Vec::new();
+    let mut injected: Vec<String> = Vec::new();
+    let mut skipped_empty: Vec<String> = Vec::new();
+    let mut skipped_expired: Vec<String> = Vec::new();
+    let mut non_empty = |key_name: &str, value: &str| -> lkr_core::Result<bool> {
+        if !value.is_empty() {
+            return Ok(true);
+        }
+        if opts.strict {
+            return Err(lkr_core::Error::Usage(format!(
+                "Key '{}' has an empty value. Re-store it with `lkr set {} --force`.",
+                key_name, key_name
+            )));
+        }
+        skipped_empty.push(key_name.to_string());
+        Ok(false)
+    };
+
+    if opts.only_admin {
+        // Deliberate override of SECURITY.md T7 — gated on an explicit ack
+        if !opts.i_understand {
+            return Err(lkr_core::Error::Usage(
+                "--only-admin injects admin keys, which can manage billing, members and \
+                 other keys, into the child process. Re-run with --i-understand if you \
+                 trust the command."
+                    .to_string(),
+            ));
+        }
+        let mut blocked: Vec<String> = Vec::new();
+        for entry in store.list(true)? {
+            if entry.status == KeyStatus::AclBlocked {
+                blocked.push(entry.name);
+                continue;
+            }
+            if entry.kind != Some(KeyKind::Admin) {
+                continue;
+            }
+            let (value, _kind) = store.get(&entry.name)?;
+            if !non_empty(&entry.name, &value)? {
+                continue;
+            }
+            pairs.push((env_name(&entry.name), value));
+            if let Some(url) = &entry.custom_endpoint {
+                endpoints.push((endpoint_name(&entry.name), url.clone()));
+            }
+            injected.push(entry.name);
+        }
+        warn_acl_blocked(&blocked);
+        eprintln!(
+            "⚠ Injecting {} ADMIN key(s). The command can use them to change your organization.",
+            pairs.len()
+        );
+        return Ok(Injection {
+            names: injected,
+            entries: pairs,
+            endpoints,
+            skipped_empty,
+            skipped_expired,
+        });
+    }
+
+    // Specific keys requested — admin keys are rejected (SECURITY.md T7)
+    for key_name in &resolve_exec_keys(store, &opts.keys)? {
+        let (value, kind) = store.get(key_name)?;
+        if kind == KeyKind::Admin {
+            return Err(lkr_core::Error::Usage(format!(
+                "admin key \"{}\" cannot be used with exec. Use runtime keys only.",
+                key_name
+            )));
+        }
+        if kind == KeyKind::Service && !opts.include_service {
+            return Err(lkr_core::Error::Usage(format!(
+                "service key \"{}\" is only injected with --include-service.",
+                key_name
+            )));
+        }
+        if !non_empty(key_name, &value)? {
+            continue;
+        }
+        pairs.push((env_name(key_name), value));
+        if let Some(url) = store.endpoint(key_name)? {
+            endpoints.push((endpoint_name(key_name), url));
+        }
+        injected.push(key_name.clone());
+    }
+
+    let inject_all = opts.keys.is_empty() && opts.key_prefixes.is_empty();
+    if inject_all || !opts.key_prefixes.is_empty() {
+        // list(false) returns runtime keys only; with --include-service the
+        // full list is needed, minus admin keys
+        let listed = store.list(opts.include_service)?;
+        let mut blocked: Vec<String> = Vec::new();
+        for entry in &listed {
+            if entry.kind == Some(KeyKind::Admin) {
+                continue;
+            }
+            let wanted = inject_all
+                || opts
+                    .key_prefixes
+                    .iter()
+                    .any(|p| entry.name.starts_with(p.as_str()));
+            if !wanted || injected.contains(&entry.name) {
+                continue;
+            }
+            if entry.status == KeyStatus::AclBlocked {
+                blocked.push(entry.name.clone());
+                continue;
+            }
+            // entry.status == Ok means data was already read by list(),
+            // but we still need the raw value. Re-fetch via get().
+            let value = match store.get(&entry.name) {
+                Ok((value, _kind)) => value,
+                Err(lkr_core::Error::KeyExpired { .. }) => {
+                    skipped_expired.push(entry.name.clone());
+                    continue;
+                }
+                Err(_) => continue,
+            };
+            if !non_empty(&entry.name, &value)? {
+                continue;
+            }
+            pairs.push((env_name(&entry.name), value));
+            if let Some(url) = &entry.custom_endpoint {
+                endpoints.push((endpoint_name(&entry.name), url.clone()));
+            }
+            injected.push(entry.name.clone());
+        }
+        warn_acl_blocked(&blocked);
+    }
+
+    Ok(Injection {
+        names: injected,
+        entries: pairs,
+        endpoints,
+        skipped_empty,
+        skipped_expired,
+    })
+}
+
+/// `--fail-if-missing`: an error naming every required key that is not
+/// about to be injected, with why, so the child is never started without
+/// it. Keys are matched by name, not env var, and the reason comes from
+/// metadata: a key that wasn't selected is never handed out.
+fn ensure_required(
+    store: &impl KeyStore,
+    injection: &Injection,
+    opts: &ExecOptions,
+) -> lkr_core::Result<()> {
+    let now = chrono::Utc::now();
+    let mut missing = Vec::new();
+    for name in &opts.fail_if_missing {
+        lkr_core::validate_key_name(name)?;
+        if injection.names.contains(name) {
+            continue;
+        }
+        let reason = match store.get_with_metadata(name) {
+            Err(lkr_core::Error::KeyNotFound { .. }) => "not found".to_string(),
+            Err(e) => e.to_string(),
+            Ok(entry) if entry.expires_at.is_some_and(|at| at <= now) => "expired".to_string(),
+            Ok(entry) if entry.value.is_empty() => "empty value".to_string(),
+            Ok(entry) => format!("{} key not selected for injection", entry.kind),
+        };
+        missing.push(format!("{} ({})", name, reason));
+    }
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(lkr_core::Error::Usage(format!(
+        "Required key(s) missing, command not run: {}",
+        missing.join(", ")
+    )))
+}
+
+/// Expand `-k` arguments: `@tag` becomes every runtime key carrying the
+/// tag, in `list` order; plain names pass through. Duplicates are dropped.
+fn resolve_exec_keys(store: &impl KeyStore, keys: &[String]) -> lkr_core::Result<Vec<String>> {
+    let mut listed = None;
+    let mut resolved: Vec<String> = Vec::new();
+    for key in keys {
+        let names = match key.strip_prefix('@') {
+            None => vec![key.clone()],
+            Some(tag) => {
+                lkr_core::validate_tag(tag)?;
+                if listed.is_none() {
+                    listed = Some(store.list(false)?);
+                }
+                let names: Vec<String> = listed
+                    .iter()
+                    .flatten()
+                    .filter(|e| e.kind == Some(KeyKind::Runtime) && e.has_tag(tag))
+                    .map(|e| e.name.clone())
+                    .collect();
+                if names.is_empty() {
+                    return Err(lkr_core::Error::Usage(format!(
+                        "No runtime keys are tagged '{}' (see `lkr list --tag {}`)",
+                        tag, tag
+                    )));
+                }
+                names
+            }
+        };
+        for name in names {
+            if !resolved.contains(&name) {
+                resolved.push(name);
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// An injected env var the parent process already sets to a different value.
+struct EnvConflict {
+    env_var: String,
+    old_masked: String,
+    new_masked: String,
+}
+
+/// Compare the vars about to be injected with the parent env (`lookup`).
+/// A parent value equal to the injected one is not a conflict.
+fn env_conflicts(
+    injection: &Injection,
+    lookup: impl Fn(&str) -> Option<lkr_core::Zeroizing<String>>,
+) -> Vec<EnvConflict> {
+    let injected = injection
+        .entries
+        .iter()
+        .map(|(var, value)| (var, value.as_str()))
+        .chain(
+            injection
+                .endpoints
+                .iter()
+                .map(|(var, url)| (var, url.as_str())),
+        );
+    injected
+        .filter_map(|(env_var, new)| {
+            let old = lookup(env_var).filter(|old| old.as_str() != new)?;
+            Some(EnvConflict {
+                env_var: env_var.clone(),
+                old_masked: lkr_core::mask_value(&old),
+                new_masked: lkr_core::mask_value(new),
+            })
+        })
+        .collect()
+}
+
+fn warn_acl_blocked(blocked: &[String]) {
+    if !blocked.is_empty() {
+        eprintln!(
+            "⚠ {} key(s) skipped (ACL mismatch): {}",
+            blocked.len(),
+            blocked.join(", ")
+        );
+        eprintln!("  Run `lkr harden` to fix ACL for these keys.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::keymanager::MockStore;
+
+    fn store() -> MockStore {
+        let store = MockStore::new();
+        for (name, kind) in [
+            ("myproject:prod", KeyKind::Runtime),
+            ("myproject:dev", KeyKind::Runtime),
+            ("other:prod", KeyKind::Runtime),
+            ("myproject:admin", KeyKind::Admin),
+        ] {
+            store.set(name, "sk-test-value", kind, false).unwrap();
+        }
+        store
+    }
+
+    fn env_names(opts: &ExecOptions) -> Vec<String> {
+        let mut names: Vec<String> = collect_entries(&store(), opts)
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_empty_value_is_skipped_or_aborts_when_strict() {
+        let store = store();
+        store.insert_unchecked("legacy:old", "", KeyKind::Runtime);
+
+        let injection = collect_entries(&store, &ExecOptions::default()).unwrap();
+        assert_eq!(injection.skipped_empty, vec!["legacy:old"]);
+        assert!(injection.entries.iter().all(|(var, _)| var != "LEGACY_OLD"));
+        assert_eq!(injection.entries.len(), 3);
+
+        let opts = ExecOptions {
+            keys: vec!["legacy:old".to_string()],
+            strict: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            collect_entries(&store, &opts),
+            Err(lkr_core::Error::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_expired_key_is_skipped_and_reported() {
+        let store = store();
+        store
+            .update_metadata(
+                "myproject:dev",
+                &lkr_core::MetadataUpdate::Expiry(Some(
+                    chrono::Utc::now() - chrono::Duration::hours(1),
+                )),
+            )
+            .unwrap();
+
+        let injection = collect_entries(&store, &ExecOptions::default()).unwrap();
+        assert_eq!(injection.skipped_expired, vec!["myproject:dev"]);
+        assert!(
+            injection
+                .entries
+                .iter()
+                .all(|(var, _)| var != "MYPROJECT_DEV")
+        );
+
+        let opts = ExecOptions {
+            key_prefixes: vec!["myproject:".to_string()],
+            ..Default::default()
+        };
+        let injection = collect_entries(&store, &opts).unwrap();
+        assert_eq!(injection.skipped_expired, vec!["myproject:dev"]);
+    }
+
+    fn spawn(script: &str) -> Child {
+        std::process::Command::new("sh")
+            .args(["-c", script])
+            .spawn()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_timeout_keeps_exit_code_of_fast_child() {
+        let code = run_with_timeout(
+            spawn("exit 3"),
+            Duration::from_secs(5),
+            Duration::ZERO,
+            false,
+            None,
+        );
+        assert_eq!(code.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_timeout_sigterm_within_grace() {
+        let code = run_with_timeout(
+            spawn("sleep 10"),
+            Duration::from_millis(100),
+            Duration::from_secs(2),
+            false,
+            None,
+        );
+        assert_eq!(code.unwrap(), 128 + libc::SIGTERM);
+    }
+
+    #[test]
+    fn test_timeout_sigkill_when_sigterm_ignored() {
+        let code = run_with_timeout(
+            spawn("trap '' TERM; while :; do :; done"),
+            Duration::from_millis(100),
+            Duration::from_millis(200),
+            false,
+            None,
+        );
+        assert_eq!(code.unwrap(), 128 + libc::SIGKILL);
+    }
+
+    #[test]
+    fn test_timeout_signals_whole_process_group() {
+        let pid_file = std::env::temp_dir().join(format!("lkr-exec-pgrp-{}", std::process::id()));
+        let child = std::process::Command::new("sh")
+            .args([
+                "-c",
+                &format!("sleep 30 & echo $! > {}; wait", pid_file.display()),
+            ])
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        let grandchild: libc::pid_t = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+
+        let code = run_with_timeout(
+            child,
+            Duration::from_millis(100),
+            Duration::from_secs(2),
+            true,
+            None,
+        );
+        assert_eq!(code.unwrap(), 128 + libc::SIGTERM);
+        // The backgrounded sleep got the SIGTERM too
+        std::thread::sleep(Duration::from_millis(200));
+        // SAFETY: signal 0 only checks the pid exists
+        assert_ne!(unsafe { libc::kill(grandchild, 0) }, 0);
+        std::fs::remove_file(&pid_file).unwrap();
+    }
+
+    fn required(store: &MockStore, opts: &ExecOptions) -> lkr_core::Result<()> {
+        let injection = collect_entries(store, opts)?;
+        ensure_required(store, &injection, opts)
+    }
+
+    #[test]
+    fn test_fail_if_missing_passes_when_required_keys_are_injected() {
+        let store = store();
+        let opts = ExecOptions {
+            key_prefixes: vec!["myproject:".to_string()],
+            fail_if_missing: vec!["myproject:prod".to_string(), "myproject:dev".to_string()],
+            ..Default::default()
+        };
+        required(&store, &opts).unwrap();
+    }
+
+    #[test]
+    fn test_fail_if_missing_aborts_before_spawn() {
+        let store = store();
+        // Missing from the store
+        let opts = ExecOptions {
+            fail_if_missing: vec!["openai:prod".to_string()],
+            ..Default::default()
+        };
+        let err = required(&store, &opts).unwrap_err();
+        assert!(err.to_string().contains("openai:prod (not found)"));
+
+        // Stored, but the prefix doesn't select it
+        let opts = ExecOptions {
+            key_prefixes: vec!["myproject:".to_string()],
+            fail_if_missing: vec!["other:prod".to_string()],
+            ..Default::default()
+        };
+        let err = required(&store, &opts).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("other:prod (runtime key not selected")
+        );
+
+        // Empty values are skipped, so they count as missing
+        store.insert_unchecked("legacy:old", "", KeyKind::Runtime);
+        let opts = ExecOptions {
+            fail_if_missing: vec!["legacy:old".to_string()],
+            ..Default::default()
+        };
+        let err = required(&store, &opts).unwrap_err();
+        assert!(err.to_string().contains("legacy:old (empty value)"));
+
+        // Another key with the same env var (OPENAI_API_KEY) doesn't count
+        store
+            .set("openai:dev", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        let opts = ExecOptions {
+            keys: vec!["openai:dev".to_string()],
+            fail_if_missing: vec!["openai:prod".to_string()],
+            ..Default::default()
+        };
+        let err = required(&store, &opts).unwrap_err();
+        assert!(err.to_string().contains("openai:prod (not found)"));
+
+        // Required admin keys are reported, not read out
+        let opts = ExecOptions {
+            keys: vec!["myproject:prod".to_string()],
+            fail_if_missing: vec!["myproject:admin".to_string()],
+            ..Default::default()
+        };
+        let err = required(&store, &opts).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("myproject:admin (admin key not selected")
+        );
+
+        // cmd_exec checks before spawning: the command would fail if run
+        let opts = ExecOptions {
+            fail_if_missing: vec!["openai:prod".to_string()],
+            ..Default::default()
+        };
+        let err = cmd_exec(&store, &["false".to_string()], &opts, false).unwrap_err();
+        assert!(err.to_string().contains("command not run"));
+    }
+
+    #[test]
+    fn test_tag_expands_to_tagged_runtime_keys() {
+        let store = store();
+        let prod = vec!["prod".to_string()];
+        for name in ["myproject:prod", "other:prod", "myproject:admin"] {
+            store.update_tags(name, &prod, &[]).unwrap();
+        }
+
+        let keys = vec!["@prod".to_string(), "other:prod".to_string()];
+        // Admin keys never match a tag; a name listed twice is kept once
+        assert_eq!(
+            resolve_exec_keys(&store, &keys).unwrap(),
+            ["myproject:prod", "other:prod"]
+        );
+
+        let opts = ExecOptions {
+            keys: vec!["@prod".to_string()],
+            ..Default::default()
+        };
+        let mut names: Vec<_> = collect_entries(&store, &opts)
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["MYPROJECT_PROD", "OTHER_PROD"]);
+
+        assert!(resolve_exec_keys(&store, &["@staging".to_string()]).is_err());
+        assert!(resolve_exec_keys(&store, &["@Bad".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_key_prefix_filters_matching_runtime_keys() {
+        let opts = ExecOptions {
+            key_prefixes: vec!["myproject:".to_string()],
+            ..Default::default()
+        };
+        // other:prod does not match; myproject:admin is never injected
+        assert_eq!(env_names(&opts), vec!["MYPROJECT_DEV", "MYPROJECT_PROD"]);
+    }
+
+    #[test]
+    fn test_key_prefixes_are_or_combined() {
+        let opts = ExecOptions {
+            key_prefixes: vec!["myproject:prod".to_string(), "other:".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(env_names(&opts), vec!["MYPROJECT_PROD", "OTHER_PROD"]);
+    }
+
+    #[test]
+    fn test_key_prefix_without_match_injects_nothing() {
+        let opts = ExecOptions {
+            key_prefixes: vec!["nomatch:".to_string()],
+            ..Default::default()
+        };
+        assert!(env_names(&opts).is_empty());
+    }
+
+    #[test]
+    fn test_env_prefix_applies_to_every_name() {
+        let opts = ExecOptions {
+            keys: vec!["other:prod".to_string()],
+            key_prefixes: vec!["myproject:prod".to_string()],
+            env_prefix: Some("APP_".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            env_names(&opts),
+            vec!["APP_MYPROJECT_PROD", "APP_OTHER_PROD"]
+        );
+    }
+
+    #[test]
+    fn test_no_filters_injects_all_runtime_keys() {
+        assert_eq!(env_names(&ExecOptions::default()).len(), 3);
+    }
+
+    #[test]
+    fn test_service_keys_need_include_service() {
+        let store = store();
+        store
+            .set("shared:svc", "sk-service", KeyKind::Service, false)
+            .unwrap();
+        let names = |opts: &ExecOptions| -> Vec<String> {
+            let mut names: Vec<_> = collect_entries(&store, opts)
+                .unwrap()
+                .entries
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert!(!names(&ExecOptions::default()).contains(&"SHARED_SVC".to_string()));
+        let included = names(&ExecOptions {
+            include_service: true,
+            ..Default::default()
+        });
+        assert!(included.contains(&"SHARED_SVC".to_string()));
+        // Admin keys stay out even though the full list is read
+        assert!(!included.contains(&"MYPROJECT_ADMIN".to_string()));
+
+        let named = ExecOptions {
+            keys: vec!["shared:svc".to_string()],
+            ..Default::default()
+        };
+        assert!(collect_entries(&store, &named).is_err());
+    }
+
+    #[test]
+    fn test_inherited_vars_match_patterns() {
+        let parent = [
+            ("PATH", "/usr/bin"),
+            ("HOME", "/home/me"),
+            ("LC_ALL", "C"),
+            ("LC_CTYPE", "UTF-8"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+        ]
+        .map(|(k, v)| (OsString::from(k), OsString::from(v)));
+        let patterns = ["PATH".to_string(), "LC_*".to_string()];
+
+        let kept: Vec<_> = inherited_vars(parent, &patterns)
+            .into_iter()
+            .map(|(k, _)| k.into_string().unwrap())
+            .collect();
+        assert_eq!(kept, ["PATH", "LC_ALL", "LC_CTYPE"]);
+        assert!(inherited_vars([(OsString::from("PATH"), OsString::new())], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_custom_endpoint_exported_with_key() {
+        let store = store();
+        store
+            .set_endpoint("myproject:prod", Some("https://proxy.example.com"))
+            .unwrap();
+        let opts = ExecOptions {
+            keys: vec!["myproject:prod".to_string(), "other:prod".to_string()],
+            env_prefix: Some("APP_".to_string()),
+            ..Default::default()
+        };
+        let injection = collect_entries(&store, &opts).unwrap();
+        assert_eq!(injection.entries.len(), 2);
+        assert_eq!(
+            injection.endpoints,
+            vec![(
+                "APP_MYPROJECT_PROD_BASE_URL".to_string(),
+                "https://proxy.example.com".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_env_conflicts_against_parent_env() {
+        let opts = ExecOptions {
+            keys: vec!["myproject:prod".to_string(), "other:prod".to_string()],
+            ..Default::default()
+        };
+        let injection = collect_entries(&store(), &opts).unwrap();
+        let parent = |var: &str| {
+            let value = match var {
+                "MYPROJECT_PROD" => "sk-parent-value-0000",
+                // Same value as the Keychain: nothing is overridden
+                "OTHER_PROD" => "sk-test-value",
+                _ => return None,
+            };
+            Some(lkr_core::Zeroizing::new(value.to_string()))
+        };
+
+        let conflicts = env_conflicts(&injection, parent);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].env_var, "MYPROJECT_PROD");
+        assert_eq!(
+            conflicts[0].old_masked,
+            lkr_core::mask_value("sk-parent-value-0000")
+        );
+        assert!(!conflicts[0].old_masked.contains("parent-value"));
+        assert!(env_conflicts(&injection, |_| None).is_empty());
+    }
+
+    #[test]
+    fn test_only_admin_requires_acknowledgement() {
+        let opts = ExecOptions {
+            only_admin: true,
+            ..Default::default()
+        };
+        let err = collect_entries(&store(), &opts).err().unwrap();
+        assert!(err.to_string().contains("--i-understand"));
+    }
+
+    #[test]
+    fn test_only_admin_injects_admin_keys_only() {
+        let opts = ExecOptions {
+            only_admin: true,
+            i_understand: true,
+            ..Default::default()
+        };
+        assert_eq!(env_names(&opts), vec!["MYPROJECT_ADMIN"]);
+    }
+
+    #[test]
+    fn test_acknowledgement_alone_injects_no_admin_keys() {
+        let opts = ExecOptions {
+            i_understand: true,
+            ..Default::default()
+        };
+        assert!(!env_names(&opts).contains(&"MYPROJECT_ADMIN".to_string()));
+    }
 }
@@ -0,0 +1,242 @@
+use lkr_core::{ImportEntry, KeyEntry, KeyStore};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// `lkr export --passphrase -o FILE`: write every key (admin included) to a
+/// passphrase-encrypted file in the `lkr import` JSON format. Plaintext
+/// exports are not offered. An existing file is only replaced with `force`.
+pub(crate) fn cmd_export(
+    store: &impl KeyStore,
+    output: &str,
+    passphrase_stdin: bool,
+    force: bool,
+    stdout_is_tty: bool,
+) -> lkr_core::Result<()> {
+    tty_guard(stdout_is_tty, force)?;
+    let entries = collect_entries(store)?;
+    if entries.is_empty() {
+        eprintln!("No keys to export.");
+        return Ok(());
+    }
+    if !force && Path::new(output).exists() {
+        return Err(lkr_core::Error::Usage(format!(
+            "'{}' already exists. Use --force to overwrite.",
+            output
+        )));
+    }
+
+    let passphrase = if passphrase_stdin {
+        crate::passphrase::read_stdin()?
+    } else {
+        crate::passphrase::read_new()?
+    };
+    let plaintext = Zeroizing::new(
+        serde_json::to_vec_pretty(&entries)
+            .map_err(|e| lkr_core::Error::Usage(format!("Serialization failed: {}", e)))?,
+    );
+    let sealed = lkr_core::crypto::encrypt(&plaintext, &passphrase)?;
+    write_private(Path::new(output), &sealed)?;
+
+    eprintln!("Exported {} key(s) to {}", entries.len(), output);
+    Ok(())
+}
+
+/// Block `export` in non-interactive environments unless `--force`, as for
+/// `lkr gen`: with `--passphrase-stdin` the caller picks the passphrase, so
+/// the file is as good as every value in plaintext, admin keys included.
+fn tty_guard(stdout_is_tty: bool, force: bool) -> lkr_core::Result<()> {
+    if !stdout_is_tty && !force {
+        return Err(lkr_core::Error::TtyGuard {
+            message: "`lkr export` is blocked in non-interactive environments.\n  \
+                It writes every key, admin keys included.\n  \
+                Use `lkr export --force` to override."
+                .to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Write `content` to `path` with mode 0600 via a temp file and rename, so
+/// a replaced file never keeps its old permissions and a failed write never
+/// leaves a truncated export behind.
+fn write_private(path: &Path, content: &[u8]) -> lkr_core::Result<()> {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let tmp_path = parent.join(format!(".lkr-export-{}.tmp", std::process::id()));
+    let written = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&tmp_path)
+        .and_then(|mut f| f.write_all(content).and_then(|()| f.flush()))
+        .and_then(|()| fs::rename(&tmp_path, path));
+    written.map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        lkr_core::Error::Usage(format!("Cannot write '{}': {}", path.display(), e))
+    })
+}
+
+/// `lkr export --names-only [-o FILE]`: `{name, kind, provider}` for every
+/// key, so a teammate knows what to set up. No values, so no encryption.
+pub(crate) fn cmd_export_names(
+    store: &impl KeyStore,
+    output: Option<&str>,
+) -> lkr_core::Result<()> {
+    let manifest = names_manifest(&store.list(true)?);
+    let text = serde_json::to_string_pretty(&manifest).unwrap();
+    match output {
+        Some(path) => {
+            std::fs::write(path, format!("{}\n", text))
+                .map_err(|e| lkr_core::Error::Usage(format!("Cannot write '{}': {}", path, e)))?;
+            eprintln!("Exported {} key name(s) to {}", manifest.len(), path);
+        }
+        None => println!("{}", text),
+    }
+    Ok(())
+}
+
+fn names_manifest(entries: &[KeyEntry]) -> Vec<serde_json::Value> {
+    entries
+        .iter()
+        .map(|e| {
+            serde_json::json!({
+                "name": e.name,
+                // None only for ACL-blocked keys
+                "kind": e.kind.map(|k| k.to_string()),
+                "provider": e.provider,
+            })
+        })
+        .collect()
+}
+
+/// Every stored key with its value, in `list` order.
+fn collect_entries(store: &impl KeyStore) -> lkr_core::Result<Vec<ImportEntry>> {
+    store
+        .list(true)?
+        .into_iter()
+        .map(|entry| {
+            // Expired keys are backed up too
+            let stored = store.get_with_metadata(&entry.name)?;
+            Ok(ImportEntry {
+                name: entry.name,
+                value: stored.value.clone(),
+                kind: stored.kind,
+                source: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::KeyKind;
+    use lkr_core::keymanager::MockStore;
+
+    #[test]
+    fn test_names_manifest_has_no_values() {
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-prod-secret", KeyKind::Runtime, false)
+            .unwrap();
+        store
+            .set("openai:admin", "sk-admin-secret", KeyKind::Admin, false)
+            .unwrap();
+
+        let manifest = names_manifest(&store.list(true).unwrap());
+        assert_eq!(
+            serde_json::Value::Array(manifest.clone()),
+            serde_json::json!([
+                {"name": "openai:admin", "kind": "admin", "provider": "openai"},
+                {"name": "openai:prod", "kind": "runtime", "provider": "openai"},
+            ])
+        );
+        let text = serde_json::to_string(&manifest).unwrap();
+        assert!(!text.contains("secret"));
+    }
+
+    fn temp_dir(test: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("lkr-export-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_export_blocked_without_tty() {
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-prod-1", KeyKind::Runtime, false)
+            .unwrap();
+        let result = cmd_export(&store, "unused.lkr", true, false, false);
+        assert!(matches!(result, Err(lkr_core::Error::TtyGuard { .. })));
+    }
+
+    #[test]
+    fn test_export_refuses_to_overwrite_without_force() {
+        let dir = temp_dir("overwrite");
+        let output = dir.join("keys.lkr");
+        fs::write(&output, "keep me").unwrap();
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-prod-1", KeyKind::Runtime, false)
+            .unwrap();
+
+        let result = cmd_export(&store, output.to_str().unwrap(), true, false, true);
+        assert!(matches!(result, Err(lkr_core::Error::Usage(_))));
+        assert_eq!(fs::read_to_string(&output).unwrap(), "keep me");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_write_private_replaces_file_with_0600() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = temp_dir("perms");
+        let output = dir.join("keys.lkr");
+        fs::write(&output, "old").unwrap();
+        fs::set_permissions(&output, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&output, b"sealed").unwrap();
+        assert_eq!(fs::read(&output).unwrap(), b"sealed");
+        let mode = fs::metadata(&output).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_export_roundtrips_through_import_format() {
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-prod-1", KeyKind::Runtime, false)
+            .unwrap();
+        store
+            .set("openai:admin", "sk-admin-1", KeyKind::Admin, false)
+            .unwrap();
+
+        let entries = collect_entries(&store).unwrap();
+        let sealed =
+            lkr_core::crypto::encrypt(&serde_json::to_vec(&entries).unwrap(), "pw").unwrap();
+        let opened = lkr_core::crypto::decrypt(&sealed, "pw").unwrap();
+        let parsed = crate::cmd::import::parse_entries(&opened).unwrap();
+
+        let mut names: Vec<_> = parsed
+            .iter()
+            .map(|e| (e.name.as_str(), e.value.as_str(), e.kind))
+            .collect();
+        names.sort_by_key(|(name, _, _)| *name);
+        assert_eq!(
+            names,
+            [
+                ("openai:admin", "sk-admin-1", KeyKind::Admin),
+                ("openai:prod", "sk-prod-1", KeyKind::Runtime),
+            ]
+        );
+    }
+}
@@ -1,59 +1,79 @@
-use crate::util::confirm;
-use lkr_core::KeyStore;
-use std::path::Path;
+use crate::prompt::confirm;
+use lkr_core::{
+    ChecksumStore, KeyStore, OutputPerms, Resolution, TemplateFormat, TemplatePolicy, Verification,
+};
+use notify::Watcher;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+/// Flags for `lkr gen`, bundled so new options don't grow the signature.
+#[derive(Default)]
+pub(crate) struct GenOptions {
+    pub output: Option<String>,
+    pub force: bool,
+    pub check: bool,
+    /// Append missing variables to an existing output instead of overwriting
+    pub append: bool,
+    /// Compare the output against the checksum recorded when it was generated
+    pub verify: bool,
+    /// Fill variables no key matches from the process environment
+    pub env_fallback: bool,
+    /// Fail without writing if any placeholder is unresolved
+    pub strict: bool,
+    /// Pretty-print the output through serde_json, failing if it is not JSON
+    pub strict_json: bool,
+    /// Mode and group for written outputs (`--mode`, `--group`)
+    pub perms: OutputPerms,
+    /// Treat the template as an MCP config (`--format mcp`)
+    pub mcp: bool,
+    pub json: bool,
+}
 
 pub(crate) fn cmd_gen(
     store: &impl KeyStore,
     template: &str,
-    output: Option<&str>,
-    force: bool,
+    opts: &GenOptions,
     stdout_is_tty: bool,
 ) -> lkr_core::Result<()> {
-    // v0.2.0 TTY guard: block gen in non-interactive environments unless --force.
-    // Generated files contain resolved secrets — risky in agent/CI contexts.
-    if !stdout_is_tty && !force {
-        return Err(lkr_core::Error::TtyGuard {
-            message: "`lkr gen` is blocked in non-interactive environments.\n  \
-                Use `lkr exec -- <command>` to inject keys as env vars instead.\n  \
-                Or use `lkr gen --force` to override."
-                .to_string(),
-        });
-    }
+    let GenOptions {
+        ref output,
+        force,
+        check,
+        append,
+        verify,
+        env_fallback,
+        strict,
+        strict_json,
+        perms,
+        mcp,
+        json,
+    } = *opts;
+
+    tty_guard(stdout_is_tty, force, check || verify)?;
+    let policy = TemplatePolicy::from_env()?
+        .with_env_fallback(env_fallback)
+        .with_strict(strict)
+        .with_strict_json(strict_json)
+        .with_output_perms(perms);
 
     let template_path = Path::new(template);
-    if !template_path.exists() {
-        return Err(lkr_core::Error::Template(format!(
-            "Template file not found: {}",
-            template
-        )));
-    }
+    let output_path = resolve_paths(template_path, output.as_deref())?;
 
-    // Derive output path: .env.example → .env, foo.template → foo
-    let output_path = match output {
-        Some(o) => std::path::PathBuf::from(o),
-        None => {
-            let name = template_path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy();
-            let derived = if name.ends_with(".example") {
-                name.trim_end_matches(".example").to_string()
-            } else if name.ends_with(".template") {
-                name.trim_end_matches(".template").to_string()
-            } else {
-                return Err(lkr_core::Error::Template(
-                    "Cannot derive output path. Use -o to specify output file.".to_string(),
-                ));
-            };
-            template_path
-                .parent()
-                .unwrap_or(Path::new("."))
-                .join(derived)
-        }
-    };
+    if check {
+        return check_output(store, template_path, &output_path, &policy, json);
+    }
+    if verify {
+        let checksums = ChecksumStore::new(ChecksumStore::default_path()?);
+        return verify_output(&checksums, template_path, &output_path);
+    }
 
-    // Check if output exists and not --force
-    if output_path.exists()
+    // Check if output exists and not --force (append never overwrites)
+    if !append
+        && output_path.exists()
         && !force
         && !confirm(&format!(
             "Output file '{}' already exists. Overwrite? [y/N] ",
@@ -61,20 +81,30 @@ pub(crate) fn cmd_gen(
         ))
     {
         eprintln!("Cancelled.");
+        if json {
+            print_summary(&output_path, &[], false, None);
+        }
         return Ok(());
     }
 
-    // .gitignore check (skipped outside git repos)
-    if let Some(false) = lkr_core::check_gitignore(&output_path) {
-        eprintln!(
-            "Warning: '{}' is NOT in .gitignore. Generated files may contain secrets!",
-            output_path.display()
-        );
-        eprintln!("  Consider adding it to .gitignore before committing.");
+    warn_if_not_gitignored(&output_path);
+
+    if append {
+        return append_output(store, template_path, &output_path, &policy, json);
     }
 
     // Generate
-    let result = lkr_core::generate(store, template_path, &output_path)?;
+    let result = if mcp {
+        lkr_core::generate_mcp(store, template_path, &output_path, &policy)?
+    } else {
+        lkr_core::generate(store, template_path, &output_path, &policy)?
+    };
+    record_checksum(&output_path, &result.output_checksum, template_path);
+
+    if json {
+        print_summary(&output_path, &result.resolutions, true, None);
+        return Ok(());
+    }
 
     // Report
     let resolved: Vec<_> = result
@@ -82,10 +112,15 @@ pub(crate) fn cmd_gen(
         .iter()
         .filter(|r| r.key_name.is_some())
         .collect();
+    let from_env: Vec<_> = result
+        .resolutions
+        .iter()
+        .filter(|r| r.env_var.is_some())
+        .collect();
     let unresolved: Vec<_> = result
         .resolutions
         .iter()
-        .filter(|r| r.key_name.is_none())
+        .filter(|r| !r.is_resolved())
         .collect();
 
     if !resolved.is_empty() {
@@ -96,6 +131,9 @@ pub(crate) fn cmd_gen(
                 r.placeholder,
                 r.key_name.as_deref().unwrap_or("?")
             );
+            if !r.mcp_servers.is_empty() {
+                eprintln!("      (MCP servers: {})", r.mcp_servers.join(", "));
+            }
             if r.alternatives.len() > 1 {
                 let others: Vec<&str> = r
                     .alternatives
@@ -110,6 +148,17 @@ pub(crate) fn cmd_gen(
         }
     }
 
+    if !from_env.is_empty() {
+        eprintln!("  Resolved from environment (no matching key):");
+        for r in &from_env {
+            eprintln!(
+                "    {:<24} <- ${}",
+                r.placeholder,
+                r.env_var.as_deref().unwrap_or("?")
+            );
+        }
+    }
+
     if !unresolved.is_empty() {
         eprintln!("  Kept as-is (no matching key):");
         for r in &unresolved {
@@ -120,9 +169,836 @@ pub(crate) fn cmd_gen(
     eprintln!(
         "\n  Generated: {} ({} resolved, {} unresolved)",
         output_path.display(),
-        resolved.len(),
+        resolved.len() + from_env.len(),
         unresolved.len()
     );
 
     Ok(())
 }
+
+/// A `gen --stdin-json` request: template content plus explicit vars.
+struct StdinRequest {
+    template: String,
+    /// Detected from the content when absent
+    format: Option<TemplateFormat>,
+    vars: BTreeMap<String, Zeroizing<String>>,
+}
+
+/// `lkr gen --stdin-json`: read `{"template", "format", "vars"}` from stdin
+/// and print the rendered template to stdout. Nothing is written to disk.
+pub(crate) fn cmd_gen_stdin_json(
+    store: &impl KeyStore,
+    opts: &GenOptions,
+    stdout_is_tty: bool,
+) -> lkr_core::Result<()> {
+    // The rendered output carries secrets just like a generated file
+    tty_guard(stdout_is_tty, opts.force, false)?;
+
+    let mut input = Zeroizing::new(String::new());
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| lkr_core::Error::Usage(format!("Failed to read stdin: {}", e)))?;
+    let request = parse_stdin_request(&input)?;
+
+    let policy = TemplatePolicy::from_env()?
+        .with_env_fallback(opts.env_fallback)
+        .with_strict(opts.strict)
+        .with_strict_json(opts.strict_json)
+        .with_vars(request.vars);
+    let result = lkr_core::render_str(store, &request.template, request.format, &policy)?;
+    print!("{}", result.content);
+
+    let unresolved: Vec<_> = result
+        .resolutions
+        .iter()
+        .filter(|r| !r.is_resolved())
+        .map(|r| r.placeholder.as_str())
+        .collect();
+    if !unresolved.is_empty() {
+        eprintln!("  Kept as-is (no matching key): {}", unresolved.join(", "));
+    }
+    Ok(())
+}
+
+fn parse_stdin_request(input: &str) -> lkr_core::Result<StdinRequest> {
+    let invalid = |reason: String| lkr_core::Error::Usage(format!("--stdin-json: {}", reason));
+    let value: serde_json::Value =
+        serde_json::from_str(input).map_err(|e| invalid(lkr_core::json_error_summary(&e)))?;
+
+    let template = value
+        .get("template")
+        .and_then(|t| t.as_str())
+        .ok_or_else(|| invalid("\"template\" must be a string".to_string()))?
+        .to_string();
+    let format = match value.get("format") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(f)) => Some(f.parse().map_err(invalid)?),
+        Some(_) => return Err(invalid("\"format\" must be a string".to_string())),
+    };
+    let mut vars = BTreeMap::new();
+    match value.get("vars") {
+        None | Some(serde_json::Value::Null) => {}
+        Some(serde_json::Value::Object(map)) => {
+            for (name, v) in map {
+                let v = v
+                    .as_str()
+                    .ok_or_else(|| invalid(format!("var '{}' must be a string", name)))?;
+                vars.insert(name.clone(), Zeroizing::new(v.to_string()));
+            }
+        }
+        Some(_) => return Err(invalid("\"vars\" must be an object".to_string())),
+    }
+    Ok(StdinRequest {
+        template,
+        format,
+        vars,
+    })
+}
+
+/// `lkr gen -t A -o A' -t B -o B'`: generate several outputs at once.
+///
+/// All templates are resolved before any output is written, so one bad
+/// template leaves every output untouched. `jobs` pairs each template with
+/// its `-o` (derived from the template name when `None`).
+pub(crate) fn cmd_gen_many(
+    store: &impl KeyStore,
+    jobs: &[(String, Option<String>)],
+    opts: &GenOptions,
+    stdout_is_tty: bool,
+) -> lkr_core::Result<()> {
+    if opts.check || opts.append || opts.verify {
+        return Err(lkr_core::Error::Usage(
+            "--check, --append and --verify work on a single template.".to_string(),
+        ));
+    }
+    tty_guard(stdout_is_tty, opts.force, false)?;
+    let policy = TemplatePolicy::from_env()?
+        .with_env_fallback(opts.env_fallback)
+        .with_strict(opts.strict)
+        .with_strict_json(opts.strict_json)
+        .with_output_perms(opts.perms);
+
+    let mut paths: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (template, output) in jobs {
+        let template_path = PathBuf::from(template);
+        let output_path = resolve_paths(&template_path, output.as_deref())?;
+        if paths.iter().any(|(_, o)| *o == output_path) {
+            return Err(lkr_core::Error::Usage(format!(
+                "Output '{}' is given more than once.",
+                output_path.display()
+            )));
+        }
+        paths.push((template_path, output_path));
+    }
+
+    let existing: Vec<String> = paths
+        .iter()
+        .filter(|(_, o)| o.exists())
+        .map(|(_, o)| o.display().to_string())
+        .collect();
+    if !existing.is_empty()
+        && !opts.force
+        && !confirm(&format!(
+            "Output file(s) already exist: {}. Overwrite? [y/N] ",
+            existing.join(", ")
+        ))
+    {
+        eprintln!("Cancelled.");
+        return Ok(());
+    }
+
+    for (_, output_path) in &paths {
+        warn_if_not_gitignored(output_path);
+    }
+
+    let pairs: Vec<(&Path, &Path)> = paths
+        .iter()
+        .map(|(t, o)| (t.as_path(), o.as_path()))
+        .collect();
+    let results = lkr_core::generate_all(store, &pairs, &policy)?;
+    for ((template_path, output_path), result) in paths.iter().zip(&results) {
+        record_checksum(output_path, &result.output_checksum, template_path);
+    }
+
+    if opts.json {
+        let summaries: Vec<_> = paths
+            .iter()
+            .zip(&results)
+            .map(|((_, o), r)| gen_summary(o, &r.resolutions, true, None))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&summaries).unwrap());
+        return Ok(());
+    }
+
+    let (mut total_resolved, mut total_unresolved) = (0, 0);
+    for ((_, output_path), result) in paths.iter().zip(&results) {
+        let resolved = result
+            .resolutions
+            .iter()
+            .filter(|r| r.is_resolved())
+            .count();
+        let unresolved: Vec<&str> = result
+            .resolutions
+            .iter()
+            .filter(|r| !r.is_resolved())
+            .map(|r| r.placeholder.as_str())
+            .collect();
+        eprintln!(
+            "  {}: {} resolved, {} unresolved",
+            output_path.display(),
+            resolved,
+            unresolved.len()
+        );
+        if !unresolved.is_empty() {
+            eprintln!("    Kept as-is: {}", unresolved.join(", "));
+        }
+        total_resolved += resolved;
+        total_unresolved += unresolved.len();
+    }
+    eprintln!(
+        "\n  Generated {} file(s): {} resolved, {} unresolved.",
+        results.len(),
+        total_resolved,
+        total_unresolved
+    );
+    Ok(())
+}
+
+/// `lkr gen --watch`: keep outputs in sync until Ctrl+C.
+///
+/// Every output is (re)written once up front. After that, a template edit
+/// (filesystem event) or a Keychain change (polled every `interval`) re-runs
+/// `generate` for each output whose rendered content changed.
+pub(crate) fn cmd_gen_watch(
+    store: &impl KeyStore,
+    jobs: &[(String, Option<String>)],
+    opts: &GenOptions,
+    interval: Duration,
+    stdout_is_tty: bool,
+) -> lkr_core::Result<()> {
+    if opts.check || opts.append || opts.verify {
+        return Err(lkr_core::Error::Usage(
+            "--watch cannot be combined with --check, --append or --verify.".to_string(),
+        ));
+    }
+    tty_guard(stdout_is_tty, opts.force, false)?;
+    let policy = TemplatePolicy::from_env()?
+        .with_env_fallback(opts.env_fallback)
+        .with_strict(opts.strict)
+        .with_strict_json(opts.strict_json)
+        .with_output_perms(opts.perms);
+
+    let mut paths: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (template, output) in jobs {
+        let template_path = PathBuf::from(template);
+        let output_path = resolve_paths(&template_path, output.as_deref())?;
+        if paths.iter().any(|(_, o)| *o == output_path) {
+            return Err(lkr_core::Error::Usage(format!(
+                "Output '{}' is given more than once.",
+                output_path.display()
+            )));
+        }
+        paths.push((template_path, output_path));
+    }
+    let existing: Vec<String> = paths
+        .iter()
+        .filter(|(_, o)| o.exists())
+        .map(|(_, o)| o.display().to_string())
+        .collect();
+    if !existing.is_empty()
+        && !opts.force
+        && !confirm(&format!(
+            "Output file(s) already exist: {}. Overwrite them while watching? [y/N] ",
+            existing.join(", ")
+        ))
+    {
+        eprintln!("Cancelled.");
+        return Ok(());
+    }
+    for (_, output_path) in &paths {
+        warn_if_not_gitignored(output_path);
+    }
+
+    // Watch the parent directories: editors often save by replacing the file
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| lkr_core::Error::Usage(format!("Cannot watch templates: {}", e)))?;
+    for (template_path, _) in &paths {
+        let dir = match template_path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        watcher
+            .watch(dir, notify::RecursiveMode::NonRecursive)
+            .map_err(|e| {
+                lkr_core::Error::Usage(format!("Cannot watch '{}': {}", dir.display(), e))
+            })?;
+    }
+
+    eprintln!(
+        "Watching {} template(s); checking Keychain every {}s. Press Ctrl+C to stop.",
+        paths.len(),
+        interval.as_secs()
+    );
+    let mut last_written = HashMap::new();
+    loop {
+        match refresh_outputs(store, &paths, &policy, &mut last_written) {
+            Ok(written) => {
+                for (template_path, output_path, checksum) in written {
+                    record_checksum(&output_path, &checksum, &template_path);
+                    eprintln!(
+                        "Regenerated {} at {}",
+                        output_path.display(),
+                        chrono::Local::now().format("%H:%M:%S")
+                    );
+                }
+            }
+            // A half-saved template is common mid-edit; wait for the next change
+            Err(e) => eprintln!("Warning: {}", e),
+        }
+
+        match rx.recv_timeout(interval) {
+            Ok(_) | Err(mpsc::RecvTimeoutError::Timeout) => {
+                // Coalesce the burst of events a single save produces
+                std::thread::sleep(Duration::from_millis(100));
+                while rx.try_recv().is_ok() {}
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+/// Re-render every `(template, output)` pair and regenerate the outputs
+/// whose content differs from what was last written. Returns
+/// `(template, output, checksum)` for each output written.
+fn refresh_outputs(
+    store: &impl KeyStore,
+    paths: &[(PathBuf, PathBuf)],
+    policy: &TemplatePolicy,
+    last_written: &mut HashMap<PathBuf, String>,
+) -> lkr_core::Result<Vec<(PathBuf, PathBuf, String)>> {
+    let mut written = Vec::new();
+    for (template_path, output_path) in paths {
+        let rendered = lkr_core::render_template(store, template_path, policy)?;
+        let checksum = lkr_core::sha256_hex(rendered.content.as_bytes());
+        if last_written.get(output_path) == Some(&checksum) {
+            continue;
+        }
+        let result = lkr_core::generate(store, template_path, output_path, policy)?;
+        last_written.insert(output_path.clone(), result.output_checksum.clone());
+        written.push((
+            template_path.clone(),
+            output_path.clone(),
+            result.output_checksum,
+        ));
+    }
+    Ok(written)
+}
+
+/// v0.2.0 TTY guard: block gen in non-interactive environments unless --force.
+/// Generated files contain resolved secrets — risky in agent/CI contexts.
+/// --check and --verify write nothing and print no values, so CI may run them freely.
+fn tty_guard(stdout_is_tty: bool, force: bool, check: bool) -> lkr_core::Result<()> {
+    if !stdout_is_tty && !force && !check {
+        return Err(lkr_core::Error::TtyGuard {
+            message: "`lkr gen` is blocked in non-interactive environments.\n  \
+                Use `lkr exec -- <command>` to inject keys as env vars instead.\n  \
+                Or use `lkr gen --force` to override."
+                .to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// `--mode OCTAL` / `--group NAME` as validated output permissions.
+pub(crate) fn output_perms(
+    mode: Option<&str>,
+    group: Option<&str>,
+    i_understand: bool,
+) -> lkr_core::Result<OutputPerms> {
+    let mode = mode.map(parse_mode).transpose()?;
+    let gid = group.map(group_id).transpose()?;
+    OutputPerms::new(
+        mode.unwrap_or(OutputPerms::default().mode()),
+        gid,
+        i_understand,
+    )
+}
+
+/// Octal mode as written for chmod: `640`, `0640` or `0o640`.
+fn parse_mode(s: &str) -> lkr_core::Result<u32> {
+    let digits = s.strip_prefix("0o").unwrap_or(s);
+    u32::from_str_radix(digits, 8).map_err(|_| {
+        lkr_core::Error::Usage(format!("Invalid mode '{}': expected octal, e.g. 0640.", s))
+    })
+}
+
+/// Group name (or numeric gid) to gid.
+fn group_id(name: &str) -> lkr_core::Result<u32> {
+    if let Ok(gid) = name.parse() {
+        return Ok(gid);
+    }
+    let unknown = || lkr_core::Error::Usage(format!("Unknown group '{}'.", name));
+    let c_name = std::ffi::CString::new(name).map_err(|_| unknown())?;
+    // SAFETY: getgrnam gets a valid C string; gr_gid is read before any
+    // other call can reuse the static result.
+    let group = unsafe { libc::getgrnam(c_name.as_ptr()) };
+    if group.is_null() {
+        return Err(unknown());
+    }
+    Ok(unsafe { (*group).gr_gid })
+}
+
+/// Check the template exists and work out its output path.
+fn resolve_paths(template_path: &Path, output: Option<&str>) -> lkr_core::Result<PathBuf> {
+    if !template_path.exists() {
+        return Err(lkr_core::Error::template(format!(
+            "Template file not found: {}",
+            template_path.display()
+        )));
+    }
+
+    // Derive output path: .env.example → .env, foo.template → foo
+    match output {
+        Some(o) => Ok(PathBuf::from(o)),
+        None => {
+            let name = template_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            let derived = if name.ends_with(".example") {
+                name.trim_end_matches(".example").to_string()
+            } else if name.ends_with(".template") {
+                name.trim_end_matches(".template").to_string()
+            } else {
+                return Err(lkr_core::Error::template(format!(
+                    "Cannot derive output path for '{}'. Use -o to specify output file.",
+                    template_path.display()
+                )));
+            };
+            Ok(template_path
+                .parent()
+                .unwrap_or(Path::new("."))
+                .join(derived))
+        }
+    }
+}
+
+/// .gitignore check (skipped outside git repos)
+fn warn_if_not_gitignored(output_path: &Path) {
+    if let Some(false) = lkr_core::check_gitignore(output_path) {
+        eprintln!(
+            "Warning: '{}' is NOT in .gitignore. Generated files may contain secrets!",
+            output_path.display()
+        );
+        eprintln!("  Consider adding it to .gitignore before committing.");
+    }
+}
+
+/// `lkr gen --append`: add only the variables the output does not have yet.
+fn append_output(
+    store: &impl KeyStore,
+    template_path: &Path,
+    output_path: &Path,
+    policy: &TemplatePolicy,
+    json: bool,
+) -> lkr_core::Result<()> {
+    let result = lkr_core::generate_append(store, template_path, output_path, policy)?;
+    record_checksum(output_path, &result.output_checksum, template_path);
+
+    if json {
+        print_summary(
+            output_path,
+            &result.resolutions,
+            result.appended_count > 0,
+            None,
+        );
+        return Ok(());
+    }
+
+    if result.appended_count == 0 {
+        eprintln!("No new keys to append.");
+        return Ok(());
+    }
+
+    for r in &result.resolutions {
+        match (&r.key_name, &r.env_var) {
+            (Some(key_name), _) => eprintln!("    {:<24} <- {}", r.placeholder, key_name),
+            (None, Some(env_var)) => eprintln!("    {:<24} <- ${}", r.placeholder, env_var),
+            (None, None) => eprintln!("    {:<24} (no matching key, not appended)", r.placeholder),
+        }
+    }
+    eprintln!(
+        "\n  Appended {} line(s) to {}",
+        result.appended_count,
+        output_path.display()
+    );
+    Ok(())
+}
+
+/// `lkr gen --check`: fail if regenerating would change the output's structure.
+fn check_output(
+    store: &impl KeyStore,
+    template_path: &Path,
+    output_path: &Path,
+    policy: &TemplatePolicy,
+    json: bool,
+) -> lkr_core::Result<()> {
+    if !output_path.exists() {
+        return Err(lkr_core::Error::template(format!(
+            "'{}' does not exist. Run `lkr gen {}` to create it.",
+            output_path.display(),
+            template_path.display()
+        )));
+    }
+
+    let result = lkr_core::check(store, template_path, output_path, policy)?;
+    if json {
+        print_summary(
+            output_path,
+            &result.resolutions,
+            false,
+            Some(result.is_up_to_date()),
+        );
+    }
+    match result.mismatch_line {
+        None => {
+            if !json {
+                eprintln!("  Up to date: {}", output_path.display());
+            }
+            Ok(())
+        }
+        Some(line) => Err(lkr_core::Error::template(format!(
+            "'{}' is out of date (first difference at line {}). Run `lkr gen {}` to regenerate.",
+            output_path.display(),
+            line,
+            template_path.display()
+        ))),
+    }
+}
+
+/// Remember what was written so `lkr gen --verify` can spot later edits.
+/// Failing to record never fails the generation itself.
+pub(crate) fn record_checksum(output_path: &Path, checksum: &str, template_path: &Path) {
+    if checksum.is_empty() {
+        return;
+    }
+    let recorded = ChecksumStore::default_path().and_then(|path| {
+        ChecksumStore::new(path).record(output_path, checksum, Some(template_path))
+    });
+    if let Err(e) = recorded {
+        eprintln!(
+            "Warning: could not record checksum for '{}': {}",
+            output_path.display(),
+            e
+        );
+    }
+}
+
+/// `lkr gen --verify`: fail if the output changed since lkr last wrote it.
+fn verify_output(
+    checksums: &ChecksumStore,
+    template_path: &Path,
+    output_path: &Path,
+) -> lkr_core::Result<()> {
+    match checksums.verify(output_path)? {
+        Verification::Match(record) => {
+            eprintln!(
+                "  Unmodified: {} (generated {})",
+                output_path.display(),
+                record.generated_at.format("%Y-%m-%d %H:%M UTC")
+            );
+            Ok(())
+        }
+        Verification::Modified(_) => {
+            eprintln!(
+                "WARNING: {} may have been modified since it was generated by lkr.",
+                output_path.display()
+            );
+            Err(lkr_core::Error::template(format!(
+                "'{}' does not match its recorded checksum.",
+                output_path.display()
+            )))
+        }
+        Verification::Missing(_) => Err(lkr_core::Error::template(format!(
+            "'{}' does not exist. Run `lkr gen {}` to create it.",
+            output_path.display(),
+            template_path.display()
+        ))),
+        Verification::Unrecorded => Err(lkr_core::Error::template(format!(
+            "No checksum recorded for '{}'. Run `lkr gen {}` to generate it with lkr.",
+            output_path.display(),
+            template_path.display()
+        ))),
+    }
+}
+
+fn print_summary(
+    output_path: &Path,
+    resolutions: &[Resolution],
+    written: bool,
+    up_to_date: Option<bool>,
+) {
+    let summary = gen_summary(output_path, resolutions, written, up_to_date);
+    println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+}
+
+/// `--json` summary: placeholder, key and env var names only — never values.
+/// `up_to_date` is only present for `--check`.
+fn gen_summary(
+    output_path: &Path,
+    resolutions: &[Resolution],
+    written: bool,
+    up_to_date: Option<bool>,
+) -> serde_json::Value {
+    let resolved: Vec<_> = resolutions
+        .iter()
+        .filter_map(|r| {
+            let mut entry = match (&r.key_name, &r.env_var) {
+                (Some(key_name), _) => serde_json::json!({
+                    "placeholder": r.placeholder,
+                    "key_name": key_name,
+                }),
+                (None, Some(env_var)) => serde_json::json!({
+                    "placeholder": r.placeholder,
+                    "env_var": env_var,
+                }),
+                (None, None) => return None,
+            };
+            if !r.mcp_servers.is_empty() {
+                entry["mcp_servers"] = serde_json::json!(r.mcp_servers);
+            }
+            Some(entry)
+        })
+        .collect();
+    let unresolved: Vec<_> = resolutions
+        .iter()
+        .filter(|r| !r.is_resolved())
+        .map(|r| serde_json::json!({ "placeholder": r.placeholder }))
+        .collect();
+
+    let mut summary = serde_json::json!({
+        "output": output_path.display().to_string(),
+        "resolved": resolved,
+        "unresolved": unresolved,
+        "written": written,
+    });
+    if let Some(up_to_date) = up_to_date {
+        summary["up_to_date"] = serde_json::Value::Bool(up_to_date);
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_perms_from_flags() {
+        assert_eq!(
+            output_perms(None, None, false).unwrap(),
+            OutputPerms::default()
+        );
+        for mode in ["640", "0640", "0o640"] {
+            assert_eq!(output_perms(Some(mode), None, false).unwrap().mode(), 0o640);
+        }
+        assert!(output_perms(Some("rw-r-----"), None, false).is_err());
+        assert!(output_perms(Some("0644"), None, false).is_err());
+        assert_eq!(
+            output_perms(Some("0640"), Some("20"), false).unwrap().gid(),
+            Some(20)
+        );
+        assert!(output_perms(None, Some("no-such-group-lkr"), false).is_err());
+    }
+
+    #[test]
+    fn test_gen_summary_matches_resolutions() {
+        let resolutions = vec![
+            Resolution {
+                placeholder: "OPENAI_API_KEY".to_string(),
+                key_name: Some("openai:prod".to_string()),
+                alternatives: vec!["openai:prod".to_string(), "openai:dev".to_string()],
+                env_var: None,
+                mcp_servers: vec![],
+            },
+            Resolution {
+                placeholder: "GROQ_API_KEY".to_string(),
+                key_name: None,
+                alternatives: vec![],
+                env_var: None,
+                mcp_servers: vec![],
+            },
+            Resolution {
+                placeholder: "DATABASE_URL".to_string(),
+                key_name: None,
+                alternatives: vec![],
+                env_var: Some("DATABASE_URL".to_string()),
+                mcp_servers: vec![],
+            },
+        ];
+
+        let summary = gen_summary(Path::new(".env"), &resolutions, true, None);
+        assert_eq!(
+            summary,
+            serde_json::json!({
+                "output": ".env",
+                "resolved": [
+                    { "placeholder": "OPENAI_API_KEY", "key_name": "openai:prod" },
+                    { "placeholder": "DATABASE_URL", "env_var": "DATABASE_URL" },
+                ],
+                "unresolved": [{ "placeholder": "GROQ_API_KEY" }],
+                "written": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_stdin_json_request_renders() {
+        use lkr_core::KeyKind;
+        use lkr_core::keymanager::MockStore;
+
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-prod-1", KeyKind::Runtime, false)
+            .unwrap();
+        let input = r#"{
+            "template": "{\"openai\": \"{{lkr:openai:prod}}\", \"x\": \"{{lkr:custom:x}}\"}",
+            "format": "json",
+            "vars": {"custom:x": "y"}
+        }"#;
+
+        let request = parse_stdin_request(input).unwrap();
+        assert_eq!(request.format, Some(TemplateFormat::Json));
+        let policy = TemplatePolicy::default().with_vars(request.vars);
+        let result =
+            lkr_core::render_str(&store, &request.template, request.format, &policy).unwrap();
+        assert_eq!(result.content, r#"{"openai": "sk-prod-1", "x": "y"}"#);
+
+        assert!(parse_stdin_request(r#"{"format": "json"}"#).is_err());
+        assert!(parse_stdin_request(r#"{"template": "", "format": "yaml"}"#).is_err());
+        assert!(parse_stdin_request(r#"{"template": "", "vars": {"A": 1}}"#).is_err());
+    }
+
+    #[test]
+    fn test_gen_summary_check_mode() {
+        let summary = gen_summary(Path::new(".env"), &[], false, Some(false));
+        assert_eq!(summary["written"], false);
+        assert_eq!(summary["up_to_date"], false);
+    }
+
+    #[test]
+    fn test_gen_many_rejects_duplicate_outputs_before_writing() {
+        let dir = std::env::temp_dir().join(format!("lkr-gen-many-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let template = dir.join(".env.example");
+        std::fs::write(&template, "OPENAI_API_KEY=\n").unwrap();
+        let template = template.display().to_string();
+        let output = dir.join(".env").display().to_string();
+
+        let store = lkr_core::keymanager::MockStore::new();
+        let opts = GenOptions {
+            force: true,
+            ..Default::default()
+        };
+        let jobs = vec![
+            (template.clone(), Some(output.clone())),
+            (template, Some(output.clone())),
+        ];
+        let err = cmd_gen_many(&store, &jobs, &opts, true).unwrap_err();
+        assert!(err.to_string().contains("more than once"));
+        assert!(!Path::new(&output).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_verify_output_flags_modified_file() {
+        let dir = std::env::temp_dir().join(format!("lkr-gen-verify-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let checksums = ChecksumStore::new(dir.join("checksums.json"));
+        let template = dir.join(".env.example");
+        let output = dir.join(".env");
+
+        assert!(verify_output(&checksums, &template, &output).is_err());
+
+        std::fs::write(&output, "OPENAI_API_KEY=sk-test\n").unwrap();
+        checksums
+            .record(
+                &output,
+                &lkr_core::sha256_hex(b"OPENAI_API_KEY=sk-test\n"),
+                Some(&template),
+            )
+            .unwrap();
+        assert!(verify_output(&checksums, &template, &output).is_ok());
+
+        std::fs::write(&output, "OPENAI_API_KEY=sk-edited\n").unwrap();
+        let err = verify_output(&checksums, &template, &output).unwrap_err();
+        assert!(err.to_string().contains("recorded checksum"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_refresh_outputs_rewrites_only_on_change() {
+        let dir = std::env::temp_dir().join(format!("lkr-gen-watch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let template = dir.join(".env.example");
+        let output = dir.join(".env");
+        std::fs::write(&template, "OPENAI_API_KEY=\n").unwrap();
+        let paths = vec![(template.clone(), output.clone())];
+        let store = lkr_core::keymanager::MockStore::new();
+        let policy = TemplatePolicy::default();
+        let mut last = HashMap::new();
+
+        // First pass writes, an unchanged second pass does not
+        assert_eq!(
+            refresh_outputs(&store, &paths, &policy, &mut last)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(
+            refresh_outputs(&store, &paths, &policy, &mut last)
+                .unwrap()
+                .is_empty()
+        );
+
+        // Keychain change
+        store
+            .set("openai:prod", "sk-new", lkr_core::KeyKind::Runtime, false)
+            .unwrap();
+        assert_eq!(
+            refresh_outputs(&store, &paths, &policy, &mut last)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            std::fs::read_to_string(&output).unwrap(),
+            "OPENAI_API_KEY=sk-new\n"
+        );
+
+        // Template change
+        std::fs::write(&template, "OPENAI_API_KEY=\nDEBUG=1\n").unwrap();
+        assert_eq!(
+            refresh_outputs(&store, &paths, &policy, &mut last)
+                .unwrap()
+                .len(),
+            1
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_gen_many_rejects_check() {
+        let store = lkr_core::keymanager::MockStore::new();
+        let opts = GenOptions {
+            check: true,
+            ..Default::default()
+        };
+        let jobs = vec![
+            ("a.example".to_string(), None),
+            ("b.example".to_string(), None),
+        ];
+        assert!(cmd_gen_many(&store, &jobs, &opts, true).is_err());
+    }
+}
@@ -1,16 +1,69 @@
-use crate::util::schedule_clipboard_clear;
-use lkr_core::{KeyStore, mask_value};
-use std::io::{self, Write};
+use crate::clipboard::{Clipboard, Selection, SystemClipboard, copy_with_auto_clear};
+use lkr_core::{KeyKind, KeyStore, Zeroizing, mask_value};
+use std::io::{self, IsTerminal, Write};
+use std::time::Duration;
+
+/// How often `--wait` re-checks the Keychain.
+const WAIT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Flags for `lkr get`, bundled so new options don't grow the signature.
+#[derive(Default)]
+pub(crate) struct GetOptions {
+    pub show: bool,
+    pub plain: bool,
+    pub force_plain: bool,
+    pub json: bool,
+    /// Seconds until the clipboard is auto-cleared (None = never).
+    /// Resolved from `--clear-after` / `--no-clear` / `LKR_CLIPBOARD_CLEAR`.
+    pub clear_after: Option<u32>,
+    /// Target selection (primary is X11/Wayland only)
+    pub selection: Selection,
+    /// Record the access in the key's metadata (opt-in read-modify-write)
+    pub count_access: bool,
+    /// Fail (exit 1) if the clipboard copy did not happen
+    pub require_clipboard: bool,
+    /// `--wait`: block while the Keychain is locked before reading
+    pub wait: bool,
+    /// Give up waiting after this long (None = until Ctrl+C)
+    pub wait_timeout: Option<Duration>,
+    /// `--format env`: print `VAR=value` (reveals the value, like `--plain`)
+    pub env_format: bool,
+    /// `--to-env-name`: variable name for the env line instead of the derived one
+    pub to_env_name: Option<String>,
+}
 
 pub(crate) fn cmd_get(
     store: &impl KeyStore,
     name: &str,
-    show: bool,
-    plain: bool,
-    force_plain: bool,
-    json: bool,
+    opts: &GetOptions,
     stdout_is_tty: bool,
 ) -> lkr_core::Result<()> {
+    let GetOptions {
+        show,
+        plain,
+        force_plain,
+        json,
+        clear_after,
+        selection,
+        count_access,
+        require_clipboard,
+        wait,
+        wait_timeout,
+        env_format,
+        ref to_env_name,
+    } = *opts;
+    // Validate before the value is read
+    let env_var = if env_format || to_env_name.is_some() {
+        if json {
+            return Err(lkr_core::Error::Usage(
+                "--format env cannot be combined with --json.".to_string(),
+            ));
+        }
+        Some(env_var_for(name, to_env_name.as_deref())?)
+    } else {
+        None
+    };
+
     // v0.2.0 TTY guard: comprehensive non-interactive protection.
     // Prevents AI agent / prompt-injection key exfiltration via pipe.
     //
@@ -38,8 +91,31 @@ pub(crate) fn cmd_get(
         eprintln!("Warning: outputting raw key value in non-interactive environment.");
     }
 
+    if wait {
+        let probe = wait_until_unlocked(
+            || store.exists(name),
+            wait_timeout,
+            io::stderr().is_terminal(),
+            std::thread::sleep,
+        );
+        // Found, missing or another error: the read below reports it
+        if let Err(e @ lkr_core::Error::KeychainLocked) = probe {
+            return Err(e);
+        }
+    }
+
     let (value, kind) = store.get(name)?;
 
+    // Only on request: a plain get stays read-only
+    if count_access && let Err(e) = store.touch(name) {
+        eprintln!("Warning: could not record access for {}: {}", name, e);
+    }
+
+    if let Some(var) = env_var {
+        println!("{}", *env_line(&var, &value));
+        return Ok(());
+    }
+
     if plain || force_plain {
         // Raw value only, no newline — for piping
         print!("{}", *value);
@@ -47,38 +123,18 @@ pub(crate) fn cmd_get(
         return Ok(());
     }
 
-    // Copy to clipboard with 30s auto-clear.
+    // Copy to clipboard with auto-clear (30s unless overridden).
     // Security: skip clipboard in non-interactive environments to prevent
     // agent bypass via `lkr get key && pbpaste`.
     let clipboard_ok = if !stdout_is_tty {
         eprintln!("Clipboard copy skipped (non-interactive environment).");
         false
     } else {
-        match arboard::Clipboard::new().and_then(|mut cb| cb.set_text(&*value)) {
-            Ok(()) => {
-                schedule_clipboard_clear(30);
-                eprintln!("Copied to clipboard (auto-clears in 30s)");
-                true
-            }
-            Err(e) => {
-                eprintln!("Warning: clipboard unavailable ({})", e);
-                false
-            }
-        }
+        copy_to_clipboard(&mut SystemClipboard, selection, &value, clear_after)
     };
 
     if json {
-        let display_value = if show {
-            (*value).clone()
-        } else {
-            mask_value(&value)
-        };
-        let obj = serde_json::json!({
-            "name": name,
-            "kind": kind.to_string(),
-            "value": display_value,
-            "clipboard": clipboard_ok,
-        });
+        let obj = json_object(name, kind, &value, show, clipboard_ok);
         println!("{}", serde_json::to_string_pretty(&obj).unwrap());
     } else if show {
         println!("{}", *value);
@@ -86,5 +142,318 @@ pub(crate) fn cmd_get(
         println!("  {}  ({})", mask_value(&value), kind);
     }
 
+    check_clipboard_copied(name, clipboard_ok, require_clipboard)
+}
+
+/// Variable name for `--format env`: `to_env_name` if given (and a valid
+/// shell identifier), otherwise the name `lkr gen` and `lkr exec` derive.
+fn env_var_for(name: &str, to_env_name: Option<&str>) -> lkr_core::Result<String> {
+    let Some(var) = to_env_name else {
+        return Ok(lkr_core::key_to_env_var(name));
+    };
+    let mut chars = var.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(lkr_core::Error::Usage(format!(
+            "--to-env-name '{}' is not a valid variable name (letters, digits and _, not starting with a digit).",
+            var
+        )));
+    }
+    Ok(var.to_string())
+}
+
+/// `VAR='value'` for `--format env`, single-quoted so `eval "$(lkr get …)"`
+/// assigns the value as is, whatever characters it holds.
+fn env_line(var: &str, value: &str) -> Zeroizing<String> {
+    let escaped = Zeroizing::new(value.replace('\'', "'\\''"));
+    Zeroizing::new(format!("{}='{}'", var, *escaped))
+}
+
+/// `--wait`: re-run `probe` every [`WAIT_INTERVAL`] while it fails with
+/// `KeychainLocked`, and return its first other result. Running out of
+/// `timeout` returns `KeychainLocked`. Used both for unlocking the keychain
+/// and before `lkr get` reads.
+pub(crate) fn wait_until_unlocked<T>(
+    mut probe: impl FnMut() -> lkr_core::Result<T>,
+    timeout: Option<Duration>,
+    spinner: bool,
+    mut sleep: impl FnMut(Duration),
+) -> lkr_core::Result<T> {
+    const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+    let mut waited = Duration::ZERO;
+    let mut tick = 0;
+    loop {
+        let result = probe();
+        let locked = matches!(result, Err(lkr_core::Error::KeychainLocked));
+        if !locked || timeout.is_some_and(|t| waited >= t) {
+            if tick > 0 && spinner {
+                eprint!("\r\x1b[2K");
+            }
+            return result;
+        }
+        if spinner {
+            eprint!(
+                "\r\x1b[2K{} Waiting for Keychain to be unlocked...",
+                FRAMES[tick % FRAMES.len()]
+            );
+        } else if tick == 0 {
+            eprintln!("Waiting for Keychain to be unlocked...");
+        }
+        tick += 1;
+        sleep(WAIT_INTERVAL);
+        waited += WAIT_INTERVAL;
+    }
+}
+
+/// `lkr get --json`: `value` is masked unless `show`; `masked` and
+/// `fingerprint` never reveal the key, so tooling can compare keys across
+/// machines without `--show`.
+fn json_object(
+    name: &str,
+    kind: KeyKind,
+    value: &str,
+    show: bool,
+    clipboard: bool,
+) -> serde_json::Value {
+    let masked = mask_value(value);
+    serde_json::json!({
+        "name": name,
+        "kind": kind.to_string(),
+        "value": if show { value.to_string() } else { masked.clone() },
+        "masked": masked,
+        "fingerprint": lkr_core::fingerprint(value),
+        "clipboard": clipboard,
+    })
+}
+
+/// Copy with auto-clear, reporting the outcome on stderr. Returns whether
+/// the value reached the clipboard.
+fn copy_to_clipboard(
+    clipboard: &mut impl Clipboard,
+    selection: Selection,
+    value: &str,
+    clear_after: Option<u32>,
+) -> bool {
+    match copy_with_auto_clear(clipboard, selection, value, clear_after) {
+        Ok(()) => {
+            let target = match selection {
+                Selection::Clipboard => "clipboard",
+                Selection::Primary => "primary selection",
+            };
+            match clear_after {
+                Some(secs) => eprintln!("Copied to {} (auto-clears in {}s)", target, secs),
+                None => eprintln!("Copied to {} (auto-clear disabled)", target),
+            }
+            true
+        }
+        Err(e) => {
+            eprintln!("Warning: clipboard unavailable ({})", e);
+            false
+        }
+    }
+}
+
+/// `--require-clipboard`: a failed or skipped copy becomes an error (exit 1)
+/// instead of a warning.
+fn check_clipboard_copied(name: &str, copied: bool, required: bool) -> lkr_core::Result<()> {
+    if required && !copied {
+        return Err(lkr_core::Error::Usage(format!(
+            "{} was not copied to the clipboard (--require-clipboard).",
+            name
+        )));
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct BrokenClipboard;
+
+    impl Clipboard for BrokenClipboard {
+        fn set_text(&mut self, _selection: Selection, _text: &str) -> Result<(), String> {
+            Err("no display".to_string())
+        }
+
+        fn schedule_clear(&mut self, _selection: Selection, _seconds: u32) {}
+    }
+
+    #[test]
+    fn test_wait_until_unlocked_polls_while_locked() {
+        let mut calls = 0;
+        let mut slept = Vec::new();
+        let probe = || {
+            calls += 1;
+            if calls < 3 {
+                Err(lkr_core::Error::KeychainLocked)
+            } else {
+                Ok(true)
+            }
+        };
+        wait_until_unlocked(probe, None, false, |d| slept.push(d)).unwrap();
+        assert_eq!(calls, 3);
+        assert_eq!(slept, [WAIT_INTERVAL, WAIT_INTERVAL]);
+    }
+
+    #[test]
+    fn test_wait_until_unlocked_times_out() {
+        let mut calls = 0;
+        let probe = || {
+            calls += 1;
+            Err::<bool, _>(lkr_core::Error::KeychainLocked)
+        };
+        let err = wait_until_unlocked(probe, Some(Duration::from_secs(5)), false, |_| {});
+        assert!(matches!(err, Err(lkr_core::Error::KeychainLocked)));
+        // Checks at 0s, 2s, 4s and 6s
+        assert_eq!(calls, 4);
+    }
+
+    #[test]
+    fn test_wait_until_unlocked_stops_on_other_results() {
+        let mut slept = false;
+        wait_until_unlocked(|| Ok(false), Some(Duration::ZERO), false, |_| slept = true).unwrap();
+        let result = wait_until_unlocked(
+            || Err::<bool, _>(lkr_core::Error::KeyNotFound { name: "x:y".into() }),
+            None,
+            false,
+            |_| slept = true,
+        );
+        assert!(matches!(result, Err(lkr_core::Error::KeyNotFound { .. })));
+        assert!(!slept);
+    }
+
+    #[test]
+    fn test_wait_polls_a_locked_store_until_unlocked() {
+        let store = lkr_core::keymanager::MockStore::new();
+        store
+            .set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        store.set_locked(true);
+        assert!(matches!(
+            store.get("openai:prod"),
+            Err(lkr_core::Error::KeychainLocked)
+        ));
+
+        let mut sleeps = 0;
+        let found = wait_until_unlocked(
+            || store.exists("openai:prod"),
+            None,
+            false,
+            |_| {
+                sleeps += 1;
+                if sleeps == 2 {
+                    store.set_locked(false);
+                }
+            },
+        )
+        .unwrap();
+        assert!(found);
+        assert_eq!(sleeps, 2);
+    }
+
+    #[test]
+    fn test_get_wait_gives_up_on_a_locked_store() {
+        let store = lkr_core::keymanager::MockStore::new();
+        store
+            .set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        store.set_locked(true);
+        let opts = GetOptions {
+            wait: true,
+            wait_timeout: Some(Duration::ZERO),
+            json: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            cmd_get(&store, "openai:prod", &opts, true),
+            Err(lkr_core::Error::KeychainLocked)
+        ));
+    }
+
+    #[test]
+    fn test_to_env_name_overrides_derived_name() {
+        assert_eq!(
+            env_var_for("openai:prod", None).unwrap(),
+            lkr_core::key_to_env_var("openai:prod")
+        );
+        assert_eq!(
+            env_var_for("openai:prod", Some("LLM_TOKEN")).unwrap(),
+            "LLM_TOKEN"
+        );
+        for bad in ["1TOKEN", "LLM-TOKEN", "", "A B"] {
+            assert!(env_var_for("openai:prod", Some(bad)).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_env_line_survives_eval() {
+        for value in [
+            "sk-plain",
+            "a b",
+            "$HOME",
+            "it's",
+            "x; echo pwned",
+            "`id`",
+            "\"q\"",
+        ] {
+            let line = env_line("LLM_TOKEN", value);
+            let out = std::process::Command::new("sh")
+                .args(["-c", "eval \"$1\"; printf %s \"$LLM_TOKEN\"", "sh", &line])
+                .output()
+                .unwrap();
+            assert_eq!(String::from_utf8(out.stdout).unwrap(), value, "{}", *line);
+        }
+        assert_eq!(*env_line("A", "it's"), "A='it'\\''s'");
+    }
+
+    #[test]
+    fn test_env_format_is_guarded_like_plain() {
+        let store = lkr_core::keymanager::MockStore::new();
+        store
+            .set("openai:prod", "sk-secret", KeyKind::Runtime, false)
+            .unwrap();
+        let opts = GetOptions {
+            env_format: true,
+            to_env_name: Some("LLM_TOKEN".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            cmd_get(&store, "openai:prod", &opts, false),
+            Err(lkr_core::Error::TtyGuard { .. })
+        ));
+        let opts = GetOptions { json: true, ..opts };
+        assert!(matches!(
+            cmd_get(&store, "openai:prod", &opts, true),
+            Err(lkr_core::Error::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_json_object_masks_value_by_default() {
+        let value = "sk-proj-secret-value-1234";
+        let obj = json_object("openai:prod", KeyKind::Runtime, value, false, false);
+        assert_eq!(obj["kind"], "runtime");
+        assert_eq!(obj["value"], mask_value(value));
+        assert_eq!(obj["masked"], mask_value(value));
+        assert_eq!(obj["fingerprint"], lkr_core::fingerprint(value));
+        assert!(!obj.to_string().contains(value));
+
+        let shown = json_object("openai:prod", KeyKind::Runtime, value, true, false);
+        assert_eq!(shown["value"], value);
+        assert_eq!(shown["masked"], mask_value(value));
+    }
+
+    #[test]
+    fn test_require_clipboard_fails_when_copy_fails() {
+        let copied = copy_to_clipboard(&mut BrokenClipboard, Selection::Clipboard, "sk", None);
+        assert!(!copied);
+        assert!(check_clipboard_copied("openai:prod", copied, true).is_err());
+        // Without the flag a failed copy stays a warning
+        assert!(check_clipboard_copied("openai:prod", copied, false).is_ok());
+        assert!(check_clipboard_copied("openai:prod", true, true).is_ok());
+    }
+}
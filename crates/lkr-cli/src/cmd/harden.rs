@@ -1,5 +1,30 @@
 use lkr_core::error::Error;
-use lkr_core::{KeyStore, KeychainStore};
+use lkr_core::{KeyStore, KeychainStore, StoredEntry};
+
+/// The reads and writes a re-ACL needs: whole entries, so no metadata is
+/// lost and expired keys are rewritten like any other.
+pub(crate) trait Reacl {
+    fn read_entry(&self, name: &str) -> lkr_core::Result<StoredEntry>;
+    fn rewrite_entry(&self, name: &str, entry: &StoredEntry) -> lkr_core::Result<()>;
+}
+
+impl Reacl for KeychainStore {
+    fn read_entry(&self, name: &str) -> lkr_core::Result<StoredEntry> {
+        self.get_entry_interactive(name)
+    }
+
+    fn rewrite_entry(&self, name: &str, entry: &StoredEntry) -> lkr_core::Result<()> {
+        self.set_entry_interactive(name, entry, true)
+    }
+}
+
+/// What happened to one key.
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Hardened,
+    Skipped,
+    Failed,
+}
 
 /// Re-apply ACL to all keys (after binary update/reinstall).
 pub(crate) fn cmd_harden(store: &KeychainStore, dry_run: bool) -> lkr_core::Result<()> {
@@ -13,6 +38,7 @@ pub(crate) fn cmd_harden(store: &KeychainStore, dry_run: bool) -> lkr_core::Resu
     }
 
     let total = entries.len();
+    warn_nonstandard_admin_labels(&entries);
 
     if dry_run {
         eprintln!("  Would re-apply ACL to {} key(s):", total);
@@ -39,47 +65,11 @@ pub(crate) fn cmd_harden(store: &KeychainStore, dry_run: bool) -> lkr_core::Resu
     let mut fail_count: usize = 0;
 
     for (i, entry) in entries.iter().enumerate() {
-        let idx = i + 1;
-        eprint!("  [{}/{}] {} — ", idx, total, entry.name);
-
-        // Step 1: Read current value via interactive dialog
-        let (value, kind) = match store.get_interactive(&entry.name) {
-            Ok(v) => v,
-            Err(Error::UserCanceled) => {
-                eprintln!("skipped (denied)");
-                skip_count += 1;
-                continue;
-            }
-            Err(Error::InteractionNotAllowed) => {
-                eprintln!("FAILED");
-                eprintln!("         `lkr harden` requires a GUI environment (macOS desktop).");
-                eprintln!("         It cannot run over SSH, in CI, or as a launchd service.");
-                fail_count += 1;
-                continue;
-            }
-            Err(e) => {
-                eprintln!("FAILED to read: {}", e);
-                fail_count += 1;
-                continue;
-            }
-        };
-
-        // Step 2: Re-create with fresh ACL (interactive: allows macOS dialog
-        // for delete/set when ACL cdhash no longer matches this binary)
-        match store.set_interactive(&entry.name, &value, kind, true) {
-            Ok(()) => {
-                eprintln!("hardened");
-                success_count += 1;
-            }
-            Err(e) => {
-                eprintln!("FAILED to re-create: {}", e);
-                eprintln!("         ⚠ The key may have been deleted during this operation.");
-                eprintln!(
-                    "         Recovery: `lkr set {} --kind {}` to re-register.",
-                    entry.name, kind
-                );
-                fail_count += 1;
-            }
+        eprint!("  [{}/{}] {} — ", i + 1, total, entry.name);
+        match harden_key(store, &entry.name) {
+            Outcome::Hardened => success_count += 1,
+            Outcome::Skipped => skip_count += 1,
+            Outcome::Failed => fail_count += 1,
         }
     }
 
@@ -108,3 +98,135 @@ pub(crate) fn cmd_harden(store: &KeychainStore, dry_run: bool) -> lkr_core::Resu
 
     Ok(())
 }
+
+/// Interactive read, then delete and re-create with a fresh ACL (the
+/// interactive write allows macOS dialogs for delete/set when the ACL
+/// cdhash no longer matches this binary). Finishes the progress line.
+fn harden_key(store: &impl Reacl, name: &str) -> Outcome {
+    // Step 1: Read the current entry via interactive dialog
+    let entry = match store.read_entry(name) {
+        Ok(entry) => entry,
+        Err(Error::UserCanceled) => {
+            eprintln!("skipped (denied)");
+            return Outcome::Skipped;
+        }
+        Err(Error::InteractionNotAllowed) => {
+            eprintln!("FAILED");
+            eprintln!("         `lkr harden` requires a GUI environment (macOS desktop).");
+            eprintln!("         It cannot run over SSH, in CI, or as a launchd service.");
+            return Outcome::Failed;
+        }
+        Err(e) => {
+            eprintln!("FAILED to read: {}", e);
+            return Outcome::Failed;
+        }
+    };
+
+    // Step 2: Re-create it, metadata and all
+    match store.rewrite_entry(name, &entry) {
+        Ok(()) => {
+            eprintln!("hardened");
+            Outcome::Hardened
+        }
+        Err(e) => {
+            eprintln!("FAILED to re-create: {}", e);
+            eprintln!("         ⚠ The key may have been deleted during this operation.");
+            eprintln!(
+                "         Recovery: `lkr set {} --kind {}` to re-register.",
+                name, entry.kind
+            );
+            Outcome::Failed
+        }
+    }
+}
+
+/// Admin keys not labelled `admin` are invisible to `lkr usage`.
+fn warn_nonstandard_admin_labels(entries: &[lkr_core::KeyEntry]) {
+    let names: Vec<&str> = entries
+        .iter()
+        .filter(|e| e.has_nonstandard_admin_label())
+        .map(|e| e.name.as_str())
+        .collect();
+    if names.is_empty() {
+        return;
+    }
+    eprintln!(
+        "  ⚠ Admin key(s) with a non-standard label: {}",
+        names.join(", ")
+    );
+    eprintln!("    {}", lkr_core::ADMIN_LABEL_WARNING);
+    eprintln!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::keymanager::MockStore;
+    use lkr_core::{KeyKind, MetadataUpdate};
+
+    impl Reacl for MockStore {
+        fn read_entry(&self, name: &str) -> lkr_core::Result<StoredEntry> {
+            self.get_with_metadata(name)
+        }
+
+        fn rewrite_entry(&self, name: &str, entry: &StoredEntry) -> lkr_core::Result<()> {
+            self.delete(name)?;
+            self.set_entry(name, entry, false)
+        }
+    }
+
+    #[test]
+    fn test_harden_keeps_expiry_even_when_expired() {
+        let store = MockStore::new();
+        let past = chrono::Utc::now() - chrono::Duration::days(1);
+        let future = chrono::Utc::now() + chrono::Duration::days(30);
+        for (name, expires_at) in [("openai:old", past), ("openai:prod", future)] {
+            store
+                .set_with(
+                    name,
+                    "sk-test-1",
+                    KeyKind::Runtime,
+                    false,
+                    &[MetadataUpdate::Expiry(Some(expires_at))],
+                )
+                .unwrap();
+            assert_eq!(harden_key(&store, name), Outcome::Hardened);
+            let entry = store.get_with_metadata(name).unwrap();
+            assert_eq!(entry.expires_at, Some(expires_at));
+        }
+    }
+
+    #[test]
+    fn test_harden_keeps_all_metadata() {
+        let store = MockStore::new();
+        for value in ["sk-test-1", "sk-test-2"] {
+            store
+                .set_with(
+                    "openai:prod",
+                    value,
+                    KeyKind::Runtime,
+                    true,
+                    &[
+                        MetadataUpdate::Description(Some("CI deploys".to_string())),
+                        MetadataUpdate::Endpoint(Some("https://proxy.example".to_string())),
+                        MetadataUpdate::Source(lkr_core::SOURCE_IMPORT.to_string()),
+                        MetadataUpdate::Tags {
+                            add: vec!["ci".to_string()],
+                            remove: vec![],
+                        },
+                    ],
+                )
+                .unwrap();
+        }
+        store.touch("openai:prod").unwrap();
+        let before = store.get_with_metadata("openai:prod").unwrap();
+        assert!(before.last_rotated.is_some() && before.access_count == 1);
+
+        assert_eq!(harden_key(&store, "openai:prod"), Outcome::Hardened);
+        let after = store.get_with_metadata("openai:prod").unwrap();
+        assert_eq!(
+            serde_json::to_value(&after).unwrap(),
+            serde_json::to_value(&before).unwrap()
+        );
+    }
+}
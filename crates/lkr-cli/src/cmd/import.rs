@@ -0,0 +1,397 @@
+use crate::prompt::confirm;
+use lkr_core::{ImportEntry, ImportMode, ImportReport, KeyKind, KeyStore, MetadataUpdate};
+use std::io::{Read, Write};
+use zeroize::Zeroizing;
+
+/// `lkr import FILE`: bulk-import keys from a JSON array of
+/// `{"name", "value", "kind"}` objects (`-` reads stdin).
+pub(crate) fn cmd_import(
+    store: &impl KeyStore,
+    file: &str,
+    mode_str: &str,
+    json: bool,
+) -> lkr_core::Result<()> {
+    let mode: ImportMode = mode_str.parse().map_err(lkr_core::Error::Usage)?;
+
+    let content = read_input(file)?;
+    import_entries(store, parse_entries(&content)?, mode, json)
+}
+
+/// `lkr import --from-lkr PATH`: import a file written by `lkr export`.
+pub(crate) fn cmd_import_from_lkr(
+    store: &impl KeyStore,
+    path: &str,
+    mode_str: &str,
+    passphrase_stdin: bool,
+    json: bool,
+) -> lkr_core::Result<()> {
+    let mode: ImportMode = mode_str.parse().map_err(lkr_core::Error::Usage)?;
+    if passphrase_stdin && path == "-" {
+        return Err(lkr_core::Error::Usage(
+            "--passphrase-stdin needs a file path; stdin is taken by the passphrase".to_string(),
+        ));
+    }
+
+    let content = read_input(path)?;
+    if !lkr_core::crypto::is_encrypted(&content) {
+        return Err(lkr_core::Error::Usage(format!(
+            "'{}' is not an encrypted lkr export; use `lkr import {}` for plain JSON",
+            path, path
+        )));
+    }
+    let passphrase = if passphrase_stdin {
+        crate::passphrase::read_stdin()?
+    } else {
+        crate::passphrase::read()?
+    };
+    let plaintext = lkr_core::crypto::decrypt(&content, &passphrase)?;
+    import_entries(store, parse_entries(&plaintext)?, mode, json)
+}
+
+fn import_entries(
+    store: &impl KeyStore,
+    mut entries: Vec<ImportEntry>,
+    mode: ImportMode,
+    json: bool,
+) -> lkr_core::Result<()> {
+    for entry in &mut entries {
+        entry.source = Some(lkr_core::SOURCE_IMPORT.to_string());
+    }
+    if entries.is_empty() {
+        eprintln!("Nothing to import.");
+        return Ok(());
+    }
+
+    let report = store.import_batch(&entries, mode)?;
+    print_report(&report, mode, json);
+    Ok(())
+}
+
+/// `lkr import --names-only MANIFEST`: prompt for the value of each key in
+/// a manifest written by `lkr export --names-only`, storing it under the
+/// manifest's name and kind. An empty answer skips the key.
+pub(crate) fn cmd_import_names(
+    store: &impl KeyStore,
+    file: &str,
+    skip_existing: bool,
+) -> lkr_core::Result<()> {
+    let content = read_input(file)?;
+    let manifest = parse_manifest(&content)?;
+    import_names(store, &manifest, skip_existing, |name, kind| {
+        eprint!("Enter API key for {} ({}, empty to skip): ", name, kind);
+        std::io::stderr().flush().ok();
+        rpassword::read_password()
+            .map(Zeroizing::new)
+            .map_err(|e| lkr_core::Error::keychain(format!("Failed to read input: {}", e)))
+    })
+}
+
+/// Prompt (via `read_value`) and store each manifest entry. Existing keys
+/// are checked up front, so a conflict fails before any prompt.
+fn import_names(
+    store: &impl KeyStore,
+    manifest: &[(String, KeyKind)],
+    skip_existing: bool,
+    mut read_value: impl FnMut(&str, KeyKind) -> lkr_core::Result<Zeroizing<String>>,
+) -> lkr_core::Result<()> {
+    let mut existing = Vec::new();
+    for (name, _) in manifest {
+        if store.exists(name)? {
+            existing.push(name.as_str());
+        }
+    }
+    if !existing.is_empty() && !skip_existing {
+        return Err(lkr_core::Error::Usage(format!(
+            "{} key(s) already exist: {}. Re-run with --skip-existing to set up the rest.",
+            existing.len(),
+            existing.join(", ")
+        )));
+    }
+
+    let (mut stored, mut skipped) = (0, 0);
+    for (name, kind) in manifest {
+        if existing.contains(&name.as_str()) {
+            eprintln!("  Skipped {} (already exists)", name);
+            skipped += 1;
+            continue;
+        }
+        let value = read_value(name, *kind)?;
+        if value.trim().is_empty() {
+            eprintln!("  Skipped {} (no value entered)", name);
+            skipped += 1;
+            continue;
+        }
+        store.set_with(
+            name,
+            value.trim(),
+            *kind,
+            false,
+            &[MetadataUpdate::Source(
+                lkr_core::SOURCE_INTERACTIVE.to_string(),
+            )],
+        )?;
+        stored += 1;
+    }
+    eprintln!("Stored: {}, Skipped: {}", stored, skipped);
+    Ok(())
+}
+
+/// `(name, kind)` for each `{name, kind, provider}` record of a names-only
+/// manifest. A missing or null kind (an ACL-blocked key at export time)
+/// means runtime.
+fn parse_manifest(content: &[u8]) -> lkr_core::Result<Vec<(String, KeyKind)>> {
+    let invalid = |reason: String| lkr_core::Error::Usage(format!("Invalid manifest: {}", reason));
+    let records: Vec<serde_json::Value> =
+        serde_json::from_slice(content).map_err(|e| invalid(e.to_string()))?;
+    records
+        .iter()
+        .map(|record| {
+            let name = record["name"]
+                .as_str()
+                .ok_or_else(|| invalid("every entry needs a \"name\"".to_string()))?;
+            let kind = match record["kind"].as_str() {
+                Some(kind) => kind.parse().map_err(invalid)?,
+                None => KeyKind::Runtime,
+            };
+            Ok((name.to_string(), kind))
+        })
+        .collect()
+}
+
+/// Label used by `lkr import --from-env` when `--label` is not given.
+const FROM_ENV_LABEL: &str = "imported";
+
+/// `lkr import --from-env`: store each known provider env var that is set
+/// (e.g. `OPENAI_API_KEY`) as `<provider>:<label>`, confirming per key
+/// unless `yes`.
+pub(crate) fn cmd_import_from_env(
+    store: &impl KeyStore,
+    label: Option<&str>,
+    mode_str: &str,
+    yes: bool,
+    json: bool,
+) -> lkr_core::Result<()> {
+    let mode: ImportMode = mode_str.parse().map_err(lkr_core::Error::Usage)?;
+    let label = label.unwrap_or(FROM_ENV_LABEL);
+
+    let found = env_entries(|var| std::env::var(var).ok().map(Zeroizing::new), label);
+    if found.is_empty() {
+        eprintln!("No known provider env vars are set.");
+        return Ok(());
+    }
+
+    let entries: Vec<ImportEntry> = found
+        .into_iter()
+        .filter(|(var, entry)| {
+            yes || confirm(&format!(
+                "Import {} ({}) as {}? [y/N] ",
+                var,
+                lkr_core::mask_value(&entry.value),
+                entry.name
+            ))
+        })
+        .map(|(_, entry)| entry)
+        .collect();
+    if entries.is_empty() {
+        eprintln!("Nothing to import.");
+        return Ok(());
+    }
+
+    let report = store.import_batch(&entries, mode)?;
+    print_report(&report, mode, json);
+    Ok(())
+}
+
+/// Known provider env vars that `lookup` finds set and non-empty, paired
+/// with the entry each would be imported as.
+fn env_entries(
+    lookup: impl Fn(&str) -> Option<Zeroizing<String>>,
+    label: &str,
+) -> Vec<(&'static str, ImportEntry)> {
+    lkr_core::known_env_vars()
+        .iter()
+        .filter_map(|&(var, provider)| {
+            let value = lookup(var).filter(|v| !v.is_empty())?;
+            Some((
+                var,
+                ImportEntry {
+                    name: format!("{}:{}", provider, label),
+                    value: value.to_string(),
+                    kind: KeyKind::Runtime,
+                    source: Some(lkr_core::SOURCE_ENV.to_string()),
+                },
+            ))
+        })
+        .collect()
+}
+
+fn print_report(report: &ImportReport, mode: ImportMode, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report).unwrap());
+        return;
+    }
+
+    if mode == ImportMode::Skip {
+        for name in &report.skipped {
+            eprintln!("  Skipped {} (already exists)", name);
+        }
+    }
+    for change in &report.kind_changes {
+        eprintln!(
+            "  {} kind changed: {} -> {}",
+            change.name,
+            change.from.map_or("?".to_string(), |k| k.to_string()),
+            change.to
+        );
+    }
+    eprintln!(
+        "Imported: {}, Skipped: {}, Replaced: {}",
+        report.imported.len(),
+        report.skipped.len(),
+        report.replaced.len()
+    );
+}
+
+/// Read the whole import file (or stdin for `-`) into zeroizing memory.
+pub(crate) fn read_input(file: &str) -> lkr_core::Result<Zeroizing<Vec<u8>>> {
+    let mut content = Zeroizing::new(Vec::new());
+    let read = if file == "-" {
+        std::io::stdin().read_to_end(&mut content)
+    } else {
+        std::fs::File::open(file).and_then(|mut f| f.read_to_end(&mut content))
+    };
+    read.map_err(|e| lkr_core::Error::Usage(format!("Cannot read '{}': {}", file, e)))?;
+    Ok(content)
+}
+
+pub(crate) fn parse_entries(content: &[u8]) -> lkr_core::Result<Vec<ImportEntry>> {
+    serde_json::from_slice(content).map_err(|e| {
+        lkr_core::Error::Usage(format!(
+            "Invalid import file: {}",
+            lkr_core::json_error_summary(&e)
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::keymanager::MockStore;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_entries() {
+        let entries = parse_entries(
+            r#"[
+                {"name": "openai:prod", "value": "sk-1", "kind": "runtime"},
+                {"name": "openai:admin", "value": "sk-admin-1", "kind": "admin"}
+            ]"#
+            .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].kind, KeyKind::Admin);
+    }
+
+    #[test]
+    fn test_env_entries_imports_known_vars() {
+        let env = HashMap::from([
+            ("OPENAI_API_KEY", "sk-proj-env-1234"),
+            ("ANTHROPIC_API_KEY", "sk-ant-env-5678"),
+            ("GROQ_API_KEY", ""),
+            ("UNRELATED_TOKEN", "x"),
+        ]);
+        let lookup = |var: &str| env.get(var).map(|v| Zeroizing::new(v.to_string()));
+
+        let found = env_entries(lookup, FROM_ENV_LABEL);
+        let names: Vec<_> = found.iter().map(|(_, e)| e.name.as_str()).collect();
+        assert_eq!(names, ["openai:imported", "anthropic:imported"]);
+
+        let entries: Vec<ImportEntry> = found.into_iter().map(|(_, e)| e).collect();
+        let store = MockStore::new();
+        store.import_batch(&entries, ImportMode::Merge).unwrap();
+        assert_eq!(*store.get("openai:imported").unwrap().0, "sk-proj-env-1234");
+        let stored = store.list(true).unwrap();
+        assert!(
+            stored
+                .iter()
+                .all(|e| e.source.as_deref() == Some(lkr_core::SOURCE_ENV))
+        );
+    }
+
+    #[test]
+    fn test_env_entries_uses_label() {
+        let lookup = |var: &str| (var == "OPENAI_API_KEY").then(|| Zeroizing::new("sk-1".into()));
+        let found = env_entries(lookup, "laptop");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "OPENAI_API_KEY");
+        assert_eq!(found[0].1.name, "openai:laptop");
+    }
+
+    #[test]
+    fn test_import_names_prompts_and_stores_each_entry() {
+        let manifest = parse_manifest(
+            br#"[
+                {"name": "openai:prod", "kind": "runtime", "provider": "openai"},
+                {"name": "openai:admin", "kind": "admin", "provider": "openai"},
+                {"name": "anthropic:main", "kind": null, "provider": "anthropic"}
+            ]"#,
+        )
+        .unwrap();
+        let store = MockStore::new();
+        let mut prompts = Vec::new();
+        import_names(&store, &manifest, false, |name, kind| {
+            prompts.push((name.to_string(), kind));
+            Ok(Zeroizing::new(format!(" sk-{} \n", name.len())))
+        })
+        .unwrap();
+
+        assert_eq!(
+            prompts,
+            [
+                ("openai:prod".to_string(), KeyKind::Runtime),
+                ("openai:admin".to_string(), KeyKind::Admin),
+                ("anthropic:main".to_string(), KeyKind::Runtime),
+            ]
+        );
+        let (value, kind) = store.get("openai:admin").unwrap();
+        assert_eq!((value.as_str(), kind), ("sk-12", KeyKind::Admin));
+        assert_eq!(store.get("anthropic:main").unwrap().1, KeyKind::Runtime);
+    }
+
+    #[test]
+    fn test_import_names_existing_keys() {
+        let manifest = vec![
+            ("openai:prod".to_string(), KeyKind::Runtime),
+            ("openai:dev".to_string(), KeyKind::Runtime),
+        ];
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-old", KeyKind::Runtime, false)
+            .unwrap();
+
+        // Fails before prompting for anything
+        let mut prompted = 0;
+        let err = import_names(&store, &manifest, false, |_, _| {
+            prompted += 1;
+            Ok(Zeroizing::new("sk-new".to_string()))
+        });
+        assert!(err.is_err());
+        assert_eq!(prompted, 0);
+
+        import_names(&store, &manifest, true, |_, _| {
+            prompted += 1;
+            Ok(Zeroizing::new("sk-new".to_string()))
+        })
+        .unwrap();
+        assert_eq!(prompted, 1);
+        assert_eq!(*store.get("openai:prod").unwrap().0, "sk-old");
+        assert_eq!(*store.get("openai:dev").unwrap().0, "sk-new");
+    }
+
+    #[test]
+    fn test_parse_entries_rejects_non_array() {
+        let err = parse_entries(br#"{"name": "openai:prod"}"#).unwrap_err();
+        assert!(matches!(err, lkr_core::Error::Usage(_)));
+    }
+}
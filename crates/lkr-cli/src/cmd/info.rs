@@ -0,0 +1,111 @@
+use crate::util::{format_age, format_until};
+use chrono::{DateTime, Utc};
+use lkr_core::{KeyStore, StoredEntry};
+
+/// `lkr info NAME`: every piece of metadata stored for one key. The value
+/// is only ever shown masked.
+pub(crate) fn cmd_info(store: &impl KeyStore, name: &str, json: bool) -> lkr_core::Result<()> {
+    lkr_core::validate_key_name(name)?;
+    let entry = store.get_with_metadata(name)?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&info_json(name, &entry)).unwrap()
+        );
+    } else {
+        for (label, value) in info_lines(name, &entry, Utc::now()) {
+            println!("  {:<14} {}", format!("{}:", label), value);
+        }
+    }
+    Ok(())
+}
+
+fn info_json(name: &str, entry: &StoredEntry) -> serde_json::Value {
+    serde_json::json!({
+        "name": name,
+        "kind": entry.kind,
+        "masked_value": lkr_core::mask_value(&entry.value),
+        "description": entry.description,
+        "created_at": entry.created_at,
+        "updated_at": entry.updated_at,
+        "last_rotated": entry.last_rotated,
+        "expires_at": entry.expires_at,
+        "last_accessed": entry.last_accessed,
+        "access_count": entry.access_count,
+        "custom_endpoint": entry.custom_endpoint,
+        "source": entry.source,
+    })
+}
+
+fn info_lines(name: &str, entry: &StoredEntry, now: DateTime<Utc>) -> Vec<(&'static str, String)> {
+    let when = |ts: Option<DateTime<Utc>>, missing: &str| {
+        ts.map_or(missing.to_string(), |ts| {
+            // Only `expires_at` can be in the future
+            let relative = if ts > now {
+                format_until(ts, now)
+            } else {
+                format_age(ts, now)
+            };
+            format!("{} ({})", ts.format("%Y-%m-%d %H:%M UTC"), relative)
+        })
+    };
+    let or_dash = |text: &Option<String>| text.clone().unwrap_or_else(|| "-".to_string());
+    vec![
+        ("Name", name.to_string()),
+        ("Kind", entry.kind.to_string()),
+        ("Value", lkr_core::mask_value(&entry.value)),
+        ("Description", or_dash(&entry.description)),
+        ("Created", when(entry.created_at, "unknown")),
+        ("Updated", when(entry.updated_at, "unknown")),
+        ("Rotated", when(entry.last_rotated, "never")),
+        ("Expires", when(entry.expires_at, "never")),
+        (
+            "Last access",
+            format!(
+                "{} ({} recorded)",
+                when(entry.last_accessed, "never"),
+                entry.access_count
+            ),
+        ),
+        ("Endpoint", or_dash(&entry.custom_endpoint)),
+        ("Source", or_dash(&entry.source)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::keymanager::MockStore;
+    use lkr_core::{KeyKind, MetadataUpdate};
+
+    #[test]
+    fn test_info_shows_metadata_but_never_the_value() {
+        let store = MockStore::new();
+        store
+            .set(
+                "openai:prod",
+                "sk-secret-value-1234",
+                KeyKind::Runtime,
+                false,
+            )
+            .unwrap();
+        store
+            .update_metadata(
+                "openai:prod",
+                &MetadataUpdate::Description(Some("CI deploys".to_string())),
+            )
+            .unwrap();
+        let entry = store.get_with_metadata("openai:prod").unwrap();
+
+        let json = info_json("openai:prod", &entry);
+        assert_eq!(json["kind"], "runtime");
+        assert_eq!(json["description"], "CI deploys");
+        assert!(json["created_at"].is_string());
+        assert!(json["last_rotated"].is_null());
+
+        let lines = info_lines("openai:prod", &entry, Utc::now());
+        assert!(lines.contains(&("Rotated", "never".to_string())));
+        let text = format!("{} {:?}", json, lines);
+        assert!(!text.contains("sk-secret-value-1234"));
+    }
+}
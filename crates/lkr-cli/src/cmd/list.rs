@@ -1,15 +1,90 @@
-use lkr_core::{KeyStatus, KeyStore};
+use crate::cmd::check_expiry::EXPIRING_SOON;
+use crate::util::{format_age, format_until};
+use chrono::{DateTime, Utc};
+use lkr_core::{KeyEntry, KeyStatus, KeyStore};
+use std::time::Duration;
+
+/// `lkr_schema_version` of the `list --json` output. Bump it whenever a
+/// field is added or removed (see docs/JSON_SCHEMA.md).
+pub(crate) const SCHEMA_VERSION: &str = "2";
+
+/// Flags for `lkr list`.
+#[derive(Default)]
+pub(crate) struct ListOptions {
+    /// Include admin and service keys (`--all`)
+    pub include_admin: bool,
+    pub unused_since: Option<Duration>,
+    pub verbose: bool,
+    /// List expired keys too, flagged `[EXPIRED]`
+    pub show_expired: bool,
+    /// Only keys carrying this tag (`--tag`)
+    pub tag: Option<String>,
+    /// Oldest first by `created_at` instead of by name (`--sort age`)
+    pub by_age: bool,
+}
 
 pub(crate) fn cmd_list(
     store: &impl KeyStore,
-    include_admin: bool,
+    opts: &ListOptions,
     json: bool,
 ) -> lkr_core::Result<()> {
-    let entries = store.list(include_admin)?;
+    let ListOptions {
+        include_admin,
+        unused_since,
+        verbose,
+        show_expired,
+        ref tag,
+        by_age,
+    } = *opts;
+    let mut entries = store.list(include_admin)?;
+    if let Some(tag) = tag {
+        lkr_core::validate_tag(tag)?;
+        entries.retain(|e| e.has_tag(tag));
+    }
+    if by_age {
+        sort_by_age(&mut entries);
+    }
+    if !verbose {
+        // Keep `list --json` output stable for existing scripts
+        for entry in &mut entries {
+            entry.created_at = None;
+            entry.last_rotated = None;
+            entry.updated_at = None;
+        }
+    }
+
+    let now = Utc::now();
+    // `--json` always has every key, with an `expired` flag
+    let hidden_expired = if json {
+        0
+    } else {
+        hide_expired(&mut entries, show_expired, now)
+    };
+    if let Some(threshold) = unused_since {
+        let cutoff = chrono::Duration::from_std(threshold)
+            .ok()
+            .and_then(|d| now.checked_sub_signed(d))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        entries.retain(|e| is_unused_since(e, cutoff));
+
+        if entries.is_empty() {
+            if json {
+                print_json(&entries, now);
+            } else {
+                eprintln!("No unused keys.");
+            }
+            return Ok(());
+        }
+    }
 
     if entries.is_empty() {
         if json {
-            println!("[]");
+            print_json(&entries, now);
+        } else if hidden_expired > 0 {
+            eprintln!(
+                "No unexpired keys ({} expired; --show-expired to list them).",
+                hidden_expired
+            );
         } else {
             eprintln!("No keys stored.\n");
             eprintln!("  Get started:");
@@ -20,7 +95,7 @@ pub(crate) fn cmd_list(
     }
 
     if json {
-        println!("{}", serde_json::to_string_pretty(&entries).unwrap());
+        print_json(&entries, now);
         return Ok(());
     }
 
@@ -30,25 +105,79 @@ pub(crate) fn cmd_list(
         .filter(|e| e.status == KeyStatus::AclBlocked)
         .count();
 
-    println!("  {:<14} {:<20} {:<10} Value", "Provider", "Name", "Kind");
-    println!("  {}", "-".repeat(60));
+    let mut header = format!("  {:<14} {:<20} {:<10} ", "Provider", "Name", "Kind");
+    if unused_since.is_some() {
+        header.push_str(&format!("{:<12} ", "Last access"));
+    }
+    if verbose {
+        header.push_str(&format!("{:<12} {:<12} ", "Created", "Rotated"));
+    }
+    let show_expiry = entries.iter().any(|e| e.expires_at.is_some());
+    if show_expiry {
+        header.push_str(&format!("{:<12} ", "Expires"));
+    }
+    // Notes are shown whenever a key has one, not only with --verbose
+    let show_descriptions = verbose || entries.iter().any(|e| e.description.is_some());
+    header.push_str("Value");
+    if show_descriptions {
+        header.push_str(&format!("{:<10}Description", ""));
+    }
+    println!("{}", header);
+    println!("  {}", "-".repeat(header.chars().count().max(60) - 2));
     for entry in &entries {
         let kind_str = match (&entry.status, &entry.kind) {
             (KeyStatus::AclBlocked, _) => "⚠ blocked".to_string(),
             (_, Some(k)) => k.to_string(),
             (_, None) => "?".to_string(),
         };
-        let value_str = if entry.status == KeyStatus::AclBlocked {
-            "(ACL mismatch — run `lkr harden`)"
+        let value_str = value_column(entry, now);
+        let mut row = format!(
+            "  {:<14} {:<20} {:<10} ",
+            entry.provider, entry.name, kind_str
+        );
+        if unused_since.is_some() {
+            // Never-accessed keys are flagged distinctly from stale ones
+            let last_str = entry
+                .last_accessed
+                .map_or("never".to_string(), |ts| format_age(ts, now));
+            row.push_str(&format!("{:<12} ", last_str));
+        }
+        if verbose {
+            let created_str = entry
+                .created_at
+                .map_or("-".to_string(), |ts| format_age(ts, now));
+            let rotated_str = entry
+                .last_rotated
+                .map_or("never".to_string(), |ts| format_age(ts, now));
+            row.push_str(&format!("{:<12} {:<12} ", created_str, rotated_str));
+        }
+        if show_expiry {
+            row.push_str(&format!("{:<12} ", expires_column(entry, now)));
+        }
+        if show_descriptions {
+            row.push_str(&format!("{:<14} ", value_str));
+            row.push_str(&truncate(entry.description.as_deref().unwrap_or(""), 30));
         } else {
-            &entry.masked_value
-        };
+            row.push_str(&value_str);
+        }
+        println!("{}", row.trim_end());
+    }
+    if unused_since.is_some() {
+        let never_count = entries.iter().filter(|e| e.last_accessed.is_none()).count();
         println!(
-            "  {:<14} {:<20} {:<10} {}",
-            entry.provider, entry.name, kind_str, value_str
+            "\n  {} unused key(s) ({} never accessed)",
+            entries.len(),
+            never_count
+        );
+    } else {
+        println!("\n  {} key(s) stored in Keychain", entries.len());
+    }
+    if hidden_expired > 0 {
+        println!(
+            "  {} expired key(s) hidden (--show-expired to list them)",
+            hidden_expired
         );
     }
-    println!("\n  {} key(s) stored in Keychain", entries.len());
 
     if blocked_count > 0 {
         eprintln!(
@@ -59,3 +188,291 @@ pub(crate) fn cmd_list(
 
     Ok(())
 }
+
+/// The Value column: the masked value, flagged when expired.
+fn value_column(entry: &KeyEntry, now: DateTime<Utc>) -> String {
+    if entry.status == KeyStatus::AclBlocked {
+        "(ACL mismatch — run `lkr harden`)".to_string()
+    } else if entry.is_expired(now) {
+        format!("[EXPIRED] {}", entry.masked_value)
+    } else {
+        entry.masked_value.clone()
+    }
+}
+
+/// The Expires column: the date, or how soon with a `⚠` when that is
+/// within [`EXPIRING_SOON`].
+fn expires_column(entry: &KeyEntry, now: DateTime<Utc>) -> String {
+    match entry.expires_at {
+        None => "-".to_string(),
+        Some(_) if entry.is_expired(now) => "expired".to_string(),
+        Some(ts) if entry.expires_within(now, EXPIRING_SOON) => {
+            format!("⚠ {}", format_until(ts, now))
+        }
+        Some(ts) => ts.format("%Y-%m-%d").to_string(),
+    }
+}
+
+/// Drop expired keys from `entries` unless `show_expired`, returning how
+/// many were dropped.
+fn hide_expired(entries: &mut Vec<KeyEntry>, show_expired: bool, now: DateTime<Utc>) -> usize {
+    if show_expired {
+        return 0;
+    }
+    let before = entries.len();
+    entries.retain(|e| !e.is_expired(now));
+    before - entries.len()
+}
+
+fn print_json(entries: &[KeyEntry], now: DateTime<Utc>) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&json_output(entries, now)).unwrap()
+    );
+}
+
+/// `list --json`: the entries, each with `expired`, wrapped with the
+/// schema version.
+fn json_output(entries: &[KeyEntry], now: DateTime<Utc>) -> serde_json::Value {
+    let keys: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            let mut key = serde_json::to_value(entry).unwrap();
+            key["expired"] = entry.is_expired(now).into();
+            key
+        })
+        .collect();
+    serde_json::json!({
+        "lkr_schema_version": SCHEMA_VERSION,
+        "keys": keys,
+    })
+}
+
+/// `lkr list --json-schema`: JSON Schema (draft 2020-12) of the `--json`
+/// output.
+pub(crate) fn print_json_schema() {
+    println!("{}", serde_json::to_string_pretty(&json_schema()).unwrap());
+}
+
+fn json_schema() -> serde_json::Value {
+    let timestamp = serde_json::json!({"type": "string", "format": "date-time"});
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "lkr list --json",
+        "type": "object",
+        "required": ["lkr_schema_version", "keys"],
+        "properties": {
+            "lkr_schema_version": {"const": SCHEMA_VERSION},
+            "keys": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": [
+                        "name", "provider", "label", "kind", "masked_value",
+                        "status", "last_accessed", "access_count", "expired"
+                    ],
+                    "properties": {
+                        "name": {"type": "string", "description": "provider:label"},
+                        "provider": {"type": "string"},
+                        "label": {"type": "string"},
+                        "kind": {
+                            "enum": ["runtime", "admin", "service", null],
+                            "description": "null when the key is ACL-blocked"
+                        },
+                        "masked_value": {"type": "string"},
+                        "status": {"enum": ["ok", "acl_blocked"]},
+                        "last_accessed": {"oneOf": [timestamp, {"type": "null"}]},
+                        "access_count": {"type": "integer", "minimum": 0},
+                        "created_at": timestamp,
+                        "last_rotated": timestamp,
+                        "updated_at": timestamp,
+                        "description": {"type": "string"},
+                        "custom_endpoint": {"type": "string"},
+                        "source": {"type": "string"},
+                        "expires_at": timestamp,
+                        "tags": {"type": "array", "items": {"type": "string"}},
+                        "expired": {"type": "boolean"}
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Shorten `text` to at most `max` characters, marking the cut with `…`.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(max - 1).collect();
+    short.push('…');
+    short
+}
+
+/// Oldest first. Keys stored before `created_at` was recorded have none
+/// and come first; ties keep name order.
+fn sort_by_age(entries: &mut [KeyEntry]) {
+    entries.sort_by_key(|e| e.created_at);
+}
+
+/// A key is unused if it was last accessed before `cutoff`, or never.
+fn is_unused_since(entry: &KeyEntry, cutoff: DateTime<Utc>) -> bool {
+    entry.last_accessed.is_none_or(|ts| ts < cutoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::KeyKind;
+
+    fn entry(name: &str, last_accessed: Option<DateTime<Utc>>) -> KeyEntry {
+        let (provider, label) = name.split_once(':').unwrap();
+        KeyEntry {
+            name: name.to_string(),
+            provider: provider.to_string(),
+            label: label.to_string(),
+            kind: Some(KeyKind::Runtime),
+            masked_value: "sk-t...1234".to_string(),
+            status: KeyStatus::Ok,
+            last_accessed,
+            access_count: 0,
+            created_at: None,
+            last_rotated: None,
+            updated_at: None,
+            description: None,
+            custom_endpoint: None,
+            source: None,
+            expires_at: None,
+            tags: Vec::new(),
+        }
+    }
+
+    fn expiring(name: &str, expires_at: DateTime<Utc>) -> KeyEntry {
+        KeyEntry {
+            expires_at: Some(expires_at),
+            ..entry(name, None)
+        }
+    }
+
+    #[test]
+    fn test_unused_since_filters_recent_stale_and_never() {
+        let now = Utc::now();
+        let cutoff = now - chrono::Duration::days(30);
+        let recent = entry("openai:recent", Some(now - chrono::Duration::days(2)));
+        let stale = entry("openai:stale", Some(now - chrono::Duration::days(90)));
+        let never = entry("openai:never", None);
+
+        assert!(!is_unused_since(&recent, cutoff));
+        assert!(is_unused_since(&stale, cutoff));
+        assert!(is_unused_since(&never, cutoff));
+    }
+
+    #[test]
+    fn test_sort_by_age_oldest_first() {
+        let now = Utc::now();
+        let created = |name: &str, days: Option<i64>| KeyEntry {
+            created_at: days.map(|d| now - chrono::Duration::days(d)),
+            ..entry(name, None)
+        };
+        let mut entries = vec![
+            created("anthropic:new", Some(1)),
+            created("openai:legacy", None),
+            created("openai:old", Some(400)),
+            created("openai:mid", Some(30)),
+        ];
+        sort_by_age(&mut entries);
+        assert_eq!(
+            names(&entries),
+            ["openai:legacy", "openai:old", "openai:mid", "anthropic:new"]
+        );
+    }
+
+    #[test]
+    fn test_json_output_has_schema_version() {
+        for entries in [vec![], vec![entry("openai:prod", None)]] {
+            let out = json_output(&entries, Utc::now());
+            assert_eq!(out["lkr_schema_version"], SCHEMA_VERSION);
+            assert_eq!(out["keys"].as_array().unwrap().len(), entries.len());
+        }
+        assert_eq!(
+            json_schema()["properties"]["lkr_schema_version"]["const"],
+            SCHEMA_VERSION
+        );
+    }
+
+    fn names(entries: &[KeyEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    fn with_expiry(now: DateTime<Utc>) -> Vec<KeyEntry> {
+        vec![
+            entry("openai:forever", None),
+            expiring("openai:later", now + chrono::Duration::days(1)),
+            expiring("openai:gone", now - chrono::Duration::days(1)),
+        ]
+    }
+
+    #[test]
+    fn test_expired_keys_hidden_by_default() {
+        let now = Utc::now();
+        let mut entries = with_expiry(now);
+        assert_eq!(hide_expired(&mut entries, false, now), 1);
+        assert_eq!(names(&entries), ["openai:forever", "openai:later"]);
+    }
+
+    #[test]
+    fn test_show_expired_keeps_and_flags_them() {
+        let now = Utc::now();
+        let mut entries = with_expiry(now);
+        assert_eq!(hide_expired(&mut entries, true, now), 0);
+        assert_eq!(entries.len(), 3);
+
+        let values: Vec<_> = entries.iter().map(|e| value_column(e, now)).collect();
+        assert_eq!(
+            values,
+            ["sk-t...1234", "sk-t...1234", "[EXPIRED] sk-t...1234"]
+        );
+    }
+
+    #[test]
+    fn test_expires_column_highlights_soon() {
+        let now = Utc::now();
+        let mut entries = with_expiry(now);
+        entries.push(expiring("openai:month", now + chrono::Duration::days(30)));
+
+        let column: Vec<_> = entries.iter().map(|e| expires_column(e, now)).collect();
+        let month = (now + chrono::Duration::days(30))
+            .format("%Y-%m-%d")
+            .to_string();
+        assert_eq!(column, ["-", "⚠ in 1d", "expired", month.as_str()]);
+    }
+
+    #[test]
+    fn test_json_output_flags_expired() {
+        let now = Utc::now();
+        let out = json_output(&with_expiry(now), now);
+        let flags: Vec<_> = out["keys"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|k| (k["name"].as_str().unwrap(), k["expired"].as_bool().unwrap()))
+            .collect();
+        assert_eq!(
+            flags,
+            [
+                ("openai:forever", false),
+                ("openai:later", false),
+                ("openai:gone", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncate_description() {
+        assert_eq!(truncate("short", 30), "short");
+        let long = "a".repeat(40);
+        let cut = truncate(&long, 30);
+        assert_eq!(cut.chars().count(), 30);
+        assert!(cut.ends_with('…'));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::util::confirm;
+use crate::prompt::confirm;
 use lkr_core::{KeyStore, KeychainStore};
 
 /// Migrate keys from login.keychain to LKR custom keychain (v0.3.0).
@@ -1,11 +1,26 @@
+pub(crate) mod batch_set;
+pub(crate) mod check_expiry;
+pub(crate) mod chkind;
+pub(crate) mod doctor;
+pub(crate) mod estimate;
 pub(crate) mod exec;
+pub(crate) mod export;
 pub(crate) mod r#gen;
 pub(crate) mod get;
 pub(crate) mod harden;
+pub(crate) mod import;
+pub(crate) mod info;
 pub(crate) mod init;
 pub(crate) mod list;
 pub(crate) mod lock;
 pub(crate) mod migrate;
+pub(crate) mod providers;
+pub(crate) mod rename;
 pub(crate) mod rm;
+pub(crate) mod rotate;
+pub(crate) mod self_test;
 pub(crate) mod set;
+pub(crate) mod set_many;
+pub(crate) mod tag;
 pub(crate) mod usage;
+pub(crate) mod verify_backup;
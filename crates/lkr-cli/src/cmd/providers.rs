@@ -0,0 +1,71 @@
+/// One row of `lkr providers`.
+struct ProviderInfo {
+    name: &'static str,
+    env_var: &'static str,
+    usage: bool,
+}
+
+/// `lkr providers`: the providers templates and `exec` map to env vars, and
+/// which of them `lkr usage` can report on. Static tables only; no Keychain.
+pub(crate) fn cmd_providers(json: bool) {
+    let providers = known_providers();
+    if json {
+        let out: Vec<_> = providers
+            .iter()
+            .map(|p| {
+                serde_json::json!({
+                    "provider": p.name,
+                    "env_var": p.env_var,
+                    "usage": p.usage,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&out).unwrap());
+        return;
+    }
+
+    println!("  {:<16} {:<24} USAGE", "PROVIDER", "ENV VAR");
+    for p in &providers {
+        println!(
+            "  {:<16} {:<24} {}",
+            p.name,
+            p.env_var,
+            if p.usage { "yes" } else { "-" }
+        );
+    }
+    println!(
+        "\n  Other providers work too; their env var is the key name uppercased (custom:dev -> CUSTOM_DEV)."
+    );
+}
+
+fn known_providers() -> Vec<ProviderInfo> {
+    let mut providers: Vec<_> = lkr_core::known_env_vars()
+        .iter()
+        .map(|&(env_var, name)| ProviderInfo {
+            name,
+            env_var,
+            usage: lkr_core::USAGE_PROVIDERS.contains(&name),
+        })
+        .collect();
+    providers.sort_by_key(|p| p.name);
+    providers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_providers_flags_usage_support() {
+        let providers = known_providers();
+        let openai = providers.iter().find(|p| p.name == "openai").unwrap();
+        assert_eq!(openai.env_var, "OPENAI_API_KEY");
+        assert!(openai.usage);
+        let groq = providers.iter().find(|p| p.name == "groq").unwrap();
+        assert!(!groq.usage);
+        // Every usage provider is also a known template provider
+        for name in lkr_core::USAGE_PROVIDERS {
+            assert!(providers.iter().any(|p| p.name == *name));
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use lkr_core::KeyStore;
+
+/// `lkr rename OLD NEW`: move a key to a new name without re-entering its
+/// value. An existing NEW is only overwritten with `force`.
+pub(crate) fn cmd_rename(
+    store: &impl KeyStore,
+    old_name: &str,
+    new_name: &str,
+    force: bool,
+) -> lkr_core::Result<()> {
+    store.with_locked(|s| s.rename(old_name, new_name, force))?;
+    eprintln!("Renamed {} to {}", old_name, new_name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::KeyKind;
+    use lkr_core::keymanager::MockStore;
+
+    fn store() -> MockStore {
+        let store = MockStore::new();
+        store
+            .set("openai:old", "sk-old", KeyKind::Runtime, false)
+            .unwrap();
+        store
+            .set("openai:taken", "sk-taken", KeyKind::Runtime, false)
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn test_rename_to_existing_needs_force() {
+        let store = store();
+        let err = cmd_rename(&store, "openai:old", "openai:taken", false).unwrap_err();
+        assert!(matches!(err, lkr_core::Error::KeyAlreadyExists { .. }));
+        assert_eq!(&*store.get("openai:old").unwrap().0, "sk-old");
+        assert_eq!(&*store.get("openai:taken").unwrap().0, "sk-taken");
+
+        cmd_rename(&store, "openai:old", "openai:taken", true).unwrap();
+        assert!(!store.exists("openai:old").unwrap());
+        assert_eq!(&*store.get("openai:taken").unwrap().0, "sk-old");
+    }
+
+    #[test]
+    fn test_rename_nonexistent_and_invalid_names() {
+        let store = store();
+        assert!(matches!(
+            cmd_rename(&store, "openai:missing", "openai:new", false),
+            Err(lkr_core::Error::KeyNotFound { .. })
+        ));
+        assert!(matches!(
+            cmd_rename(&store, "openai:old", "not a name", false),
+            Err(lkr_core::Error::InvalidKeyName { .. })
+        ));
+        assert!(store.exists("openai:old").unwrap());
+    }
+
+    #[test]
+    fn test_rename_round_trip() {
+        let store = store();
+        cmd_rename(&store, "openai:old", "anthropic:moved", false).unwrap();
+        cmd_rename(&store, "anthropic:moved", "openai:old", false).unwrap();
+        let names: Vec<_> = store
+            .list(true)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, ["openai:old", "openai:taken"]);
+        assert_eq!(&*store.get("openai:old").unwrap().0, "sk-old");
+    }
+}
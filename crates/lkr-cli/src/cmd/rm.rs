@@ -1,13 +1,321 @@
-use crate::util::confirm;
-use lkr_core::KeyStore;
+use crate::prompt::{confirm, confirm_admin};
+use lkr_core::{ChecksumRecord, ChecksumStore, KeyKind, KeyStore, TemplatePolicy};
+use std::path::Path;
+use zeroize::Zeroizing;
 
-pub(crate) fn cmd_rm(store: &impl KeyStore, name: &str, force: bool) -> lkr_core::Result<()> {
-    if !force && !confirm(&format!("Remove key '{}'? [y/N] ", name)) {
-        eprintln!("Cancelled.");
+/// Flags for `lkr rm`.
+#[derive(Default)]
+pub(crate) struct RmOptions {
+    pub force: bool,
+    pub if_exists: bool,
+    /// Afterwards, offer to regenerate or delete generated files using the key
+    pub cascade: bool,
+    /// With `cascade`: delete those files without asking
+    pub cascade_delete: bool,
+    /// List what would be removed and stop
+    pub dry_run: bool,
+}
+
+/// Remove a key. With `if_exists`, a missing key is success (like SQL
+/// `DROP ... IF EXISTS`); the name is still validated.
+pub(crate) fn cmd_rm(store: &impl KeyStore, name: &str, opts: &RmOptions) -> lkr_core::Result<()> {
+    if opts.dry_run {
+        return dry_run(store, name, opts.if_exists);
+    }
+
+    // Nothing to confirm when the key is already gone
+    let absent = opts.if_exists && !store.exists(name)?;
+    // Read before deleting: confirmation needs the kind, --cascade the value
+    let current = if absent || (opts.force && !opts.cascade) {
+        None
+    } else {
+        // Not `get`: an expired admin key still needs the typed confirmation
+        store
+            .get_with_metadata(name)
+            .ok()
+            .map(|entry| (Zeroizing::new(entry.value.clone()), entry.kind))
+    };
+
+    if !opts.force && !absent {
+        // Admin keys need the full name typed back, not just a y
+        let confirmed = if matches!(current, Some((_, KeyKind::Admin))) {
+            confirm_admin("remove", name)
+        } else {
+            confirm(&format!("Remove key '{}'? [y/N] ", name))
+        };
+        if !confirmed {
+            eprintln!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    // delete() validates the name before looking the key up
+    match store.delete(name) {
+        Ok(()) => eprintln!("Removed {}", name),
+        Err(lkr_core::Error::KeyNotFound { .. }) if opts.if_exists => {
+            eprintln!("{} already absent", name);
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    }
+
+    if let Some((value, _)) = current.filter(|_| opts.cascade) {
+        cascade(store, name, &value, opts.cascade_delete)?;
+    }
+    Ok(())
+}
+
+/// `lkr rm --dry-run`: print the keys that would be removed, one per line
+/// on stdout, and delete nothing.
+fn dry_run(store: &impl KeyStore, name: &str, if_exists: bool) -> lkr_core::Result<()> {
+    let targets = rm_targets(store, name)?;
+    if targets.is_empty() {
+        if is_pattern(name) || if_exists {
+            eprintln!("Dry run: no keys match {}; nothing would be removed.", name);
+            return Ok(());
+        }
+        return Err(lkr_core::Error::KeyNotFound {
+            name: name.to_string(),
+        });
+    }
+    for target in &targets {
+        println!("{}", target);
+    }
+    eprintln!("Dry run: {} key(s) would be removed.", targets.len());
+    Ok(())
+}
+
+/// Keys `name` refers to: every key matching it when it is a glob
+/// (`openai:*`), else the key itself if it exists.
+fn rm_targets(store: &impl KeyStore, name: &str) -> lkr_core::Result<Vec<String>> {
+    if is_pattern(name) {
+        return Ok(store
+            .list(true)?
+            .into_iter()
+            .map(|e| e.name)
+            .filter(|n| lkr_core::glob_match(name, n))
+            .collect());
+    }
+    lkr_core::validate_key_name(name)?;
+    Ok(if store.exists(name)? {
+        vec![name.to_string()]
+    } else {
+        Vec::new()
+    })
+}
+
+fn is_pattern(name: &str) -> bool {
+    name.contains(['*', '?'])
+}
+
+/// File types `--cascade` looks at.
+const CASCADE_EXTENSIONS: &[&str] = &["env", "json", "yaml", "toml"];
+
+/// `lkr rm --cascade`: handle lkr-generated files under the current
+/// directory that contain the removed key's value or env var name.
+fn cascade(
+    store: &impl KeyStore,
+    name: &str,
+    value: &Zeroizing<String>,
+    delete_all: bool,
+) -> lkr_core::Result<()> {
+    let checksums = ChecksumStore::new(ChecksumStore::default_path()?);
+    let root = std::env::current_dir()
+        .map_err(|e| lkr_core::Error::Usage(format!("Cannot read current directory: {}", e)))?;
+    let env_var = lkr_core::key_to_env_var(name);
+    let affected = stale_outputs(checksums.records()?, &root, &[value.as_str(), &env_var]);
+
+    if affected.is_empty() {
+        eprintln!("No generated files under {} use {}.", root.display(), name);
         return Ok(());
     }
 
-    store.delete(name)?;
-    eprintln!("Removed {}", name);
+    let policy = TemplatePolicy::from_env()?;
+    for record in &affected {
+        let path = record.path.as_path();
+        if delete_all {
+            delete_output(&checksums, path)?;
+            continue;
+        }
+        if let Some(template) = &record.template
+            && template.exists()
+            && confirm(&format!(
+                "{} used {}. Regenerate from {}? [y/N] ",
+                path.display(),
+                name,
+                template.display()
+            ))
+        {
+            let result = lkr_core::generate(store, template, path, &policy)?;
+            crate::cmd::r#gen::record_checksum(path, &result.output_checksum, template);
+            eprintln!("  Regenerated {}", path.display());
+        } else if confirm(&format!("Delete {}? [y/N] ", path.display())) {
+            delete_output(&checksums, path)?;
+        } else {
+            eprintln!("  Kept {}", path.display());
+        }
+    }
     Ok(())
 }
+
+/// Recorded outputs under `root` with a cascade extension whose content
+/// contains any of `markers`.
+fn stale_outputs(
+    records: Vec<ChecksumRecord>,
+    root: &Path,
+    markers: &[&str],
+) -> Vec<ChecksumRecord> {
+    records
+        .into_iter()
+        .filter(|r| r.path.starts_with(root) && has_cascade_extension(&r.path))
+        .filter(|r| {
+            // Generated files hold secrets; the content never leaves this scope
+            std::fs::read_to_string(&r.path)
+                .map(Zeroizing::new)
+                .is_ok_and(|content| markers.iter().any(|m| content.contains(m)))
+        })
+        .collect()
+}
+
+/// `*.env`, `*.json`, `*.yaml`, `*.toml` — including a bare `.env`.
+fn has_cascade_extension(path: &Path) -> bool {
+    let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    CASCADE_EXTENSIONS.iter().any(|ext| {
+        file_name
+            .strip_suffix(ext)
+            .is_some_and(|stem| stem.ends_with('.'))
+    })
+}
+
+fn delete_output(checksums: &ChecksumStore, path: &Path) -> lkr_core::Result<()> {
+    std::fs::remove_file(path).map_err(|e| {
+        lkr_core::Error::template_with(format!("Cannot delete '{}'", path.display()), e)
+    })?;
+    checksums.forget(path)?;
+    eprintln!("  Deleted {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::keymanager::MockStore;
+
+    fn forced(if_exists: bool) -> RmOptions {
+        RmOptions {
+            force: true,
+            if_exists,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rm_if_exists_missing_key_succeeds() {
+        let store = MockStore::new();
+        assert!(cmd_rm(&store, "openai:gone", &forced(true)).is_ok());
+        // Without the flag it is still an error
+        assert!(matches!(
+            cmd_rm(&store, "openai:gone", &forced(false)),
+            Err(lkr_core::Error::KeyNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rm_if_exists_deletes_existing_key() {
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-test-12345678", KeyKind::Runtime, false)
+            .unwrap();
+        cmd_rm(&store, "openai:prod", &forced(true)).unwrap();
+        assert!(!store.exists("openai:prod").unwrap());
+    }
+
+    #[test]
+    fn test_rm_if_exists_still_validates_name() {
+        let store = MockStore::new();
+        assert!(matches!(
+            cmd_rm(&store, "no-colon", &forced(true)),
+            Err(lkr_core::Error::InvalidKeyName { .. })
+        ));
+    }
+
+    #[test]
+    fn test_dry_run_lists_glob_matches_and_deletes_nothing() {
+        let store = MockStore::new();
+        for name in ["openai:prod", "openai:dev", "anthropic:main"] {
+            store
+                .set(name, "sk-test-12345678", KeyKind::Runtime, false)
+                .unwrap();
+        }
+        store
+            .set("openai:admin", "sk-admin-12345678", KeyKind::Admin, false)
+            .unwrap();
+
+        let mut targets = rm_targets(&store, "openai:*").unwrap();
+        targets.sort();
+        assert_eq!(targets, ["openai:admin", "openai:dev", "openai:prod"]);
+
+        let opts = RmOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        cmd_rm(&store, "openai:*", &opts).unwrap();
+        cmd_rm(&store, "openai:prod", &opts).unwrap();
+        assert_eq!(store.list(true).unwrap().len(), 4);
+
+        // An exact name must exist, as for a real rm
+        assert!(matches!(
+            cmd_rm(&store, "openai:gone", &opts),
+            Err(lkr_core::Error::KeyNotFound { .. })
+        ));
+        assert!(rm_targets(&store, "groq:*").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cascade_extensions() {
+        assert!(has_cascade_extension(Path::new("/p/.env")));
+        assert!(has_cascade_extension(Path::new("/p/prod.env")));
+        assert!(has_cascade_extension(Path::new("/p/.mcp.json")));
+        assert!(has_cascade_extension(Path::new("/p/config.yaml")));
+        assert!(has_cascade_extension(Path::new("/p/app.toml")));
+        assert!(!has_cascade_extension(Path::new("/p/.env.local")));
+        assert!(!has_cascade_extension(Path::new("/p/json")));
+        assert!(!has_cascade_extension(Path::new("/p/.zshrc")));
+    }
+
+    #[test]
+    fn test_stale_outputs_match_value_or_env_var_under_root() {
+        let dir = std::env::temp_dir().join(format!("lkr-rm-cascade-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        let files = [
+            (dir.join(".env"), "OPENAI_API_KEY=sk-gone\n"),
+            (dir.join("sub/mcp.json"), r#"{"OPENAI_API_KEY": "x"}"#),
+            (dir.join("other.env"), "GROQ_API_KEY=gsk\n"),
+        ];
+        let mut records = Vec::new();
+        for (path, content) in &files {
+            std::fs::write(path, content).unwrap();
+            records.push(ChecksumRecord {
+                path: path.clone(),
+                checksum: lkr_core::sha256_hex(content.as_bytes()),
+                generated_at: chrono::Utc::now(),
+                template: None,
+            });
+        }
+        // Outside the scanned root
+        records.push(ChecksumRecord {
+            path: std::env::temp_dir().join("elsewhere.env"),
+            checksum: String::new(),
+            generated_at: chrono::Utc::now(),
+            template: None,
+        });
+
+        let found = stale_outputs(records, &dir, &["sk-gone", "OPENAI_API_KEY"]);
+        let paths: Vec<_> = found.iter().map(|r| r.path.clone()).collect();
+        assert_eq!(paths, [files[0].0.clone(), files[1].0.clone()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+use crate::cmd::set::{read_value, stored_value, truncate_description};
+use crate::prompt::confirm_admin;
+use lkr_core::{KeyKind, KeyStore, MetadataUpdate};
+use std::io;
+
+/// `lkr rotate NAME [--kind KIND]`: prompt for a new value and replace the
+/// stored one, keeping the key's kind (unless overridden) and metadata. A
+/// `description` replaces the stored one.
+pub(crate) fn cmd_rotate(
+    store: &impl KeyStore,
+    name: &str,
+    kind: Option<&str>,
+    description: Option<&str>,
+) -> lkr_core::Result<()> {
+    let kind: Option<KeyKind> = kind
+        .map(|k| {
+            k.parse().map_err(|reason| lkr_core::Error::InvalidKeyName {
+                name: name.to_string(),
+                reason,
+            })
+        })
+        .transpose()?;
+
+    // Fail before prompting for a value that has nowhere to go
+    lkr_core::validate_key_name(name)?;
+    if !store.exists(name)? {
+        return Err(lkr_core::Error::KeyNotFound {
+            name: name.to_string(),
+        });
+    }
+    if kind == Some(KeyKind::Admin) && !confirm_admin("store", name) {
+        eprintln!("Cancelled.");
+        return Ok(());
+    }
+
+    let value = read_value(&mut io::stderr(), name, false, rpassword::read_password)?;
+    let description = description.map(truncate_description);
+    rotate_key(
+        store,
+        name,
+        stored_value(&value, false),
+        kind,
+        description.as_deref(),
+    )?;
+    eprintln!("Rotated {}", name);
+    Ok(())
+}
+
+/// Replace the value, switching to `kind` and `description` if given.
+fn rotate_key(
+    store: &impl KeyStore,
+    name: &str,
+    value: &str,
+    kind: Option<KeyKind>,
+    description: Option<&str>,
+) -> lkr_core::Result<()> {
+    let updates: Vec<MetadataUpdate> = description
+        .map(|text| MetadataUpdate::Description(Some(text.to_string())))
+        .into_iter()
+        .collect();
+    store.with_locked(|s| {
+        // Still only an existing key: rotate never creates one
+        let kind = kind.unwrap_or(s.get_with_metadata(name)?.kind);
+        s.set_with(name, value, kind, true, &updates)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::keymanager::MockStore;
+
+    #[test]
+    fn test_rotate_key_keeps_or_overrides_kind() {
+        let store = MockStore::new();
+        store
+            .set("stripe:shared", "sk-old", KeyKind::Service, false)
+            .unwrap();
+
+        rotate_key(&store, "stripe:shared", "sk-new", None, None).unwrap();
+        let (value, kind) = store.get("stripe:shared").unwrap();
+        assert_eq!((value.as_str(), kind), ("sk-new", KeyKind::Service));
+
+        rotate_key(
+            &store,
+            "stripe:shared",
+            "sk-newer",
+            Some(KeyKind::Runtime),
+            Some("rotated after leak"),
+        )
+        .unwrap();
+        let entry = store.get_with_metadata("stripe:shared").unwrap();
+        assert_eq!(entry.value, "sk-newer");
+        assert_eq!(entry.kind, KeyKind::Runtime);
+        assert_eq!(entry.description.as_deref(), Some("rotated after leak"));
+
+        let err = rotate_key(
+            &store,
+            "stripe:missing",
+            "sk-1",
+            Some(KeyKind::Runtime),
+            None,
+        );
+        assert!(matches!(err, Err(lkr_core::Error::KeyNotFound { .. })));
+        assert!(!store.exists("stripe:missing").unwrap());
+    }
+}
@@ -0,0 +1,112 @@
+use lkr_core::{KeyKind, KeyStore, KeychainStore};
+
+/// Key the self-test writes. It lives under [`lkr_core::SELF_TEST_SERVICE_NAME`],
+/// never next to real keys.
+const PROBE_KEY: &str = "selftest:probe";
+
+/// Outcome of one step of the cycle (`Err` holds the reason).
+struct Step {
+    name: &'static str,
+    result: Result<(), String>,
+}
+
+/// `lkr self-test`: set → get → list → delete a dummy key in a scratch
+/// service of the LKR keychain, reporting each step.
+pub(crate) fn cmd_self_test(store: KeychainStore, json: bool) -> lkr_core::Result<()> {
+    let steps = run_cycle(&store.with_service(lkr_core::SELF_TEST_SERVICE_NAME));
+    let failed: Vec<_> = steps.iter().filter(|s| s.result.is_err()).collect();
+
+    if json {
+        let out: Vec<_> = steps
+            .iter()
+            .map(|s| {
+                serde_json::json!({
+                    "step": s.name,
+                    "ok": s.result.is_ok(),
+                    "error": s.result.as_ref().err(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&out).unwrap());
+    } else {
+        for step in &steps {
+            match &step.result {
+                Ok(()) => println!("  PASS  {}", step.name),
+                Err(reason) => println!("  FAIL  {}: {}", step.name, reason),
+            }
+        }
+    }
+
+    if failed.is_empty() {
+        if !json {
+            println!("\n  Keychain integration works.");
+        }
+        Ok(())
+    } else {
+        Err(lkr_core::Error::Usage(format!(
+            "Self-test failed at: {}",
+            failed.iter().map(|s| s.name).collect::<Vec<_>>().join(", ")
+        )))
+    }
+}
+
+/// Run the cycle against `store`. Later steps are skipped once `set`
+/// fails, but the probe key is always cleaned up.
+fn run_cycle(store: &impl KeyStore) -> Vec<Step> {
+    let value = format!("lkr-self-test-{}", std::process::id());
+    // Leftover from an interrupted run
+    let _ = store.delete(PROBE_KEY);
+
+    let mut steps = vec![Step {
+        name: "set",
+        result: store
+            .set(PROBE_KEY, &value, KeyKind::Runtime, true)
+            .map_err(|e| e.to_string()),
+    }];
+    if steps[0].result.is_ok() {
+        steps.push(Step {
+            name: "get",
+            result: match store.get(PROBE_KEY) {
+                Ok((read, KeyKind::Runtime)) if *read == value => Ok(()),
+                Ok(_) => Err("read back a different value or kind".to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+        });
+        steps.push(Step {
+            name: "list",
+            result: match store.list(true) {
+                Ok(entries) if entries.iter().any(|e| e.name == PROBE_KEY) => Ok(()),
+                Ok(_) => Err("probe key missing from list".to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+        });
+    }
+    steps.push(Step {
+        name: "delete",
+        result: store
+            .delete(PROBE_KEY)
+            .map_err(|e| e.to_string())
+            .and_then(|()| match store.exists(PROBE_KEY) {
+                Ok(false) => Ok(()),
+                Ok(true) => Err("probe key still present after delete".to_string()),
+                Err(e) => Err(e.to_string()),
+            }),
+    });
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::keymanager::MockStore;
+
+    #[test]
+    fn test_cycle_passes_on_scratch_store() {
+        let store = MockStore::new();
+        let steps = run_cycle(&store);
+        let names: Vec<_> = steps.iter().map(|s| s.name).collect();
+        assert_eq!(names, ["set", "get", "list", "delete"]);
+        assert!(steps.iter().all(|s| s.result.is_ok()));
+        assert!(!store.exists(PROBE_KEY).unwrap());
+    }
+}
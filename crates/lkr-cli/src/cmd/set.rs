@@ -1,31 +1,495 @@
-use lkr_core::{KeyKind, KeyStore};
+use crate::prompt::{confirm_admin, confirm_default};
+use chrono::{DateTime, Utc};
+use lkr_core::{KeyKind, KeyStore, MetadataUpdate, Validity};
 use std::io::{self, Write};
 use zeroize::Zeroizing;
 
+#[derive(Default)]
+pub(crate) struct SetOptions {
+    pub kind: String,
+    pub force: bool,
+    /// Validate against the provider API before storing
+    pub test: bool,
+    /// With `test`: validate only, never store
+    pub no_store: bool,
+    /// Custom API base URL stored alongside the key
+    pub endpoint: Option<String>,
+    /// After storing, print the stored value's fingerprint to stderr
+    pub print_fingerprint: bool,
+    /// Store the value verbatim, keeping leading/trailing whitespace
+    pub no_trim: bool,
+    /// Note stored with the key (truncated to `MAX_DESCRIPTION_LEN` chars)
+    pub description: Option<String>,
+    /// Read the value without printing the prompt (a wrapper shows its own)
+    pub no_prompt: bool,
+    /// Drop quotes wrapping the whole value without asking
+    pub strip_quotes: bool,
+    /// When the key stops being handed out (`--expires-in`/`--expires-at`)
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
 pub(crate) fn cmd_set(
     store: &impl KeyStore,
     name: &str,
-    kind_str: &str,
-    force: bool,
+    opts: &SetOptions,
 ) -> lkr_core::Result<()> {
-    let kind: KeyKind = kind_str
+    let kind: KeyKind = opts
+        .kind
         .parse()
         .map_err(|reason| lkr_core::Error::InvalidKeyName {
             name: name.to_string(),
             reason,
         })?;
 
+    let endpoint = opts
+        .endpoint
+        .as_deref()
+        .map(lkr_core::normalize_endpoint)
+        .transpose()?;
+    let description = opts.description.as_deref().map(truncate_description);
+    if let Some(expires_at) = opts.expires_at
+        && expires_at <= Utc::now()
+    {
+        return Err(lkr_core::Error::Usage(format!(
+            "Expiry {} is already in the past.",
+            expires_at.to_rfc3339()
+        )));
+    }
+
+    // Fail before prompting for the value if it can't be tested
+    let test_provider = if opts.test {
+        let provider = name.split(':').next().unwrap_or(name).to_lowercase();
+        if !lkr_core::can_validate(&provider) {
+            return Err(lkr_core::Error::Usage(format!(
+                "--test is not supported for provider '{}' (supported: openai, anthropic).",
+                provider
+            )));
+        }
+        Some(provider)
+    } else {
+        None
+    };
+
+    if kind == KeyKind::Admin && !opts.force && !confirm_admin("store", name) {
+        eprintln!("Cancelled.");
+        return Ok(());
+    }
+
     // Read value from prompt (not CLI args — prevents shell history exposure)
-    // Wrapped in Zeroizing to zero memory on drop.
-    eprint!("Enter API key for {}: ", name);
-    io::stderr().flush().ok();
-    let value = Zeroizing::new(
-        rpassword::read_password()
-            .map_err(|e| lkr_core::Error::Keychain(format!("Failed to read input: {}", e)))?,
-    );
+    let value = read_value(
+        &mut io::stderr(),
+        name,
+        opts.no_prompt,
+        rpassword::read_password,
+    )?;
+    let input = stored_value(&value, opts.no_trim);
+    let input = unquote(input, opts.strip_quotes, opts.no_prompt, || {
+        confirm_default("Strip them? [Y/n] ", true)
+    });
+
+    if let Some(provider) = test_provider {
+        let valid = test_key(&provider, input, endpoint.as_deref())?;
+        if opts.no_store {
+            return if valid {
+                Ok(())
+            } else {
+                Err(lkr_core::Error::Usage(format!(
+                    "{} did not validate.",
+                    name
+                )))
+            };
+        }
+        // An invalid key defaults to not storing it
+        let skip_prompt = valid && opts.force;
+        let prompt = if valid {
+            "Store anyway? [Y/n] "
+        } else {
+            "Store anyway? [y/N] "
+        };
+        if !skip_prompt && !confirm_default(prompt, valid) {
+            eprintln!("Not stored.");
+            return Ok(());
+        }
+    }
+
+    let mut metadata = Vec::new();
+    if let Some(text) = &description {
+        metadata.push(MetadataUpdate::Description(Some(text.clone())));
+    }
+    if let Some(endpoint) = &endpoint {
+        metadata.push(MetadataUpdate::Endpoint(Some(endpoint.clone())));
+    }
+    if let Some(expires_at) = opts.expires_at {
+        metadata.push(MetadataUpdate::Expiry(Some(expires_at)));
+    }
+    store_key(
+        store,
+        name,
+        input,
+        kind,
+        opts.force,
+        lkr_core::SOURCE_INTERACTIVE,
+        &metadata,
+    )?;
+    if let Some(endpoint) = &endpoint {
+        eprintln!("Stored {} (kind: {}, endpoint: {})", name, kind, endpoint);
+    } else {
+        eprintln!("Stored {} (kind: {})", name, kind);
+    }
+    if let Some(expires_at) = opts.expires_at {
+        eprintln!("  Expires {}", expires_at.format("%Y-%m-%d %H:%M UTC"));
+    }
+
+    if opts.print_fingerprint {
+        eprintln!("Fingerprint: {}", stored_fingerprint(store, name)?);
+    }
+    Ok(())
+}
 
-    store.set(name, value.trim(), kind, force)?;
+/// Read the value without echo, after printing the prompt to `prompt_out`
+/// unless `no_prompt`. Wrapped in Zeroizing to zero memory on drop.
+pub(crate) fn read_value(
+    prompt_out: &mut impl Write,
+    name: &str,
+    no_prompt: bool,
+    read: impl FnOnce() -> io::Result<String>,
+) -> lkr_core::Result<Zeroizing<String>> {
+    if !no_prompt {
+        write!(prompt_out, "Enter API key for {}: ", name).ok();
+        prompt_out.flush().ok();
+    }
+    read()
+        .map(Zeroizing::new)
+        .map_err(|e| lkr_core::Error::keychain(format!("Failed to read input: {}", e)))
+}
+
+/// The value to store: trimmed by default, as typed with `--no-trim`. An
+/// empty result is still rejected by the store.
+pub(crate) fn stored_value(input: &str, no_trim: bool) -> &str {
+    if no_trim { input } else { input.trim() }
+}
+
+/// The value inside a pair of matching `"` or `'` quotes wrapping all of
+/// `input`, or None if it isn't wrapped. Borrows, so no unzeroized copy.
+fn quoted_inner(input: &str) -> Option<&str> {
+    ['"', '\''].into_iter().find_map(|quote| {
+        input
+            .strip_prefix(quote)?
+            .strip_suffix(quote)
+            .filter(|inner| !inner.is_empty())
+    })
+}
+
+/// `input` without wrapping quotes if the user wants them gone: always
+/// with `strip_quotes`, else after asking via `confirm`. With `no_prompt`
+/// there is no one to ask, so the prompt's default (strip) applies.
+fn unquote(
+    input: &str,
+    strip_quotes: bool,
+    no_prompt: bool,
+    confirm: impl FnOnce() -> bool,
+) -> &str {
+    let Some(inner) = quoted_inner(input) else {
+        return input;
+    };
+    if strip_quotes {
+        return inner;
+    }
+    if no_prompt {
+        eprintln!("Note: stripped the quotes wrapping the value.");
+        return inner;
+    }
+    eprintln!("Warning: the value is wrapped in quotes (pasted from JSON or a shell?).");
+    if confirm() { inner } else { input }
+}
+
+/// Cut a description down to `MAX_DESCRIPTION_LEN` characters, warning
+/// when anything is dropped.
+pub(crate) fn truncate_description(text: &str) -> String {
+    match text.char_indices().nth(lkr_core::MAX_DESCRIPTION_LEN) {
+        Some((end, _)) => {
+            eprintln!(
+                "Warning: description truncated to {} characters.",
+                lkr_core::MAX_DESCRIPTION_LEN
+            );
+            text[..end].to_string()
+        }
+        None => text.to_string(),
+    }
+}
+
+/// Fingerprint of the value as read back from the store, so it confirms
+/// what was actually written.
+fn stored_fingerprint(store: &impl KeyStore, name: &str) -> lkr_core::Result<String> {
+    let (value, _) = store.get(name)?;
+    Ok(lkr_core::fingerprint(&value))
+}
 
-    eprintln!("Stored {} (kind: {})", name, kind);
+/// Store the value in one write with which input path it came from and
+/// any other `metadata` given (what isn't given is kept from an existing
+/// key).
+fn store_key(
+    store: &impl KeyStore,
+    name: &str,
+    value: &str,
+    kind: KeyKind,
+    force: bool,
+    source: &str,
+    metadata: &[MetadataUpdate],
+) -> lkr_core::Result<()> {
+    let mut updates = vec![MetadataUpdate::Source(source.to_string())];
+    updates.extend_from_slice(metadata);
+    store.set_with(name, value, kind, force, &updates)?;
+    // Advisory only: the key is stored either way
+    if lkr_core::is_nonstandard_admin_label(name, kind) {
+        eprintln!("Warning: {}", lkr_core::ADMIN_LABEL_WARNING);
+    }
     Ok(())
 }
+
+/// Validate a key against its provider and report the verdict on stderr.
+/// Returns whether the key is known to be valid; an inconclusive check
+/// (network error, timeout, unexpected status) counts as not valid.
+fn test_key(provider: &str, value: &str, endpoint: Option<&str>) -> lkr_core::Result<bool> {
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|e| lkr_core::Error::Usage(format!("Failed to start async runtime: {}", e)))?;
+
+    eprintln!(
+        "Validating against {} API{} (timeout {}s)...",
+        provider,
+        endpoint.map_or(String::new(), |url| format!(" at {}", url)),
+        lkr_core::VALIDATE_TIMEOUT.as_secs()
+    );
+    match rt.block_on(lkr_core::validate_key(provider, value, endpoint)) {
+        Ok(validity) => {
+            eprintln!("  {}", validity);
+            Ok(validity == Validity::Valid)
+        }
+        Err(e) => {
+            eprintln!("  UNVERIFIED: {}", e);
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::keymanager::MockStore;
+
+    fn source_of(store: &MockStore, name: &str) -> Option<String> {
+        store
+            .list(true)
+            .unwrap()
+            .into_iter()
+            .find(|e| e.name == name)
+            .and_then(|e| e.source)
+    }
+
+    #[test]
+    fn test_interactive_set_records_source() {
+        let store = MockStore::new();
+        store_key(
+            &store,
+            "openai:prod",
+            "sk-1",
+            KeyKind::Runtime,
+            false,
+            lkr_core::SOURCE_INTERACTIVE,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            source_of(&store, "openai:prod").as_deref(),
+            Some("interactive")
+        );
+    }
+
+    #[test]
+    fn test_unquote_never_prompts_with_no_prompt() {
+        let no_ask = || panic!("must not prompt");
+        assert_eq!(unquote("\"sk-abc\"", false, true, no_ask), "sk-abc");
+        assert_eq!(unquote("\"sk-abc\"", true, false, no_ask), "sk-abc");
+        assert_eq!(unquote("sk-abc", false, false, no_ask), "sk-abc");
+        assert_eq!(unquote("'sk-abc'", false, false, || false), "'sk-abc'");
+        assert_eq!(unquote("'sk-abc'", false, false, || true), "sk-abc");
+    }
+
+    #[test]
+    fn test_quoted_inner() {
+        assert_eq!(quoted_inner("\"sk-abc\""), Some("sk-abc"));
+        assert_eq!(quoted_inner("'sk-abc'"), Some("sk-abc"));
+        assert_eq!(quoted_inner("sk-abc"), None);
+        assert_eq!(quoted_inner("\"sk-abc'"), None);
+        assert_eq!(quoted_inner("\"sk-abc"), None);
+        assert_eq!(quoted_inner("sk-\"abc\""), None);
+        assert_eq!(quoted_inner("\"\""), None);
+        assert_eq!(quoted_inner("\""), None);
+    }
+
+    #[test]
+    fn test_no_trim_keeps_surrounding_whitespace() {
+        let store = MockStore::new();
+        for (name, no_trim) in [("custom:trimmed", false), ("custom:verbatim", true)] {
+            let value = stored_value("  aGVsbG8=\t", no_trim);
+            store_key(
+                &store,
+                name,
+                value,
+                KeyKind::Runtime,
+                false,
+                lkr_core::SOURCE_INTERACTIVE,
+                &[],
+            )
+            .unwrap();
+        }
+        assert_eq!(&*store.get("custom:trimmed").unwrap().0, "aGVsbG8=");
+        assert_eq!(&*store.get("custom:verbatim").unwrap().0, "  aGVsbG8=\t");
+        assert!(matches!(
+            store.set(
+                "custom:empty",
+                stored_value("", true),
+                KeyKind::Runtime,
+                false
+            ),
+            Err(lkr_core::Error::EmptyValue)
+        ));
+    }
+
+    #[test]
+    fn test_description_stored_with_key_and_truncated() {
+        let store = MockStore::new();
+        let long = "é".repeat(lkr_core::MAX_DESCRIPTION_LEN + 10);
+        let description = truncate_description(&long);
+        assert_eq!(description.chars().count(), lkr_core::MAX_DESCRIPTION_LEN);
+        store_key(
+            &store,
+            "google:sa",
+            "{}",
+            KeyKind::Service,
+            false,
+            lkr_core::SOURCE_INTERACTIVE,
+            &[MetadataUpdate::Description(Some(description.clone()))],
+        )
+        .unwrap();
+        let entry = &store.list(true).unwrap()[0];
+        assert_eq!(entry.description.as_deref(), Some(description.as_str()));
+
+        // Replacing the value without --description keeps the note
+        store_key(
+            &store,
+            "google:sa",
+            "{\"v\": 2}",
+            KeyKind::Service,
+            true,
+            lkr_core::SOURCE_INTERACTIVE,
+            &[],
+        )
+        .unwrap();
+        let entry = &store.list(true).unwrap()[0];
+        assert_eq!(entry.description.as_deref(), Some(description.as_str()));
+        assert_eq!(truncate_description("short"), "short");
+    }
+
+    #[test]
+    fn test_store_key_writes_metadata_with_value() {
+        let store = lkr_core::InstrumentedStore::new(MockStore::new());
+        store_key(
+            &store,
+            "openai:proxy",
+            "sk-1",
+            KeyKind::Runtime,
+            false,
+            lkr_core::SOURCE_INTERACTIVE,
+            &[
+                MetadataUpdate::Description(Some("via proxy".to_string())),
+                MetadataUpdate::Endpoint(Some("https://proxy.example.com".to_string())),
+            ],
+        )
+        .unwrap();
+        let metrics = store.metrics();
+        assert_eq!(metrics.calls("set_with"), 1);
+        assert_eq!(metrics.calls("update_metadata"), 0);
+        assert_eq!(metrics.calls("set_endpoint"), 0);
+
+        let entry = store.get_with_metadata("openai:proxy").unwrap();
+        assert_eq!(entry.description.as_deref(), Some("via proxy"));
+        assert_eq!(
+            entry.custom_endpoint.as_deref(),
+            Some("https://proxy.example.com")
+        );
+        assert_eq!(entry.source.as_deref(), Some("interactive"));
+    }
+
+    #[test]
+    fn test_expiry_survives_replacing_the_value() {
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-old", KeyKind::Runtime, false)
+            .unwrap();
+        let expires_at = Utc::now() + chrono::Duration::days(7);
+        // A new value clears the old expiry; the one given is set after
+        store_key(
+            &store,
+            "openai:prod",
+            "sk-new",
+            KeyKind::Runtime,
+            true,
+            lkr_core::SOURCE_INTERACTIVE,
+            &[MetadataUpdate::Expiry(Some(expires_at))],
+        )
+        .unwrap();
+        let entry = store.get_with_metadata("openai:prod").unwrap();
+        assert_eq!(entry.value, "sk-new");
+        assert_eq!(entry.expires_at, Some(expires_at));
+    }
+
+    #[test]
+    fn test_no_prompt_reads_without_prompt_text() {
+        let mut stderr = Vec::new();
+        let value =
+            read_value(&mut stderr, "openai:prod", true, || Ok("sk-1".to_string())).unwrap();
+        assert_eq!(&*value, "sk-1");
+        assert!(stderr.is_empty());
+
+        read_value(&mut stderr, "openai:prod", false, || Ok("sk-1".to_string())).unwrap();
+        assert_eq!(
+            String::from_utf8(stderr).unwrap(),
+            "Enter API key for openai:prod: "
+        );
+    }
+
+    #[test]
+    fn test_stored_fingerprint_matches_helper() {
+        let store = MockStore::new();
+        store_key(
+            &store,
+            "openai:prod",
+            "sk-proj-abc123",
+            KeyKind::Runtime,
+            false,
+            lkr_core::SOURCE_INTERACTIVE,
+            &[],
+        )
+        .unwrap();
+        let printed = stored_fingerprint(&store, "openai:prod").unwrap();
+        assert_eq!(printed, lkr_core::fingerprint("sk-proj-abc123"));
+        assert!(!printed.contains("sk-proj"));
+    }
+
+    #[test]
+    fn test_imported_keys_record_source() {
+        let store = MockStore::new();
+        let mut entries = crate::cmd::import::parse_entries(
+            br#"[{"name": "openai:bulk", "value": "sk-1", "source": "forged"}]"#,
+        )
+        .unwrap();
+        // Input can't choose its own source
+        assert_eq!(entries[0].source, None);
+        entries[0].source = Some(lkr_core::SOURCE_IMPORT.to_string());
+        store
+            .import_batch(&entries, lkr_core::ImportMode::Merge)
+            .unwrap();
+        assert_eq!(source_of(&store, "openai:bulk").as_deref(), Some("import"));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::cmd::import::{parse_entries, read_input};
+use lkr_core::{ImportMode, ImportReport, KeyStore};
+use zeroize::Zeroizing;
+
+/// `lkr set-many --file FILE`: store a batch of `{"name", "value", "kind"}`
+/// entries in one go (`-` reads stdin). Passphrase-encrypted files (as
+/// written by `lkr export`) are decrypted after a single prompt. The whole
+/// batch is validated before any key is written, and a failed write rolls
+/// back the keys already written (best-effort, see `import_batch`).
+pub(crate) fn cmd_set_many(store: &impl KeyStore, file: &str, force: bool) -> lkr_core::Result<()> {
+    let content = read_input(file)?;
+    let Some(report) = set_many(store, content, force, crate::passphrase::read)? else {
+        eprintln!("Nothing to store.");
+        return Ok(());
+    };
+
+    for name in &report.skipped {
+        eprintln!(
+            "  Skipped {} (already exists; use --force to overwrite)",
+            name
+        );
+    }
+    eprintln!(
+        "Stored {} key(s)",
+        report.imported.len() + report.replaced.len()
+    );
+    Ok(())
+}
+
+/// Decode `content` and import its entries, tagged as file-sourced.
+/// `None` if the file holds no entries.
+fn set_many(
+    store: &impl KeyStore,
+    content: Zeroizing<Vec<u8>>,
+    force: bool,
+    passphrase: impl FnOnce() -> lkr_core::Result<Zeroizing<String>>,
+) -> lkr_core::Result<Option<ImportReport>> {
+    let plaintext = decode(content, passphrase)?;
+    let mut entries = parse_entries(&plaintext)?;
+    for entry in &mut entries {
+        entry.source = Some(lkr_core::SOURCE_FILE.to_string());
+    }
+    if entries.is_empty() {
+        return Ok(None);
+    }
+
+    let mode = if force {
+        ImportMode::Replace
+    } else {
+        ImportMode::Merge
+    };
+    store.import_batch(&entries, mode).map(Some)
+}
+
+/// Decrypt `content` if it is an encrypted key file (asking for the
+/// passphrase once); plain JSON passes through with a warning.
+fn decode(
+    content: Zeroizing<Vec<u8>>,
+    passphrase: impl FnOnce() -> lkr_core::Result<Zeroizing<String>>,
+) -> lkr_core::Result<Zeroizing<Vec<u8>>> {
+    if lkr_core::crypto::is_encrypted(&content) {
+        let passphrase = passphrase()?;
+        return lkr_core::crypto::decrypt(&content, &passphrase);
+    }
+    eprintln!("Warning: key file is not encrypted. Delete it once the keys are stored.");
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lkr_core::keymanager::MockStore;
+
+    const ENTRIES: &str = r#"[
+        {"name": "openai:prod", "value": "sk-1", "kind": "runtime"},
+        {"name": "anthropic:main", "value": "sk-ant-1"}
+    ]"#;
+
+    #[test]
+    fn test_decode_encrypted_prompts_once() {
+        let sealed = lkr_core::crypto::encrypt(ENTRIES.as_bytes(), "pw").unwrap();
+        let mut prompts = 0;
+        let plain = decode(Zeroizing::new(sealed), || {
+            prompts += 1;
+            Ok(Zeroizing::new("pw".to_string()))
+        })
+        .unwrap();
+        assert_eq!(prompts, 1);
+        assert_eq!(parse_entries(&plain).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_decode_plain_json_never_prompts() {
+        let plain = decode(Zeroizing::new(ENTRIES.as_bytes().to_vec()), || {
+            panic!("no passphrase needed")
+        })
+        .unwrap();
+        assert_eq!(&*plain, ENTRIES.as_bytes());
+    }
+
+    #[test]
+    fn test_invalid_entry_aborts_whole_batch() {
+        let store = MockStore::new();
+        let entries = parse_entries(
+            br#"[
+                {"name": "openai:prod", "value": "sk-1"},
+                {"name": "bad name", "value": "sk-2"}
+            ]"#,
+        )
+        .unwrap();
+        assert!(store.import_batch(&entries, ImportMode::Merge).is_err());
+        assert!(store.list(true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_encrypted_file_roundtrips_into_store() {
+        let sealed = lkr_core::crypto::encrypt(ENTRIES.as_bytes(), "pw").unwrap();
+        let store = MockStore::new();
+        let report = set_many(&store, Zeroizing::new(sealed), false, || {
+            Ok(Zeroizing::new("pw".to_string()))
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(report.imported.len(), 2);
+
+        let stored = store.get_with_metadata("openai:prod").unwrap();
+        assert_eq!(stored.value, "sk-1");
+        assert_eq!(stored.kind, lkr_core::KeyKind::Runtime);
+        assert_eq!(stored.source.as_deref(), Some(lkr_core::SOURCE_FILE));
+        assert_eq!(&*store.get("anthropic:main").unwrap().0, "sk-ant-1");
+
+        let sealed = lkr_core::crypto::encrypt(ENTRIES.as_bytes(), "pw").unwrap();
+        let other = MockStore::new();
+        let wrong = set_many(&other, Zeroizing::new(sealed), false, || {
+            Ok(Zeroizing::new("not-pw".to_string()))
+        });
+        assert!(wrong.is_err());
+        assert!(other.list(true).unwrap().is_empty());
+    }
+}
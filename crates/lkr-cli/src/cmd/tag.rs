@@ -0,0 +1,63 @@
+use lkr_core::KeyStore;
+
+/// `lkr tag NAME [+TAG|-TAG]...`: change a key's tags and print the result.
+/// Without changes, only prints them.
+pub(crate) fn cmd_tag(
+    store: &impl KeyStore,
+    name: &str,
+    changes: &[String],
+    json: bool,
+) -> lkr_core::Result<()> {
+    let (add, remove) = parse_changes(changes)?;
+    let tags = if changes.is_empty() {
+        lkr_core::validate_key_name(name)?;
+        store.get_with_metadata(name)?.tags.clone()
+    } else {
+        store.with_locked(|s| s.update_tags(name, &add, &remove))?
+    };
+    if json {
+        println!("{}", serde_json::json!({"name": name, "tags": tags}));
+    } else if tags.is_empty() {
+        println!("{}: no tags", name);
+    } else {
+        println!("{}: {}", name, tags.join(", "));
+    }
+    Ok(())
+}
+
+/// Split `+tag` / `-tag` arguments into tags to add and to remove.
+fn parse_changes(changes: &[String]) -> lkr_core::Result<(Vec<String>, Vec<String>)> {
+    let mut add = Vec::new();
+    let mut remove = Vec::new();
+    for change in changes {
+        if let Some(tag) = change.strip_prefix('+') {
+            add.push(tag.to_string());
+        } else if let Some(tag) = change.strip_prefix('-') {
+            remove.push(tag.to_string());
+        } else {
+            return Err(lkr_core::Error::Usage(format!(
+                "Invalid tag change '{}': use +TAG to add or -TAG to remove",
+                change
+            )));
+        }
+    }
+    Ok((add, remove))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_changes() {
+        let (add, remove) = parse_changes(&args(&["+prod", "-staging", "+ci"])).unwrap();
+        assert_eq!(add, ["prod", "ci"]);
+        assert_eq!(remove, ["staging"]);
+        assert!(parse_changes(&args(&["prod"])).is_err());
+        assert_eq!(parse_changes(&[]).unwrap(), (vec![], vec![]));
+    }
+}
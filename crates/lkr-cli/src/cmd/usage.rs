@@ -1,12 +1,69 @@
-use lkr_core::KeyStore;
+use crate::format::{OutputFormat, csv_field};
+use lkr_core::{CostReport, EndpointsConfig, FetchOptions, FetchProgress, KeyStore, ModelSort};
+use std::io::{self, IsTerminal};
+use std::sync::mpsc;
+
+/// Flags for `lkr usage`, bundled so new options don't grow the signature.
+#[derive(Default)]
+pub(crate) struct UsageOptions {
+    pub refresh: bool,
+    /// Bypass the response cache entirely (`--no-cache`)
+    pub no_cache: bool,
+    pub compare_previous: bool,
+    /// `NAME:VALUE` headers for every request (`--header`)
+    pub headers: Vec<String>,
+    /// Fold line items under this many cents into "Other" (`--min-cost`)
+    pub min_cost: Option<f64>,
+    /// `--by-model` with its `--sort` order
+    pub model_sort: Option<ModelSort>,
+    /// Attribute cost to projects (`--breakdown-by-key`)
+    pub by_project: bool,
+    /// Read provider settings from here instead of the default
+    /// endpoints.toml (`--provider-config`)
+    pub provider_config: Option<String>,
+    /// Note rate-limit retries on stderr (`--verbose`)
+    pub verbose: bool,
+}
 
 pub(crate) fn cmd_usage(
     store: &impl KeyStore,
     provider: Option<&str>,
-    refresh: bool,
-    json: bool,
+    opts: &UsageOptions,
+    format: OutputFormat,
 ) -> lkr_core::Result<()> {
+    if opts.compare_previous && format == OutputFormat::Csv {
+        return Err(lkr_core::Error::Usage(
+            "--format csv is not supported with --compare-previous".to_string(),
+        ));
+    }
+    let json = format == OutputFormat::Json;
     let cache = lkr_core::UsageCache::default();
+    let config = match &opts.provider_config {
+        Some(path) => {
+            let path = std::path::Path::new(path);
+            if !path.exists() {
+                return Err(lkr_core::Error::Usage(format!(
+                    "Provider config '{}' not found",
+                    path.display()
+                )));
+            }
+            EndpointsConfig::load(path)?
+        }
+        None => EndpointsConfig::load(&EndpointsConfig::default_path()?)?,
+    };
+    let cli_headers = opts
+        .headers
+        .iter()
+        .map(|h| lkr_core::parse_header(h))
+        .collect::<lkr_core::Result<Vec<_>>>()?;
+    let fetch_opts = |provider: &str| FetchOptions {
+        refresh: opts.refresh,
+        no_cache: opts.no_cache,
+        extra_headers: merge_headers(config.extra_headers(provider), &cli_headers),
+        min_cost_cents: opts.min_cost,
+        by_project: opts.by_project,
+        custom_usage: config.custom_usage(provider).cloned(),
+    };
 
     let rt = tokio::runtime::Runtime::new()
         .map_err(|e| lkr_core::Error::Usage(format!("Failed to start async runtime: {}", e)))?;
@@ -26,11 +83,53 @@ pub(crate) fn cmd_usage(
         }
     };
 
+    if opts.compare_previous {
+        return cmd_usage_compare(
+            store,
+            &rt,
+            &providers,
+            &cache,
+            fetch_opts,
+            json,
+            opts.verbose,
+        );
+    }
+
+    // Live status line while fetching; stdout stays clean for piping
+    let show_progress = format == OutputFormat::Table && io::stderr().is_terminal();
+
     let mut reports = Vec::new();
     let mut errors = Vec::new();
     for p in &providers {
-        match rt.block_on(lkr_core::fetch_cost(store, p, &cache, refresh)) {
-            Ok(report) => reports.push(report),
+        let fetched = if show_progress || opts.verbose {
+            let (tx, rx) = mpsc::channel();
+            let verbose = opts.verbose;
+            let spinner = std::thread::spawn(move || {
+                if show_progress {
+                    print_progress(rx, verbose)
+                } else {
+                    print_retries(rx)
+                }
+            });
+            let fetched = rt.block_on(lkr_core::fetch_cost_with_progress(
+                store,
+                p,
+                &cache,
+                &fetch_opts(p),
+                tx,
+            ));
+            let _ = spinner.join();
+            fetched
+        } else {
+            rt.block_on(lkr_core::fetch_cost(store, p, &cache, &fetch_opts(p)))
+        };
+        match fetched {
+            Ok(mut report) => {
+                if let Some(sort) = opts.model_sort {
+                    report.models = report.model_breakdown(sort);
+                }
+                reports.push(report);
+            }
             Err(e) => {
                 eprintln!("  {}: {}", p, e);
                 errors.push(e);
@@ -38,8 +137,12 @@ pub(crate) fn cmd_usage(
         }
     }
 
-    if json {
-        println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+    if format != OutputFormat::Table {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+        } else {
+            print!("{}", usage_csv(&reports));
+        }
         // Return error if ALL fetches failed (partial success is OK)
         if reports.is_empty() && !errors.is_empty() {
             return Err(errors.remove(0));
@@ -63,12 +166,10 @@ pub(crate) fn cmd_usage(
         );
         println!("  {}", "-".repeat(50));
 
-        for item in &report.line_items {
-            println!(
-                "    {:<30} {}",
-                item.description,
-                lkr_core::format_cost(item.cost_cents)
-            );
+        if opts.model_sort.is_some() {
+            print_models(report);
+        } else {
+            print_line_items(report);
         }
 
         println!(
@@ -90,3 +191,279 @@ pub(crate) fn cmd_usage(
     println!();
     Ok(())
 }
+
+/// `--format csv`: one row per line item. Token columns are empty when the
+/// provider did not report them.
+fn usage_csv(reports: &[CostReport]) -> String {
+    let mut out = String::from(
+        "provider,period_start,period_end,description,cost_usd,input_tokens,output_tokens\n",
+    );
+    let count = |n: Option<u64>| n.map_or(String::new(), |n| n.to_string());
+    for report in reports {
+        for item in &report.line_items {
+            out.push_str(&format!(
+                "{},{},{},{},{:.2},{},{}\n",
+                csv_field(&report.provider),
+                report.period_start,
+                report.period_end,
+                csv_field(&item.description),
+                item.cost_cents / 100.0,
+                count(item.input_tokens),
+                count(item.output_tokens)
+            ));
+        }
+    }
+    out
+}
+
+/// Line item rows, with a Tokens column when any item has token counts.
+fn print_line_items(report: &CostReport) {
+    if !report.line_items.iter().any(|i| i.has_tokens()) {
+        for item in &report.line_items {
+            println!(
+                "    {:<30} {}",
+                item.description,
+                lkr_core::format_cost(item.cost_cents)
+            );
+        }
+        return;
+    }
+    println!("    {:<30} {:<12} Tokens", "", "Cost");
+    for item in &report.line_items {
+        let tokens = if item.has_tokens() {
+            format!(
+                "{} in / {} out",
+                item.input_tokens.unwrap_or(0),
+                item.output_tokens.unwrap_or(0)
+            )
+        } else {
+            "-".to_string()
+        };
+        println!(
+            "    {:<30} {:<12} {}",
+            item.description,
+            lkr_core::format_cost(item.cost_cents),
+            tokens
+        );
+    }
+}
+
+/// `--by-model` rows; token counts only when the provider reported them.
+fn print_models(report: &CostReport) {
+    for model in &report.models {
+        let tokens = match (model.input_tokens, model.output_tokens) {
+            (None, None) => String::new(),
+            (i, o) => format!("  {} in / {} out tokens", i.unwrap_or(0), o.unwrap_or(0)),
+        };
+        println!(
+            "    {:<30} {}{}",
+            model.name,
+            lkr_core::format_cost(model.cost_cents),
+            tokens
+        );
+    }
+}
+
+/// `lkr usage --compare-previous`: current month vs. the previous calendar month.
+fn cmd_usage_compare(
+    store: &impl KeyStore,
+    rt: &tokio::runtime::Runtime,
+    providers: &[String],
+    cache: &lkr_core::UsageCache,
+    fetch_opts: impl Fn(&str) -> FetchOptions,
+    json: bool,
+    verbose: bool,
+) -> lkr_core::Result<()> {
+    let mut diffs = Vec::new();
+    let mut errors = Vec::new();
+    for p in providers {
+        let opts = fetch_opts(p);
+        let (tx, rx) = mpsc::channel();
+        let printer = verbose.then(|| std::thread::spawn(move || print_retries(rx)));
+        let fetched = rt.block_on(async {
+            let current = lkr_core::fetch_cost_with_progress(store, p, cache, &opts, tx.clone());
+            let current = current.await?;
+            let previous = lkr_core::fetch_previous_cost_with_progress(store, p, &opts, tx);
+            let previous = previous.await?;
+            Ok::<_, lkr_core::Error>(lkr_core::diff_reports(&current, &previous))
+        });
+        if let Some(printer) = printer {
+            let _ = printer.join();
+        }
+        match fetched {
+            Ok(diff) => diffs.push(diff),
+            Err(e) => {
+                eprintln!("  {}: {}", p, e);
+                errors.push(e);
+            }
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diffs).unwrap());
+        if diffs.is_empty() && !errors.is_empty() {
+            return Err(errors.remove(0));
+        }
+        return Ok(());
+    }
+
+    if diffs.is_empty() {
+        if errors.is_empty() {
+            eprintln!("No usage data available.");
+            return Ok(());
+        }
+        return Err(errors.remove(0));
+    }
+
+    for diff in &diffs {
+        println!(
+            "\n  {} — {} to {} vs {} to {}",
+            diff.current.provider,
+            diff.current.period_start,
+            diff.current.period_end,
+            diff.previous.period_start,
+            diff.previous.period_end
+        );
+        println!("  {}", "-".repeat(66));
+        println!(
+            "    {:<30} {:>10} {:>10} {:>10}",
+            "", "Current", "Previous", "Change"
+        );
+
+        for item in &diff.line_item_diffs {
+            println!(
+                "    {:<30} {:>10} {:>10} {:>+9.1}%",
+                item.description,
+                lkr_core::format_cost(item.current_cents),
+                lkr_core::format_cost(item.previous_cents),
+                item.delta_pct
+            );
+        }
+
+        println!(
+            "  {:<32} {:>10} {:>10} {:>+9.1}%",
+            "Total",
+            lkr_core::format_cost(diff.current.total_cost_cents),
+            lkr_core::format_cost(diff.previous.total_cost_cents),
+            diff.delta_pct
+        );
+    }
+
+    println!();
+    Ok(())
+}
+
+/// Render fetch progress as a single, self-erasing stderr line; with
+/// `verbose`, rate-limit retries are also kept as lines of their own.
+/// Returns once the sender is dropped (fetch finished or failed).
+fn print_progress(rx: mpsc::Receiver<FetchProgress>, verbose: bool) {
+    let mut provider = String::new();
+    for event in rx {
+        let stage = match event {
+            FetchProgress::Started { provider: p } => {
+                provider = p;
+                "starting"
+            }
+            FetchProgress::RequestSent => "requesting",
+            FetchProgress::RateLimited { retry_in_secs } => {
+                if verbose {
+                    eprintln!("\r\x1b[2K{}", retry_note(&provider, retry_in_secs));
+                }
+                "rate limited, retrying"
+            }
+            FetchProgress::ResponseReceived => "response received",
+            FetchProgress::Parsing => "parsing",
+            FetchProgress::Done => "done",
+        };
+        eprint!("\r\x1b[2K  {}: {}...", provider, stage);
+    }
+    eprint!("\r\x1b[2K");
+}
+
+/// `--verbose` without the status line: print each rate-limit retry.
+fn print_retries(rx: mpsc::Receiver<FetchProgress>) {
+    let mut provider = String::new();
+    for event in rx {
+        match event {
+            FetchProgress::Started { provider: p } => provider = p,
+            FetchProgress::RateLimited { retry_in_secs } => {
+                eprintln!("{}", retry_note(&provider, retry_in_secs))
+            }
+            _ => {}
+        }
+    }
+}
+
+fn retry_note(provider: &str, retry_in_secs: u64) -> String {
+    format!("  {provider}: rate limited, retrying in {retry_in_secs}s...")
+}
+
+/// Config headers for a provider, overridden (case-insensitively) by the
+/// per-invocation `--header` values.
+fn merge_headers(
+    mut configured: Vec<(String, String)>,
+    cli: &[(String, String)],
+) -> Vec<(String, String)> {
+    configured.retain(|(name, _)| !cli.iter().any(|(n, _)| n.eq_ignore_ascii_case(name)));
+    configured.extend(cli.iter().cloned());
+    configured
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(name: &str, value: &str) -> (String, String) {
+        (name.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_usage_csv_rows() {
+        let report = CostReport {
+            provider: "openai".to_string(),
+            period_start: "2026-02-01".to_string(),
+            period_end: "2026-02-28".to_string(),
+            total_cost_cents: 1250.0,
+            currency: "usd".to_string(),
+            line_items: vec![
+                lkr_core::CostLineItem {
+                    description: "gpt-4o, input".to_string(),
+                    cost_cents: 1200.0,
+                    input_tokens: Some(4000),
+                    output_tokens: Some(0),
+                    currency: "usd".to_string(),
+                },
+                lkr_core::CostLineItem {
+                    description: "web search".to_string(),
+                    cost_cents: 50.0,
+                    input_tokens: None,
+                    output_tokens: None,
+                    currency: "usd".to_string(),
+                },
+            ],
+            models: Vec::new(),
+        };
+        assert_eq!(
+            usage_csv(&[report]),
+            "provider,period_start,period_end,description,cost_usd,input_tokens,output_tokens\n\
+             openai,2026-02-01,2026-02-28,\"gpt-4o, input\",12.00,4000,0\n\
+             openai,2026-02-01,2026-02-28,web search,0.50,,\n"
+        );
+    }
+
+    #[test]
+    fn test_cli_headers_override_config() {
+        let merged = merge_headers(
+            vec![pair("X-Cost-Center", "eng"), pair("X-Team", "core")],
+            &[pair("x-cost-center", "ops"), pair("X-Request-ID", "1")],
+        );
+        assert_eq!(
+            merged,
+            vec![
+                pair("X-Team", "core"),
+                pair("x-cost-center", "ops"),
+                pair("X-Request-ID", "1")
+            ]
+        );
+    }
+}
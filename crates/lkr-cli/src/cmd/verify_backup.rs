@@ -0,0 +1,159 @@
+use crate::cmd::import::read_input;
+use chrono::{DateTime, Local};
+use lkr_core::ImportEntry;
+use zeroize::Zeroizing;
+
+/// What `lkr verify-backup` found. Values are never reported.
+#[derive(Debug)]
+struct BackupReport {
+    encrypted: bool,
+    valid: usize,
+    /// `(name, reason)` for each entry that would fail to import
+    invalid: Vec<(String, String)>,
+}
+
+/// `lkr verify-backup PATH`: decrypt a key file (age, see `lkr_core::crypto`)
+/// and check every entry would import, without writing anything to the store.
+pub(crate) fn cmd_verify_backup(path: &str, json: bool) -> lkr_core::Result<()> {
+    let content = read_input(path)?;
+    // The file format has no timestamp; the file's mtime is the best we have
+    let created = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(DateTime::<Local>::from);
+    let report = verify(content, crate::passphrase::read)?;
+
+    if json {
+        let invalid: Vec<_> = report
+            .invalid
+            .iter()
+            .map(|(name, reason)| serde_json::json!({"name": name, "reason": reason}))
+            .collect();
+        let out = serde_json::json!({
+            "path": path,
+            "encrypted": report.encrypted,
+            "valid": report.valid,
+            "invalid": invalid,
+            "created": created.map(|c| c.to_rfc3339()),
+        });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap());
+    } else if report.invalid.is_empty() {
+        if !report.encrypted {
+            eprintln!("Warning: {} is not encrypted.", path);
+        }
+        let created = created.map_or(String::new(), |c| {
+            format!(", created {}", c.format("%Y-%m-%d %H:%M"))
+        });
+        println!("Backup verified: {} valid entries{}", report.valid, created);
+    } else {
+        for (name, reason) in &report.invalid {
+            eprintln!("  Invalid: {} ({})", name, reason);
+        }
+    }
+
+    if !report.invalid.is_empty() {
+        return Err(lkr_core::Error::Usage(format!(
+            "Backup check failed: {} of {} entries invalid",
+            report.invalid.len(),
+            report.invalid.len() + report.valid
+        )));
+    }
+    Ok(())
+}
+
+fn verify(
+    content: Zeroizing<Vec<u8>>,
+    passphrase: impl FnOnce() -> lkr_core::Result<Zeroizing<String>>,
+) -> lkr_core::Result<BackupReport> {
+    let encrypted = lkr_core::crypto::is_encrypted(&content);
+    let plaintext = if encrypted {
+        lkr_core::crypto::decrypt(&content, &passphrase()?)?
+    } else {
+        content
+    };
+    let entries: Vec<ImportEntry> = serde_json::from_slice(&plaintext).map_err(|e| {
+        lkr_core::Error::Usage(format!(
+            "Backup is not a valid key list: {}",
+            lkr_core::json_error_summary(&e)
+        ))
+    })?;
+
+    let mut report = BackupReport {
+        encrypted,
+        valid: 0,
+        invalid: Vec::new(),
+    };
+    for entry in &entries {
+        let problem = match lkr_core::validate_key_name(&entry.name) {
+            Err(lkr_core::Error::InvalidKeyName { reason, .. }) => Some(reason),
+            Err(e) => Some(e.to_string()),
+            Ok(()) if entry.value.is_empty() => Some("empty value".to_string()),
+            Ok(()) => None,
+        };
+        match problem {
+            Some(reason) => report.invalid.push((entry.name.clone(), reason)),
+            None => report.valid += 1,
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTRIES: &str = r#"[
+        {"name": "openai:prod", "value": "sk-1", "kind": "runtime"},
+        {"name": "anthropic:main", "value": "sk-ant-1"}
+    ]"#;
+
+    fn sealed(plaintext: &[u8]) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(lkr_core::crypto::encrypt(plaintext, "pw").unwrap())
+    }
+
+    fn pw() -> lkr_core::Result<Zeroizing<String>> {
+        Ok(Zeroizing::new("pw".to_string()))
+    }
+
+    #[test]
+    fn test_verify_counts_valid_entries() {
+        let report = verify(sealed(ENTRIES.as_bytes()), pw).unwrap();
+        assert!(report.encrypted);
+        assert_eq!(report.valid, 2);
+        assert!(report.invalid.is_empty());
+    }
+
+    #[test]
+    fn test_verify_reports_invalid_names_and_empty_values() {
+        let report = verify(
+            sealed(
+                br#"[
+                    {"name": "openai:prod", "value": "sk-1"},
+                    {"name": "bad name", "value": "sk-2"},
+                    {"name": "openai:empty", "value": ""}
+                ]"#,
+            ),
+            pw,
+        )
+        .unwrap();
+        assert_eq!(report.valid, 1);
+        let names: Vec<_> = report.invalid.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["bad name", "openai:empty"]);
+    }
+
+    #[test]
+    fn test_verify_specific_errors() {
+        let wrong = verify(sealed(ENTRIES.as_bytes()), || {
+            Ok(Zeroizing::new("nope".to_string()))
+        });
+        assert!(wrong.unwrap_err().to_string().contains("wrong passphrase"));
+
+        let mut truncated = sealed(ENTRIES.as_bytes());
+        truncated.truncate(40); // inside the header
+        let err = verify(truncated, pw).unwrap_err();
+        assert!(err.to_string().contains("truncated"));
+
+        let err = verify(sealed(b"{not json"), pw).unwrap_err();
+        assert!(err.to_string().contains("not a valid key list"));
+    }
+}
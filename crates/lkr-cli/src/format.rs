@@ -0,0 +1,68 @@
+//! `--format` for commands with more than one output shape.
+
+/// How a command prints its result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Aligned columns for reading in a terminal
+    #[default]
+    Table,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Pretty-printed JSON
+    Json,
+}
+
+impl OutputFormat {
+    /// The explicit `--format`, else `json` for the deprecated global
+    /// `--json` (with a warning on stderr), else `table`.
+    pub(crate) fn resolve(format: Option<Self>, json: bool, command: &str) -> Self {
+        match format {
+            Some(format) => format,
+            None if json => {
+                eprintln!(
+                    "Warning: `lkr {} --json` is deprecated; use `--format json`.",
+                    command
+                );
+                Self::Json
+            }
+            None => Self::Table,
+        }
+    }
+}
+
+/// One CSV field, quoted when it contains a comma, quote or line break.
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("gpt-4o"), "gpt-4o");
+        assert_eq!(csv_field("gpt-4o, input"), "\"gpt-4o, input\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_resolve_prefers_explicit_format() {
+        assert_eq!(
+            OutputFormat::resolve(Some(OutputFormat::Csv), true, "usage"),
+            OutputFormat::Csv
+        );
+        assert_eq!(
+            OutputFormat::resolve(None, true, "usage"),
+            OutputFormat::Json
+        );
+        assert_eq!(
+            OutputFormat::resolve(None, false, "usage"),
+            OutputFormat::Table
+        );
+    }
+}
@@ -2,7 +2,11 @@ use clap::{Parser, Subcommand};
 use lkr_core::{KeyStore, KeychainStore};
 use std::io::{self, IsTerminal};
 
+mod clipboard;
 mod cmd;
+mod format;
+mod passphrase;
+mod prompt;
 mod util;
 
 #[derive(Parser)]
@@ -19,6 +23,10 @@ struct Cli {
     /// Output as JSON
     #[arg(long, global = true)]
     json: bool,
+
+    /// Print how many store calls were made and how long they took (stderr)
+    #[arg(long, global = true)]
+    timings: bool,
 }
 
 #[derive(Subcommand)]
@@ -28,11 +36,81 @@ enum Commands {
         /// Key name in provider:label format (e.g. openai:prod)
         name: String,
 
-        /// Key kind: runtime (default) or admin
+        /// Key kind: runtime (default), admin, or service (shared credential:
+        /// usable in templates, hidden from list and exec by default)
         #[arg(long, default_value = "runtime")]
         kind: String,
 
-        /// Overwrite existing key without confirmation
+        /// Overwrite existing key without confirmation, and skip the typed
+        /// confirmation for admin keys (with --test: also store without
+        /// asking once the key validates)
+        #[arg(long)]
+        force: bool,
+
+        /// Validate the key against the provider API before storing
+        #[arg(long)]
+        test: bool,
+
+        /// With --test: validate only, never store
+        #[arg(long, requires = "test")]
+        no_store: bool,
+
+        /// Custom API base URL for this key (enterprise proxy, self-hosted)
+        #[arg(long, value_name = "URL")]
+        endpoint: Option<String>,
+
+        /// After storing, print the key's fingerprint (hash prefix, never the value)
+        #[arg(long)]
+        print_fingerprint: bool,
+
+        /// Store the value exactly as typed, keeping leading/trailing whitespace
+        #[arg(long)]
+        no_trim: bool,
+
+        /// Note stored with the key (max 512 characters; longer text is truncated)
+        #[arg(long, value_name = "TEXT", visible_aliases = ["comment", "note"])]
+        description: Option<String>,
+
+        /// Read the value (still without echo) but print no prompt, for wrappers with their own UI
+        #[arg(long)]
+        no_prompt: bool,
+
+        /// Strip quotes wrapping the whole value without asking (e.g. pasted "sk-...")
+        #[arg(long)]
+        strip_quotes: bool,
+
+        /// Expire the key after DURATION (e.g. 3600s, 12h, 30d); `get` then refuses it
+        #[arg(long, value_name = "DURATION", value_parser = util::parse_duration)]
+        expires_in: Option<std::time::Duration>,
+
+        /// Expire the key at an RFC 3339 time (e.g. 2026-12-31T23:59:00Z)
+        #[arg(
+            long,
+            value_name = "TIME",
+            value_parser = util::parse_timestamp,
+            conflicts_with = "expires_in"
+        )]
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    },
+
+    /// Store many keys at once from a JSON file (optionally passphrase-encrypted)
+    SetMany {
+        /// File with [{"name", "value", "kind"}, ...], or - for stdin
+        #[arg(long)]
+        file: String,
+
+        /// Overwrite existing keys (default: keep them and report as skipped)
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Store keys from a JSON or TOML file, continuing past bad entries
+    BatchSet {
+        /// JSON [{"name", "value", "kind"}, ...] or TOML [[key]] tables, or - for stdin
+        #[arg(long)]
+        file: String,
+
+        /// Overwrite existing keys (default: keep them and report as skipped)
         #[arg(long)]
         force: bool,
     },
@@ -53,14 +131,89 @@ enum Commands {
         /// Force raw output even in non-interactive environments (use with caution)
         #[arg(long)]
         force_plain: bool,
+
+        /// Clear the clipboard after SECONDS (0 disables). Overrides LKR_CLIPBOARD_CLEAR.
+        #[arg(long, value_name = "SECONDS")]
+        clear_after: Option<u32>,
+
+        /// Do not auto-clear the clipboard
+        #[arg(long, conflicts_with = "clear_after")]
+        no_clear: bool,
+
+        /// Target selection: clipboard, or primary (X11/Wayland middle-click; clipboard elsewhere)
+        #[arg(long, value_enum, default_value_t = clipboard::Selection::Clipboard)]
+        selection: clipboard::Selection,
+
+        /// Record this read (last access time and access count) in the key's metadata
+        #[arg(long)]
+        count_access: bool,
+
+        /// Exit 1 if the clipboard copy fails or is skipped (instead of only warning)
+        #[arg(long, conflicts_with_all = ["plain", "force_plain"])]
+        require_clipboard: bool,
+
+        /// Wait for a locked Keychain to be unlocked, optionally giving up after SECONDS
+        #[arg(long, value_name = "SECONDS", num_args = 0..=1)]
+        wait: Option<Option<u64>>,
+
+        /// Output format: env prints VAR=value (reveals the value; guarded like --plain)
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            value_parser = ["env"],
+            conflicts_with_all = ["show", "plain", "require_clipboard"]
+        )]
+        format: Option<String>,
+
+        /// Variable name for --format env instead of the derived one (implies --format env)
+        #[arg(
+            long,
+            value_name = "NAME",
+            conflicts_with_all = ["show", "plain", "require_clipboard"]
+        )]
+        to_env_name: Option<String>,
     },
 
     /// List stored keys
     #[command(alias = "ls")]
     List {
-        /// Include admin keys
+        /// Include admin and service keys
         #[arg(long)]
         all: bool,
+
+        /// Only show keys not accessed within DURATION (e.g. 30d, 12h), including never-accessed keys
+        #[arg(long, value_name = "DURATION", value_parser = util::parse_duration)]
+        unused_since: Option<std::time::Duration>,
+
+        /// Also show created/rotated times (adds timestamps to --json)
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Print the JSON Schema of the --json output and exit (no Keychain access)
+        #[arg(long)]
+        json_schema: bool,
+
+        /// Also list expired keys, flagged [EXPIRED] (--json always includes them)
+        #[arg(long)]
+        show_expired: bool,
+
+        /// Only keys with this tag
+        #[arg(long, value_name = "TAG")]
+        tag: Option<String>,
+
+        /// Row order: name, or age (oldest created first)
+        #[arg(long, default_value = "name", value_parser = ["name", "age"])]
+        sort: String,
+    },
+
+    /// Add (+TAG) or remove (-TAG) tags on a key; with no changes, print its tags
+    Tag {
+        /// Key name in provider:label format
+        name: String,
+
+        /// Changes, e.g. +prod -staging
+        #[arg(allow_hyphen_values = true, value_name = "+TAG|-TAG")]
+        changes: Vec<String>,
     },
 
     /// Remove a key from Keychain
@@ -68,9 +221,147 @@ enum Commands {
         /// Key name in provider:label format
         name: String,
 
-        /// Skip confirmation prompt
+        /// Skip confirmation prompt (including the typed confirmation for admin keys)
         #[arg(long)]
         force: bool,
+
+        /// Succeed if the key does not exist (idempotent cleanup)
+        #[arg(long)]
+        if_exists: bool,
+
+        /// Then regenerate or delete lkr-generated files here that used the key
+        #[arg(long)]
+        cascade: bool,
+
+        /// With --cascade: delete those files without asking
+        #[arg(long, requires = "cascade")]
+        cascade_delete: bool,
+
+        /// Print the keys that would be removed (NAME may be a glob like openai:*); delete nothing
+        #[arg(long, conflicts_with_all = ["force", "cascade"])]
+        dry_run: bool,
+    },
+
+    /// Rename a key, keeping its value and kind (no need to re-enter the secret)
+    Rename {
+        /// Current key name
+        old_name: String,
+
+        /// New key name in provider:label format
+        new_name: String,
+
+        /// Overwrite the new name if a key already has it
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Replace a key's value (prompted, never an argument), keeping its kind and metadata
+    Rotate {
+        /// Key name in provider:label format
+        name: String,
+
+        /// Change the kind as well: runtime, admin or service
+        #[arg(long)]
+        kind: Option<String>,
+
+        /// Replace the key's note (max 512 characters)
+        #[arg(long, value_name = "TEXT", visible_alias = "comment")]
+        description: Option<String>,
+    },
+
+    /// Change a key's kind without re-entering its value
+    Chkind {
+        /// Key name in provider:label format
+        name: String,
+
+        /// New kind: runtime, admin or service
+        #[arg(long)]
+        kind: String,
+
+        /// Skip the typed confirmation when promoting to admin
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Show all stored metadata for one key (the value only masked)
+    Info {
+        /// Key name in provider:label format
+        name: String,
+    },
+
+    /// Fail if any key has expired or expires soon (for CI and pre-commit hooks)
+    CheckExpiry {
+        /// How far ahead counts as expiring soon (e.g. 48h, 7d)
+        #[arg(long, value_name = "DURATION", default_value = "48h", value_parser = util::parse_duration)]
+        within: std::time::Duration,
+    },
+
+    /// Import keys from a JSON file: [{"name", "value", "kind"}, ...]
+    Import {
+        /// JSON file path, or - for stdin
+        #[arg(required_unless_present_any = ["from_env", "from_lkr"])]
+        file: Option<String>,
+
+        /// FILE is a manifest from `lkr export --names-only`: prompt for each key's value
+        #[arg(long, requires = "file", conflicts_with = "mode")]
+        names_only: bool,
+
+        /// With --names-only: skip keys that already exist instead of failing
+        #[arg(long, requires = "names_only")]
+        skip_existing: bool,
+
+        /// Import known provider env vars (e.g. OPENAI_API_KEY) from the current environment
+        #[arg(long, conflicts_with_all = ["file", "from_lkr"])]
+        from_env: bool,
+
+        /// Import a passphrase-encrypted file written by `lkr export`
+        #[arg(long, value_name = "PATH", conflicts_with = "file")]
+        from_lkr: Option<String>,
+
+        /// Read the --from-lkr passphrase from the first line of stdin
+        #[arg(long, requires = "from_lkr")]
+        passphrase_stdin: bool,
+
+        /// Label for keys imported with --from-env (stored as <provider>:<label>)
+        #[arg(long, requires = "from_env")]
+        label: Option<String>,
+
+        /// Import every found env var without asking
+        #[arg(long, requires = "from_env")]
+        yes: bool,
+
+        /// Existing keys: merge (keep, default), replace (overwrite), or skip (keep and list them)
+        #[arg(long, default_value = "merge")]
+        mode: String,
+    },
+
+    /// Export all keys to a passphrase-encrypted file (for migrations)
+    #[command(group = clap::ArgGroup::new("mode").required(true))]
+    Export {
+        /// File to write (created with mode 0600; --names-only defaults to stdout)
+        #[arg(
+            short,
+            long,
+            value_name = "FILE",
+            required_unless_present = "names_only"
+        )]
+        output: Option<String>,
+
+        /// Prompt for the passphrase to encrypt with
+        #[arg(long, group = "mode")]
+        passphrase: bool,
+
+        /// Read the passphrase from the first line of stdin
+        #[arg(long, group = "mode")]
+        passphrase_stdin: bool,
+
+        /// Write an unencrypted manifest of names, kinds and providers only (no values)
+        #[arg(long, group = "mode")]
+        names_only: bool,
+
+        /// Overwrite an existing file; also allows exporting from a non-interactive environment
+        #[arg(long, conflicts_with = "names_only")]
+        force: bool,
     },
 
     /// Show API usage costs for the current month
@@ -81,20 +372,173 @@ enum Commands {
         /// Force fresh API fetch (reserved for future file-based caching)
         #[arg(long)]
         refresh: bool,
+
+        /// Neither read nor write the response cache (for debugging)
+        #[arg(long, conflicts_with = "refresh")]
+        no_cache: bool,
+
+        /// Compare against the previous calendar month
+        #[arg(long)]
+        compare_previous: bool,
+
+        /// Extra request header NAME:VALUE (repeatable; overrides endpoints.toml)
+        #[arg(long = "header", value_name = "NAME:VALUE")]
+        headers: Vec<String>,
+
+        /// Fold line items cheaper than CENTS into "Other" (totals unchanged)
+        #[arg(long, value_name = "CENTS")]
+        min_cost: Option<f64>,
+
+        /// Group cost by model (adds "models" to --json output)
+        #[arg(long, conflicts_with = "compare_previous")]
+        by_model: bool,
+
+        /// Model order with --by-model: cost or tokens
+        #[arg(long, default_value = "cost", requires = "by_model")]
+        sort: String,
+
+        /// Attribute OpenAI cost to projects (and so to project-scoped keys);
+        /// needs the provider, e.g. `lkr usage openai --breakdown-by-key`
+        #[arg(long, conflicts_with = "by_model", requires = "provider")]
+        breakdown_by_key: bool,
+
+        /// Read provider settings (headers, custom usage endpoints) from
+        /// PATH instead of ~/.config/lkr/endpoints.toml
+        #[arg(long, value_name = "PATH")]
+        provider_config: Option<String>,
+
+        /// Note each rate-limit (HTTP 429) retry on stderr
+        #[arg(long)]
+        verbose: bool,
+
+        /// Output format (replaces the deprecated --json for this command)
+        #[arg(long, value_enum)]
+        format: Option<format::OutputFormat>,
+    },
+
+    /// Check lkr configuration for problems
+    Doctor {
+        /// Also check the Keychain itself (prompts for the keychain password)
+        #[arg(long)]
+        keychain: bool,
+    },
+
+    /// List known providers, their env vars and usage support
+    Providers,
+
+    /// Decrypt and check a key file without storing anything
+    VerifyBackup {
+        /// Passphrase-encrypted age file (as written by `export --passphrase`), or - for stdin
+        path: String,
+    },
+
+    /// Estimate the cost of a run from token counts (no API calls)
+    Estimate {
+        /// Provider name (openai, anthropic)
+        #[arg(long)]
+        provider: String,
+
+        /// Model name (e.g. gpt-4o, claude-sonnet-4)
+        #[arg(long)]
+        model: String,
+
+        /// Number of input (prompt) tokens
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        input_tokens: u64,
+
+        /// Number of output (completion) tokens
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        output_tokens: u64,
     },
 
     /// Generate config from template (resolves Keychain keys)
     Gen {
         /// Template file path (e.g. .env.example, .mcp.json.template)
-        template: String,
+        #[arg(required_unless_present_any = ["templates", "stdin_json"])]
+        template: Option<String>,
+
+        /// Template to generate (repeatable; pair each with an -o, in order)
+        #[arg(
+            short = 't',
+            long = "template",
+            value_name = "TEMPLATE",
+            conflicts_with = "template"
+        )]
+        templates: Vec<String>,
 
         /// Output file path (default: template name without .example/.template suffix)
         #[arg(short, long)]
-        output: Option<String>,
+        output: Vec<String>,
 
         /// Overwrite output file without confirmation
         #[arg(long)]
         force: bool,
+
+        /// Verify the existing output is up to date (ignores secret values; exits 1 if stale)
+        #[arg(long)]
+        check: bool,
+
+        /// Append only variables missing from the existing output (.env templates)
+        #[arg(long, conflicts_with = "check")]
+        append: bool,
+
+        /// Check the output has not been edited since lkr generated it (exits 1 if modified)
+        #[arg(long, conflicts_with_all = ["check", "append", "force"])]
+        verify: bool,
+
+        /// Fill variables with no matching key from the same-named env var (keys win)
+        #[arg(long, conflicts_with = "verify")]
+        env_fallback: bool,
+
+        /// Fail without writing if any placeholder is unresolved
+        #[arg(long, conflicts_with_all = ["check", "verify"])]
+        strict: bool,
+
+        /// Write output even with unresolved placeholders (default; overrides --strict)
+        #[arg(long, overrides_with = "strict")]
+        allow_unresolved: bool,
+
+        /// Parse the rendered output as JSON and pretty-print it (fails if it is not
+        /// valid JSON; object keys are sorted)
+        #[arg(long, conflicts_with_all = ["check", "append", "verify"])]
+        strict_json: bool,
+
+        /// Keep regenerating when a template or the Keychain changes (until Ctrl+C)
+        #[arg(long, conflicts_with_all = ["check", "append", "verify"])]
+        watch: bool,
+
+        /// With --watch: seconds between Keychain checks
+        #[arg(long, value_name = "SECS", default_value_t = 30, requires = "watch")]
+        interval: u64,
+
+        /// Template format to enforce: mcp validates an MCP config (placeholders only in
+        /// server env values) and reports which server gets which key
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            value_parser = ["mcp"],
+            conflicts_with_all = ["templates", "check", "append", "verify", "watch", "stdin_json"]
+        )]
+        format: Option<String>,
+
+        /// Permissions for written outputs, in octal (default 0600; never executable)
+        #[arg(long, value_name = "OCTAL", conflicts_with_all = ["check", "verify", "stdin_json"])]
+        mode: Option<String>,
+
+        /// Give written outputs to this group (name or gid), e.g. with --mode 0640
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["check", "verify", "stdin_json"])]
+        group: Option<String>,
+
+        /// Allow a --mode that lets every user read the secrets
+        #[arg(long, requires = "mode")]
+        i_understand: bool,
+
+        /// Read {"template", "format", "vars"} as JSON from stdin; print the result to stdout
+        #[arg(
+            long,
+            conflicts_with_all = ["template", "templates", "output", "check", "append", "verify", "watch"]
+        )]
+        stdin_json: bool,
     },
 
     /// Initialize LKR secure keychain (run once after install)
@@ -110,6 +554,9 @@ enum Commands {
         dry_run: bool,
     },
 
+    /// Check the Keychain works: set, get, list and delete a dummy key in a scratch service
+    SelfTest,
+
     /// Migrate keys from login.keychain to LKR keychain
     Migrate {
         /// Preview changes without applying
@@ -126,10 +573,86 @@ enum Commands {
     /// Keys never appear in stdout, files, or clipboard — the safest way
     /// to pass secrets to child processes.
     Exec {
-        /// Key names to inject (e.g. -k openai:prod). Omit to inject all runtime keys.
+        /// Key names to inject (e.g. -k openai:prod, or -k @prod for every runtime key
+        /// tagged prod). Omit to inject all runtime keys.
         #[arg(short = 'k', long = "key")]
         keys: Vec<String>,
 
+        /// Inject all runtime keys whose name starts with PREFIX (repeatable, OR-combined)
+        #[arg(long = "key-prefix", value_name = "PREFIX")]
+        key_prefixes: Vec<String>,
+
+        /// Abort before running the command unless NAME is injected (repeatable)
+        #[arg(long, value_name = "NAME")]
+        fail_if_missing: Vec<String>,
+
+        /// Prepend PREFIX to every injected env var name (e.g. APP_ → APP_OPENAI_API_KEY)
+        #[arg(long, value_name = "PREFIX")]
+        env_prefix: Option<String>,
+
+        /// Inject all runtime keys (the default without -k; explicit for scripts)
+        #[arg(long, conflicts_with_all = ["keys", "only_admin"])]
+        only_runtime: bool,
+
+        /// DANGER: inject all admin keys instead of runtime keys. Admin keys can
+        /// manage billing, members and other keys; only use with a trusted tool.
+        /// Requires --i-understand.
+        #[arg(long, conflicts_with_all = ["keys", "key_prefixes"])]
+        only_admin: bool,
+
+        /// Acknowledge the risk of --only-admin
+        #[arg(long, requires = "only_admin")]
+        i_understand: bool,
+
+        /// Also inject service keys (shared credentials hidden from list and exec by default)
+        #[arg(long, conflicts_with = "only_admin")]
+        include_service: bool,
+
+        /// Don't warn when an injected var overrides one already set in the environment
+        #[arg(long)]
+        no_warn_conflicts: bool,
+
+        /// Abort if an injected var is already set in the environment
+        #[arg(long)]
+        fail_on_conflict: bool,
+
+        /// Abort on a key with an empty value (default: skip it with a warning)
+        #[arg(long)]
+        strict: bool,
+
+        /// Stop the command after SECS: SIGTERM, then SIGKILL after the grace period
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+
+        /// Seconds between SIGTERM and SIGKILL when --timeout expires
+        #[arg(long, value_name = "SECS", default_value_t = 5, requires = "timeout")]
+        grace_period: u64,
+
+        /// Same as --grace-period 0
+        #[arg(long, conflicts_with = "grace_period", requires = "timeout")]
+        no_grace: bool,
+
+        /// Run the command with an empty environment apart from the injected keys
+        #[arg(long)]
+        no_inherit_env: bool,
+
+        /// With --no-inherit-env: keep parent env vars whose name matches GLOB (repeatable)
+        #[arg(
+            long = "inherit-pattern",
+            value_name = "GLOB",
+            requires = "no_inherit_env"
+        )]
+        inherit_patterns: Vec<String>,
+
+        /// Run the command in its own process group and forward Ctrl+C/SIGTERM to
+        /// the whole group, grandchildren included (default)
+        #[arg(long, overrides_with = "no_process_group")]
+        process_group: bool,
+
+        /// Leave the command in lkr's process group (for commands that manage their own)
+        #[arg(long, overrides_with = "process_group")]
+        no_process_group: bool,
+
         /// Show injected key count and env var names on stderr
         #[arg(long)]
         verbose: bool,
@@ -150,6 +673,378 @@ pub(crate) fn exit_for_tty_guard(e: &lkr_core::Error) -> ! {
     std::process::exit(2);
 }
 
+/// Run a command that works with any `KeyStore` backend.
+fn run_command(
+    store: &impl KeyStore,
+    command: Commands,
+    json: bool,
+    stdout_is_tty: bool,
+) -> lkr_core::Result<()> {
+    match command {
+        Commands::Set {
+            name,
+            kind,
+            force,
+            test,
+            no_store,
+            endpoint,
+            print_fingerprint,
+            no_trim,
+            description,
+            no_prompt,
+            strip_quotes,
+            expires_in,
+            expires_at,
+        } => {
+            let expires_at = match expires_in {
+                Some(duration) => Some(
+                    chrono::Duration::from_std(duration)
+                        .ok()
+                        .and_then(|d| chrono::Utc::now().checked_add_signed(d))
+                        .ok_or_else(|| {
+                            lkr_core::Error::Usage("--expires-in is too large.".to_string())
+                        })?,
+                ),
+                None => expires_at,
+            };
+            let opts = cmd::set::SetOptions {
+                kind,
+                force,
+                test,
+                no_store,
+                endpoint,
+                print_fingerprint,
+                no_trim,
+                description,
+                no_prompt,
+                strip_quotes,
+                expires_at,
+            };
+            cmd::set::cmd_set(store, &name, &opts)
+        }
+        Commands::Get {
+            name,
+            show,
+            plain,
+            force_plain,
+            clear_after,
+            no_clear,
+            selection,
+            count_access,
+            require_clipboard,
+            wait,
+            format,
+            to_env_name,
+        } => {
+            let env_clear = std::env::var(util::CLIPBOARD_CLEAR_ENV).ok();
+            let opts = cmd::get::GetOptions {
+                show,
+                plain,
+                force_plain,
+                json,
+                clear_after: util::resolve_clipboard_clear(
+                    clear_after,
+                    no_clear,
+                    env_clear.as_deref(),
+                ),
+                selection,
+                count_access,
+                require_clipboard,
+                wait: wait.is_some(),
+                wait_timeout: wait.flatten().map(std::time::Duration::from_secs),
+                env_format: format.is_some(),
+                to_env_name,
+            };
+            cmd::get::cmd_get(store, &name, &opts, stdout_is_tty)
+        }
+        Commands::List {
+            all,
+            unused_since,
+            verbose,
+            json_schema: _,
+            show_expired,
+            tag,
+            sort,
+        } => {
+            let opts = cmd::list::ListOptions {
+                include_admin: all,
+                unused_since,
+                verbose,
+                show_expired,
+                tag,
+                by_age: sort == "age",
+            };
+            cmd::list::cmd_list(store, &opts, json)
+        }
+        Commands::Tag { name, changes } => cmd::tag::cmd_tag(store, &name, &changes, json),
+        Commands::Rm {
+            name,
+            force,
+            if_exists,
+            cascade,
+            cascade_delete,
+            dry_run,
+        } => {
+            let opts = cmd::rm::RmOptions {
+                force,
+                if_exists,
+                cascade,
+                cascade_delete,
+                dry_run,
+            };
+            cmd::rm::cmd_rm(store, &name, &opts)
+        }
+        Commands::Rename {
+            old_name,
+            new_name,
+            force,
+        } => cmd::rename::cmd_rename(store, &old_name, &new_name, force),
+        Commands::Rotate {
+            name,
+            kind,
+            description,
+        } => cmd::rotate::cmd_rotate(store, &name, kind.as_deref(), description.as_deref()),
+        Commands::Chkind { name, kind, force } => {
+            cmd::chkind::cmd_chkind(store, &name, &kind, force)
+        }
+        Commands::Info { name } => cmd::info::cmd_info(store, &name, json),
+        Commands::CheckExpiry { within } => {
+            cmd::check_expiry::cmd_check_expiry(store, within, json)
+        }
+        Commands::SetMany { file, force } => cmd::set_many::cmd_set_many(store, &file, force),
+        Commands::BatchSet { file, force } => cmd::batch_set::cmd_batch_set(store, &file, force),
+        Commands::Import {
+            file,
+            from_env,
+            label,
+            yes,
+            mode,
+            from_lkr,
+            passphrase_stdin,
+            names_only,
+            skip_existing,
+        } => match (file, from_lkr) {
+            // clap makes FILE, --from-env and --from-lkr mutually exclusive
+            (Some(file), _) if names_only => {
+                cmd::import::cmd_import_names(store, &file, skip_existing)
+            }
+            (Some(file), _) => cmd::import::cmd_import(store, &file, &mode, json),
+            (None, Some(path)) => {
+                cmd::import::cmd_import_from_lkr(store, &path, &mode, passphrase_stdin, json)
+            }
+            (None, None) => {
+                debug_assert!(from_env);
+                cmd::import::cmd_import_from_env(store, label.as_deref(), &mode, yes, json)
+            }
+        },
+        Commands::Export {
+            output,
+            passphrase: _,
+            passphrase_stdin,
+            names_only,
+            force,
+        } => {
+            if names_only {
+                cmd::export::cmd_export_names(store, output.as_deref())
+            } else {
+                // clap requires --output unless --names-only
+                let output = output.expect("--output is required");
+                cmd::export::cmd_export(store, &output, passphrase_stdin, force, stdout_is_tty)
+            }
+        }
+        Commands::Usage {
+            provider,
+            refresh,
+            no_cache,
+            compare_previous,
+            headers,
+            min_cost,
+            by_model,
+            sort,
+            breakdown_by_key,
+            provider_config,
+            verbose,
+            format,
+        } => {
+            let model_sort = if by_model {
+                Some(sort.parse().map_err(lkr_core::Error::Usage)?)
+            } else {
+                None
+            };
+            let opts = cmd::usage::UsageOptions {
+                refresh,
+                no_cache,
+                compare_previous,
+                headers,
+                min_cost,
+                model_sort,
+                by_project: breakdown_by_key,
+                provider_config,
+                verbose,
+            };
+            let format = format::OutputFormat::resolve(format, json, "usage");
+            cmd::usage::cmd_usage(store, provider.as_deref(), &opts, format)
+        }
+        Commands::Gen {
+            template,
+            templates,
+            output,
+            force,
+            check,
+            append,
+            verify,
+            env_fallback,
+            strict,
+            allow_unresolved: _,
+            strict_json,
+            watch,
+            interval,
+            format,
+            mode,
+            group,
+            i_understand,
+            stdin_json,
+        } => {
+            let perms = cmd::r#gen::output_perms(mode.as_deref(), group.as_deref(), i_understand)?;
+            let templates = template.map_or(templates, |t| vec![t]);
+            if !output.is_empty() && output.len() != templates.len() {
+                return Err(lkr_core::Error::Usage(format!(
+                    "Got {} template(s) but {} -o output(s): give one -o per template, or none.",
+                    templates.len(),
+                    output.len()
+                )));
+            }
+            let mut outputs = output.into_iter().map(Some);
+            let jobs: Vec<(String, Option<String>)> = templates
+                .into_iter()
+                .map(|t| (t, outputs.next().flatten()))
+                .collect();
+
+            let mut opts = cmd::r#gen::GenOptions {
+                force,
+                check,
+                append,
+                verify,
+                env_fallback,
+                strict,
+                strict_json,
+                perms,
+                mcp: format.as_deref() == Some("mcp"),
+                json,
+                ..Default::default()
+            };
+            if stdin_json {
+                cmd::r#gen::cmd_gen_stdin_json(store, &opts, stdout_is_tty)
+            } else if watch {
+                let interval = std::time::Duration::from_secs(interval.max(1));
+                cmd::r#gen::cmd_gen_watch(store, &jobs, &opts, interval, stdout_is_tty)
+            } else if let [(template, output)] = jobs.as_slice() {
+                opts.output = output.clone();
+                cmd::r#gen::cmd_gen(store, template, &opts, stdout_is_tty)
+            } else {
+                cmd::r#gen::cmd_gen_many(store, &jobs, &opts, stdout_is_tty)
+            }
+        }
+        Commands::Exec {
+            keys,
+            key_prefixes,
+            fail_if_missing,
+            env_prefix,
+            only_runtime: _,
+            only_admin,
+            i_understand,
+            include_service,
+            no_warn_conflicts,
+            fail_on_conflict,
+            strict,
+            timeout,
+            grace_period,
+            no_grace,
+            no_inherit_env,
+            inherit_patterns,
+            process_group: _,
+            no_process_group,
+            verbose,
+            command,
+        } => {
+            let opts = cmd::exec::ExecOptions {
+                keys,
+                key_prefixes,
+                env_prefix,
+                only_admin,
+                i_understand,
+                include_service,
+                no_warn_conflicts,
+                fail_on_conflict,
+                strict,
+                fail_if_missing,
+                timeout: timeout.map(std::time::Duration::from_secs),
+                grace_period: std::time::Duration::from_secs(if no_grace {
+                    0
+                } else {
+                    grace_period
+                }),
+                no_inherit_env,
+                inherit_patterns,
+                process_group: !no_process_group,
+                verbose,
+            };
+            cmd::exec::cmd_exec(store, &command, &opts, stdout_is_tty)
+        }
+        Commands::Init
+        | Commands::Lock
+        | Commands::Estimate { .. }
+        | Commands::Doctor { .. }
+        | Commands::Providers
+        | Commands::VerifyBackup { .. }
+        | Commands::Migrate { .. }
+        | Commands::Harden { .. }
+        | Commands::SelfTest => unreachable!(),
+    }
+}
+
+/// `LKR_BACKEND=file` keeps keys unencrypted; say so on every run so it
+/// is never left on by accident (see docs/SECURITY.md).
+fn warn_plaintext_backend() {
+    let path = lkr_core::FileStore::default_path()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| "~/.config/lkr/keys.json".to_string());
+    eprintln!(
+        "Warning: {}=file stores keys unencrypted in {} (CI and testing only).",
+        lkr_core::BACKEND_ENV,
+        path
+    );
+}
+
+/// Reopen the store a command ran against, to suggest key names after a
+/// miss. The keychain is still unlocked from the command, so this never
+/// prompts; `None` if it cannot be opened.
+fn hint_store(backend: lkr_core::Backend) -> Option<Box<dyn KeyStore>> {
+    match backend {
+        lkr_core::Backend::Keychain => lkr_core::custom_keychain::open()
+            .ok()
+            .map(|kc| Box::new(KeychainStore::new_v3(kc)) as Box<dyn KeyStore>),
+        other => other.open().ok(),
+    }
+}
+
+/// `run_command`, with `--timings` reporting the store calls it made.
+fn run_timed(
+    store: impl KeyStore,
+    command: Commands,
+    json: bool,
+    stdout_is_tty: bool,
+    timings: bool,
+) -> lkr_core::Result<()> {
+    if !timings {
+        return run_command(&store, command, json, stdout_is_tty);
+    }
+    let store = lkr_core::InstrumentedStore::new(store);
+    let result = run_command(&store, command, json, stdout_is_tty);
+    eprintln!("timings: {}", store.metrics());
+    result
+}
+
 fn main() {
     let cli = Cli::parse();
 
@@ -162,92 +1057,125 @@ fn main() {
             cmd::init::cmd_init(stdin_is_tty);
             return;
         }
+        // No keys involved — don't prompt for the keychain password
+        Commands::Estimate {
+            provider,
+            model,
+            input_tokens,
+            output_tokens,
+        } => cmd::estimate::cmd_estimate(&provider, &model, input_tokens, output_tokens, cli.json),
+        Commands::Doctor { keychain: false } => cmd::doctor::cmd_doctor(cli.json),
+        Commands::Providers => {
+            cmd::providers::cmd_providers(cli.json);
+            Ok(())
+        }
+        Commands::VerifyBackup { path } => cmd::verify_backup::cmd_verify_backup(&path, cli.json),
+        Commands::List {
+            json_schema: true, ..
+        } => {
+            cmd::list::print_json_schema();
+            Ok(())
+        }
         Commands::Lock => {
             cmd::lock::cmd_lock();
             return;
         }
         _ => {
-            // All other commands need an unlocked store
-            let store = match util::open_and_unlock(stdin_is_tty) {
-                Ok(s) => s,
-                Err(lkr_core::Error::NotInitialized) => {
-                    eprintln!("Error: LKR keychain is not initialized.");
-                    eprintln!("\n  Run `lkr init` to create the secure keychain.");
-                    std::process::exit(1);
-                }
-                Err(lkr_core::Error::PasswordWrong) => {
-                    eprintln!("Error: Wrong password. Maximum retries exceeded.");
-                    std::process::exit(1);
-                }
-                Err(e @ lkr_core::Error::TtyGuard { .. }) => exit_for_tty_guard(&e),
+            let json = cli.json;
+            let timings = cli.timings;
+            let backend = match lkr_core::Backend::from_env() {
+                Ok(b) => b,
                 Err(e) => {
                     eprintln!("Error: {}", e);
                     std::process::exit(1);
                 }
             };
 
-            match cli.command {
-                Commands::Set { name, kind, force } => {
-                    cmd::set::cmd_set(&store, &name, &kind, force)
-                }
-                Commands::Get {
-                    name,
-                    show,
-                    plain,
-                    force_plain,
-                } => cmd::get::cmd_get(
-                    &store,
-                    &name,
-                    show,
-                    plain,
-                    force_plain,
-                    cli.json,
-                    stdout_is_tty,
-                ),
-                Commands::List { all } => cmd::list::cmd_list(&store, all, cli.json),
-                Commands::Rm { name, force } => cmd::rm::cmd_rm(&store, &name, force),
-                Commands::Usage { provider, refresh } => {
-                    cmd::usage::cmd_usage(&store, provider.as_deref(), refresh, cli.json)
+            if backend == lkr_core::Backend::Keychain {
+                // Keychain commands need an unlocked Custom Keychain
+                let unlock_wait = match &cli.command {
+                    Commands::Get { wait, .. } => {
+                        wait.map(|t| t.map(std::time::Duration::from_secs))
+                    }
+                    _ => None,
+                };
+                let store = match util::open_and_unlock(stdin_is_tty, unlock_wait) {
+                    Ok(s) => s,
+                    Err(lkr_core::Error::NotInitialized) => {
+                        eprintln!("Error: LKR keychain is not initialized.");
+                        eprintln!("\n  Run `lkr init` to create the secure keychain.");
+                        std::process::exit(1);
+                    }
+                    Err(lkr_core::Error::PasswordWrong) => {
+                        eprintln!("Error: Wrong password. Maximum retries exceeded.");
+                        std::process::exit(1);
+                    }
+                    Err(e @ lkr_core::Error::TtyGuard { .. }) => exit_for_tty_guard(&e),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    }
+                };
+
+                match cli.command {
+                    Commands::Migrate { dry_run, yes } => {
+                        cmd::migrate::cmd_migrate(&store, dry_run, yes)
+                    }
+                    Commands::Harden { dry_run } => cmd::harden::cmd_harden(&store, dry_run),
+                    Commands::SelfTest => cmd::self_test::cmd_self_test(store, json),
+                    Commands::Doctor { .. } => cmd::doctor::cmd_doctor_keychain(&store, json),
+                    command => run_timed(store, command, json, stdout_is_tty, timings),
                 }
-                Commands::Gen {
-                    template,
-                    output,
-                    force,
-                } => {
-                    cmd::r#gen::cmd_gen(&store, &template, output.as_deref(), force, stdout_is_tty)
+            } else {
+                if backend == lkr_core::Backend::File {
+                    warn_plaintext_backend();
                 }
-                Commands::Migrate { dry_run, yes } => {
-                    cmd::migrate::cmd_migrate(&store, dry_run, yes)
+                match cli.command {
+                    Commands::Migrate { .. }
+                    | Commands::Harden { .. }
+                    | Commands::SelfTest
+                    | Commands::Doctor { .. } => Err(lkr_core::Error::Usage(format!(
+                        "This command only applies to the keychain backend ({}={}).",
+                        lkr_core::BACKEND_ENV,
+                        backend
+                    ))),
+                    command => lkr_core::default_store()
+                        .and_then(|store| run_timed(store, command, json, stdout_is_tty, timings)),
                 }
-                Commands::Harden { dry_run } => cmd::harden::cmd_harden(&store, dry_run),
-                Commands::Exec {
-                    keys,
-                    verbose,
-                    command,
-                } => cmd::exec::cmd_exec(&store, &keys, &command, stdout_is_tty, verbose),
-                Commands::Init | Commands::Lock => unreachable!(),
             }
         }
     };
 
     if let Err(e) = result {
+        let e = if matches!(e, lkr_core::Error::NotRolledBack(_)) {
+            eprintln!(
+                "Warning: multi-step keychain operation failed. \
+                 Steps completed before the failure were not rolled back."
+            );
+            e.into_root()
+        } else {
+            e
+        };
         // 3-layer error messages: WHAT happened / WHY / WHAT TO DO
         match &e {
             lkr_core::Error::TtyGuard { .. } => exit_for_tty_guard(&e),
 
             lkr_core::Error::KeyNotFound { name } => {
                 eprintln!("Error: Key '{}' not found.", name);
-                // Check legacy login.keychain for migrate guidance
-                let legacy_store = KeychainStore::new();
-                if legacy_store.exists(name).unwrap_or(false) {
+                let backend = lkr_core::Backend::from_env().ok();
+                // Check legacy login.keychain for migrate guidance (only
+                // the keychain backend has anything to migrate)
+                if backend == Some(lkr_core::Backend::Keychain)
+                    && KeychainStore::new().exists(name).unwrap_or(false)
+                {
                     eprintln!(
                         "  Why: The key exists in login.keychain but not in the LKR keychain."
                     );
                     eprintln!("  Fix: Run `lkr migrate` to move your keys.");
                 } else {
                     eprintln!("  Fix: Run `lkr set {}` to store a new key.", name);
-                    // Suggest similar keys
-                    if let Ok(entries) = legacy_store.list(true) {
+                    // Suggest similar keys from the store that was searched
+                    if let Some(Ok(entries)) = backend.and_then(hint_store).map(|s| s.list(true)) {
                         let suggestions: Vec<&str> = entries
                             .iter()
                             .filter(|entry| {
@@ -266,6 +1194,15 @@ fn main() {
                 }
             }
 
+            lkr_core::Error::KeyExpired { name, expired_at } => {
+                eprintln!(
+                    "Error: Key '{}' expired at {}.",
+                    name,
+                    expired_at.format("%Y-%m-%d %H:%M UTC")
+                );
+                eprintln!("  Fix: Run `lkr rotate {}` to store a fresh value.", name);
+            }
+
             lkr_core::Error::KeyAlreadyExists { name } => {
                 eprintln!("Error: Key '{}' already exists.", name);
                 eprintln!("  Fix: Use `lkr set {} --force` to overwrite.", name);
@@ -314,6 +1251,12 @@ fn main() {
                 eprintln!("Error: {}", e);
             }
         }
+        // Captured only under RUST_BACKTRACE=1
+        if let Some(trace) = e.backtrace()
+            && trace.status() == std::backtrace::BacktraceStatus::Captured
+        {
+            eprintln!("\nBacktrace:\n{}", trace);
+        }
 
         std::process::exit(1);
     }
@@ -325,6 +1268,10 @@ fn main() {
 
 #[cfg(test)]
 mod tests {
+    use crate::cmd::exec::ExecOptions;
+    use crate::cmd::r#gen::GenOptions;
+    use crate::cmd::get::GetOptions;
+    use clap::Parser;
     use lkr_core::keymanager::MockStore;
     use lkr_core::{KeyKind, KeyStore};
 
@@ -347,12 +1294,69 @@ mod tests {
         matches!(err, lkr_core::Error::TtyGuard { .. })
     }
 
+    fn get_opts(show: bool, plain: bool, force_plain: bool, json: bool) -> GetOptions {
+        GetOptions {
+            show,
+            plain,
+            force_plain,
+            json,
+            ..Default::default()
+        }
+    }
+
+    fn exec_opts(keys: &[&str]) -> ExecOptions {
+        ExecOptions {
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn gen_opts(output: Option<&str>, force: bool, check: bool) -> GenOptions {
+        GenOptions {
+            output: output.map(String::from),
+            force,
+            check,
+            ..Default::default()
+        }
+    }
+
+    fn access_count(store: &MockStore, name: &str) -> u64 {
+        let entries = store.list(true).unwrap();
+        entries
+            .iter()
+            .find(|e| e.name == name)
+            .unwrap()
+            .access_count
+    }
+
+    #[test]
+    fn test_get_count_access_is_opt_in() {
+        let store = setup_store_with_key();
+        let plain = get_opts(false, true, false, false);
+        crate::cmd::get::cmd_get(&store, "openai:prod", &plain, true).unwrap();
+        assert_eq!(access_count(&store, "openai:prod"), 0);
+
+        let counted = GetOptions {
+            count_access: true,
+            ..plain
+        };
+        crate::cmd::get::cmd_get(&store, "openai:prod", &counted, true).unwrap();
+        crate::cmd::get::cmd_get(&store, "openai:prod", &counted, true).unwrap();
+        assert_eq!(access_count(&store, "openai:prod"), 2);
+        let entry = &store.list(false).unwrap()[0];
+        assert!(entry.last_accessed.is_some());
+    }
+
     #[test]
     fn test_get_non_tty_bare_blocked() {
         let store = setup_store_with_key();
         // lkr get key (no flags, non-TTY) → blocked
-        let result =
-            crate::cmd::get::cmd_get(&store, "openai:prod", false, false, false, false, false);
+        let result = crate::cmd::get::cmd_get(
+            &store,
+            "openai:prod",
+            &get_opts(false, false, false, false),
+            false,
+        );
         assert!(result.is_err());
         assert!(is_tty_guard_error(&result.unwrap_err()));
     }
@@ -361,8 +1365,12 @@ mod tests {
     fn test_get_non_tty_show_blocked() {
         let store = setup_store_with_key();
         // lkr get key --show (non-TTY) → blocked
-        let result =
-            crate::cmd::get::cmd_get(&store, "openai:prod", true, false, false, false, false);
+        let result = crate::cmd::get::cmd_get(
+            &store,
+            "openai:prod",
+            &get_opts(true, false, false, false),
+            false,
+        );
         assert!(result.is_err());
         assert!(is_tty_guard_error(&result.unwrap_err()));
     }
@@ -371,8 +1379,12 @@ mod tests {
     fn test_get_non_tty_plain_blocked() {
         let store = setup_store_with_key();
         // lkr get key --plain (non-TTY) → blocked
-        let result =
-            crate::cmd::get::cmd_get(&store, "openai:prod", false, true, false, false, false);
+        let result = crate::cmd::get::cmd_get(
+            &store,
+            "openai:prod",
+            &get_opts(false, true, false, false),
+            false,
+        );
         assert!(result.is_err());
         assert!(is_tty_guard_error(&result.unwrap_err()));
     }
@@ -381,8 +1393,12 @@ mod tests {
     fn test_get_non_tty_json_show_blocked() {
         let store = setup_store_with_key();
         // lkr get key --json --show (non-TTY) → blocked (raw value in JSON)
-        let result =
-            crate::cmd::get::cmd_get(&store, "openai:prod", true, false, false, true, false);
+        let result = crate::cmd::get::cmd_get(
+            &store,
+            "openai:prod",
+            &get_opts(true, false, false, true),
+            false,
+        );
         assert!(result.is_err());
         assert!(is_tty_guard_error(&result.unwrap_err()));
     }
@@ -391,8 +1407,12 @@ mod tests {
     fn test_get_non_tty_json_masked_passes() {
         let store = setup_store_with_key();
         // lkr get key --json (non-TTY, no --show) → pass (masked value)
-        let result =
-            crate::cmd::get::cmd_get(&store, "openai:prod", false, false, false, true, false);
+        let result = crate::cmd::get::cmd_get(
+            &store,
+            "openai:prod",
+            &get_opts(false, false, false, true),
+            false,
+        );
         assert!(result.is_ok());
     }
 
@@ -400,8 +1420,12 @@ mod tests {
     fn test_get_non_tty_force_plain_passes() {
         let store = setup_store_with_key();
         // lkr get key --force-plain (non-TTY) → pass (explicit override)
-        let result =
-            crate::cmd::get::cmd_get(&store, "openai:prod", false, false, true, false, false);
+        let result = crate::cmd::get::cmd_get(
+            &store,
+            "openai:prod",
+            &get_opts(false, false, true, false),
+            false,
+        );
         assert!(result.is_ok());
     }
 
@@ -410,8 +1434,12 @@ mod tests {
         let store = setup_store_with_key();
         // lkr get key (TTY) → pass (clipboard copy + masked display)
         // Note: clipboard ops will fail in test env, but the function should succeed
-        let result =
-            crate::cmd::get::cmd_get(&store, "openai:prod", false, false, false, false, true);
+        let result = crate::cmd::get::cmd_get(
+            &store,
+            "openai:prod",
+            &get_opts(false, false, false, false),
+            true,
+        );
         assert!(result.is_ok());
     }
 
@@ -421,8 +1449,12 @@ mod tests {
     fn test_gen_non_tty_blocked() {
         let store = setup_store_with_key();
         // lkr gen (non-TTY, no --force) → blocked
-        let result =
-            crate::cmd::r#gen::cmd_gen(&store, "/nonexistent/template", None, false, false);
+        let result = crate::cmd::r#gen::cmd_gen(
+            &store,
+            "/nonexistent/template",
+            &gen_opts(None, false, false),
+            false,
+        );
         assert!(result.is_err());
         assert!(is_tty_guard_error(&result.unwrap_err()));
     }
@@ -431,12 +1463,63 @@ mod tests {
     fn test_gen_non_tty_force_passes() {
         let store = setup_store_with_key();
         // lkr gen --force (non-TTY) → passes TTY guard (may fail on file I/O, that's OK)
-        let result = crate::cmd::r#gen::cmd_gen(&store, "/nonexistent/template", None, true, false);
+        let result = crate::cmd::r#gen::cmd_gen(
+            &store,
+            "/nonexistent/template",
+            &gen_opts(None, true, false),
+            false,
+        );
         // Should NOT be a TtyGuard error — it will be a Template error (file not found)
         assert!(result.is_err());
         assert!(!is_tty_guard_error(&result.unwrap_err()));
     }
 
+    #[test]
+    fn test_gen_check_non_tty_passes_guard() {
+        let store = setup_store_with_key();
+        // lkr gen --check (non-TTY) → allowed: nothing is written or printed
+        let result = crate::cmd::r#gen::cmd_gen(
+            &store,
+            "/nonexistent/template",
+            &gen_opts(None, false, true),
+            false,
+        );
+        assert!(result.is_err());
+        assert!(!is_tty_guard_error(&result.unwrap_err()));
+    }
+
+    #[test]
+    fn test_gen_check_up_to_date_and_stale() {
+        let store = setup_store_with_key();
+        let dir = std::env::temp_dir().join(format!("lkr-test-gen-check-{}", std::process::id()));
+        let _ = std::fs::create_dir_all(&dir);
+        let template = dir.join(".env.example");
+        let output = dir.join(".env");
+        std::fs::write(&template, "OPENAI_API_KEY=x\n").unwrap();
+        let (template, output) = (template.to_str().unwrap(), output.to_str().unwrap());
+
+        crate::cmd::r#gen::cmd_gen(&store, template, &gen_opts(Some(output), true, false), true)
+            .unwrap();
+        let result = crate::cmd::r#gen::cmd_gen(
+            &store,
+            template,
+            &gen_opts(Some(output), false, true),
+            false,
+        );
+        assert!(result.is_ok());
+
+        std::fs::write(template, "OPENAI_API_KEY=x\nPORT=3000\n").unwrap();
+        let result = crate::cmd::r#gen::cmd_gen(
+            &store,
+            template,
+            &gen_opts(Some(output), false, true),
+            false,
+        );
+        assert!(matches!(result, Err(lkr_core::Error::Template { .. })));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     // -- exec tests --
 
     #[test]
@@ -448,9 +1531,8 @@ mod tests {
 
         let result = crate::cmd::exec::cmd_exec(
             &store,
-            &["openai:admin".to_string()],
             &["echo".to_string(), "hello".to_string()],
-            false,
+            &exec_opts(&["openai:admin"]),
             false,
         );
 
@@ -489,9 +1571,8 @@ mod tests {
         // First admin key should be caught
         let result = crate::cmd::exec::cmd_exec(
             &store,
-            &["openai:admin".to_string(), "anthropic:admin".to_string()],
             &["echo".to_string()],
-            false,
+            &exec_opts(&["openai:admin", "anthropic:admin"]),
             false,
         );
 
@@ -512,9 +1593,8 @@ mod tests {
 
         let result = crate::cmd::exec::cmd_exec(
             &store,
-            &["openai:prod".to_string(), "anthropic:admin".to_string()],
             &["echo".to_string()],
-            false,
+            &exec_opts(&["openai:prod", "anthropic:admin"]),
             false,
         );
 
@@ -522,4 +1602,27 @@ mod tests {
         let err_msg = format!("{}", result.unwrap_err());
         assert!(err_msg.contains("anthropic:admin"));
     }
+
+    #[test]
+    fn test_set_note_is_an_alias_of_description() {
+        for flag in ["--description", "--comment", "--note"] {
+            let cli =
+                super::Cli::try_parse_from(["lkr", "set", "anthropic:main", flag, "project X"])
+                    .unwrap();
+            match cli.command {
+                super::Commands::Set { description, .. } => {
+                    assert_eq!(description.as_deref(), Some("project X"))
+                }
+                _ => panic!("expected set"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_usage_breakdown_by_key_requires_provider() {
+        assert!(super::Cli::try_parse_from(["lkr", "usage", "--breakdown-by-key"]).is_err());
+        assert!(
+            super::Cli::try_parse_from(["lkr", "usage", "openai", "--breakdown-by-key"]).is_ok()
+        );
+    }
 }
@@ -0,0 +1,65 @@
+//! Passphrase input for encrypted key files (`set-many`, `export`,
+//! `import --from-lkr`, `verify-backup`).
+
+use std::io::{self, BufRead, Write};
+use zeroize::Zeroizing;
+
+/// Prompt once for the passphrase of an existing file.
+pub(crate) fn read() -> lkr_core::Result<Zeroizing<String>> {
+    prompt("Passphrase: ")
+}
+
+/// Prompt twice for a passphrase that will encrypt a new file.
+pub(crate) fn read_new() -> lkr_core::Result<Zeroizing<String>> {
+    let first = prompt("New passphrase: ")?;
+    if first.is_empty() {
+        return Err(lkr_core::Error::Usage(
+            "Passphrase must not be empty".to_string(),
+        ));
+    }
+    let second = prompt("Repeat passphrase: ")?;
+    if first != second {
+        return Err(lkr_core::Error::Usage(
+            "Passphrases do not match".to_string(),
+        ));
+    }
+    Ok(first)
+}
+
+/// `--passphrase-stdin`: the first line of stdin, for scripted use.
+pub(crate) fn read_stdin() -> lkr_core::Result<Zeroizing<String>> {
+    read_line_from(&mut io::stdin().lock())
+}
+
+fn read_line_from(input: &mut impl BufRead) -> lkr_core::Result<Zeroizing<String>> {
+    let mut line = Zeroizing::new(String::new());
+    input
+        .read_line(&mut line)
+        .map_err(|e| lkr_core::Error::Usage(format!("Failed to read passphrase: {}", e)))?;
+    let passphrase = Zeroizing::new(line.trim_end_matches(['\r', '\n']).to_string());
+    if passphrase.is_empty() {
+        return Err(lkr_core::Error::Usage("No passphrase on stdin".to_string()));
+    }
+    Ok(passphrase)
+}
+
+fn prompt(label: &str) -> lkr_core::Result<Zeroizing<String>> {
+    eprint!("{}", label);
+    io::stderr().flush().ok();
+    rpassword::read_password()
+        .map(Zeroizing::new)
+        .map_err(|e| lkr_core::Error::Usage(format!("Failed to read passphrase: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_line_from_strips_newline_only() {
+        let mut input = io::Cursor::new(" pass phrase \r\nignored\n");
+        assert_eq!(&*read_line_from(&mut input).unwrap(), " pass phrase ");
+        assert!(read_line_from(&mut io::Cursor::new("\n")).is_err());
+        assert!(read_line_from(&mut io::Cursor::new("")).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Yes/no confirmation prompts shared by every command.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// Ask a `[y/N]` question on stderr and read the answer from stdin.
+///
+/// Anything other than `y`/`yes` (case-insensitive) is a no — including
+/// EOF, read errors, and an empty line. When stdin is not a terminal the
+/// prompt is declined without reading, so a pipe can never confirm a
+/// destructive action by accident; callers offer `--force`/`--yes` instead.
+pub(crate) fn confirm(prompt: &str) -> bool {
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    confirm_with(prompt, &mut stdin.lock(), interactive)
+}
+
+/// Like `confirm`, but an empty answer means `default_yes` (for `[Y/n]`
+/// prompts). EOF and non-interactive stdin are still a no.
+pub(crate) fn confirm_default(prompt: &str, default_yes: bool) -> bool {
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    confirm_default_with(prompt, &mut stdin.lock(), interactive, default_yes)
+}
+
+/// `confirm` with an injectable reader, for tests.
+pub(crate) fn confirm_with(prompt: &str, input: &mut impl BufRead, interactive: bool) -> bool {
+    confirm_default_with(prompt, input, interactive, false)
+}
+
+/// `confirm_default` with an injectable reader, for tests.
+pub(crate) fn confirm_default_with(
+    prompt: &str,
+    input: &mut impl BufRead,
+    interactive: bool,
+    default_yes: bool,
+) -> bool {
+    eprint!("{}", prompt);
+    if !interactive {
+        eprintln!("\n  stdin is not interactive; answering no. Use --force or --yes to proceed.");
+        return false;
+    }
+    io::stderr().flush().ok();
+
+    let mut answer = String::new();
+    match input.read_line(&mut answer) {
+        Ok(0) | Err(_) => {
+            // EOF (e.g. Ctrl-D): end the prompt line before the caller reports
+            eprintln!();
+            false
+        }
+        Ok(_) if answer.trim().is_empty() => default_yes,
+        Ok(_) => is_yes(&answer),
+    }
+}
+
+/// Make the user type an admin key's full name before `action` ("store",
+/// "remove") touches it — a `[y/N]` is too easy to answer on autopilot.
+pub(crate) fn confirm_admin(action: &str, name: &str) -> bool {
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    confirm_admin_with(action, name, &mut stdin.lock(), interactive)
+}
+
+/// `confirm_admin` with an injectable reader, for tests.
+pub(crate) fn confirm_admin_with(
+    action: &str,
+    name: &str,
+    input: &mut impl BufRead,
+    interactive: bool,
+) -> bool {
+    eprint!(
+        "You are about to {} an ADMIN key '{}'. This key has elevated privileges. \
+         Type the key name to confirm: ",
+        action, name
+    );
+    if !interactive {
+        eprintln!("\n  stdin is not interactive; not confirmed. Use --force to proceed.");
+        return false;
+    }
+    io::stderr().flush().ok();
+
+    let mut answer = String::new();
+    match input.read_line(&mut answer) {
+        Ok(0) | Err(_) => {
+            eprintln!();
+            false
+        }
+        Ok(_) => answer.trim_end_matches(['\n', '\r']) == name,
+    }
+}
+
+fn is_yes(answer: &str) -> bool {
+    let answer = answer.trim();
+    answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(input: &str) -> bool {
+        confirm_with("Proceed? [y/N] ", &mut input.as_bytes(), true)
+    }
+
+    #[test]
+    fn test_yes_answers() {
+        assert!(answer("y\n"));
+        assert!(answer("Y\n"));
+        assert!(answer("yes\n"));
+        assert!(answer("  YES  \n"));
+        // No trailing newline (EOF right after the answer)
+        assert!(answer("y"));
+    }
+
+    #[test]
+    fn test_eof_is_no() {
+        assert!(!answer(""));
+    }
+
+    #[test]
+    fn test_empty_and_other_input_is_no() {
+        assert!(!answer("\n"));
+        assert!(!answer("n\n"));
+        assert!(!answer("yep\n"));
+        assert!(!answer("no\n"));
+    }
+
+    #[test]
+    fn test_default_yes_applies_only_to_empty_answer() {
+        let answer =
+            |input: &str| confirm_default_with("Store? [Y/n] ", &mut input.as_bytes(), true, true);
+        assert!(answer("\n"));
+        assert!(answer("y\n"));
+        assert!(!answer("n\n"));
+        assert!(!answer(""));
+    }
+
+    #[test]
+    fn test_admin_confirm_requires_exact_name() {
+        let answer =
+            |input: &str| confirm_admin_with("remove", "openai:admin", &mut input.as_bytes(), true);
+        assert!(answer("openai:admin\n"));
+        assert!(answer("openai:admin"));
+        assert!(!answer("y\n"));
+        assert!(!answer("openai\n"));
+        assert!(!answer("OPENAI:ADMIN\n"));
+        assert!(!answer(" openai:admin\n"));
+        assert!(!answer(""));
+        assert!(!confirm_admin_with(
+            "remove",
+            "openai:admin",
+            &mut "openai:admin\n".as_bytes(),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_non_interactive_declines_without_reading() {
+        let mut input = "y\n".as_bytes();
+        assert!(!confirm_with("Proceed? [y/N] ", &mut input, false));
+        // Input left untouched
+        assert_eq!(input, b"y\n");
+    }
+}
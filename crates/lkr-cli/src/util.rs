@@ -1,5 +1,5 @@
 use lkr_core::KeychainStore;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
 
 /// Blocks password prompts when stdin isn't an interactive terminal.
 ///
@@ -20,55 +20,126 @@ pub(crate) fn guard_stdin_tty(stdin_is_tty: bool) -> lkr_core::Result<()> {
     })
 }
 
-pub(crate) fn confirm(prompt: &str) -> bool {
-    eprint!("{}", prompt);
-    io::stderr().flush().ok();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).ok();
-    input.trim().eq_ignore_ascii_case("y")
+/// Default clipboard auto-clear delay for `lkr get`.
+pub(crate) const DEFAULT_CLIPBOARD_CLEAR_SECS: u32 = 30;
+
+/// Env var that sets the clipboard auto-clear delay globally
+/// (seconds; `0` or `off` disables).
+pub(crate) const CLIPBOARD_CLEAR_ENV: &str = "LKR_CLIPBOARD_CLEAR";
+
+/// Resolve the clipboard auto-clear delay. Precedence: flag > env > default.
+///
+/// Returns `None` when auto-clear is disabled (`--no-clear`,
+/// `--clear-after 0`, or `LKR_CLIPBOARD_CLEAR=0|off`). An unparsable env
+/// value is ignored with a warning rather than failing the command.
+pub(crate) fn resolve_clipboard_clear(
+    clear_after: Option<u32>,
+    no_clear: bool,
+    env_value: Option<&str>,
+) -> Option<u32> {
+    if no_clear {
+        return None;
+    }
+    if let Some(secs) = clear_after {
+        return (secs > 0).then_some(secs);
+    }
+    if let Some(raw) = env_value {
+        let raw = raw.trim();
+        if raw.eq_ignore_ascii_case("off") {
+            return None;
+        }
+        match raw.parse::<u32>() {
+            Ok(secs) => return (secs > 0).then_some(secs),
+            Err(_) => eprintln!(
+                "Warning: ignoring invalid {}={:?} (expected seconds or 'off')",
+                CLIPBOARD_CLEAR_ENV, raw
+            ),
+        }
+    }
+    Some(DEFAULT_CLIPBOARD_CLEAR_SECS)
 }
 
-/// Spawn a detached background process that clears the clipboard after `seconds`.
+/// Parse a human duration like `90s`, `30m`, `12h`, `30d`, or `2w`.
 ///
-/// Uses SHA-256 hash comparison to avoid clearing if the user copied something else.
-/// The raw key value is never passed as a process argument (prevents `ps` exposure).
-pub(crate) fn schedule_clipboard_clear(seconds: u32) {
-    // Capture SHA-256 hash of current clipboard content
-    let hash_output = std::process::Command::new("sh")
-        .arg("-c")
-        .arg("pbpaste 2>/dev/null | shasum -a 256 | cut -d' ' -f1")
-        .output();
-
-    let expected_hash = match hash_output {
-        Ok(output) => String::from_utf8_lossy(&output.stdout).trim().to_string(),
-        Err(_) => return, // Can't hash — skip auto-clear silently
+/// Used as a clap `value_parser`, so errors are plain strings.
+pub(crate) fn parse_duration(s: &str) -> Result<std::time::Duration, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("missing unit in '{s}' (use s, m, h, d, or w)"))?;
+    let (num, unit) = s.split_at(split);
+    let n: u64 = num
+        .parse()
+        .map_err(|_| format!("invalid duration '{s}' (e.g. 30d, 12h)"))?;
+    let secs_per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => {
+            return Err(format!(
+                "unknown unit '{unit}' in '{s}' (use s, m, h, d, or w)"
+            ));
+        }
     };
+    n.checked_mul(secs_per_unit)
+        .map(std::time::Duration::from_secs)
+        .ok_or_else(|| format!("duration '{s}' is too large"))
+}
 
-    if expected_hash.is_empty() {
-        return;
-    }
+/// Parse an RFC 3339 / ISO 8601 timestamp like `2026-12-31T23:59:00Z`
+/// (any offset; stored as UTC).
+///
+/// Used as a clap `value_parser`, so errors are plain strings.
+pub(crate) fn parse_timestamp(s: &str) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    chrono::DateTime::parse_from_rfc3339(s.trim())
+        .map(|ts| ts.with_timezone(&chrono::Utc))
+        .map_err(|e| format!("invalid timestamp '{s}' ({e}; e.g. 2026-12-31T23:59:00Z)"))
+}
 
-    // Spawn detached process: sleep → compare hash → clear if unchanged
-    let script = format!(
-        "sleep {} && current=$(pbpaste 2>/dev/null | shasum -a 256 | cut -d' ' -f1) && \
-         [ \"$current\" = \"{}\" ] && printf '' | pbcopy",
-        seconds, expected_hash
-    );
+/// Format a future timestamp relative to `now`, e.g. "in 5h" or "in 3d".
+pub(crate) fn format_until(
+    ts: chrono::DateTime<chrono::Utc>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> String {
+    let secs = (ts - now).num_seconds().max(0);
+    match secs {
+        0..60 => "in <1m".to_string(),
+        60..3600 => format!("in {}m", secs / 60),
+        3600..86_400 => format!("in {}h", secs / 3600),
+        _ => format!("in {}d", secs / 86_400),
+    }
+}
 
-    let _ = std::process::Command::new("sh")
-        .arg("-c")
-        .arg(&script)
-        .stdin(std::process::Stdio::null())
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .spawn(); // Detach — orphaned child survives parent exit
+/// Format a timestamp relative to `now`, e.g. "3d ago" or "5h ago".
+///
+/// Not `humantime`: its `format_duration` spells out every unit
+/// ("3days 4h 12m 5s"), while list columns want the one coarsest unit, and
+/// a new dependency would buy nothing this match doesn't already do.
+pub(crate) fn format_age(
+    ts: chrono::DateTime<chrono::Utc>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> String {
+    let secs = (now - ts).num_seconds().max(0);
+    match secs {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86_400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
 }
 
 /// Open and unlock the Custom Keychain with password retry.
 ///
 /// Returns a v0.3.0 KeychainStore ready for operations.
-/// Prompts for password up to 3 times.
-pub(crate) fn open_and_unlock(stdin_is_tty: bool) -> lkr_core::Result<KeychainStore> {
+/// Prompts for password up to 3 times. With `wait` (`lkr get --wait`), an
+/// unlock refused because the session is locked is retried until it goes
+/// through or the timeout (`None` = no limit) runs out.
+pub(crate) fn open_and_unlock(
+    stdin_is_tty: bool,
+    wait: Option<Option<std::time::Duration>>,
+) -> lkr_core::Result<KeychainStore> {
     if !lkr_core::custom_keychain::is_initialized() {
         return Err(lkr_core::Error::NotInitialized);
     }
@@ -82,9 +153,19 @@ pub(crate) fn open_and_unlock(stdin_is_tty: bool) -> lkr_core::Result<KeychainSt
         eprint!("LKR keychain password: ");
         io::stderr().flush().ok();
         let password = rpassword::read_password()
-            .map_err(|e| lkr_core::Error::Keychain(format!("Failed to read password: {e}")))?;
+            .map_err(|e| lkr_core::Error::keychain(format!("Failed to read password: {e}")))?;
 
-        match lkr_core::custom_keychain::unlock(&mut kc, &password) {
+        let mut unlock = || lkr_core::custom_keychain::unlock(&mut kc, &password);
+        let unlocked = match wait {
+            Some(timeout) => crate::cmd::get::wait_until_unlocked(
+                unlock,
+                timeout,
+                io::stderr().is_terminal(),
+                std::thread::sleep,
+            ),
+            None => unlock(),
+        };
+        match unlocked {
             Ok(()) => return Ok(KeychainStore::new_v3(kc)),
             Err(lkr_core::Error::PasswordWrong) => {
                 if attempt < MAX_RETRIES {
@@ -109,6 +190,90 @@ mod tests {
         assert!(guard_stdin_tty(true).is_ok());
     }
 
+    #[test]
+    fn test_resolve_clipboard_clear_default() {
+        assert_eq!(
+            resolve_clipboard_clear(None, false, None),
+            Some(DEFAULT_CLIPBOARD_CLEAR_SECS)
+        );
+    }
+
+    #[test]
+    fn test_resolve_clipboard_clear_env_over_default() {
+        assert_eq!(resolve_clipboard_clear(None, false, Some("10")), Some(10));
+        assert_eq!(resolve_clipboard_clear(None, false, Some("0")), None);
+        assert_eq!(resolve_clipboard_clear(None, false, Some("off")), None);
+        assert_eq!(resolve_clipboard_clear(None, false, Some("OFF")), None);
+    }
+
+    #[test]
+    fn test_resolve_clipboard_clear_invalid_env_falls_back() {
+        assert_eq!(
+            resolve_clipboard_clear(None, false, Some("soon")),
+            Some(DEFAULT_CLIPBOARD_CLEAR_SECS)
+        );
+    }
+
+    #[test]
+    fn test_resolve_clipboard_clear_flag_over_env() {
+        assert_eq!(
+            resolve_clipboard_clear(Some(5), false, Some("off")),
+            Some(5)
+        );
+        assert_eq!(resolve_clipboard_clear(Some(0), false, Some("60")), None);
+        assert_eq!(resolve_clipboard_clear(None, true, Some("60")), None);
+    }
+
+    #[test]
+    fn test_parse_duration_units() {
+        use std::time::Duration;
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("12h").unwrap(), Duration::from_secs(43_200));
+        assert_eq!(
+            parse_duration("30d").unwrap(),
+            Duration::from_secs(2_592_000)
+        );
+        assert_eq!(
+            parse_duration("2w").unwrap(),
+            Duration::from_secs(1_209_600)
+        );
+    }
+
+    #[test]
+    fn test_parse_duration_rejects_invalid() {
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("30y").is_err());
+        assert!(parse_duration("-1d").is_err());
+    }
+
+    #[test]
+    fn test_format_age() {
+        let now = chrono::Utc::now();
+        assert_eq!(format_age(now, now), "just now");
+        assert_eq!(format_age(now - chrono::Duration::hours(5), now), "5h ago");
+        assert_eq!(format_age(now - chrono::Duration::days(40), now), "40d ago");
+    }
+
+    #[test]
+    fn test_format_until() {
+        let now = chrono::Utc::now();
+        assert_eq!(format_until(now, now), "in <1m");
+        assert_eq!(format_until(now + chrono::Duration::hours(5), now), "in 5h");
+        assert_eq!(format_until(now + chrono::Duration::days(3), now), "in 3d");
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(
+            parse_timestamp("2026-12-31T09:00:00+09:00").unwrap(),
+            parse_timestamp("2026-12-31T00:00:00Z").unwrap()
+        );
+        assert!(parse_timestamp("2026-12-31").is_err());
+        assert!(parse_timestamp("tomorrow").is_err());
+    }
+
     #[test]
     fn test_guard_stdin_tty_blocks_when_non_interactive() {
         let result = guard_stdin_tty(false);
@@ -0,0 +1,162 @@
+//! Backend selection shared by the CLI and the Tauri app.
+//!
+//! `LKR_BACKEND` picks the store; unset means the platform default.
+
+use crate::error::{Error, Result};
+use crate::file_store::FileStore;
+use crate::keymanager::{KeyStore, KeychainStore, MockStore};
+use std::sync::{Arc, OnceLock};
+
+/// Environment variable that selects the key store backend.
+pub const BACKEND_ENV: &str = "LKR_BACKEND";
+
+/// Available key store backends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// macOS Keychain (default)
+    Keychain,
+    /// Plaintext JSON file — see `FileStore`
+    File,
+    /// In-memory, shared by every store opened in the process and
+    /// discarded on exit (tests and demos). Each CLI run starts empty.
+    Memory,
+}
+
+impl Backend {
+    /// The backend used when `LKR_BACKEND` is unset.
+    pub fn platform_default() -> Self {
+        Backend::Keychain
+    }
+
+    /// Read `LKR_BACKEND`. Unset or empty means `platform_default()`;
+    /// an unknown value is an error rather than a silent fallback.
+    pub fn from_env() -> Result<Self> {
+        Self::from_env_value(std::env::var(BACKEND_ENV).ok().as_deref())
+    }
+
+    fn from_env_value(value: Option<&str>) -> Result<Self> {
+        match value.map(str::trim) {
+            None | Some("") => Ok(Self::platform_default()),
+            Some(v) => v.parse().map_err(Error::Usage),
+        }
+    }
+
+    /// Open a store for this backend.
+    pub fn open(self) -> Result<Box<dyn KeyStore>> {
+        self.open_with(FileStore::default_path)
+    }
+
+    /// `open`, with the file backend's path from `file_path`.
+    fn open_with(
+        self,
+        file_path: impl FnOnce() -> Result<std::path::PathBuf>,
+    ) -> Result<Box<dyn KeyStore>> {
+        Ok(match self {
+            Backend::Keychain => Box::new(KeychainStore::new()),
+            Backend::File => Box::new(FileStore::new(file_path()?)),
+            Backend::Memory => Box::new(Arc::clone(memory_store())),
+        })
+    }
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Keychain => write!(f, "keychain"),
+            Backend::File => write!(f, "file"),
+            Backend::Memory => write!(f, "memory"),
+        }
+    }
+}
+
+impl std::str::FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "keychain" => Ok(Backend::Keychain),
+            "file" => Ok(Backend::File),
+            "memory" => Ok(Backend::Memory),
+            _ => Err(format!(
+                "Unknown {} '{}'. Must be 'keychain', 'file', or 'memory'.",
+                BACKEND_ENV, s
+            )),
+        }
+    }
+}
+
+/// The process-wide store behind `Backend::Memory`, so a key set through
+/// one `default_store()` is seen by the next (the desktop app opens a
+/// store per command).
+fn memory_store() -> &'static Arc<MockStore> {
+    static STORE: OnceLock<Arc<MockStore>> = OnceLock::new();
+    STORE.get_or_init(|| Arc::new(MockStore::new()))
+}
+
+/// Open the store selected by `LKR_BACKEND` (or the platform default).
+pub fn default_store() -> Result<Box<dyn KeyStore>> {
+    Backend::from_env()?.open()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymanager::KeyKind;
+
+    #[test]
+    fn test_from_env_value() {
+        assert_eq!(Backend::from_env_value(None).unwrap(), Backend::Keychain);
+        assert_eq!(
+            Backend::from_env_value(Some("")).unwrap(),
+            Backend::Keychain
+        );
+        assert_eq!(
+            Backend::from_env_value(Some("file")).unwrap(),
+            Backend::File
+        );
+        assert_eq!(
+            Backend::from_env_value(Some(" Memory ")).unwrap(),
+            Backend::Memory
+        );
+        assert!(matches!(
+            Backend::from_env_value(Some("vault")).unwrap_err(),
+            Error::Usage(_)
+        ));
+    }
+
+    #[test]
+    fn test_open_backends() {
+        // Goes through from_env_value rather than LKR_BACKEND itself:
+        // setting the variable would race with tests on other threads.
+        let store = Backend::from_env_value(Some("memory"))
+            .unwrap()
+            .open()
+            .unwrap();
+        store
+            .set("openai:prod", "sk-test-1", KeyKind::Runtime, false)
+            .unwrap();
+        assert_eq!(&*store.get("openai:prod").unwrap().0, "sk-test-1");
+        // Every memory store in the process shares the same keys
+        assert!(
+            Backend::Memory
+                .open()
+                .unwrap()
+                .exists("openai:prod")
+                .unwrap()
+        );
+
+        // A scratch path rather than the real ~/.config/lkr/keys.json
+        let dir = std::env::temp_dir().join(format!("lkr-test-backend-{}", std::process::id()));
+        let path = dir.join("keys.json");
+        let store = Backend::File.open_with(|| Ok(path.clone())).unwrap();
+        store
+            .set("openai:prod", "sk-test-1", KeyKind::Runtime, false)
+            .unwrap();
+        assert!(path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
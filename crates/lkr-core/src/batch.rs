@@ -0,0 +1,447 @@
+//! Key files for `lkr batch-set`: many keys in one JSON or TOML file,
+//! stored entry by entry so one bad entry doesn't stop the rest.
+//!
+//! JSON is an array of `{"name", "value", "kind"}` objects; TOML is a list
+//! of `[[key]]` tables with the same fields. `kind` defaults to runtime.
+
+use crate::error::{Error, Result, json_error_summary};
+use crate::keymanager::{KeyKind, KeyStore, MetadataUpdate, SOURCE_FILE, validate_key_name};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Syntax of a batch file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchFormat {
+    Json,
+    Toml,
+}
+
+impl BatchFormat {
+    /// From the file extension (`.json`, `.toml`), else from the content:
+    /// JSON starts with `[` or `{`, a `[[key]]` table does not count.
+    pub fn detect(path: &Path, content: &str) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Self::Toml,
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => {
+                let start = content.trim_start();
+                if (start.starts_with('[') && !start.starts_with("[[")) || start.starts_with('{') {
+                    Self::Json
+                } else {
+                    Self::Toml
+                }
+            }
+        }
+    }
+}
+
+/// One entry of a batch file.
+pub struct BatchEntry {
+    /// Key name as written, or `#N` (1-based position) when it has none
+    pub name: String,
+    /// Value and kind to store, or why the entry can't be stored
+    pub parsed: std::result::Result<(Zeroizing<String>, KeyKind), String>,
+}
+
+impl std::fmt::Debug for BatchEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parsed = match &self.parsed {
+            Ok((_, kind)) => Ok(("<redacted>", kind)),
+            Err(reason) => Err(reason),
+        };
+        f.debug_struct("BatchEntry")
+            .field("name", &self.name)
+            .field("parsed", &parsed)
+            .finish()
+    }
+}
+
+/// What happened to one entry in [`set_batch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchStatus {
+    Stored,
+    /// Overwrote an existing key (`force`)
+    Replaced,
+    /// Key already exists and `force` was not given
+    Skipped,
+    Failed(String),
+}
+
+/// Per-entry outcome of [`set_batch`], in file order.
+#[derive(Debug, Default)]
+pub struct BatchResult {
+    pub entries: Vec<(String, BatchStatus)>,
+}
+
+impl BatchResult {
+    pub fn count(&self, matches: impl Fn(&BatchStatus) -> bool) -> usize {
+        self.entries.iter().filter(|(_, s)| matches(s)).count()
+    }
+
+    /// `(name, reason)` for every entry that failed.
+    pub fn errors(&self) -> Vec<(&str, &str)> {
+        self.entries
+            .iter()
+            .filter_map(|(name, status)| match status {
+                BatchStatus::Failed(reason) => Some((name.as_str(), reason.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Fields of one entry before validation.
+#[derive(Deserialize)]
+struct RawEntry {
+    name: Option<String>,
+    value: Option<String>,
+    kind: Option<String>,
+}
+
+/// Parse a batch file. A malformed file is an error; a malformed entry
+/// is returned with `parsed` holding the reason, so the rest still go in.
+/// A name given more than once fails every entry that uses it, as it is
+/// unclear which value was meant.
+///
+/// The returned values are `Zeroizing`, but parsing copies them first: the
+/// `serde_json::Value` tree (JSON) or `toml_edit` document (TOML) holds
+/// each value until this function returns, and those copies are freed
+/// without being zeroed.
+pub fn parse_batch_file(content: &str, format: BatchFormat) -> Result<Vec<BatchEntry>> {
+    let raw = match format {
+        BatchFormat::Json => json_entries(content)?,
+        BatchFormat::Toml => toml_entries(content)?,
+    };
+    let mut entries: Vec<BatchEntry> = raw
+        .into_iter()
+        .enumerate()
+        .map(|(i, raw)| match raw {
+            Ok(raw) => check_entry(i, raw),
+            Err(reason) => BatchEntry {
+                name: format!("#{}", i + 1),
+                parsed: Err(reason),
+            },
+        })
+        .collect();
+
+    let mut uses: HashMap<&str, usize> = HashMap::new();
+    for entry in &entries {
+        *uses.entry(entry.name.as_str()).or_default() += 1;
+    }
+    let duplicates: HashSet<String> = uses
+        .into_iter()
+        .filter(|&(_, n)| n > 1)
+        .map(|(name, _)| name.to_string())
+        .collect();
+    for entry in &mut entries {
+        if duplicates.contains(&entry.name) {
+            entry.parsed = Err("name appears more than once in the file".to_string());
+        }
+    }
+    Ok(entries)
+}
+
+fn json_entries(content: &str) -> Result<Vec<std::result::Result<RawEntry, String>>> {
+    let items: Vec<serde_json::Value> = serde_json::from_str(content)
+        .map_err(|e| Error::Usage(format!("Invalid batch file: {}", json_error_summary(&e))))?;
+    Ok(items
+        .into_iter()
+        // Not the serde error: a type mismatch quotes the value
+        .map(|item| {
+            serde_json::from_value(item)
+                .map_err(|_| "not an object of string `name`, `value` and `kind`".to_string())
+        })
+        .collect())
+}
+
+fn toml_entries(content: &str) -> Result<Vec<std::result::Result<RawEntry, String>>> {
+    // Parse errors quote the offending line, which may hold a value
+    let doc: toml_edit::DocumentMut = content
+        .parse()
+        .map_err(|_| Error::Usage("Invalid batch file: not valid TOML".to_string()))?;
+    let Some(item) = doc.get("key") else {
+        return Ok(Vec::new());
+    };
+    let tables = item.as_array_of_tables().ok_or_else(|| {
+        Error::Usage("Invalid batch file: `key` must be [[key]] tables".to_string())
+    })?;
+    Ok(tables
+        .iter()
+        .map(|table| {
+            let field = |name: &str| match table.get(name) {
+                None => Ok(None),
+                Some(item) => item
+                    .as_str()
+                    .map(|s| Some(s.to_string()))
+                    .ok_or_else(|| format!("`{}` must be a string", name)),
+            };
+            Ok(RawEntry {
+                name: field("name")?,
+                value: field("value")?,
+                kind: field("kind")?,
+            })
+        })
+        .collect())
+}
+
+fn check_entry(index: usize, raw: RawEntry) -> BatchEntry {
+    let RawEntry { name, value, kind } = raw;
+    let value = value.map(Zeroizing::new);
+    let Some(name) = name else {
+        return BatchEntry {
+            name: format!("#{}", index + 1),
+            parsed: Err("missing `name`".to_string()),
+        };
+    };
+    let parsed = (|| {
+        validate_key_name(&name).map_err(|e| match e {
+            Error::InvalidKeyName { reason, .. } => reason,
+            other => other.to_string(),
+        })?;
+        let value = value.ok_or("missing `value`")?;
+        if value.is_empty() {
+            return Err("empty value".to_string());
+        }
+        let kind = match kind {
+            Some(kind) => kind.parse::<KeyKind>()?,
+            None => KeyKind::Runtime,
+        };
+        Ok((value, kind))
+    })();
+    BatchEntry { name, parsed }
+}
+
+/// Store every usable entry, recording it as from a file. Failures are
+/// collected rather than returned, so one bad entry doesn't stop the rest.
+pub fn set_batch(store: &impl KeyStore, entries: &[BatchEntry], force: bool) -> BatchResult {
+    let mut result = BatchResult::default();
+    for entry in entries {
+        let status = match &entry.parsed {
+            Err(reason) => BatchStatus::Failed(reason.clone()),
+            Ok((value, kind)) => match store_entry(store, &entry.name, value, *kind, force) {
+                Ok(status) => status,
+                Err(e) if matches!(e.root(), Error::KeyAlreadyExists { .. }) => {
+                    BatchStatus::Skipped
+                }
+                Err(e) => BatchStatus::Failed(e.to_string()),
+            },
+        };
+        result.entries.push((entry.name.clone(), status));
+    }
+    result
+}
+
+fn store_entry(
+    store: &impl KeyStore,
+    name: &str,
+    value: &str,
+    kind: KeyKind,
+    force: bool,
+) -> Result<BatchStatus> {
+    store.with_locked(|s| {
+        let existed = s.exists(name)?;
+        s.set_with(
+            name,
+            value,
+            kind,
+            force,
+            &[MetadataUpdate::Source(SOURCE_FILE.to_string())],
+        )?;
+        Ok(if existed {
+            BatchStatus::Replaced
+        } else {
+            BatchStatus::Stored
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymanager::MockStore;
+
+    const JSON: &str = r#"[
+        {"name": "openai:prod", "value": "sk-1", "kind": "runtime"},
+        {"name": "openai:admin", "value": "sk-admin", "kind": "admin"},
+        {"name": "anthropic:main", "value": "sk-ant-1"}
+    ]"#;
+
+    const TOML: &str = r#"
+# Keys for a new machine
+[[key]]
+name = "openai:prod"
+value = "sk-1"
+kind = "runtime"
+
+[[key]]
+name = "openai:admin"
+value = "sk-admin"
+kind = "admin"
+
+[[key]]
+name = "anthropic:main"
+value = "sk-ant-1"
+"#;
+
+    fn summary(entries: &[BatchEntry]) -> Vec<(&str, std::result::Result<(&str, KeyKind), &str>)> {
+        entries
+            .iter()
+            .map(|e| {
+                let parsed = match &e.parsed {
+                    Ok((value, kind)) => Ok((value.as_str(), *kind)),
+                    Err(reason) => Err(reason.as_str()),
+                };
+                (e.name.as_str(), parsed)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_json_and_toml_parse_the_same() {
+        let expected = vec![
+            ("openai:prod", Ok(("sk-1", KeyKind::Runtime))),
+            ("openai:admin", Ok(("sk-admin", KeyKind::Admin))),
+            ("anthropic:main", Ok(("sk-ant-1", KeyKind::Runtime))),
+        ];
+        let json = parse_batch_file(JSON, BatchFormat::Json).unwrap();
+        assert_eq!(summary(&json), expected);
+        let toml = parse_batch_file(TOML, BatchFormat::Toml).unwrap();
+        assert_eq!(summary(&toml), expected);
+    }
+
+    #[test]
+    fn test_bad_entries_are_reported_not_fatal() {
+        let json = r#"[
+            {"name": "openai:prod", "value": "sk-1"},
+            {"name": "bad name", "value": "sk-2"},
+            {"name": "openai:empty", "value": ""},
+            {"name": "openai:kind", "value": "sk-3", "kind": "root"},
+            {"value": "sk-4"},
+            {"name": "openai:novalue"},
+            "not an object"
+        ]"#;
+        let entries = parse_batch_file(json, BatchFormat::Json).unwrap();
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "openai:prod",
+                "bad name",
+                "openai:empty",
+                "openai:kind",
+                "#5",
+                "openai:novalue",
+                "#7"
+            ]
+        );
+        assert!(entries[0].parsed.is_ok());
+        assert!(entries[1..].iter().all(|e| e.parsed.is_err()));
+        assert_eq!(entries[2].parsed.as_ref().unwrap_err(), "empty value");
+        assert_eq!(entries[5].parsed.as_ref().unwrap_err(), "missing `value`");
+    }
+
+    #[test]
+    fn test_duplicate_names_fail_every_use() {
+        let json = r#"[
+            {"name": "openai:prod", "value": "sk-1"},
+            {"name": "anthropic:main", "value": "sk-ant-1"},
+            {"name": "openai:prod", "value": "sk-2"}
+        ]"#;
+        let entries = parse_batch_file(json, BatchFormat::Json).unwrap();
+        let duplicate = Err("name appears more than once in the file");
+        assert_eq!(
+            summary(&entries),
+            [
+                ("openai:prod", duplicate),
+                ("anthropic:main", Ok(("sk-ant-1", KeyKind::Runtime))),
+                ("openai:prod", duplicate),
+            ]
+        );
+
+        let store = MockStore::new();
+        let result = set_batch(&store, &entries, false);
+        assert_eq!(result.errors().len(), 2);
+        assert!(!store.exists("openai:prod").unwrap());
+    }
+
+    #[test]
+    fn test_toml_non_string_field_is_an_entry_error() {
+        let toml = "[[key]]\nname = \"openai:prod\"\nvalue = 42\n";
+        let entries = parse_batch_file(toml, BatchFormat::Toml).unwrap();
+        assert_eq!(summary(&entries), [("#1", Err("`value` must be a string"))]);
+    }
+
+    #[test]
+    fn test_malformed_file_is_an_error_without_values() {
+        let err = parse_batch_file(
+            r#"[{"name": "a:b", "value": "sk-secret""#,
+            BatchFormat::Json,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Invalid batch file"));
+
+        let err = parse_batch_file("[[key]]\nvalue = \"sk-secret", BatchFormat::Toml).unwrap_err();
+        assert!(!err.to_string().contains("sk-secret"));
+
+        assert!(parse_batch_file("key = \"x\"", BatchFormat::Toml).is_err());
+        assert!(
+            parse_batch_file("# nothing yet\n", BatchFormat::Toml)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            BatchFormat::detect(Path::new("keys.toml"), "["),
+            BatchFormat::Toml
+        );
+        assert_eq!(
+            BatchFormat::detect(Path::new("keys.JSON"), "[[key]]"),
+            BatchFormat::Json
+        );
+        assert_eq!(BatchFormat::detect(Path::new("-"), JSON), BatchFormat::Json);
+        assert_eq!(BatchFormat::detect(Path::new("-"), TOML), BatchFormat::Toml);
+        assert_eq!(
+            BatchFormat::detect(Path::new("keys"), "  [[key]]\n"),
+            BatchFormat::Toml
+        );
+    }
+
+    #[test]
+    fn test_set_batch_continues_past_failures() {
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-old", KeyKind::Runtime, false)
+            .unwrap();
+        let json = r#"[
+            {"name": "openai:prod", "value": "sk-new"},
+            {"name": "bad name", "value": "sk-2"},
+            {"name": "anthropic:main", "value": "sk-ant-1"}
+        ]"#;
+        let entries = parse_batch_file(json, BatchFormat::Json).unwrap();
+
+        let result = set_batch(&store, &entries, false);
+        assert_eq!(result.entries[0].1, BatchStatus::Skipped);
+        assert!(matches!(result.entries[1].1, BatchStatus::Failed(_)));
+        assert_eq!(result.entries[2].1, BatchStatus::Stored);
+        assert_eq!(result.errors().len(), 1);
+        assert_eq!(&*store.get("openai:prod").unwrap().0, "sk-old");
+        assert_eq!(
+            store
+                .get_with_metadata("anthropic:main")
+                .unwrap()
+                .source
+                .as_deref(),
+            Some(SOURCE_FILE)
+        );
+
+        let result = set_batch(&store, &entries, true);
+        assert_eq!(result.entries[0].1, BatchStatus::Replaced);
+        assert_eq!(result.count(|s| *s == BatchStatus::Replaced), 2);
+        assert_eq!(&*store.get("openai:prod").unwrap().0, "sk-new");
+    }
+}
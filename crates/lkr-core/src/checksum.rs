@@ -0,0 +1,257 @@
+//! Checksums of `lkr gen` outputs, used by `lkr gen --verify` to detect
+//! files edited after generation.
+//!
+//! Records live in `~/.local/share/lkr/checksums.json` (mode 0600), keyed by
+//! absolute output path.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Hex-encoded SHA-256 of `data`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// One generated output as recorded at generation time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecksumRecord {
+    pub path: PathBuf,
+    pub checksum: String,
+    pub generated_at: DateTime<Utc>,
+    /// Template the output was generated from, for regeneration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<PathBuf>,
+}
+
+/// Outcome of comparing an output file against its recorded checksum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// File content matches the recorded checksum.
+    Match(ChecksumRecord),
+    /// File content differs from what lkr wrote.
+    Modified(ChecksumRecord),
+    /// A record exists but the file is gone.
+    Missing(ChecksumRecord),
+    /// lkr has no record of generating this file.
+    Unrecorded,
+}
+
+/// File-backed store of [`ChecksumRecord`]s.
+pub struct ChecksumStore {
+    path: PathBuf,
+}
+
+impl ChecksumStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `~/.local/share/lkr/checksums.json`
+    pub fn default_path() -> Result<PathBuf> {
+        let home = home::home_dir()
+            .ok_or_else(|| Error::Usage("Cannot determine home directory".to_string()))?;
+        Ok(home
+            .join(".local")
+            .join("share")
+            .join("lkr")
+            .join("checksums.json"))
+    }
+
+    /// Record (or replace) the checksum for `output_path`, generated from
+    /// `template` when known.
+    pub fn record(
+        &self,
+        output_path: &Path,
+        checksum: &str,
+        template: Option<&Path>,
+    ) -> Result<()> {
+        let path = absolute(output_path)?;
+        let template = template.map(absolute).transpose()?;
+        let mut records = self.load()?;
+        records.insert(
+            path.display().to_string(),
+            ChecksumRecord {
+                path,
+                checksum: checksum.to_string(),
+                generated_at: Utc::now(),
+                template,
+            },
+        );
+        self.save(&records)
+    }
+
+    /// Every recorded output, in path order.
+    pub fn records(&self) -> Result<Vec<ChecksumRecord>> {
+        Ok(self.load()?.into_values().collect())
+    }
+
+    /// Drop the record for `output_path` (e.g. after deleting the file).
+    pub fn forget(&self, output_path: &Path) -> Result<()> {
+        let path = absolute(output_path)?;
+        let mut records = self.load()?;
+        if records.remove(&path.display().to_string()).is_some() {
+            self.save(&records)?;
+        }
+        Ok(())
+    }
+
+    /// Compare the current content of `output_path` with its record.
+    pub fn verify(&self, output_path: &Path) -> Result<Verification> {
+        let path = absolute(output_path)?;
+        let Some(record) = self.load()?.remove(&path.display().to_string()) else {
+            return Ok(Verification::Unrecorded);
+        };
+        match fs::read(&path) {
+            Ok(content) if sha256_hex(&content) == record.checksum => {
+                Ok(Verification::Match(record))
+            }
+            Ok(_) => Ok(Verification::Modified(record)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Verification::Missing(record)),
+            Err(e) => Err(Error::template_with(
+                format!("Cannot read '{}'", path.display()),
+                e,
+            )),
+        }
+    }
+
+    fn load(&self) -> Result<BTreeMap<String, ChecksumRecord>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => {
+                return Err(Error::Storage(format!(
+                    "Cannot read '{}': {}",
+                    self.path.display(),
+                    e
+                )));
+            }
+        };
+        serde_json::from_str(&content).map_err(|e| {
+            Error::Storage(format!(
+                "Invalid checksum file '{}': {}",
+                self.path.display(),
+                e
+            ))
+        })
+    }
+
+    fn save(&self, records: &BTreeMap<String, ChecksumRecord>) -> Result<()> {
+        let parent = self.path.parent().unwrap_or(Path::new("."));
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(parent)
+            .map_err(|e| Error::Storage(format!("Cannot create '{}': {}", parent.display(), e)))?;
+
+        let content = serde_json::to_string_pretty(records)
+            .map_err(|e| Error::Storage(format!("Serialization failed: {}", e)))?;
+
+        let tmp_path = parent.join(format!(".lkr-checksums-{}.tmp", std::process::id()));
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp_path)
+            .map_err(|e| Error::Storage(format!("Cannot write '{}': {}", tmp_path.display(), e)))?;
+        file.write_all(content.as_bytes())
+            .and_then(|()| file.flush())
+            .map_err(|e| Error::Storage(format!("Write failed: {}", e)))?;
+
+        fs::rename(&tmp_path, &self.path).map_err(|e| {
+            let _ = fs::remove_file(&tmp_path);
+            Error::Storage(format!("Cannot rename to '{}': {}", self.path.display(), e))
+        })
+    }
+}
+
+fn absolute(path: &Path) -> Result<PathBuf> {
+    std::path::absolute(path)
+        .map_err(|e| Error::template_with(format!("Cannot resolve '{}'", path.display()), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lkr-test-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_sha256_hex_known_vector() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_verify_detects_modification() {
+        let dir = temp_dir("checksum-verify");
+        let store = ChecksumStore::new(dir.join("state").join("checksums.json"));
+        let output = dir.join(".env");
+
+        assert_eq!(store.verify(&output).unwrap(), Verification::Unrecorded);
+
+        fs::write(&output, "A=1\n").unwrap();
+        store.record(&output, &sha256_hex(b"A=1\n"), None).unwrap();
+        assert!(matches!(
+            store.verify(&output).unwrap(),
+            Verification::Match(_)
+        ));
+
+        fs::write(&output, "A=2\n").unwrap();
+        assert!(matches!(
+            store.verify(&output).unwrap(),
+            Verification::Modified(_)
+        ));
+
+        fs::remove_file(&output).unwrap();
+        assert!(matches!(
+            store.verify(&output).unwrap(),
+            Verification::Missing(_)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_record_overwrites_and_is_private() {
+        let dir = temp_dir("checksum-record");
+        let file = dir.join("checksums.json");
+        let store = ChecksumStore::new(&file);
+        let output = dir.join(".env");
+
+        store.record(&output, "old", None).unwrap();
+        store
+            .record(&output, "new", Some(&dir.join(".env.example")))
+            .unwrap();
+
+        let records = store.records().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].checksum, "new");
+        assert_eq!(records[0].template, Some(dir.join(".env.example")));
+        let mode = fs::metadata(&file).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+
+        store.forget(&output).unwrap();
+        assert!(store.records().unwrap().is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,144 @@
+//! Passphrase-encrypted key files (`lkr set-many`, `lkr export --passphrase`,
+//! `lkr verify-backup`).
+//!
+//! Files are standard [age](https://age-encryption.org/v1) files with a
+//! single passphrase (scrypt) recipient, so `age -d` can open them too.
+
+use crate::error::{Error, Result};
+use age::DecryptError;
+use age::secrecy::SecretString;
+use zeroize::Zeroizing;
+
+/// First bytes of every (binary, unarmored) age file.
+const MAGIC: &[u8] = b"age-encryption.org/v1\n";
+/// Largest scrypt work factor (log2 N) `decrypt` accepts: the factor comes
+/// from the file's header, so without a cap a crafted file could demand
+/// minutes of work and gigabytes of memory. 22 is the `age` CLI's default
+/// limit; files written here use about 1s of work (`age` picks ~18-20).
+const MAX_WORK_FACTOR: u8 = 22;
+
+/// Whether `data` looks like a file written by [`encrypt`].
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Encrypt `plaintext` with a key derived from `passphrase`.
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    encrypt_with(plaintext, passphrase, None)
+}
+
+/// `encrypt`, optionally with a fixed scrypt work factor instead of the
+/// ~1s `age` calibrates for this machine.
+fn encrypt_with(plaintext: &[u8], passphrase: &str, work_factor: Option<u8>) -> Result<Vec<u8>> {
+    if passphrase.is_empty() {
+        return Err(Error::Crypto("Passphrase must not be empty".to_string()));
+    }
+    let mut recipient = age::scrypt::Recipient::new(SecretString::from(passphrase));
+    if let Some(log_n) = work_factor {
+        recipient.set_work_factor(log_n);
+    }
+    age::encrypt(&recipient, plaintext)
+        .map_err(|e| Error::Crypto(format!("Encryption failed: {}", e)))
+}
+
+/// Decrypt a file written by [`encrypt`]. A wrong passphrase and a
+/// corrupted file are indistinguishable by design.
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Zeroizing<Vec<u8>>> {
+    if !is_encrypted(data) {
+        return Err(Error::Crypto("Not an lkr encrypted file".to_string()));
+    }
+    let mut identity = age::scrypt::Identity::new(SecretString::from(passphrase));
+    identity.set_max_work_factor(MAX_WORK_FACTOR);
+    age::decrypt(&identity, data)
+        .map(Zeroizing::new)
+        .map_err(|e| Error::Crypto(decrypt_error_message(e)))
+}
+
+fn decrypt_error_message(err: DecryptError) -> String {
+    match err {
+        DecryptError::DecryptionFailed
+        | DecryptError::KeyDecryptionFailed
+        | DecryptError::InvalidMac
+        | DecryptError::NoMatchingKeys => {
+            "Decryption failed: wrong passphrase or corrupted file".to_string()
+        }
+        DecryptError::ExcessiveWork { required, .. } => format!(
+            "Encrypted file asks for scrypt work factor {} (at most {} allowed)",
+            required, MAX_WORK_FACTOR
+        ),
+        DecryptError::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+            "Encrypted file is truncated".to_string()
+        }
+        // A payload chunk that fails to open surfaces as an I/O error
+        DecryptError::Io(_) => "Decryption failed: wrong passphrase or corrupted file".to_string(),
+        other => format!("Encrypted file is corrupted: {}", other),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cheap scrypt: the KDF cost is not what's under test
+    fn seal(plaintext: &[u8], passphrase: &str) -> Vec<u8> {
+        encrypt_with(plaintext, passphrase, Some(10)).unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let sealed = seal(b"[{\"name\":\"openai:prod\"}]", "correct horse");
+        assert!(is_encrypted(&sealed));
+        let opened = decrypt(&sealed, "correct horse").unwrap();
+        assert_eq!(&*opened, b"[{\"name\":\"openai:prod\"}]");
+    }
+
+    #[test]
+    fn test_wrong_passphrase_fails() {
+        let sealed = seal(b"secret", "right");
+        let err = decrypt(&sealed, "wrong").unwrap_err();
+        assert!(err.to_string().contains("wrong passphrase"));
+    }
+
+    #[test]
+    fn test_tampered_header_or_body_fails() {
+        let sealed = seal(b"secret", "pw");
+        let mut tampered = sealed.clone();
+        tampered[MAGIC.len() + 12] ^= 1; // scrypt stanza salt
+        assert!(decrypt(&tampered, "pw").is_err());
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1; // payload tag
+        assert!(decrypt(&tampered, "pw").is_err());
+        assert!(decrypt(&sealed[..sealed.len() - 20], "pw").is_err());
+        let err = decrypt(&sealed[..MAGIC.len() + 10], "pw").unwrap_err();
+        assert!(err.to_string().contains("truncated"));
+    }
+
+    #[test]
+    fn test_excessive_work_factor_rejected_before_derivation() {
+        let sealed = seal(b"secret", "pw");
+        // The stanza line is "-> scrypt <salt> 10"
+        let header = String::from_utf8_lossy(&sealed[..MAGIC.len() + 60]).into_owned();
+        let at = header.find(" 10\n").unwrap();
+        let mut tampered = sealed.clone();
+        tampered[at + 1..at + 3].copy_from_slice(b"40");
+        // Returns at once: 2^40 scrypt would never finish
+        let err = decrypt(&tampered, "pw").unwrap_err();
+        assert!(matches!(err, Error::Crypto(_)));
+        assert!(err.to_string().contains("work factor 40"));
+    }
+
+    #[test]
+    fn test_plain_json_is_not_encrypted() {
+        assert!(!is_encrypted(b"[]"));
+        assert!(decrypt(b"[]", "pw").is_err());
+    }
+
+    #[test]
+    fn test_empty_passphrase_rejected() {
+        assert!(encrypt(b"x", "").is_err());
+    }
+}
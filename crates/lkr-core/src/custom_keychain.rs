@@ -14,6 +14,7 @@ use std::path::PathBuf;
 // level we need (lock/delete/search-list management).
 unsafe extern "C" {
     fn SecKeychainLock(keychain: *const c_void) -> i32;
+    fn SecKeychainGetStatus(keychain: *const c_void, status: *mut u32) -> i32;
     fn SecKeychainDelete(keychain: *const c_void) -> i32;
     fn SecKeychainCopySearchList(search_list_out: *mut *mut c_void) -> i32;
     fn SecKeychainSetSearchList(search_list: *const c_void) -> i32;
@@ -38,6 +39,9 @@ unsafe extern "C" {
 /// Default keychain filename.
 const KEYCHAIN_FILENAME: &str = "lkr.keychain-db";
 
+/// `kSecUnlockStateStatus` bit of `SecKeychainGetStatus`.
+const UNLOCK_STATE_STATUS: u32 = 1;
+
 /// Auto-lock timeout in seconds (5 minutes).
 const AUTO_LOCK_TIMEOUT_SECS: u32 = 300;
 
@@ -47,7 +51,7 @@ const AUTO_LOCK_TIMEOUT_SECS: u32 = 300;
 /// Never falls back to the current directory — that would be a security hazard.
 pub fn keychain_path() -> Result<PathBuf> {
     let base = dirs_next()
-        .ok_or_else(|| Error::Keychain("Cannot resolve home directory. Is $HOME set?".into()))?;
+        .ok_or_else(|| Error::keychain("Cannot resolve home directory. Is $HOME set?".into()))?;
     Ok(base.join(KEYCHAIN_FILENAME))
 }
 
@@ -70,7 +74,7 @@ pub fn is_initialized() -> bool {
 pub fn create(password: &str) -> Result<SecKeychain> {
     let path = keychain_path()?;
     if path.exists() {
-        return Err(Error::Keychain(
+        return Err(Error::keychain(
             "Custom keychain already exists. Run `lkr lock` or use existing keychain.".into(),
         ));
     }
@@ -78,7 +82,9 @@ pub fn create(password: &str) -> Result<SecKeychain> {
     let keychain = CreateOptions::new()
         .password(password)
         .create(path.as_path())
-        .map_err(|e| Error::Keychain(format!("Failed to create custom keychain: {e}")))?;
+        .map_err(|source| {
+            Error::keychain_os("Failed to create custom keychain".to_string(), source)
+        })?;
 
     // Apply lock-on-sleep + auto-lock timeout
     apply_settings(&keychain)?;
@@ -96,7 +102,7 @@ pub fn open() -> Result<SecKeychain> {
         return Err(Error::NotInitialized);
     }
     SecKeychain::open(path.as_path())
-        .map_err(|e| Error::Keychain(format!("Failed to open custom keychain: {e}")))
+        .map_err(|source| Error::keychain_os("Failed to open custom keychain".to_string(), source))
 }
 
 /// Unlock the custom keychain with the given password.
@@ -106,7 +112,7 @@ pub fn unlock(keychain: &mut SecKeychain, password: &str) -> Result<()> {
         match code {
             -25293 => Error::PasswordWrong,
             -25308 => Error::KeychainLocked, // interaction not allowed
-            _ => Error::Keychain(format!("Failed to unlock: {e}")),
+            _ => Error::keychain_os("Failed to unlock".to_string(), e),
         }
     })
 }
@@ -117,20 +123,36 @@ pub fn lock(keychain: &SecKeychain) -> Result<()> {
     // as_concrete_TypeRef returns the inner CF pointer.
     let status = unsafe { SecKeychainLock(keychain.as_concrete_TypeRef() as _) };
     if status != 0 {
-        return Err(Error::Keychain(format!(
+        return Err(Error::keychain(format!(
             "Failed to lock keychain: OSStatus {status}"
         )));
     }
     Ok(())
 }
 
+/// Whether the keychain is currently locked. With user interaction
+/// disabled, a read from a locked keychain fails with the same OSStatus as
+/// an ACL refusal; this tells them apart.
+pub fn is_locked(keychain: &SecKeychain) -> Result<bool> {
+    let mut status: u32 = 0;
+    // SAFETY: keychain is a valid SecKeychainRef; status is a stack-local
+    // out-parameter.
+    let rc = unsafe { SecKeychainGetStatus(keychain.as_concrete_TypeRef() as _, &mut status) };
+    if rc != 0 {
+        return Err(Error::keychain(format!(
+            "Failed to read keychain status: OSStatus {rc}"
+        )));
+    }
+    Ok(status & UNLOCK_STATE_STATUS == 0)
+}
+
 /// Delete the custom keychain (used by cleanup/reset).
 pub fn delete(keychain: &SecKeychain) -> Result<()> {
     // SAFETY: keychain is a valid SecKeychainRef. SecKeychainDelete removes
     // the keychain file and invalidates the ref. Caller must not use it after.
     let status = unsafe { SecKeychainDelete(keychain.as_concrete_TypeRef() as _) };
     if status != 0 {
-        return Err(Error::Keychain(format!(
+        return Err(Error::keychain(format!(
             "Failed to delete keychain: OSStatus {status}"
         )));
     }
@@ -147,10 +169,12 @@ fn apply_settings(_keychain: &SecKeychain) -> Result<()> {
 
     // set_settings requires &mut, but we just created the keychain
     // so we clone the reference through a re-open
-    let mut kc = SecKeychain::open(keychain_path()?.as_path())
-        .map_err(|e| Error::Keychain(format!("Failed to reopen keychain for settings: {e}")))?;
-    kc.set_settings(&settings)
-        .map_err(|e| Error::Keychain(format!("Failed to apply keychain settings: {e}")))?;
+    let mut kc = SecKeychain::open(keychain_path()?.as_path()).map_err(|source| {
+        Error::keychain_os("Failed to reopen keychain for settings".to_string(), source)
+    })?;
+    kc.set_settings(&settings).map_err(|source| {
+        Error::keychain_os("Failed to apply keychain settings".to_string(), source)
+    })?;
     Ok(())
 }
 
@@ -173,7 +197,7 @@ pub fn ensure_not_in_search_list(keychain: &SecKeychain) -> Result<()> {
         let mut search_list: *mut c_void = std::ptr::null_mut();
         let copy_status = SecKeychainCopySearchList(&mut search_list);
         if copy_status != 0 {
-            return Err(Error::Keychain(format!(
+            return Err(Error::keychain(format!(
                 "SecKeychainCopySearchList failed: OSStatus {copy_status}"
             )));
         }
@@ -216,7 +240,7 @@ pub fn ensure_not_in_search_list(keychain: &SecKeychain) -> Result<()> {
         CFRelease(search_list as _);
 
         if set_status != 0 {
-            return Err(Error::Keychain(format!(
+            return Err(Error::keychain(format!(
                 "SecKeychainSetSearchList failed: OSStatus {set_status}"
             )));
         }
@@ -230,7 +254,7 @@ pub fn ensure_not_in_search_list(keychain: &SecKeychain) -> Result<()> {
                 let item = CFArrayGetValueAtIndex(verify_list, i);
                 if CFEqual(item, our_ref) {
                     CFRelease(verify_list as _);
-                    return Err(Error::Keychain(
+                    return Err(Error::keychain(
                         "Failed to remove custom keychain from search list (still present after set)"
                             .into(),
                     ));
@@ -253,7 +277,7 @@ pub fn is_in_search_list(keychain: &SecKeychain) -> Result<bool> {
         let mut search_list: *mut c_void = std::ptr::null_mut();
         let status = SecKeychainCopySearchList(&mut search_list);
         if status != 0 {
-            return Err(Error::Keychain(format!(
+            return Err(Error::keychain(format!(
                 "SecKeychainCopySearchList failed: OSStatus {status}"
             )));
         }
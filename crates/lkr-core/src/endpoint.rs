@@ -0,0 +1,101 @@
+//! Custom API endpoints for keys that point at non-standard deployments
+//! (enterprise proxies, Azure OpenAI, self-hosted OpenAI-compatible servers).
+
+use crate::error::{Error, Result};
+
+/// Validate a user-supplied endpoint and normalize it (trailing `/` removed).
+///
+/// Keys are sent to this URL, so plain `http://` is only accepted for
+/// loopback hosts.
+pub fn normalize_endpoint(url: &str) -> Result<String> {
+    let url = url.trim().trim_end_matches('/');
+    let invalid = |reason: &str| Error::Usage(format!("Invalid endpoint '{}': {}", url, reason));
+
+    let (scheme, rest) = url
+        .split_once("://")
+        .ok_or_else(|| invalid("expected an http(s):// URL"))?;
+    let host = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let hostname = host.rsplit('@').next().unwrap_or(host);
+    let hostname = match hostname.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or(""),
+        None => hostname.split(':').next().unwrap_or(""),
+    };
+    if hostname.is_empty() {
+        return Err(invalid("missing host"));
+    }
+    if host.contains('@') {
+        return Err(invalid("credentials in the URL are not allowed"));
+    }
+
+    match scheme.to_ascii_lowercase().as_str() {
+        "https" => {}
+        "http" if matches!(hostname, "localhost" | "127.0.0.1" | "::1") => {}
+        "http" => return Err(invalid("plain http is only allowed for localhost")),
+        _ => return Err(invalid("expected an http(s):// URL")),
+    }
+    Ok(url.to_string())
+}
+
+/// Base URL to build API paths on: `endpoint` if set, else `default`.
+///
+/// API paths already start with `/v1`, so a trailing `/v1` on the endpoint
+/// (as OpenAI SDKs expect in `OPENAI_BASE_URL`) is dropped.
+pub(crate) fn api_base(endpoint: Option<&str>, default: &str) -> String {
+    match endpoint {
+        Some(url) => {
+            let url = url.trim_end_matches('/');
+            url.strip_suffix("/v1").unwrap_or(url).to_string()
+        }
+        None => default.to_string(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_endpoint_accepts_https_and_local_http() {
+        assert_eq!(
+            normalize_endpoint("https://proxy.example.com/").unwrap(),
+            "https://proxy.example.com"
+        );
+        assert_eq!(
+            normalize_endpoint("http://localhost:8080/v1").unwrap(),
+            "http://localhost:8080/v1"
+        );
+        assert!(normalize_endpoint("http://[::1]:8080").is_ok());
+    }
+
+    #[test]
+    fn test_normalize_endpoint_rejects_unsafe_urls() {
+        assert!(normalize_endpoint("proxy.example.com").is_err());
+        assert!(normalize_endpoint("http://proxy.example.com").is_err());
+        assert!(normalize_endpoint("ftp://proxy.example.com").is_err());
+        assert!(normalize_endpoint("https://").is_err());
+        assert!(normalize_endpoint("https://user:pw@proxy.example.com").is_err());
+    }
+
+    #[test]
+    fn test_api_base() {
+        assert_eq!(
+            api_base(None, "https://api.openai.com"),
+            "https://api.openai.com"
+        );
+        assert_eq!(
+            api_base(
+                Some("https://proxy.example.com/v1/"),
+                "https://api.openai.com"
+            ),
+            "https://proxy.example.com"
+        );
+        assert_eq!(
+            api_base(Some("https://proxy.example.com/openai"), "x"),
+            "https://proxy.example.com/openai"
+        );
+    }
+}
@@ -1,12 +1,25 @@
+use std::backtrace::Backtrace;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// `std::backtrace::Backtrace` under another name: thiserror treats a
+/// field of type `Backtrace` as one to `provide()`, which needs nightly.
+/// Read it through [`Error::backtrace`] instead.
+type Trace = Backtrace;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Key not found: {name}")]
     KeyNotFound { name: String },
 
+    /// The key's `expires_at` has passed; its value is no longer handed out.
+    #[error("Key expired: {name} (at {expired_at}). Rotate it with `lkr rotate {name}`.")]
+    KeyExpired {
+        name: String,
+        expired_at: chrono::DateTime<chrono::Utc>,
+    },
+
     #[error("Key already exists: {name}. Use --force to overwrite.")]
     KeyAlreadyExists { name: String },
 
@@ -16,23 +29,63 @@ pub enum Error {
     #[error("Empty value is not allowed")]
     EmptyValue,
 
-    #[error("Keychain error: {0}")]
-    Keychain(String),
+    /// Keychain failure; `source` is the Security.framework error, if any.
+    #[error("Keychain error: {message}{}", colon_source(.source))]
+    Keychain {
+        message: String,
+        #[source]
+        source: Option<security_framework::base::Error>,
+        backtrace: Trace,
+    },
 
     #[error("Keychain is locked")]
     KeychainLocked,
 
-    #[error("Template error: {0}")]
-    Template(String),
+    /// Transient Security.framework failure (I/O, service unavailable);
+    /// retried before it is surfaced.
+    #[error("Keychain temporarily unavailable (OSStatus {status}). Try again.")]
+    KeychainBusy { status: i32 },
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+
+    #[error("Encryption error: {0}")]
+    Crypto(String),
+
+    /// Template or generated-file failure; `source` is the underlying
+    /// error (e.g. I/O while writing the output), if any.
+    #[error("Template error: {message}{}", colon_source(.source))]
+    Template {
+        message: String,
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+        backtrace: Trace,
+    },
 
     #[error("Usage API error: {0}")]
     Usage(String),
 
+    /// HTTP request that never got a response (connect, DNS, TLS, timeout).
+    /// `message` already includes the reqwest error text and any hint.
+    #[error("Usage API error: {message}")]
+    Network {
+        message: String,
+        #[source]
+        source: reqwest::Error,
+        backtrace: Trace,
+    },
+
     #[error(
         "Admin key required for {provider} usage tracking. Run `lkr set {provider}:admin --kind admin` to register."
     )]
     AdminKeyRequired { provider: String },
 
+    #[error("Rate limited by {provider} (gave up after {retries} retries). Try again later.")]
+    RateLimited { provider: String, retries: u32 },
+
+    #[error("{provider} usage is not available for this account type. {hint}")]
+    AccountTypeUnsupported { provider: String, hint: String },
+
     #[error("HTTP {status}: {body}")]
     HttpError { status: u16, body: String },
 
@@ -56,6 +109,109 @@ pub enum Error {
 
     #[error("Operation canceled by user")]
     UserCanceled,
+
+    /// A `KeyStore::with_locked` unit failed on a backend that cannot roll
+    /// back, so steps before the failure may still be applied. Displays as
+    /// the underlying error; telling the user is up to the caller.
+    #[error(transparent)]
+    NotRolledBack(Box<Error>),
+}
+
+impl Error {
+    pub fn keychain(message: String) -> Self {
+        Error::Keychain {
+            message,
+            source: None,
+            backtrace: Backtrace::capture(),
+        }
+    }
+
+    /// A `Keychain` error caused by a Security.framework failure.
+    pub fn keychain_os(message: String, source: security_framework::base::Error) -> Self {
+        Error::Keychain {
+            message,
+            source: Some(source),
+            backtrace: Backtrace::capture(),
+        }
+    }
+
+    pub fn template(message: String) -> Self {
+        Error::Template {
+            message,
+            source: None,
+            backtrace: Backtrace::capture(),
+        }
+    }
+
+    /// A `Template` error caused by `source`.
+    pub fn template_with(
+        message: String,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Error::Template {
+            message,
+            source: Some(source.into()),
+            backtrace: Backtrace::capture(),
+        }
+    }
+
+    pub fn network(message: String, source: reqwest::Error) -> Self {
+        Error::Network {
+            message,
+            source,
+            backtrace: Backtrace::capture(),
+        }
+    }
+
+    /// Where the error was created, for variants that wrap an underlying
+    /// error. Only captured when `RUST_BACKTRACE` (or `RUST_LIB_BACKTRACE`)
+    /// is set; otherwise its status is `Disabled`.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        match self.root() {
+            Error::Keychain { backtrace, .. }
+            | Error::Template { backtrace, .. }
+            | Error::Network { backtrace, .. } => Some(backtrace),
+            _ => None,
+        }
+    }
+
+    /// The underlying error, looking through `NotRolledBack`.
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::NotRolledBack(inner) => inner.root(),
+            other => other,
+        }
+    }
+
+    /// `root`, by value.
+    pub fn into_root(self) -> Error {
+        match self {
+            Error::NotRolledBack(inner) => inner.into_root(),
+            other => other,
+        }
+    }
+}
+
+/// `": <source>"` when there is a source, so the message reads the same
+/// as when the source text was formatted into it.
+fn colon_source(source: &Option<impl std::fmt::Display>) -> String {
+    source
+        .as_ref()
+        .map(|source| format!(": {}", source))
+        .unwrap_or_default()
+}
+
+/// Describe a serde_json error without its message. Data errors quote the
+/// offending value (`invalid type: string "sk-..."`), which may be a
+/// secret, so only the error category and position are kept.
+pub fn json_error_summary(e: &serde_json::Error) -> String {
+    let what = match e.classify() {
+        serde_json::error::Category::Io => "read error",
+        serde_json::error::Category::Syntax => "syntax error",
+        serde_json::error::Category::Data => "unexpected type or field",
+        serde_json::error::Category::Eof => "unexpected end of input",
+    };
+    format!("{} at line {} column {}", what, e.line(), e.column())
 }
 
 /// OSStatus codes from Security.framework.
@@ -69,6 +225,9 @@ pub mod os_status {
     pub const ERR_SEC_INVALID_KEYCHAIN: i32 = -25295;
     pub const ERR_SEC_DECODE_ERROR: i32 = -26275;
     pub const ERR_SEC_USER_CANCELED: i32 = -128;
+    pub const ERR_SEC_IO: i32 = -36;
+    pub const ERR_SEC_NOT_AVAILABLE: i32 = -25291;
+    pub const ERR_SEC_INTERNAL_COMPONENT: i32 = -2070;
 }
 
 #[cfg(test)]
@@ -86,6 +245,16 @@ mod tests {
         assert_eq!(os_status::ERR_SEC_USER_CANCELED, -128);
     }
 
+    #[test]
+    fn test_not_rolled_back_reads_as_its_root() {
+        let e = Error::NotRolledBack(Box::new(Error::KeyNotFound {
+            name: "openai:prod".to_string(),
+        }));
+        assert_eq!(e.to_string(), "Key not found: openai:prod");
+        assert!(matches!(e.root(), Error::KeyNotFound { .. }));
+        assert!(matches!(e.into_root(), Error::KeyNotFound { .. }));
+    }
+
     #[test]
     fn test_error_display_not_initialized() {
         let e = Error::NotInitialized;
@@ -123,6 +292,18 @@ mod tests {
         assert!(e.to_string().contains("openai:prod"));
     }
 
+    #[test]
+    fn test_error_display_key_expired() {
+        let e = Error::KeyExpired {
+            name: "aws:session".to_string(),
+            expired_at: "2026-01-02T03:04:05Z".parse().unwrap(),
+        };
+        let msg = e.to_string();
+        assert!(msg.contains("aws:session"));
+        assert!(msg.contains("2026-01-02 03:04:05 UTC"));
+        assert!(msg.contains("lkr rotate aws:session"));
+    }
+
     #[test]
     fn test_error_display_key_already_exists() {
         let e = Error::KeyAlreadyExists {
@@ -133,6 +314,36 @@ mod tests {
         assert!(msg.contains("--force"));
     }
 
+    #[test]
+    fn test_template_keeps_source_and_display() {
+        use std::error::Error as _;
+
+        let e = Error::template_with(
+            "Write failed".to_string(),
+            std::io::Error::other("disk full"),
+        );
+        assert_eq!(e.to_string(), "Template error: Write failed: disk full");
+        assert_eq!(e.source().unwrap().to_string(), "disk full");
+        assert!(e.backtrace().is_some());
+        // Without an underlying error there is no source
+        let e = Error::template("x".to_string());
+        assert_eq!(e.to_string(), "Template error: x");
+        assert!(e.source().is_none());
+        assert!(Error::EmptyValue.backtrace().is_none());
+    }
+
+    #[test]
+    fn test_json_error_summary_omits_value() {
+        let e = serde_json::from_str::<Vec<u32>>("[1,\n \"sk-secret\"]").unwrap_err();
+        assert!(e.to_string().contains("sk-secret"));
+        let summary = json_error_summary(&e);
+        assert!(summary.starts_with("unexpected type or field at line 2 column"));
+        assert!(!summary.contains("sk-secret"));
+
+        let e = serde_json::from_str::<Vec<u32>>("[1, \"sk-secret").unwrap_err();
+        assert!(json_error_summary(&e).starts_with("unexpected end of input at line 1"));
+    }
+
     #[test]
     fn test_error_display_user_canceled() {
         let e = Error::UserCanceled;
@@ -0,0 +1,219 @@
+//! Forward-looking cost estimates from token counts (`lkr estimate`).
+//!
+//! Unlike `usage`, nothing is fetched: token counts are multiplied by a
+//! price table. The built-in table can be overridden per model with
+//! `~/.config/lkr/prices.json`.
+
+use crate::error::{Error, Result};
+use crate::usage::{CostLineItem, CostReport};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// List prices for one model, in USD per million tokens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub provider: String,
+    pub model: String,
+    pub input_usd_per_mtok: f64,
+    pub output_usd_per_mtok: f64,
+}
+
+/// Built-in list prices (USD per million tokens). Prices change — override
+/// stale rows in `prices.json` rather than waiting for a release.
+const BUILTIN_PRICES: &[(&str, &str, f64, f64)] = &[
+    ("openai", "gpt-4o", 2.50, 10.00),
+    ("openai", "gpt-4o-mini", 0.15, 0.60),
+    ("openai", "gpt-4.1", 2.00, 8.00),
+    ("openai", "gpt-4.1-mini", 0.40, 1.60),
+    ("openai", "o3", 2.00, 8.00),
+    ("openai", "o4-mini", 1.10, 4.40),
+    ("anthropic", "claude-opus-4", 15.00, 75.00),
+    ("anthropic", "claude-sonnet-4", 3.00, 15.00),
+    ("anthropic", "claude-3-5-haiku", 0.80, 4.00),
+];
+
+/// Price lookup table: built-in rows plus optional overrides.
+#[derive(Debug, Clone)]
+pub struct PriceTable {
+    rows: Vec<ModelPrice>,
+}
+
+impl PriceTable {
+    pub fn builtin() -> Self {
+        let rows = BUILTIN_PRICES
+            .iter()
+            .map(|&(provider, model, input, output)| ModelPrice {
+                provider: provider.to_string(),
+                model: model.to_string(),
+                input_usd_per_mtok: input,
+                output_usd_per_mtok: output,
+            })
+            .collect();
+        Self { rows }
+    }
+
+    /// `~/.config/lkr/prices.json`
+    pub fn default_path() -> Result<PathBuf> {
+        let home = home::home_dir()
+            .ok_or_else(|| Error::Usage("Cannot determine home directory".to_string()))?;
+        Ok(home.join(".config").join("lkr").join("prices.json"))
+    }
+
+    /// Built-in table with overrides from `path` applied, if the file exists.
+    ///
+    /// The file is a JSON array of `ModelPrice` rows. A row replaces the
+    /// built-in row for the same provider and model, or adds a new model.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut table = Self::builtin();
+        let content = match std::fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(table),
+            Err(e) => {
+                return Err(Error::Usage(format!(
+                    "Cannot read price table '{}': {}",
+                    path.display(),
+                    e
+                )));
+            }
+        };
+        let overrides: Vec<ModelPrice> = serde_json::from_str(&content).map_err(|e| {
+            Error::Usage(format!("Invalid price table '{}': {}", path.display(), e))
+        })?;
+        for row in overrides {
+            table.upsert(row);
+        }
+        Ok(table)
+    }
+
+    fn upsert(&mut self, row: ModelPrice) {
+        match self
+            .rows
+            .iter_mut()
+            .find(|r| r.provider == row.provider && r.model == row.model)
+        {
+            Some(existing) => *existing = row,
+            None => self.rows.push(row),
+        }
+    }
+
+    /// Look up a model's prices. Unknown models are an error listing the
+    /// models known for that provider.
+    pub fn find(&self, provider: &str, model: &str) -> Result<&ModelPrice> {
+        if let Some(row) = self
+            .rows
+            .iter()
+            .find(|r| r.provider == provider && r.model == model)
+        {
+            return Ok(row);
+        }
+        let known: Vec<&str> = self
+            .rows
+            .iter()
+            .filter(|r| r.provider == provider)
+            .map(|r| r.model.as_str())
+            .collect();
+        Err(Error::Usage(if known.is_empty() {
+            format!("No prices known for provider '{}'.", provider)
+        } else {
+            format!(
+                "No price for {} model '{}'. Known: {}",
+                provider,
+                model,
+                known.join(", ")
+            )
+        }))
+    }
+}
+
+/// Estimate the cost of a run as a `CostReport` with input and output
+/// line items. The period is today's date (an estimate has no billing period).
+pub fn estimate_cost(price: &ModelPrice, input_tokens: u64, output_tokens: u64) -> CostReport {
+    let cents = |tokens: u64, usd_per_mtok: f64| tokens as f64 / 1_000_000.0 * usd_per_mtok * 100.0;
+    let line_items = vec![
+        CostLineItem {
+            description: format!("{} input ({} tokens)", price.model, input_tokens),
+            cost_cents: cents(input_tokens, price.input_usd_per_mtok),
+            input_tokens: Some(input_tokens),
+            output_tokens: None,
+            currency: "usd".to_string(),
+        },
+        CostLineItem {
+            description: format!("{} output ({} tokens)", price.model, output_tokens),
+            cost_cents: cents(output_tokens, price.output_usd_per_mtok),
+            input_tokens: None,
+            output_tokens: Some(output_tokens),
+            currency: "usd".to_string(),
+        },
+    ];
+    let today = chrono::Utc::now().date_naive().to_string();
+    CostReport {
+        provider: price.provider.clone(),
+        period_start: today.clone(),
+        period_end: today,
+        total_cost_cents: line_items.iter().map(|i| i.cost_cents).sum(),
+        currency: "usd".to_string(),
+        line_items,
+        models: Vec::new(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_math_gpt_4o() {
+        let table = PriceTable::builtin();
+        let price = table.find("openai", "gpt-4o").unwrap();
+        // 1M input @ $2.50 + 500k output @ $10.00 = $7.50
+        let report = estimate_cost(price, 1_000_000, 500_000);
+        assert_eq!(report.line_items[0].cost_cents, 250.0);
+        assert_eq!(report.line_items[1].cost_cents, 500.0);
+        assert_eq!(report.total_cost_cents, 750.0);
+        assert_eq!(crate::usage::format_cost(report.total_cost_cents), "$7.50");
+    }
+
+    #[test]
+    fn test_unknown_model_lists_known() {
+        let table = PriceTable::builtin();
+        let err = table.find("openai", "gpt-9").unwrap_err().to_string();
+        assert!(err.contains("gpt-4o"));
+        assert!(table.find("nobody", "x").is_err());
+    }
+
+    #[test]
+    fn test_overrides_replace_and_add_rows() {
+        let dir = std::env::temp_dir().join(format!("lkr-test-prices-{}", std::process::id()));
+        let _ = std::fs::create_dir_all(&dir);
+        let path = dir.join("prices.json");
+        std::fs::write(
+            &path,
+            r#"[
+                {"provider": "openai", "model": "gpt-4o", "input_usd_per_mtok": 1.0, "output_usd_per_mtok": 2.0},
+                {"provider": "mistral", "model": "large", "input_usd_per_mtok": 2.0, "output_usd_per_mtok": 6.0}
+            ]"#,
+        )
+        .unwrap();
+
+        let table = PriceTable::load(&path).unwrap();
+        assert_eq!(
+            table.find("openai", "gpt-4o").unwrap().input_usd_per_mtok,
+            1.0
+        );
+        assert!(table.find("mistral", "large").is_ok());
+        // Untouched built-in rows survive
+        assert!(table.find("anthropic", "claude-sonnet-4").is_ok());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_missing_override_file_is_builtin() {
+        let table = PriceTable::load(Path::new("/nonexistent/prices.json")).unwrap();
+        assert!(table.find("openai", "gpt-4o").is_ok());
+    }
+}
@@ -0,0 +1,326 @@
+//! Plaintext JSON file backend (`LKR_BACKEND=file`).
+//!
+//! For platforms without a supported keychain and for CI. Values are stored
+//! unencrypted, protected only by file permissions (0600 in a 0700 directory).
+
+use crate::error::{Error, Result, json_error_summary};
+use crate::keymanager::{
+    KeyEntry, KeyKind, KeyStore, MetadataUpdate, StoredEntry, check_name_length, validate_name,
+    validate_name_with,
+};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+/// Key store backed by a single JSON file: `{ "<name>": StoredEntry, ... }`.
+///
+/// Every operation re-reads the file, so concurrent processes see each
+/// other's writes. Writes are atomic (temp file + rename).
+pub struct FileStore {
+    path: PathBuf,
+    /// Serializes read-modify-write cycles within this process
+    lock: Mutex<()>,
+}
+
+impl FileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// `~/.config/lkr/keys.json`
+    pub fn default_path() -> Result<PathBuf> {
+        let home = home::home_dir()
+            .ok_or_else(|| Error::Storage("Cannot determine home directory".to_string()))?;
+        Ok(home.join(".config").join("lkr").join("keys.json"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load all entries. A missing file is an empty store.
+    fn load(&self) -> Result<BTreeMap<String, StoredEntry>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(c) => Zeroizing::new(c),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => {
+                return Err(Error::Storage(format!(
+                    "Cannot read '{}': {}",
+                    self.path.display(),
+                    e
+                )));
+            }
+        };
+        serde_json::from_str(&content).map_err(|e| {
+            Error::Storage(format!(
+                "Corrupt key file '{}': {}",
+                self.path.display(),
+                json_error_summary(&e)
+            ))
+        })
+    }
+
+    fn save(&self, entries: &BTreeMap<String, StoredEntry>) -> Result<()> {
+        let parent = self.path.parent().unwrap_or(Path::new("."));
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(parent)
+            .map_err(|e| Error::Storage(format!("Cannot create '{}': {}", parent.display(), e)))?;
+
+        let content = Zeroizing::new(
+            serde_json::to_string_pretty(entries)
+                .map_err(|e| Error::Storage(format!("Serialization failed: {}", e)))?,
+        );
+
+        let tmp_path = parent.join(format!(".lkr-keys-{}.tmp", std::process::id()));
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp_path)
+            .map_err(|e| Error::Storage(format!("Cannot write '{}': {}", tmp_path.display(), e)))?;
+        file.write_all(content.as_bytes())
+            .and_then(|()| file.flush())
+            .map_err(|e| Error::Storage(format!("Write failed: {}", e)))?;
+
+        fs::rename(&tmp_path, &self.path).map_err(|e| {
+            let _ = fs::remove_file(&tmp_path);
+            Error::Storage(format!("Cannot rename to '{}': {}", self.path.display(), e))
+        })
+    }
+}
+
+impl KeyStore for FileStore {
+    fn set_with(
+        &self,
+        name: &str,
+        value: &str,
+        kind: KeyKind,
+        force: bool,
+        updates: &[MetadataUpdate],
+    ) -> Result<()> {
+        validate_name(name)?;
+        if value.is_empty() {
+            return Err(Error::EmptyValue);
+        }
+
+        let _guard = self.lock.lock().unwrap();
+        let mut entries = self.load()?;
+        if !force && entries.contains_key(name) {
+            return Err(Error::KeyAlreadyExists {
+                name: name.to_string(),
+            });
+        }
+        if !entries.contains_key(name) {
+            check_name_length(name)?;
+        }
+        let mut entry = StoredEntry::for_set(entries.get(name), value, kind);
+        updates.iter().for_each(|u| entry.apply(u));
+        entries.insert(name.to_string(), entry);
+        self.save(&entries)
+    }
+
+    fn set_entry(&self, name: &str, entry: &StoredEntry, force: bool) -> Result<()> {
+        validate_name(name)?;
+        if entry.value.is_empty() {
+            return Err(Error::EmptyValue);
+        }
+
+        let _guard = self.lock.lock().unwrap();
+        let mut entries = self.load()?;
+        if !force && entries.contains_key(name) {
+            return Err(Error::KeyAlreadyExists {
+                name: name.to_string(),
+            });
+        }
+        if !entries.contains_key(name) {
+            check_name_length(name)?;
+        }
+        entries.insert(name.to_string(), entry.clone());
+        self.save(&entries)
+    }
+
+    fn get(&self, name: &str) -> Result<(Zeroizing<String>, KeyKind)> {
+        validate_name(name)?;
+        let _guard = self.lock.lock().unwrap();
+        match self.load()?.get(name) {
+            Some(entry) => {
+                entry.ensure_unexpired(name)?;
+                Ok((Zeroizing::new(entry.value.clone()), entry.kind))
+            }
+            None => Err(Error::KeyNotFound {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    fn get_with_metadata(&self, name: &str) -> Result<StoredEntry> {
+        validate_name(name)?;
+        let _guard = self.lock.lock().unwrap();
+        self.load()?.remove(name).ok_or_else(|| Error::KeyNotFound {
+            name: name.to_string(),
+        })
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        validate_name(name)?;
+        let _guard = self.lock.lock().unwrap();
+        let mut entries = self.load()?;
+        if entries.remove(name).is_none() {
+            return Err(Error::KeyNotFound {
+                name: name.to_string(),
+            });
+        }
+        self.save(&entries)
+    }
+
+    fn list(&self, include_admin: bool) -> Result<Vec<KeyEntry>> {
+        let _guard = self.lock.lock().unwrap();
+        // BTreeMap iteration is already sorted by name
+        let entries = self
+            .load()?
+            .iter()
+            .filter(|(_, v)| include_admin || v.kind.listed_by_default())
+            .filter_map(|(name, v)| {
+                let (provider, label) = validate_name_with(name, true).ok()?;
+                Some(KeyEntry::from_stored(name, provider, label, v))
+            })
+            .collect();
+        Ok(entries)
+    }
+
+    fn exists(&self, name: &str) -> Result<bool> {
+        validate_name(name)?;
+        let _guard = self.lock.lock().unwrap();
+        Ok(self.load()?.contains_key(name))
+    }
+
+    fn touch(&self, name: &str) -> Result<()> {
+        validate_name(name)?;
+        let _guard = self.lock.lock().unwrap();
+        let mut entries = self.load()?;
+        let Some(entry) = entries.get_mut(name) else {
+            return Err(Error::KeyNotFound {
+                name: name.to_string(),
+            });
+        };
+        entry.touch();
+        self.save(&entries)
+    }
+
+    fn update_metadata(&self, name: &str, update: &MetadataUpdate) -> Result<()> {
+        validate_name(name)?;
+        let _guard = self.lock.lock().unwrap();
+        let mut entries = self.load()?;
+        let Some(entry) = entries.get_mut(name) else {
+            return Err(Error::KeyNotFound {
+                name: name.to_string(),
+            });
+        };
+        entry.apply(update);
+        self.save(&entries)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn temp_store(test: &str) -> FileStore {
+        let dir = std::env::temp_dir().join(format!("lkr-test-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        FileStore::new(dir.join("keys.json"))
+    }
+
+    fn cleanup(store: &FileStore) {
+        let _ = fs::remove_dir_all(store.path().parent().unwrap());
+    }
+
+    #[test]
+    fn test_roundtrip_persists_across_instances() {
+        let store = temp_store("file-roundtrip");
+        store
+            .set("openai:prod", "sk-test-123", KeyKind::Runtime, false)
+            .unwrap();
+
+        let reopened = FileStore::new(store.path());
+        let (value, kind) = reopened.get("openai:prod").unwrap();
+        assert_eq!(&*value, "sk-test-123");
+        assert_eq!(kind, KeyKind::Runtime);
+
+        reopened.delete("openai:prod").unwrap();
+        assert!(!store.exists("openai:prod").unwrap());
+        cleanup(&store);
+    }
+
+    #[test]
+    fn test_missing_file_is_empty() {
+        let store = temp_store("file-missing");
+        assert!(store.list(true).unwrap().is_empty());
+        assert!(matches!(
+            store.get("openai:prod").unwrap_err(),
+            Error::KeyNotFound { .. }
+        ));
+    }
+
+    #[test]
+    fn test_file_permissions() {
+        let store = temp_store("file-perms");
+        store
+            .set("openai:prod", "sk-test-123", KeyKind::Runtime, false)
+            .unwrap();
+
+        let mode = fs::metadata(store.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        cleanup(&store);
+    }
+
+    #[test]
+    fn test_list_filters_admin() {
+        let store = temp_store("file-list");
+        store
+            .set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        store
+            .set("openai:admin", "sk-admin-1", KeyKind::Admin, false)
+            .unwrap();
+
+        assert_eq!(store.list(false).unwrap().len(), 1);
+        let all = store.list(true).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].name, "openai:admin");
+        cleanup(&store);
+    }
+
+    #[test]
+    fn test_touch_persists_access_metadata() {
+        let store = temp_store("file-touch");
+        store
+            .set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        store.touch("openai:prod").unwrap();
+
+        let entry = &FileStore::new(store.path()).list(false).unwrap()[0];
+        assert_eq!(entry.access_count, 1);
+        assert!(entry.last_accessed.is_some());
+        assert!(matches!(
+            store.touch("openai:missing"),
+            Err(Error::KeyNotFound { .. })
+        ));
+        cleanup(&store);
+    }
+}
@@ -0,0 +1,261 @@
+//! `KeyStore` decorator that counts and times calls to the wrapped store.
+//!
+//! Used by `lkr --timings` to show where time goes (e.g. a storm of
+//! Keychain prompts during `list`).
+
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use zeroize::Zeroizing;
+
+use crate::error::Result;
+use crate::keymanager::{
+    ImportEntry, ImportMode, ImportReport, KeyEntry, KeyKind, KeyStore, MetadataUpdate, StoredEntry,
+};
+
+/// Calls and total time for one `KeyStore` method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpMetrics {
+    pub op: &'static str,
+    pub calls: u64,
+    pub total: Duration,
+}
+
+/// Snapshot of an [`InstrumentedStore`]'s counters, in first-call order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StoreMetrics {
+    pub ops: Vec<OpMetrics>,
+}
+
+impl StoreMetrics {
+    /// Number of calls to `op` (0 if never called).
+    pub fn calls(&self, op: &str) -> u64 {
+        self.ops.iter().find(|m| m.op == op).map_or(0, |m| m.calls)
+    }
+}
+
+impl fmt::Display for StoreMetrics {
+    /// `list: 1 call 120ms, get: 7 calls 900ms`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ops.is_empty() {
+            return write!(f, "no store calls");
+        }
+        let parts: Vec<String> = self
+            .ops
+            .iter()
+            .map(|m| {
+                format!(
+                    "{}: {} call{} {}ms",
+                    m.op,
+                    m.calls,
+                    if m.calls == 1 { "" } else { "s" },
+                    m.total.as_millis()
+                )
+            })
+            .collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Wraps a `KeyStore`, recording every call made to it.
+///
+/// Methods with a default implementation are forwarded to the inner store
+/// (so a backend's own override is kept) and counted once under their own
+/// name; the calls they make internally are not counted separately.
+pub struct InstrumentedStore<S> {
+    inner: S,
+    metrics: Mutex<StoreMetrics>,
+}
+
+impl<S: KeyStore> InstrumentedStore<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            metrics: Mutex::new(StoreMetrics::default()),
+        }
+    }
+
+    /// Counters so far.
+    pub fn metrics(&self) -> StoreMetrics {
+        self.metrics.lock().unwrap().clone()
+    }
+
+    fn timed<T>(&self, op: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        let elapsed = start.elapsed();
+
+        let mut metrics = self.metrics.lock().unwrap();
+        match metrics.ops.iter_mut().find(|m| m.op == op) {
+            Some(m) => {
+                m.calls += 1;
+                m.total += elapsed;
+            }
+            None => metrics.ops.push(OpMetrics {
+                op,
+                calls: 1,
+                total: elapsed,
+            }),
+        }
+        result
+    }
+}
+
+impl<S: KeyStore> KeyStore for InstrumentedStore<S> {
+    fn set(&self, name: &str, value: &str, kind: KeyKind, force: bool) -> Result<()> {
+        self.timed("set", || self.inner.set(name, value, kind, force))
+    }
+
+    fn set_with(
+        &self,
+        name: &str,
+        value: &str,
+        kind: KeyKind,
+        force: bool,
+        updates: &[MetadataUpdate],
+    ) -> Result<()> {
+        self.timed("set_with", || {
+            self.inner.set_with(name, value, kind, force, updates)
+        })
+    }
+
+    fn set_entry(&self, name: &str, entry: &StoredEntry, force: bool) -> Result<()> {
+        self.timed("set_entry", || self.inner.set_entry(name, entry, force))
+    }
+
+    fn get(&self, name: &str) -> Result<(Zeroizing<String>, KeyKind)> {
+        self.timed("get", || self.inner.get(name))
+    }
+
+    fn get_with_metadata(&self, name: &str) -> Result<StoredEntry> {
+        self.timed("get_with_metadata", || self.inner.get_with_metadata(name))
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        self.timed("delete", || self.inner.delete(name))
+    }
+
+    fn list(&self, include_admin: bool) -> Result<Vec<KeyEntry>> {
+        self.timed("list", || self.inner.list(include_admin))
+    }
+
+    fn exists(&self, name: &str) -> Result<bool> {
+        self.timed("exists", || self.inner.exists(name))
+    }
+
+    fn touch(&self, name: &str) -> Result<()> {
+        self.timed("touch", || self.inner.touch(name))
+    }
+
+    fn update_metadata(&self, name: &str, update: &MetadataUpdate) -> Result<()> {
+        self.timed("update_metadata", || {
+            self.inner.update_metadata(name, update)
+        })
+    }
+
+    fn set_endpoint(&self, name: &str, endpoint: Option<&str>) -> Result<()> {
+        self.timed("set_endpoint", || self.inner.set_endpoint(name, endpoint))
+    }
+
+    fn endpoint(&self, name: &str) -> Result<Option<String>> {
+        self.timed("endpoint", || self.inner.endpoint(name))
+    }
+
+    fn search(&self, pattern: &str, include_admin: bool) -> Result<Vec<KeyEntry>> {
+        self.timed("search", || self.inner.search(pattern, include_admin))
+    }
+
+    fn rename(&self, old_name: &str, new_name: &str, force: bool) -> Result<()> {
+        self.timed("rename", || self.inner.rename(old_name, new_name, force))
+    }
+
+    fn rotate(&self, name: &str, new_value: &str) -> Result<()> {
+        self.timed("rotate", || self.inner.rotate(name, new_value))
+    }
+
+    fn set_kind(&self, name: &str, kind: KeyKind) -> Result<()> {
+        self.timed("set_kind", || self.inner.set_kind(name, kind))
+    }
+
+    fn update_tags(&self, name: &str, add: &[String], remove: &[String]) -> Result<Vec<String>> {
+        self.timed("update_tags", || self.inner.update_tags(name, add, remove))
+    }
+
+    fn import_batch(&self, entries: &[ImportEntry], mode: ImportMode) -> Result<ImportReport> {
+        self.timed("import_batch", || self.inner.import_batch(entries, mode))
+    }
+
+    fn with_locked<F, T>(&self, f: F) -> Result<T>
+    where
+        Self: Sized,
+        F: FnOnce(&Self) -> Result<T>,
+    {
+        // Hold the inner store's lock, but keep counting the calls `f` makes
+        self.inner.with_locked(|_| f(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymanager::MockStore;
+
+    #[test]
+    fn test_counts_calls_to_inner_store() {
+        let store = InstrumentedStore::new(MockStore::new());
+        store
+            .set("openai:prod", "sk-test-1", KeyKind::Runtime, false)
+            .unwrap();
+        store.get("openai:prod").unwrap();
+        store.get("openai:prod").unwrap();
+        assert!(store.get("openai:missing").is_err());
+        store.list(false).unwrap();
+        store.delete("openai:prod").unwrap();
+
+        let metrics = store.metrics();
+        assert_eq!(metrics.calls("set"), 1);
+        // Failed calls count too
+        assert_eq!(metrics.calls("get"), 3);
+        assert_eq!(metrics.calls("list"), 1);
+        assert_eq!(metrics.calls("delete"), 1);
+        assert_eq!(metrics.calls("touch"), 0);
+        let ops: Vec<_> = metrics.ops.iter().map(|m| m.op).collect();
+        assert_eq!(ops, ["set", "get", "list", "delete"]);
+    }
+
+    #[test]
+    fn test_with_locked_calls_are_counted() {
+        let store = InstrumentedStore::new(MockStore::new());
+        store
+            .with_locked(|s| {
+                s.set("openai:a", "sk-a", KeyKind::Runtime, false)?;
+                s.set("openai:b", "sk-b", KeyKind::Runtime, false)
+            })
+            .unwrap();
+        assert_eq!(store.metrics().calls("set"), 2);
+    }
+
+    #[test]
+    fn test_display() {
+        let metrics = StoreMetrics {
+            ops: vec![
+                OpMetrics {
+                    op: "list",
+                    calls: 1,
+                    total: Duration::from_millis(120),
+                },
+                OpMetrics {
+                    op: "get",
+                    calls: 7,
+                    total: Duration::from_millis(900),
+                },
+            ],
+        };
+        assert_eq!(
+            metrics.to_string(),
+            "list: 1 call 120ms, get: 7 calls 900ms"
+        );
+        assert_eq!(StoreMetrics::default().to_string(), "no store calls");
+    }
+}
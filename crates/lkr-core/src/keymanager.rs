@@ -1,14 +1,18 @@
 use crate::SERVICE_NAME;
 use crate::error::{Error, Result};
+use chrono::{DateTime, Utc};
 use core_foundation::base::TCFType;
 use core_foundation::string::CFString;
 use security_framework::item::{CloudSync, ItemClass, ItemSearchOptions, Limit, SearchResult};
 use security_framework_sys::item::kSecAttrAccount;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::c_void;
 use std::ptr;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
+use std::time::Duration;
 use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 // ---------------------------------------------------------------------------
@@ -21,6 +25,16 @@ use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 pub enum KeyKind {
     Runtime,
     Admin,
+    /// Shared service credential: resolves in templates like a runtime
+    /// key, but is hidden from `list` and `exec` like an admin key
+    Service,
+}
+
+impl KeyKind {
+    /// Whether `list` without `--all` (and bulk `exec`) includes this kind.
+    pub fn listed_by_default(self) -> bool {
+        self == KeyKind::Runtime
+    }
 }
 
 impl std::fmt::Display for KeyKind {
@@ -28,6 +42,7 @@ impl std::fmt::Display for KeyKind {
         match self {
             KeyKind::Runtime => write!(f, "runtime"),
             KeyKind::Admin => write!(f, "admin"),
+            KeyKind::Service => write!(f, "service"),
         }
     }
 }
@@ -39,8 +54,9 @@ impl std::str::FromStr for KeyKind {
         match s {
             "runtime" => Ok(KeyKind::Runtime),
             "admin" => Ok(KeyKind::Admin),
+            "service" => Ok(KeyKind::Service),
             _ => Err(format!(
-                "Invalid kind '{}'. Must be 'runtime' or 'admin'.",
+                "Invalid kind '{}'. Must be 'runtime', 'admin' or 'service'.",
                 s
             )),
         }
@@ -50,11 +66,189 @@ impl std::str::FromStr for KeyKind {
 /// Metadata stored alongside each key in Keychain.
 /// Serialized as JSON in the Keychain password field:
 ///   { "value": "<actual-api-key>", "kind": "runtime" }
-#[derive(Debug, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
-struct StoredEntry {
-    value: String,
+///
+/// Optional metadata fields are omitted when unset, so entries written by
+/// older versions (value + kind only) still deserialize.
+///
+/// Returned whole by [`KeyStore::get_with_metadata`]. The value is zeroized
+/// when the entry is dropped and left out of its `Debug` output.
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct StoredEntry {
+    pub value: String,
+    #[zeroize(skip)]
+    pub kind: KeyKind,
+    /// Last recorded access (None = never recorded)
+    #[zeroize(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_accessed: Option<DateTime<Utc>>,
+    /// Number of recorded accesses (only counted on request, see `KeyStore::touch`)
+    #[zeroize(skip)]
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub access_count: u64,
+    /// When the key was first stored (None = stored before this was recorded)
+    #[zeroize(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the value was last replaced by a different one
     #[zeroize(skip)]
-    kind: KeyKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_rotated: Option<DateTime<Utc>>,
+    /// When `set` last wrote the entry, changed value or not
+    #[zeroize(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Free-form note about the key
+    #[zeroize(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Base URL of a non-standard API (enterprise proxy, self-hosted)
+    #[zeroize(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_endpoint: Option<String>,
+    /// How the key entered lkr (`SOURCE_*`, None = not recorded)
+    #[zeroize(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// When the key stops being valid (None = never expires)
+    #[zeroize(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Groups the key belongs to (sorted, no duplicates; see [`validate_tag`])
+    #[zeroize(skip)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl std::fmt::Debug for StoredEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoredEntry")
+            .field("value", &"<redacted>")
+            .field("kind", &self.kind)
+            .field("last_accessed", &self.last_accessed)
+            .field("access_count", &self.access_count)
+            .field("created_at", &self.created_at)
+            .field("last_rotated", &self.last_rotated)
+            .field("updated_at", &self.updated_at)
+            .field("description", &self.description)
+            .field("custom_endpoint", &self.custom_endpoint)
+            .field("source", &self.source)
+            .field("expires_at", &self.expires_at)
+            .field("tags", &self.tags)
+            .finish()
+    }
+}
+
+/// `source` of a key typed at the interactive `lkr set` prompt.
+pub const SOURCE_INTERACTIVE: &str = "interactive";
+/// `source` of a key brought in by `lkr import`.
+pub const SOURCE_IMPORT: &str = "import";
+/// `source` of a key read from a key file (`lkr set-many --file`).
+pub const SOURCE_FILE: &str = "file";
+/// `source` of a key taken from an environment variable (`lkr import --from-env`).
+pub const SOURCE_ENV: &str = "env";
+
+/// A metadata change that leaves the key's value and kind alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataUpdate {
+    /// Set or clear the custom API endpoint
+    Endpoint(Option<String>),
+    /// Record where the key came from (`SOURCE_*`)
+    Source(String),
+    /// Set or clear the free-form note
+    Description(Option<String>),
+    /// Set or clear when the key expires
+    Expiry(Option<DateTime<Utc>>),
+    /// Add and remove tags (removals first; adding a present tag or
+    /// removing an absent one is a no-op)
+    Tags {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+impl StoredEntry {
+    /// A fresh entry with no metadata recorded yet.
+    pub(crate) fn new(value: &str, kind: KeyKind) -> Self {
+        Self {
+            value: value.to_string(),
+            kind,
+            last_accessed: None,
+            access_count: 0,
+            created_at: None,
+            last_rotated: None,
+            updated_at: None,
+            description: None,
+            custom_endpoint: None,
+            source: None,
+            expires_at: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// The entry `set` writes, stamped `updated_at`. A new key is stamped
+    /// `created_at`; replacing an existing key keeps its metadata, and
+    /// stamps `last_rotated` if the value changed. A new value clears
+    /// `expires_at`: the old expiry belonged to the old credential.
+    pub(crate) fn for_set(previous: Option<&StoredEntry>, value: &str, kind: KeyKind) -> Self {
+        let now = Utc::now();
+        match previous {
+            Some(previous) => {
+                let mut entry = previous.clone();
+                if entry.value != value {
+                    entry.value = value.to_string();
+                    entry.last_rotated = Some(now);
+                    entry.expires_at = None;
+                }
+                entry.kind = kind;
+                entry.updated_at = Some(now);
+                entry
+            }
+            None => {
+                let mut entry = Self::new(value, kind);
+                entry.created_at = Some(now);
+                entry.updated_at = Some(now);
+                entry
+            }
+        }
+    }
+
+    /// `KeyExpired` once `expires_at` has passed. Every store's `get` checks
+    /// this, so an expired value is never handed out.
+    pub(crate) fn ensure_unexpired(&self, name: &str) -> Result<()> {
+        match self.expires_at {
+            Some(expired_at) if expired_at <= Utc::now() => Err(Error::KeyExpired {
+                name: name.to_string(),
+                expired_at,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Record one access now.
+    pub(crate) fn touch(&mut self) {
+        self.last_accessed = Some(Utc::now());
+        self.access_count += 1;
+    }
+
+    pub(crate) fn apply(&mut self, update: &MetadataUpdate) {
+        match update {
+            MetadataUpdate::Endpoint(endpoint) => self.custom_endpoint = endpoint.clone(),
+            MetadataUpdate::Source(source) => self.source = Some(source.clone()),
+            MetadataUpdate::Description(text) => self.description = text.clone(),
+            MetadataUpdate::Expiry(at) => self.expires_at = *at,
+            MetadataUpdate::Tags { add, remove } => {
+                self.tags.retain(|t| !remove.contains(t));
+                self.tags.extend(add.iter().cloned());
+                self.tags.sort();
+                self.tags.dedup();
+            }
+        }
+    }
 }
 
 /// Status of a key in the list output.
@@ -83,9 +277,190 @@ pub struct KeyEntry {
     pub masked_value: String,
     /// Key status
     pub status: KeyStatus,
+    /// Last recorded access (None = never recorded, or ACL-blocked)
+    pub last_accessed: Option<DateTime<Utc>>,
+    /// Recorded accesses (0 = never recorded, or ACL-blocked)
+    pub access_count: u64,
+    /// When the key was first stored (None = unknown, or ACL-blocked)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the value was last replaced (None = never)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_rotated: Option<DateTime<Utc>>,
+    /// When the entry was last written (None = unknown, or ACL-blocked)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Free-form note about the key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Base URL of a non-standard API for this key (None = provider default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_endpoint: Option<String>,
+    /// How the key entered lkr (e.g. "interactive", "import"; None = unknown)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// When the key stops being valid (None = never expires)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Groups the key belongs to (empty if none, or ACL-blocked)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl KeyEntry {
+    /// Entry for a readable stored key (masked value and metadata copied).
+    pub(crate) fn from_stored(
+        name: &str,
+        provider: String,
+        label: String,
+        stored: &StoredEntry,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            provider,
+            label,
+            kind: Some(stored.kind),
+            masked_value: mask_value(&stored.value),
+            status: KeyStatus::Ok,
+            last_accessed: stored.last_accessed,
+            access_count: stored.access_count,
+            created_at: stored.created_at,
+            last_rotated: stored.last_rotated,
+            updated_at: stored.updated_at,
+            description: stored.description.clone(),
+            custom_endpoint: stored.custom_endpoint.clone(),
+            source: stored.source.clone(),
+            expires_at: stored.expires_at,
+            tags: stored.tags.clone(),
+        }
+    }
+}
+
+/// One key to import (`lkr import`), or exported by `lkr export`. The value
+/// is zeroized on drop.
+#[derive(Debug, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct ImportEntry {
+    #[zeroize(skip)]
+    pub name: String,
+    pub value: String,
+    #[zeroize(skip)]
+    #[serde(default = "default_import_kind")]
+    pub kind: KeyKind,
+    /// Recorded as the key's `source` (set by the caller, never read from input)
+    #[zeroize(skip)]
+    #[serde(skip)]
+    pub source: Option<String>,
+}
+
+fn default_import_kind() -> KeyKind {
+    KeyKind::Runtime
+}
+
+/// How `import_batch` treats keys that already exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportMode {
+    /// Keep existing keys unchanged; only add new ones
+    #[default]
+    Merge,
+    /// Overwrite existing keys, including their kind
+    Replace,
+    /// Like `Merge`, but skipped entries are reported individually
+    Skip,
+}
+
+impl std::fmt::Display for ImportMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportMode::Merge => write!(f, "merge"),
+            ImportMode::Replace => write!(f, "replace"),
+            ImportMode::Skip => write!(f, "skip"),
+        }
+    }
+}
+
+impl std::str::FromStr for ImportMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "merge" => Ok(ImportMode::Merge),
+            "replace" => Ok(ImportMode::Replace),
+            "skip" => Ok(ImportMode::Skip),
+            _ => Err(format!(
+                "Invalid mode '{}'. Must be 'merge', 'replace', or 'skip'.",
+                s
+            )),
+        }
+    }
+}
+
+/// Outcome of `import_batch`, by key name.
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    /// New keys written
+    pub imported: Vec<String>,
+    /// Existing keys left unchanged
+    pub skipped: Vec<String>,
+    /// Existing keys overwritten (`ImportMode::Replace`)
+    pub replaced: Vec<String>,
+    /// Replaced keys whose kind changed
+    pub kind_changes: Vec<KindChange>,
+}
+
+/// A kind change caused by a replace import. `from` is None if the old
+/// kind could not be read (ACL-blocked).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KindChange {
+    pub name: String,
+    pub from: Option<KeyKind>,
+    pub to: KeyKind,
+}
+
+/// Label usage tracking expects on admin keys (`get_admin_key` reads
+/// `<provider>:admin`).
+pub const ADMIN_LABEL: &str = "admin";
+
+/// Shown by front ends when an admin key is stored under any other label
+/// (see [`is_nonstandard_admin_label`]).
+pub const ADMIN_LABEL_WARNING: &str =
+    "Admin keys should use the label 'admin' (e.g. openai:admin) for usage tracking to work.";
+
+/// Whether storing `name` as `kind` makes an admin key that `lkr usage`
+/// won't find. Advisory only: stores accept the key either way.
+pub fn is_nonstandard_admin_label(name: &str, kind: KeyKind) -> bool {
+    kind == KeyKind::Admin
+        && name
+            .split_once(':')
+            .is_some_and(|(_, label)| label != ADMIN_LABEL)
 }
 
 impl KeyEntry {
+    /// Admin key whose label isn't [`ADMIN_LABEL`], so `lkr usage` won't find it.
+    pub fn has_nonstandard_admin_label(&self) -> bool {
+        self.kind == Some(KeyKind::Admin) && self.label != ADMIN_LABEL
+    }
+
+    /// Whether the key's `expires_at` is at or before `now`.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|ts| ts <= now)
+    }
+
+    /// Whether the key is not yet expired but will be within `window` of `now`.
+    pub fn expires_within(&self, now: DateTime<Utc>, window: chrono::Duration) -> bool {
+        self.expires_at.is_some_and(|ts| {
+            ts > now
+                && now
+                    .checked_add_signed(window)
+                    .is_none_or(|limit| ts <= limit)
+        })
+    }
+
+    /// Whether the key carries `tag`.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
     /// Human-readable kind string. Returns `"?"` for ACL-blocked keys
     /// whose kind cannot be determined.
     pub fn kind_display(&self) -> String {
@@ -97,15 +472,109 @@ impl KeyEntry {
 // Validation
 // ---------------------------------------------------------------------------
 
+/// Set to `1` to accept uppercase and `_` in key labels, for legacy
+/// providers whose names can't change. Applies to every command, so keys
+/// stored this way can be read and listed back.
+pub const RELAXED_NAMES_ENV: &str = "LKR_RELAXED_NAMES";
+
+/// Longest accepted `provider:label` for a new key. The Keychain truncates
+/// long account names silently, so they are rejected up front; keys stored
+/// before the limits existed can still be read, listed and removed.
+pub const MAX_KEY_NAME_LEN: usize = 255;
+/// Longest accepted provider segment for a new key.
+pub const MAX_PROVIDER_LEN: usize = 64;
+/// Longest accepted label segment for a new key.
+pub const MAX_LABEL_LEN: usize = 64;
+/// Longest description kept with a key, in characters.
+pub const MAX_DESCRIPTION_LEN: usize = 512;
+/// Longest accepted tag.
+pub const MAX_TAG_LEN: usize = 64;
+
+/// Validate a tag: `[a-z0-9][a-z0-9-]*`, at most [`MAX_TAG_LEN`] chars.
+pub fn validate_tag(tag: &str) -> Result<()> {
+    let valid = tag
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && tag
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+    if !valid {
+        return Err(Error::Usage(format!(
+            "Invalid tag '{}': use lowercase letters, digits and '-', not starting with '-'",
+            tag
+        )));
+    }
+    if tag.len() > MAX_TAG_LEN {
+        return Err(Error::Usage(format!(
+            "Tag too long ({} chars, max {})",
+            tag.len(),
+            MAX_TAG_LEN
+        )));
+    }
+    Ok(())
+}
+
 /// Validate key name format: `{provider}:{label}`
-/// Allowed characters: [a-z0-9][a-z0-9-]*
-fn validate_name(name: &str) -> Result<(String, String)> {
+/// Allowed characters: [a-z0-9][a-z0-9-]*, or [A-Za-z0-9][A-Za-z0-9_-]* for
+/// the label when `LKR_RELAXED_NAMES=1`.
+pub(crate) fn validate_name(name: &str) -> Result<(String, String)> {
+    let relaxed = std::env::var(RELAXED_NAMES_ENV).is_ok_and(|v| v == "1");
+    validate_name_with(name, relaxed)
+}
+
+/// Check `name` against the rules every store applies on write, without
+/// touching a store (e.g. to vet a backup before importing it).
+pub fn validate_key_name(name: &str) -> Result<()> {
+    validate_new_name(name).map(|_| ())
+}
+
+/// [`validate_name`] plus the length limits, for a name about to be
+/// created. Reads, deletes and rewrites of an existing key skip the limits.
+pub(crate) fn validate_new_name(name: &str) -> Result<(String, String)> {
+    check_name_length(name)?;
+    validate_name(name)
+}
+
+/// Enforce [`MAX_KEY_NAME_LEN`], [`MAX_PROVIDER_LEN`] and [`MAX_LABEL_LEN`].
+pub(crate) fn check_name_length(name: &str) -> Result<()> {
+    let too_long = |what: &str, len: usize, max: usize| Error::InvalidKeyName {
+        name: name.to_string(),
+        reason: format!("{} too long ({} chars, max {})", what, len, max),
+    };
+    // Names are ASCII once valid, but count chars so the message is right
+    // for any input
+    let len = name.chars().count();
+    if len > MAX_KEY_NAME_LEN {
+        return Err(too_long("Key name", len, MAX_KEY_NAME_LEN));
+    }
+    let Some((provider, label)) = name.split_once(':') else {
+        return Ok(());
+    };
+    let (provider_len, label_len) = (provider.chars().count(), label.chars().count());
+    if provider_len > MAX_PROVIDER_LEN {
+        return Err(too_long("Provider", provider_len, MAX_PROVIDER_LEN));
+    }
+    if label_len > MAX_LABEL_LEN {
+        return Err(too_long("Label", label_len, MAX_LABEL_LEN));
+    }
+    Ok(())
+}
+
+/// `list` parses stored names with `relaxed = true`, so keys stored under
+/// `LKR_RELAXED_NAMES=1` stay visible after it is unset.
+pub(crate) fn validate_name_with(name: &str, relaxed: bool) -> Result<(String, String)> {
     let re_part = |s: &str| -> bool {
         !s.is_empty()
             && s.chars()
                 .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
             && s.chars().next().is_some_and(|c| c.is_ascii_alphanumeric())
     };
+    let relaxed_label = |s: &str| -> bool {
+        s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            && s.chars().next().is_some_and(|c| c.is_ascii_alphanumeric())
+    };
 
     let parts: Vec<&str> = name.splitn(2, ':').collect();
     if parts.len() != 2 {
@@ -123,16 +592,34 @@ fn validate_name(name: &str) -> Result<(String, String)> {
             reason: format!("Provider '{}' must match [a-z0-9][a-z0-9-]*", provider),
         });
     }
-    if !re_part(label) {
+    if relaxed && !relaxed_label(label) {
+        return Err(Error::InvalidKeyName {
+            name: name.to_string(),
+            reason: format!("Label '{}' must match [A-Za-z0-9][A-Za-z0-9_-]*", label),
+        });
+    }
+    if !relaxed && !re_part(label) {
+        let hint = if relaxed_label(label) {
+            format!(" (set {}=1 to allow uppercase and '_')", RELAXED_NAMES_ENV)
+        } else {
+            String::new()
+        };
         return Err(Error::InvalidKeyName {
             name: name.to_string(),
-            reason: format!("Label '{}' must match [a-z0-9][a-z0-9-]*", label),
+            reason: format!("Label '{}' must match [a-z0-9][a-z0-9-]*{}", label, hint),
         });
     }
 
     Ok((provider.to_string(), label.to_string()))
 }
 
+/// Short, non-reversible identifier for a key value, for comparing keys
+/// across machines without revealing them: `sha256:` + 16 hex chars.
+pub fn fingerprint(value: &str) -> String {
+    let hex = crate::checksum::sha256_hex(value.as_bytes());
+    format!("sha256:{}", &hex[..16])
+}
+
 /// Mask an API key for display: "sk-proj-abc...xyz" → "sk-p...wxyz"
 /// Uses char iterators to avoid allocating the full char vec.
 pub fn mask_value(value: &str) -> String {
@@ -152,6 +639,45 @@ pub fn mask_value(value: &str) -> String {
     format!("{}...{}", prefix, suffix)
 }
 
+/// Match `text` against a glob `pattern` (`*` = any run, `?` = one char).
+///
+/// Iterative with single-star backtracking — no regex dependency, and
+/// linear-ish on the short key/env names it is used for.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((star_pi, star_ti)) = star {
+            pi = star_pi + 1;
+            ti = star_ti + 1;
+            star = Some((star_pi, star_ti + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// Search semantics shared by all [`KeyStore::search`] implementations:
+/// patterns containing `*` or `?` are globs over the full key name,
+/// anything else is a case-insensitive substring match.
+fn search_matches(pattern: &str, name: &str) -> bool {
+    if pattern.contains(['*', '?']) {
+        glob_match(pattern, name)
+    } else {
+        name.to_lowercase().contains(&pattern.to_lowercase())
+    }
+}
+
 // ---------------------------------------------------------------------------
 // KeyStore trait
 // ---------------------------------------------------------------------------
@@ -159,13 +685,329 @@ pub fn mask_value(value: &str) -> String {
 /// Abstraction over key storage backend.
 /// Enables MockStore for testing and KeychainStore for production.
 pub trait KeyStore {
-    fn set(&self, name: &str, value: &str, kind: KeyKind, force: bool) -> Result<()>;
+    fn set(&self, name: &str, value: &str, kind: KeyKind, force: bool) -> Result<()> {
+        self.set_with(name, value, kind, force, &[])
+    }
+    /// [`set`](KeyStore::set), applying `updates` to the entry in the same
+    /// write, so the key is never stored without its metadata.
+    fn set_with(
+        &self,
+        name: &str,
+        value: &str,
+        kind: KeyKind,
+        force: bool,
+        updates: &[MetadataUpdate],
+    ) -> Result<()>;
+    /// Write `entry` under `name` as given, metadata included. Unlike
+    /// [`set`](KeyStore::set) nothing is stamped or kept from an existing
+    /// key; `force` allows overwriting one, as for `set`.
+    fn set_entry(&self, name: &str, entry: &StoredEntry, force: bool) -> Result<()>;
     fn get(&self, name: &str) -> Result<(Zeroizing<String>, KeyKind)>;
+    /// The whole stored entry, value and metadata, for diagnostics that
+    /// need more than [`get`](KeyStore::get) returns.
+    fn get_with_metadata(&self, name: &str) -> Result<StoredEntry>;
     fn delete(&self, name: &str) -> Result<()>;
+    /// Every key when `include_admin`, otherwise only kinds
+    /// [`KeyKind::listed_by_default`] (runtime).
     fn list(&self, include_admin: bool) -> Result<Vec<KeyEntry>>;
     fn exists(&self, name: &str) -> Result<bool>;
+
+    /// Record an access to `name`: sets `last_accessed` to now and bumps
+    /// `access_count`. A read-modify-write, so callers opt in per call
+    /// (`lkr get --count-access`) rather than paying for it on every read.
+    fn touch(&self, name: &str) -> Result<()>;
+
+    /// Change metadata stored with `name` without rewriting its value.
+    fn update_metadata(&self, name: &str, update: &MetadataUpdate) -> Result<()>;
+
+    /// Set (or with `None`, clear) the custom API endpoint stored with `name`.
+    fn set_endpoint(&self, name: &str, endpoint: Option<&str>) -> Result<()> {
+        self.update_metadata(name, &MetadataUpdate::Endpoint(endpoint.map(String::from)))
+    }
+
+    /// The custom API endpoint stored with `name`, if any.
+    fn endpoint(&self, name: &str) -> Result<Option<String>> {
+        validate_name(name)?;
+        self.list(true)?
+            .into_iter()
+            .find(|e| e.name == name)
+            .map(|e| e.custom_endpoint)
+            .ok_or_else(|| Error::KeyNotFound {
+                name: name.to_string(),
+            })
+    }
+
+    /// List entries whose name matches `pattern` (glob like `openai:*`,
+    /// or a plain substring). Same masking and admin filtering as `list`.
+    fn search(&self, pattern: &str, include_admin: bool) -> Result<Vec<KeyEntry>> {
+        let mut entries = self.list(include_admin)?;
+        entries.retain(|e| search_matches(pattern, &e.name));
+        Ok(entries)
+    }
+
+    /// Rename a key, moving its whole entry: value, kind and metadata
+    /// (an expired key stays expired). Returns `KeyAlreadyExists` if
+    /// `new_name` is taken, unless `force` (as for `set`) allows
+    /// overwriting it; nothing of the overwritten key is kept.
+    ///
+    /// Copy-first: the new entry is written before the old one is removed,
+    /// so a failed write leaves the original intact. If removing the old
+    /// entry fails, the copy is rolled back (restoring an overwritten key).
+    fn rename(&self, old_name: &str, new_name: &str, force: bool) -> Result<()> {
+        validate_name(old_name)?;
+        validate_name(new_name)?;
+        if old_name == new_name {
+            return Ok(());
+        }
+
+        // Read with metadata rather than `get`, so expired keys move too
+        let entry = self.get_with_metadata(old_name)?;
+        let replaced = if force {
+            match self.get_with_metadata(new_name) {
+                Ok(previous) => Some(previous),
+                Err(Error::KeyNotFound { .. }) => None,
+                Err(e) => return Err(e),
+            }
+        } else {
+            None
+        };
+        self.set_entry(new_name, &entry, force)?;
+
+        if let Err(e) = self.delete(old_name) {
+            let _ = match replaced {
+                Some(previous) => self.set_entry(new_name, &previous, true),
+                None => self.delete(new_name),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Replace an existing key's value, keeping its kind and metadata
+    /// (`last_rotated` is stamped, `expires_at` cleared). A missing key is
+    /// `KeyNotFound`, not created; an expired one can be rotated.
+    fn rotate(&self, name: &str, new_value: &str) -> Result<()> {
+        validate_name(name)?;
+        if new_value.is_empty() {
+            return Err(Error::EmptyValue);
+        }
+        let kind = self.get_with_metadata(name)?.kind;
+        self.set(name, new_value, kind, true)
+    }
+
+    /// Add and remove tags on an existing key, returning its tags after
+    /// the change. Every tag is validated before anything is written.
+    fn update_tags(&self, name: &str, add: &[String], remove: &[String]) -> Result<Vec<String>> {
+        validate_name(name)?;
+        for tag in add.iter().chain(remove) {
+            validate_tag(tag)?;
+        }
+        self.update_metadata(
+            name,
+            &MetadataUpdate::Tags {
+                add: add.to_vec(),
+                remove: remove.to_vec(),
+            },
+        )?;
+        Ok(self.get_with_metadata(name)?.tags.clone())
+    }
+
+    /// Change an existing key's kind, keeping its value and metadata. The
+    /// value never leaves the store.
+    fn set_kind(&self, name: &str, kind: KeyKind) -> Result<()> {
+        validate_name(name)?;
+        let entry = self.get_with_metadata(name)?;
+        if entry.kind == kind {
+            return Ok(());
+        }
+        self.set(name, &entry.value, kind, true)
+    }
+
+    /// Import many keys at once. Every entry is validated before anything
+    /// is written, so one bad name or empty value aborts the whole batch.
+    /// Existing keys are handled according to `mode`.
+    ///
+    /// If a write fails midway, the keys written so far are rolled back:
+    /// new keys are deleted and replaced keys restored with their metadata.
+    /// The rollback is best-effort (it can fail like the write did), and a
+    /// replaced key whose old entry could not be read (ACL-blocked) stays
+    /// replaced.
+    fn import_batch(&self, entries: &[ImportEntry], mode: ImportMode) -> Result<ImportReport> {
+        for entry in entries {
+            validate_new_name(&entry.name)?;
+            if entry.value.is_empty() {
+                return Err(Error::EmptyValue);
+            }
+        }
+
+        let existing: HashMap<String, Option<KeyKind>> = self
+            .list(true)?
+            .into_iter()
+            .map(|e| (e.name, e.kind))
+            .collect();
+
+        let mut report = ImportReport::default();
+        // Undo log: each written key with the entry it replaced, if any
+        let mut written: Vec<(&str, Option<StoredEntry>)> = Vec::new();
+        let result = entries
+            .iter()
+            .try_for_each(|entry| match (existing.get(&entry.name), mode) {
+                (None, _) => {
+                    self.set_with(&entry.name, &entry.value, entry.kind, false, &source(entry))?;
+                    written.push((&entry.name, None));
+                    report.imported.push(entry.name.clone());
+                    Ok(())
+                }
+                (Some(_), ImportMode::Merge | ImportMode::Skip) => {
+                    report.skipped.push(entry.name.clone());
+                    Ok(())
+                }
+                (Some(&old_kind), ImportMode::Replace) => {
+                    let previous = self.get_with_metadata(&entry.name).ok();
+                    self.set_with(&entry.name, &entry.value, entry.kind, true, &source(entry))?;
+                    if let Some(previous) = previous {
+                        written.push((&entry.name, Some(previous)));
+                    }
+                    report.replaced.push(entry.name.clone());
+                    if old_kind != Some(entry.kind) {
+                        report.kind_changes.push(KindChange {
+                            name: entry.name.clone(),
+                            from: old_kind,
+                            to: entry.kind,
+                        });
+                    }
+                    Ok(())
+                }
+            });
+
+        if let Err(e) = result {
+            for (name, previous) in written.iter().rev() {
+                let _ = match previous {
+                    Some(entry) => self.set_entry(name, entry, true),
+                    None => self.delete(name),
+                };
+            }
+            return Err(e);
+        }
+        Ok(report)
+    }
+
+    /// Run several operations as one unit. `f` receives the store itself.
+    ///
+    /// How much is guaranteed depends on the backend: `MockStore` isolates
+    /// `f` from other threads and rolls back on error; the Keychain has no
+    /// transactions, so `KeychainStore` wraps any error in
+    /// `Error::NotRolledBack` to say earlier steps may remain applied. The
+    /// default (also used through `Box<dyn KeyStore>`) just runs `f`.
+    fn with_locked<F, T>(&self, f: F) -> Result<T>
+    where
+        Self: Sized,
+        F: FnOnce(&Self) -> Result<T>,
+    {
+        f(self)
+    }
+}
+
+/// The update tagging an imported key with the entry's `source`, if any.
+fn source(entry: &ImportEntry) -> Vec<MetadataUpdate> {
+    entry
+        .source
+        .iter()
+        .map(|source| MetadataUpdate::Source(source.clone()))
+        .collect()
+}
+
+/// Implements `KeyStore` for a smart pointer to a store by forwarding every
+/// method, including the defaulted ones, so backend overrides are preserved.
+macro_rules! forward_key_store {
+    ($($ptr:ident),+) => {$(
+        impl<T: KeyStore + ?Sized> KeyStore for $ptr<T> {
+            fn set(&self, name: &str, value: &str, kind: KeyKind, force: bool) -> Result<()> {
+                (**self).set(name, value, kind, force)
+            }
+
+            fn set_with(
+                &self,
+                name: &str,
+                value: &str,
+                kind: KeyKind,
+                force: bool,
+                updates: &[MetadataUpdate],
+            ) -> Result<()> {
+                (**self).set_with(name, value, kind, force, updates)
+            }
+
+            fn set_entry(&self, name: &str, entry: &StoredEntry, force: bool) -> Result<()> {
+                (**self).set_entry(name, entry, force)
+            }
+
+            fn get(&self, name: &str) -> Result<(Zeroizing<String>, KeyKind)> {
+                (**self).get(name)
+            }
+
+            fn get_with_metadata(&self, name: &str) -> Result<StoredEntry> {
+                (**self).get_with_metadata(name)
+            }
+
+            fn delete(&self, name: &str) -> Result<()> {
+                (**self).delete(name)
+            }
+
+            fn list(&self, include_admin: bool) -> Result<Vec<KeyEntry>> {
+                (**self).list(include_admin)
+            }
+
+            fn exists(&self, name: &str) -> Result<bool> {
+                (**self).exists(name)
+            }
+
+            fn touch(&self, name: &str) -> Result<()> {
+                (**self).touch(name)
+            }
+
+            fn update_metadata(&self, name: &str, update: &MetadataUpdate) -> Result<()> {
+                (**self).update_metadata(name, update)
+            }
+
+            fn set_endpoint(&self, name: &str, endpoint: Option<&str>) -> Result<()> {
+                (**self).set_endpoint(name, endpoint)
+            }
+
+            fn endpoint(&self, name: &str) -> Result<Option<String>> {
+                (**self).endpoint(name)
+            }
+
+            fn search(&self, pattern: &str, include_admin: bool) -> Result<Vec<KeyEntry>> {
+                (**self).search(pattern, include_admin)
+            }
+
+            fn rename(&self, old_name: &str, new_name: &str, force: bool) -> Result<()> {
+                (**self).rename(old_name, new_name, force)
+            }
+
+            fn rotate(&self, name: &str, new_value: &str) -> Result<()> {
+                (**self).rotate(name, new_value)
+            }
+
+            fn set_kind(&self, name: &str, kind: KeyKind) -> Result<()> {
+                (**self).set_kind(name, kind)
+            }
+
+            fn update_tags(&self, name: &str, add: &[String], remove: &[String]) -> Result<Vec<String>> {
+                (**self).update_tags(name, add, remove)
+            }
+
+            fn import_batch(&self, entries: &[ImportEntry], mode: ImportMode) -> Result<ImportReport> {
+                (**self).import_batch(entries, mode)
+            }
+        }
+    )+};
 }
 
+// `Box<dyn KeyStore>` (see `default_store`) and the shared memory backend's
+// `Arc` can be passed wherever `&impl KeyStore` is expected.
+forward_key_store!(Box, Arc);
+
 // ---------------------------------------------------------------------------
 // Keychain raw operations — direct security-framework-sys FFI
 // ---------------------------------------------------------------------------
@@ -300,15 +1142,18 @@ mod keychain_raw {
             ERR_SEC_AUTH_FAILED => Error::PasswordWrong,
             ERR_SEC_INTERACTION_NOT_ALLOWED => Error::InteractionNotAllowed,
             ERR_SEC_NO_SUCH_KEYCHAIN => Error::NotInitialized,
-            ERR_SEC_INVALID_KEYCHAIN => Error::Keychain(
+            ERR_SEC_INVALID_KEYCHAIN => Error::keychain(
                 "Keychain file is corrupted or invalid. Try `lkr init` after removing the old file."
                     .into(),
             ),
-            ERR_SEC_DECODE_ERROR => Error::Keychain(
+            ERR_SEC_DECODE_ERROR => Error::keychain(
                 "Failed to decode keychain data. The keychain file may be corrupted.".into(),
             ),
             ERR_SEC_USER_CANCELED => Error::UserCanceled,
-            _ => Error::Keychain(format!("Keychain error: OSStatus {status}")),
+            ERR_SEC_IO | ERR_SEC_NOT_AVAILABLE | ERR_SEC_INTERNAL_COMPONENT => {
+                Error::KeychainBusy { status }
+            }
+            _ => Error::keychain(format!("Keychain error: OSStatus {status}")),
         }
     }
 
@@ -372,7 +1217,7 @@ mod keychain_raw {
             }
 
             if result.is_null() {
-                return Err(Error::Keychain(
+                return Err(Error::keychain(
                     "SecItemCopyMatching returned null".to_string(),
                 ));
             }
@@ -495,14 +1340,13 @@ mod keychain_raw {
         use core_foundation::base::TCFType;
         use security_framework::os::macos::keychain::SecKeychain;
 
-        let _guard =
-            if !interactive {
-                Some(SecKeychain::disable_user_interaction().map_err(|e| {
-                    Error::Keychain(format!("Failed to disable user interaction: {e}"))
-                })?)
-            } else {
-                None
-            };
+        let _guard = if !interactive {
+            Some(SecKeychain::disable_user_interaction().map_err(|source| {
+                Error::keychain_os("Failed to disable user interaction".to_string(), source)
+            })?)
+        } else {
+            None
+        };
 
         let svc_bytes = service.as_bytes();
         let acct_bytes = account.as_bytes();
@@ -603,14 +1447,13 @@ mod keychain_raw {
 
         // Only acquire the interaction-guard when non-interactive.
         // The guard is held until this scope ends, suppressing macOS dialogs.
-        let _guard =
-            if !interactive {
-                Some(SecKeychain::disable_user_interaction().map_err(|e| {
-                    Error::Keychain(format!("Failed to disable user interaction: {e}"))
-                })?)
-            } else {
-                None
-            };
+        let _guard = if !interactive {
+            Some(SecKeychain::disable_user_interaction().map_err(|source| {
+                Error::keychain_os("Failed to disable user interaction".to_string(), source)
+            })?)
+        } else {
+            None
+        };
 
         let svc_bytes = service.as_bytes();
         let acct_bytes = account.as_bytes();
@@ -637,9 +1480,21 @@ mod keychain_raw {
         };
 
         if status != 0 {
-            // -25308 (InteractionNotAllowed) or -25293 (AuthFailed): may
-            // indicate ACL cdhash mismatch when user-interaction is disabled.
-            // Check the item's ACL to distinguish from genuine auth errors.
+            // -25308 (InteractionNotAllowed) or -25293 (AuthFailed) are also
+            // what a locked keychain returns with user-interaction disabled
+            if (status == crate::error::os_status::ERR_SEC_INTERACTION_NOT_ALLOWED
+                || status == crate::error::os_status::ERR_SEC_AUTH_FAILED)
+                && crate::custom_keychain::is_locked(keychain).unwrap_or(false)
+            {
+                if !item_ref.is_null() {
+                    // SAFETY: item_ref follows Create Rule, released on error path.
+                    unsafe { CFRelease(item_ref as _) };
+                }
+                return Err(Error::KeychainLocked);
+            }
+            // Otherwise they may indicate ACL cdhash mismatch when
+            // user-interaction is disabled. Check the item's ACL to
+            // distinguish from genuine auth errors.
             if (status == crate::error::os_status::ERR_SEC_INTERACTION_NOT_ALLOWED
                 || status == crate::error::os_status::ERR_SEC_AUTH_FAILED)
                 && !item_ref.is_null()
@@ -666,7 +1521,7 @@ mod keychain_raw {
         }
 
         if pw_data.is_null() {
-            return Err(Error::Keychain(
+            return Err(Error::keychain(
                 "SecKeychainFindGenericPassword returned null data".into(),
             ));
         }
@@ -721,15 +1576,14 @@ mod keychain_raw {
         use core_foundation::base::TCFType;
         use security_framework::os::macos::keychain::SecKeychain;
 
-        let _guard =
-            if !interactive {
-                Some(SecKeychain::disable_user_interaction().map_err(|e| {
-                    Error::Keychain(format!("Failed to disable user interaction: {e}"))
-                })?)
-            } else {
-                None
-            };
-
+        let _guard = if !interactive {
+            Some(SecKeychain::disable_user_interaction().map_err(|source| {
+                Error::keychain_os("Failed to disable user interaction".to_string(), source)
+            })?)
+        } else {
+            None
+        };
+
         let svc_bytes = service.as_bytes();
         let acct_bytes = account.as_bytes();
 
@@ -796,8 +1650,14 @@ mod keychain_raw {
         use core_foundation::boolean::CFBoolean;
         use security_framework::os::macos::keychain::SecKeychain;
 
-        let _guard = SecKeychain::disable_user_interaction()
-            .map_err(|e| Error::Keychain(format!("Failed to disable user interaction: {e}")))?;
+        // Every read below would fail as if ACL-blocked
+        if crate::custom_keychain::is_locked(keychain)? {
+            return Err(Error::KeychainLocked);
+        }
+
+        let _guard = SecKeychain::disable_user_interaction().map_err(|source| {
+            Error::keychain_os("Failed to disable user interaction".to_string(), source)
+        })?;
 
         // Security.framework constants and functions for batch item enumeration.
         #[link(name = "Security", kind = "framework")]
@@ -910,7 +1770,7 @@ mod keychain_raw {
                 // Extract SecKeychainItemRef from dict
                 let item_ref = CFDictionaryGetValue(item_dict, kSecValueRef as _);
                 if item_ref.is_null() {
-                    items.push((account, Err(Error::Keychain("No item ref".into()))));
+                    items.push((account, Err(Error::keychain("No item ref".into()))));
                     continue;
                 }
 
@@ -936,7 +1796,7 @@ mod keychain_raw {
                 } else if read_status == 0 {
                     // Success status but null data — should not happen per Apple docs,
                     // but handle defensively.
-                    items.push((account, Err(Error::Keychain("null data returned".into()))));
+                    items.push((account, Err(Error::keychain("null data returned".into()))));
                 } else if read_status == crate::error::os_status::ERR_SEC_AUTH_FAILED
                     || read_status == crate::error::os_status::ERR_SEC_INTERACTION_NOT_ALLOWED
                 {
@@ -960,6 +1820,95 @@ mod keychain_raw {
 // KeychainStore — production implementation using macOS Keychain
 // ---------------------------------------------------------------------------
 
+/// How many times a Keychain read or write that failed transiently
+/// ([`Error::KeychainBusy`]) is retried. Unset means 2; `0` disables.
+pub const KEYCHAIN_RETRIES_ENV: &str = "LKR_KEYCHAIN_RETRIES";
+
+const DEFAULT_KEYCHAIN_RETRIES: u32 = 2;
+/// Upper bound for `LKR_KEYCHAIN_RETRIES`, so a typo can't stall a command.
+const MAX_KEYCHAIN_RETRIES: u32 = 10;
+/// Wait before the first retry; doubled for each further one.
+const KEYCHAIN_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+fn keychain_retries() -> u32 {
+    std::env::var(KEYCHAIN_RETRIES_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .map_or(DEFAULT_KEYCHAIN_RETRIES, |n| n.min(MAX_KEYCHAIN_RETRIES))
+}
+
+/// Run `op`, retrying up to `retries` times while it fails with
+/// `KeychainBusy`. Lock, auth and every other error return at once.
+fn retry_transient<T>(
+    retries: u32,
+    mut sleep: impl FnMut(Duration),
+    mut op: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let mut attempt = 0;
+    loop {
+        match op() {
+            Err(Error::KeychainBusy { .. }) if attempt < retries => {
+                sleep(KEYCHAIN_RETRY_BACKOFF * 2u32.pow(attempt));
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// `retry_transient` with the configured retry count, for the actual
+/// Keychain calls in `KeychainStore`.
+fn with_keychain_retry<T>(op: impl FnMut() -> Result<T>) -> Result<T> {
+    retry_transient(keychain_retries(), thread::sleep, op)
+}
+
+/// A Keychain item under lkr's service that `list` does not return.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnlistedItem {
+    pub account: String,
+    /// Why it cannot become a `KeyEntry` (never includes the payload)
+    pub reason: String,
+}
+
+/// Items from a raw enumeration whose account is not among the `listed`
+/// names, each with the reason `list` skips it.
+pub fn find_unlisted(
+    items: impl IntoIterator<Item = (String, Result<Vec<u8>>)>,
+    listed: &HashSet<String>,
+) -> Vec<UnlistedItem> {
+    let mut unlisted: Vec<_> = items
+        .into_iter()
+        .filter(|(account, _)| !listed.contains(account))
+        .map(|(account, data)| UnlistedItem {
+            reason: skip_reason(&account, &data),
+            account,
+        })
+        .collect();
+    unlisted.sort_by(|a, b| a.account.cmp(&b.account));
+    unlisted
+}
+
+/// Mirrors the checks `KeychainStore::list` makes before it skips an item.
+fn skip_reason(account: &str, data: &Result<Vec<u8>>) -> String {
+    if let Err(e) = validate_name_with(account, true) {
+        return match e {
+            Error::InvalidKeyName { reason, .. } => format!("invalid name: {}", reason),
+            e => format!("invalid name: {}", e),
+        };
+    }
+    match data {
+        Err(e) => format!("unreadable: {}", e),
+        // Parse errors are not shown: they can quote the payload
+        Ok(bytes) => match std::str::from_utf8(bytes) {
+            Err(_) => "payload is not UTF-8".to_string(),
+            Ok(json) => match serde_json::from_str::<StoredEntry>(json) {
+                Err(_) => "payload is not a valid lkr entry".to_string(),
+                Ok(_) => "readable, but missing from list".to_string(),
+            },
+        },
+    }
+}
+
 pub struct KeychainStore {
     service: String,
     /// v0.3.0: Custom Keychain (Some = v0.3.0 mode, None = legacy mode for migrate)
@@ -986,11 +1935,30 @@ impl KeychainStore {
         }
     }
 
+    /// The same keychain, under another service name (e.g. the self-test's
+    /// scratch service).
+    pub fn with_service(mut self, service: &str) -> Self {
+        self.service = service.to_string();
+        self
+    }
+
     /// Check if this store is in v0.3.0 mode (Custom Keychain).
     pub fn is_v3(&self) -> bool {
         self.custom_keychain.is_some()
     }
 
+    /// Enumerate the Custom Keychain directly and report items that `list`
+    /// silently skips (`lkr doctor --keychain`). A legacy-mode store, whose
+    /// `list` reads every item through `get`, reports none.
+    pub fn unlisted_items(&self) -> Result<Vec<UnlistedItem>> {
+        let Some(kc) = &self.custom_keychain else {
+            return Ok(Vec::new());
+        };
+        let items = keychain_raw::list_with_refs_v3(kc, &self.service)?;
+        let listed: HashSet<String> = self.list(true)?.into_iter().map(|e| e.name).collect();
+        Ok(find_unlisted(items, &listed))
+    }
+
     /// Read a key value via interactive macOS dialog (allows "Allow" prompt).
     ///
     /// # Security
@@ -1001,16 +1969,19 @@ impl KeychainStore {
     ///
     /// Normal key reads must always go through [`KeyStore::get`] which keeps
     /// user-interaction disabled.
+    ///
+    /// Returns the whole entry, metadata included, and does not refuse an
+    /// expired key: harden rewrites entries as they are, it does not use them.
     #[doc(hidden)]
-    pub fn get_interactive(&self, name: &str) -> Result<(Zeroizing<String>, KeyKind)> {
+    pub fn get_entry_interactive(&self, name: &str) -> Result<StoredEntry> {
         validate_name(name)?;
 
         let kc = self.custom_keychain.as_ref().ok_or_else(|| {
-            Error::Keychain("get_interactive requires v0.3.0 Custom Keychain".into())
+            Error::keychain("get_entry_interactive requires v0.3.0 Custom Keychain".into())
         })?;
 
         let bytes = keychain_raw::get_v3_interactive(kc, &self.service, name)?;
-        Self::parse_stored_bytes(bytes)
+        Self::parse_stored_entry(bytes)
     }
 
     /// Re-create a key with user-interaction enabled (allows macOS dialog).
@@ -1023,21 +1994,23 @@ impl KeychainStore {
     ///
     /// Normal key writes must always go through [`KeyStore::set`] which keeps
     /// user-interaction disabled.
+    ///
+    /// Writes `entry` as-is, like [`KeyStore::set_entry`], so every metadata
+    /// field read by [`Self::get_entry_interactive`] survives the rewrite.
     #[doc(hidden)]
-    pub fn set_interactive(
+    pub fn set_entry_interactive(
         &self,
         name: &str,
-        value: &str,
-        kind: KeyKind,
+        entry: &StoredEntry,
         force: bool,
     ) -> Result<()> {
         validate_name(name)?;
-        if value.is_empty() {
+        if entry.value.is_empty() {
             return Err(Error::EmptyValue);
         }
 
         let kc = self.custom_keychain.as_ref().ok_or_else(|| {
-            Error::Keychain("set_interactive requires v0.3.0 Custom Keychain".into())
+            Error::keychain("set_entry_interactive requires v0.3.0 Custom Keychain".into())
         })?;
 
         let exists = self.exists(name)?;
@@ -1046,15 +2019,11 @@ impl KeychainStore {
                 name: name.to_string(),
             });
         }
+        if !exists {
+            check_name_length(name)?;
+        }
 
-        let stored = StoredEntry {
-            value: value.to_string(),
-            kind,
-        };
-        let json = Zeroizing::new(
-            serde_json::to_string(&stored)
-                .map_err(|e| Error::Keychain(format!("Failed to serialize: {}", e)))?,
-        );
+        let json = Self::serialize_stored(entry)?;
 
         let access =
             crate::acl::current_binary_path().and_then(|p| crate::acl::build_access(&p))?;
@@ -1081,17 +2050,97 @@ impl KeychainStore {
         Ok(())
     }
 
-    /// Parse raw Keychain bytes into (value, kind).
-    /// Shared by `get` and `get_interactive` to avoid duplication.
-    fn parse_stored_bytes(bytes: Vec<u8>) -> Result<(Zeroizing<String>, KeyKind)> {
+    /// The item data stored for `stored`. Shared by `write_stored` and
+    /// `set_entry_interactive`.
+    fn serialize_stored(stored: &StoredEntry) -> Result<Zeroizing<String>> {
+        serde_json::to_string(stored)
+            .map(Zeroizing::new)
+            .map_err(|e| Error::keychain(format!("Failed to serialize: {}", e)))
+    }
+
+    /// Serialize `stored` and write it under `name`, replacing the existing
+    /// item if `exists`. Shared by `set` and `touch`.
+    fn write_stored(&self, name: &str, stored: &StoredEntry, exists: bool) -> Result<()> {
+        let json = Self::serialize_stored(stored)?;
+        if let Some(kc) = &self.custom_keychain {
+            // v0.3.0: Custom Keychain + ACL
+            // Build ACL first (fail-closed): if this fails, the old key remains intact
+            let access =
+                crate::acl::current_binary_path().and_then(|p| crate::acl::build_access(&p))?;
+
+            if exists {
+                with_keychain_retry(|| keychain_raw::delete_v3(kc, &self.service, name))?;
+            }
+
+            let result = with_keychain_retry(|| {
+                keychain_raw::set_v3(kc, access, &self.service, name, json.as_bytes())
+            });
+
+            // Release the access ref if we created one
+            if !access.is_null() {
+                // SAFETY: access was returned by build_access() (Create Rule),
+                // retain count == 1. CFRelease is safe here.
+                unsafe {
+                    unsafe extern "C" {
+                        fn CFRelease(cf: *const c_void);
+                    }
+                    CFRelease(access as _);
+                }
+            }
+
+            result?;
+        } else {
+            // Legacy mode (v0.2.x)
+            if exists {
+                with_keychain_retry(|| keychain_raw::delete(&self.service, name))?;
+            }
+            with_keychain_retry(|| keychain_raw::set(&self.service, name, json.as_bytes()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Raw item data for `name`, with `lkr migrate` guidance for keys still
+    /// in login.keychain.
+    fn read_bytes(&self, name: &str) -> Result<Vec<u8>> {
+        let bytes = if let Some(kc) = &self.custom_keychain {
+            // v0.3.0: Try Custom Keychain first
+            match with_keychain_retry(|| keychain_raw::get_v3(kc, &self.service, name)) {
+                Ok(b) => b,
+                Err(Error::KeyNotFound { .. }) if self.legacy_exists(name) => {
+                    // Key exists in login.keychain but not in Custom Keychain
+                    return Err(Error::keychain(format!(
+                        "Key '{}' found in login.keychain but not in lkr.keychain-db. \
+                         Run `lkr migrate` to move your keys.",
+                        name
+                    )));
+                }
+                Err(e) => return Err(e),
+            }
+        } else {
+            // Legacy mode
+            with_keychain_retry(|| keychain_raw::get(&self.service, name))?
+        };
+
+        Ok(bytes)
+    }
+
+    /// Parse raw Keychain bytes into (value, kind), refusing an expired key.
+    fn parse_stored_bytes(name: &str, bytes: Vec<u8>) -> Result<(Zeroizing<String>, KeyKind)> {
+        let mut stored = Self::parse_stored_entry(bytes)?;
+        stored.ensure_unexpired(name)?;
+        let value = std::mem::take(&mut stored.value);
+        Ok((Zeroizing::new(value), stored.kind))
+    }
+
+    /// Parse raw Keychain bytes into the full stored entry, metadata included.
+    fn parse_stored_entry(bytes: Vec<u8>) -> Result<StoredEntry> {
         let json = Zeroizing::new(
             String::from_utf8(bytes)
-                .map_err(|e| Error::Keychain(format!("Invalid UTF-8 in key data: {e}")))?,
+                .map_err(|e| Error::keychain(format!("Invalid UTF-8 in key data: {e}")))?,
         );
-        let mut stored: StoredEntry = serde_json::from_str(&json)
-            .map_err(|e| Error::Keychain(format!("Failed to deserialize stored entry: {e}")))?;
-        let value = std::mem::take(&mut stored.value);
-        Ok((Zeroizing::new(value), stored.kind))
+        serde_json::from_str(&json)
+            .map_err(|e| Error::keychain(format!("Failed to deserialize stored entry: {e}")))
     }
 
     /// Extract the account name (kSecAttrAccount) from a CFDictionary.
@@ -1199,7 +2248,14 @@ impl Default for KeychainStore {
 }
 
 impl KeyStore for KeychainStore {
-    fn set(&self, name: &str, value: &str, kind: KeyKind, force: bool) -> Result<()> {
+    fn set_with(
+        &self,
+        name: &str,
+        value: &str,
+        kind: KeyKind,
+        force: bool,
+        updates: &[MetadataUpdate],
+    ) -> Result<()> {
         validate_name(name)?;
         if value.is_empty() {
             return Err(Error::EmptyValue);
@@ -1211,74 +2267,49 @@ impl KeyStore for KeychainStore {
                 name: name.to_string(),
             });
         }
+        if !exists {
+            check_name_length(name)?;
+        }
 
-        let stored = StoredEntry {
-            value: value.to_string(),
-            kind,
+        // Keep the existing entry's metadata (best-effort: an unreadable
+        // entry is replaced as if new)
+        let previous = if exists {
+            self.read_bytes(name)
+                .and_then(Self::parse_stored_entry)
+                .ok()
+        } else {
+            None
         };
-        let json = Zeroizing::new(
-            serde_json::to_string(&stored)
-                .map_err(|e| Error::Keychain(format!("Failed to serialize: {}", e)))?,
-        );
-        if let Some(kc) = &self.custom_keychain {
-            // v0.3.0: Custom Keychain + ACL
-            // Build ACL first (fail-closed): if this fails, the old key remains intact
-            let access =
-                crate::acl::current_binary_path().and_then(|p| crate::acl::build_access(&p))?;
-
-            if exists {
-                keychain_raw::delete_v3(kc, &self.service, name)?;
-            }
-
-            let result = keychain_raw::set_v3(kc, access, &self.service, name, json.as_bytes());
-
-            // Release the access ref if we created one
-            if !access.is_null() {
-                // SAFETY: access was returned by build_access() (Create Rule),
-                // retain count == 1. CFRelease is safe here.
-                unsafe {
-                    unsafe extern "C" {
-                        fn CFRelease(cf: *const c_void);
-                    }
-                    CFRelease(access as _);
-                }
-            }
+        let mut stored = StoredEntry::for_set(previous.as_ref(), value, kind);
+        updates.iter().for_each(|u| stored.apply(u));
+        self.write_stored(name, &stored, exists)
+    }
 
-            result?;
-        } else {
-            // Legacy mode (v0.2.x)
-            if exists {
-                keychain_raw::delete(&self.service, name)?;
-            }
-            keychain_raw::set(&self.service, name, json.as_bytes())?;
+    fn set_entry(&self, name: &str, entry: &StoredEntry, force: bool) -> Result<()> {
+        validate_name(name)?;
+        if entry.value.is_empty() {
+            return Err(Error::EmptyValue);
         }
-
-        Ok(())
+        let exists = self.exists(name)?;
+        if !force && exists {
+            return Err(Error::KeyAlreadyExists {
+                name: name.to_string(),
+            });
+        }
+        if !exists {
+            check_name_length(name)?;
+        }
+        self.write_stored(name, entry, exists)
     }
 
     fn get(&self, name: &str) -> Result<(Zeroizing<String>, KeyKind)> {
         validate_name(name)?;
+        Self::parse_stored_bytes(name, self.read_bytes(name)?)
+    }
 
-        let bytes = if let Some(kc) = &self.custom_keychain {
-            // v0.3.0: Try Custom Keychain first
-            match keychain_raw::get_v3(kc, &self.service, name) {
-                Ok(b) => b,
-                Err(Error::KeyNotFound { .. }) if self.legacy_exists(name) => {
-                    // Key exists in login.keychain but not in Custom Keychain
-                    return Err(Error::Keychain(format!(
-                        "Key '{}' found in login.keychain but not in lkr.keychain-db. \
-                         Run `lkr migrate` to move your keys.",
-                        name
-                    )));
-                }
-                Err(e) => return Err(e),
-            }
-        } else {
-            // Legacy mode
-            keychain_raw::get(&self.service, name)?
-        };
-
-        Self::parse_stored_bytes(bytes)
+    fn get_with_metadata(&self, name: &str) -> Result<StoredEntry> {
+        validate_name(name)?;
+        Self::parse_stored_entry(self.read_bytes(name)?)
     }
 
     fn delete(&self, name: &str) -> Result<()> {
@@ -1297,7 +2328,7 @@ impl KeyStore for KeychainStore {
             let items = keychain_raw::list_with_refs_v3(kc, &self.service)?;
             let mut entries = Vec::new();
             for (account, data_result) in items {
-                let Ok((provider, label)) = validate_name(&account) else {
+                let Ok((provider, label)) = validate_name_with(&account, true) else {
                     continue;
                 };
                 match data_result {
@@ -1311,17 +2342,10 @@ impl KeyStore for KeychainStore {
                             Ok(s) => s,
                             Err(_) => continue,
                         };
-                        if !include_admin && stored.kind == KeyKind::Admin {
+                        if !include_admin && !stored.kind.listed_by_default() {
                             continue;
                         }
-                        entries.push(KeyEntry {
-                            name: account,
-                            provider,
-                            label,
-                            kind: Some(stored.kind),
-                            masked_value: mask_value(&stored.value),
-                            status: KeyStatus::Ok,
-                        });
+                        entries.push(KeyEntry::from_stored(&account, provider, label, &stored));
                     }
                     Err(Error::AclMismatch) => {
                         // ACL-blocked: include with warning status instead of silently skipping
@@ -1332,6 +2356,16 @@ impl KeyStore for KeychainStore {
                             kind: None,
                             masked_value: String::new(),
                             status: KeyStatus::AclBlocked,
+                            last_accessed: None,
+                            access_count: 0,
+                            created_at: None,
+                            last_rotated: None,
+                            updated_at: None,
+                            description: None,
+                            custom_endpoint: None,
+                            source: None,
+                            expires_at: None,
+                            tags: Vec::new(),
                         });
                     }
                     Err(_) => {
@@ -1357,27 +2391,26 @@ impl KeyStore for KeychainStore {
             let results = match results {
                 Ok(r) => r,
                 Err(e) if e.code() == -25300 => return Ok(vec![]),
-                Err(e) => return Err(Error::Keychain(format!("Keychain search failed: {}", e))),
+                Err(source) => {
+                    return Err(Error::keychain_os(
+                        "Keychain search failed".to_string(),
+                        source,
+                    ));
+                }
             };
 
             let mut entries = Vec::new();
             for result in results {
                 if let SearchResult::Dict(dict) = result
                     && let Some(account) = Self::extract_account(&dict)
-                    && let Ok((value, kind)) = self.get(&account)
+                    // Not `get`: expired keys are still listed
+                    && let Ok(stored) = self.get_with_metadata(&account)
                 {
-                    if !include_admin && kind == KeyKind::Admin {
+                    if !include_admin && !stored.kind.listed_by_default() {
                         continue;
                     }
-                    if let Ok((provider, label)) = validate_name(&account) {
-                        entries.push(KeyEntry {
-                            name: account,
-                            provider,
-                            label,
-                            kind: Some(kind),
-                            masked_value: mask_value(&value),
-                            status: KeyStatus::Ok,
-                        });
+                    if let Ok((provider, label)) = validate_name_with(&account, true) {
+                        entries.push(KeyEntry::from_stored(&account, provider, label, &stored));
                     }
                 }
             }
@@ -1410,6 +2443,38 @@ impl KeyStore for KeychainStore {
             }
         }
     }
+
+    fn touch(&self, name: &str) -> Result<()> {
+        validate_name(name)?;
+        let mut stored = Self::parse_stored_entry(self.read_bytes(name)?)?;
+        stored.touch();
+        self.write_stored(name, &stored, true)
+    }
+
+    fn update_metadata(&self, name: &str, update: &MetadataUpdate) -> Result<()> {
+        validate_name(name)?;
+        let mut stored = Self::parse_stored_entry(self.read_bytes(name)?)?;
+        stored.apply(update);
+        self.write_stored(name, &stored, true)
+    }
+
+    fn endpoint(&self, name: &str) -> Result<Option<String>> {
+        validate_name(name)?;
+        let stored = Self::parse_stored_entry(self.read_bytes(name)?)?;
+        Ok(stored.custom_endpoint.clone())
+    }
+
+    /// Best-effort: Keychain items cannot be changed transactionally, so
+    /// steps that succeeded before a failure stay applied.
+    fn with_locked<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Self) -> Result<T>,
+    {
+        f(self).map_err(|e| match e {
+            e @ Error::NotRolledBack(_) => e,
+            e => Error::NotRolledBack(Box::new(e)),
+        })
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -1418,13 +2483,63 @@ impl KeyStore for KeychainStore {
 
 pub struct MockStore {
     keys: Mutex<HashMap<String, StoredEntry>>,
+    /// Simulated locked keychain: every operation fails (see `set_locked`)
+    locked: AtomicBool,
+    /// Thread currently inside `with_locked`; other threads wait for it
+    txn_owner: Mutex<Option<ThreadId>>,
+    txn_done: Condvar,
 }
 
 impl MockStore {
     pub fn new() -> Self {
         Self {
             keys: Mutex::new(HashMap::new()),
+            locked: AtomicBool::new(false),
+            txn_owner: Mutex::new(None),
+            txn_done: Condvar::new(),
+        }
+    }
+
+    /// Simulate a locked keychain: until unlocked, every operation fails
+    /// with `KeychainLocked`.
+    pub fn set_locked(&self, locked: bool) {
+        self.locked.store(locked, Ordering::SeqCst);
+    }
+
+    /// Lock the key map, first waiting out another thread's transaction.
+    /// Fails while the store is [`set_locked`](Self::set_locked).
+    fn lock_keys(&self) -> Result<MutexGuard<'_, HashMap<String, StoredEntry>>> {
+        if self.locked.load(Ordering::SeqCst) {
+            return Err(Error::KeychainLocked);
+        }
+        let me = thread::current().id();
+        let mut owner = self.txn_owner.lock().unwrap();
+        while owner.is_some_and(|t| t != me) {
+            owner = self.txn_done.wait(owner).unwrap();
+        }
+        // `owner` is held until the map is locked, so no transaction can
+        // start in between
+        Ok(self.keys.lock().unwrap())
+    }
+
+    /// Store an entry without `set`'s name and empty-value checks, to
+    /// simulate legacy data those guards would now reject.
+    pub fn insert_unchecked(&self, name: &str, value: &str, kind: KeyKind) {
+        let mut keys = self.lock_keys().expect("MockStore is locked");
+        let entry = StoredEntry::for_set(keys.get(name), value, kind);
+        keys.insert(name.to_string(), entry);
+    }
+}
+
+/// Ends a `MockStore` transaction, even if `f` panics.
+struct TxnGuard<'a>(&'a MockStore);
+
+impl Drop for TxnGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut owner) = self.0.txn_owner.lock() {
+            *owner = None;
         }
+        self.0.txn_done.notify_all();
     }
 }
 
@@ -1435,43 +2550,81 @@ impl Default for MockStore {
 }
 
 impl KeyStore for MockStore {
-    fn set(&self, name: &str, value: &str, kind: KeyKind, force: bool) -> Result<()> {
+    fn set_with(
+        &self,
+        name: &str,
+        value: &str,
+        kind: KeyKind,
+        force: bool,
+        updates: &[MetadataUpdate],
+    ) -> Result<()> {
         validate_name(name)?;
         if value.is_empty() {
             return Err(Error::EmptyValue);
         }
 
-        let mut keys = self.keys.lock().unwrap();
+        let mut keys = self.lock_keys()?;
         if !force && keys.contains_key(name) {
             return Err(Error::KeyAlreadyExists {
                 name: name.to_string(),
             });
         }
+        if !keys.contains_key(name) {
+            check_name_length(name)?;
+        }
 
-        keys.insert(
-            name.to_string(),
-            StoredEntry {
-                value: value.to_string(),
-                kind,
-            },
-        );
+        let mut entry = StoredEntry::for_set(keys.get(name), value, kind);
+        updates.iter().for_each(|u| entry.apply(u));
+        keys.insert(name.to_string(), entry);
+        Ok(())
+    }
+
+    fn set_entry(&self, name: &str, entry: &StoredEntry, force: bool) -> Result<()> {
+        validate_name(name)?;
+        if entry.value.is_empty() {
+            return Err(Error::EmptyValue);
+        }
+
+        let mut keys = self.lock_keys()?;
+        if !force && keys.contains_key(name) {
+            return Err(Error::KeyAlreadyExists {
+                name: name.to_string(),
+            });
+        }
+        if !keys.contains_key(name) {
+            check_name_length(name)?;
+        }
+        keys.insert(name.to_string(), entry.clone());
         Ok(())
     }
 
     fn get(&self, name: &str) -> Result<(Zeroizing<String>, KeyKind)> {
         validate_name(name)?;
-        let keys = self.keys.lock().unwrap();
+        let keys = self.lock_keys()?;
         match keys.get(name) {
-            Some(entry) => Ok((Zeroizing::new(entry.value.clone()), entry.kind)),
+            Some(entry) => {
+                entry.ensure_unexpired(name)?;
+                Ok((Zeroizing::new(entry.value.clone()), entry.kind))
+            }
             None => Err(Error::KeyNotFound {
                 name: name.to_string(),
             }),
         }
     }
 
+    fn get_with_metadata(&self, name: &str) -> Result<StoredEntry> {
+        validate_name(name)?;
+        self.lock_keys()?
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::KeyNotFound {
+                name: name.to_string(),
+            })
+    }
+
     fn delete(&self, name: &str) -> Result<()> {
         validate_name(name)?;
-        let mut keys = self.keys.lock().unwrap();
+        let mut keys = self.lock_keys()?;
         if keys.remove(name).is_none() {
             return Err(Error::KeyNotFound {
                 name: name.to_string(),
@@ -1481,20 +2634,13 @@ impl KeyStore for MockStore {
     }
 
     fn list(&self, include_admin: bool) -> Result<Vec<KeyEntry>> {
-        let keys = self.keys.lock().unwrap();
+        let keys = self.lock_keys()?;
         let mut entries: Vec<KeyEntry> = keys
             .iter()
-            .filter(|(_, v)| include_admin || v.kind == KeyKind::Runtime)
+            .filter(|(_, v)| include_admin || v.kind.listed_by_default())
             .filter_map(|(name, v)| {
-                let (provider, label) = validate_name(name).ok()?;
-                Some(KeyEntry {
-                    name: name.clone(),
-                    provider,
-                    label,
-                    kind: Some(v.kind),
-                    masked_value: mask_value(&v.value),
-                    status: KeyStatus::Ok,
-                })
+                let (provider, label) = validate_name_with(name, true).ok()?;
+                Some(KeyEntry::from_stored(name, provider, label, v))
             })
             .collect();
         entries.sort_by(|a, b| a.name.cmp(&b.name));
@@ -1503,24 +2649,80 @@ impl KeyStore for MockStore {
 
     fn exists(&self, name: &str) -> Result<bool> {
         validate_name(name)?;
-        let keys = self.keys.lock().unwrap();
+        let keys = self.lock_keys()?;
         Ok(keys.contains_key(name))
     }
-}
-
-// ---------------------------------------------------------------------------
-// Tests
-// ---------------------------------------------------------------------------
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    fn store() -> MockStore {
-        MockStore::new()
+    fn touch(&self, name: &str) -> Result<()> {
+        validate_name(name)?;
+        let mut keys = self.lock_keys()?;
+        match keys.get_mut(name) {
+            Some(entry) => {
+                entry.touch();
+                Ok(())
+            }
+            None => Err(Error::KeyNotFound {
+                name: name.to_string(),
+            }),
+        }
     }
 
-    // -- Validation --
+    fn update_metadata(&self, name: &str, update: &MetadataUpdate) -> Result<()> {
+        validate_name(name)?;
+        let mut keys = self.lock_keys()?;
+        match keys.get_mut(name) {
+            Some(entry) => {
+                entry.apply(update);
+                Ok(())
+            }
+            None => Err(Error::KeyNotFound {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    /// Isolated from other threads for the duration of `f`, and rolled
+    /// back to the starting state if `f` fails. Nested calls join the
+    /// outer transaction.
+    fn with_locked<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Self) -> Result<T>,
+    {
+        let me = thread::current().id();
+        {
+            let mut owner = self.txn_owner.lock().unwrap();
+            if *owner == Some(me) {
+                return f(self);
+            }
+            while owner.is_some() {
+                owner = self.txn_done.wait(owner).unwrap();
+            }
+            *owner = Some(me);
+        }
+        let _txn = TxnGuard(self);
+
+        let snapshot = self.keys.lock().unwrap().clone();
+        let result = f(self);
+        if result.is_err() {
+            *self.keys.lock().unwrap() = snapshot;
+        }
+        result
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> MockStore {
+        MockStore::new()
+    }
+
+    // -- Validation --
 
     #[test]
     fn test_validate_name_valid() {
@@ -1555,6 +2757,198 @@ mod tests {
         assert!(validate_name("openai:prod test").is_err());
     }
 
+    #[test]
+    fn test_validate_name_relaxed_label() {
+        assert_eq!(
+            validate_name_with("openai:Prod_1", true).unwrap(),
+            ("openai".to_string(), "Prod_1".to_string())
+        );
+        let err = validate_name_with("openai:Prod_1", false).unwrap_err();
+        assert!(err.to_string().contains(RELAXED_NAMES_ENV));
+
+        // Still forbidden when relaxed: provider case, ':' and whitespace
+        assert!(validate_name_with("OpenAI:prod", true).is_err());
+        assert!(validate_name_with("openai:a:b", true).is_err());
+        assert!(validate_name_with("openai:Prod 1", true).is_err());
+        assert!(validate_name_with("openai:_prod", true).is_err());
+    }
+
+    #[test]
+    fn test_validate_name_length_limits() {
+        let name = |provider: usize, label: usize| {
+            format!("{}:{}", "p".repeat(provider), "l".repeat(label))
+        };
+        assert!(validate_new_name(&name(MAX_PROVIDER_LEN, MAX_LABEL_LEN)).is_ok());
+
+        let err = validate_new_name(&name(MAX_PROVIDER_LEN + 1, 4)).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Provider too long (65 chars, max 64)")
+        );
+        let err = validate_new_name(&name(4, MAX_LABEL_LEN + 1)).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Label too long (65 chars, max 64)")
+        );
+
+        let err = validate_new_name(&format!("openai:{}", "x".repeat(300))).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Key name too long (307 chars, max 255)")
+        );
+    }
+
+    #[test]
+    fn test_overlong_existing_key_still_usable() {
+        let s = store();
+        let long = format!("openai:{}", "x".repeat(MAX_LABEL_LEN + 10));
+        // Stored before the limits existed
+        s.insert_unchecked(&long, "sk-1", KeyKind::Runtime);
+
+        assert_eq!(s.list(false).unwrap()[0].name, long);
+        assert_eq!(&*s.get(&long).unwrap().0, "sk-1");
+        s.rotate(&long, "sk-2").unwrap();
+        // Moving it to a new over-long name is refused, like creating one
+        let longer = format!("{}y", long);
+        assert!(matches!(
+            s.rename(&long, &longer, false),
+            Err(Error::InvalidKeyName { .. })
+        ));
+        assert!(matches!(
+            s.set(&longer, "sk-3", KeyKind::Runtime, false),
+            Err(Error::InvalidKeyName { .. })
+        ));
+        s.delete(&long).unwrap();
+        assert!(s.list(true).unwrap().is_empty());
+    }
+
+    mod length_props {
+        use super::*;
+        use proptest::prelude::*;
+
+        proptest! {
+            #[test]
+            fn segment_lengths_around_the_limits(
+                provider in (MAX_PROVIDER_LEN - 2)..=(MAX_PROVIDER_LEN + 2),
+                label in (MAX_LABEL_LEN - 2)..=(MAX_LABEL_LEN + 2),
+                relaxed in any::<bool>(),
+            ) {
+                let name = format!("{}:{}", "a".repeat(provider), "b".repeat(label));
+                let ok = provider <= MAX_PROVIDER_LEN && label <= MAX_LABEL_LEN;
+                prop_assert_eq!(check_name_length(&name).is_ok(), ok);
+                // Existing keys are read whatever their length
+                prop_assert!(validate_name_with(&name, relaxed).is_ok());
+            }
+
+            #[test]
+            fn total_length_around_the_limit(
+                len in (MAX_KEY_NAME_LEN - 2)..=(MAX_KEY_NAME_LEN + 2),
+            ) {
+                let name = format!("openai:{}", "x".repeat(len - "openai:".len()));
+                let err = validate_new_name(&name).unwrap_err().to_string();
+                // Under the total limit the label limit still applies
+                let expected = if len > MAX_KEY_NAME_LEN { "Key name too long" } else { "Label too long" };
+                prop_assert!(err.contains(expected), "{}", err);
+            }
+
+            #[test]
+            fn valid_names_within_limits_pass(
+                provider in "[a-z0-9][a-z0-9-]{0,63}",
+                label in "[a-z0-9][a-z0-9-]{0,63}",
+            ) {
+                let name = format!("{}:{}", provider, label);
+                prop_assert!(check_name_length(&name).is_ok(), "{}", name);
+                prop_assert!(validate_name_with(&name, false).is_ok(), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_retry_transient_failure_then_success() {
+        let mut calls = 0;
+        let mut waits = Vec::new();
+        let result = retry_transient(
+            2,
+            |d| waits.push(d),
+            || {
+                calls += 1;
+                if calls == 1 {
+                    Err(Error::KeychainBusy { status: -36 })
+                } else {
+                    Ok("value")
+                }
+            },
+        );
+        assert_eq!(result.unwrap(), "value");
+        assert_eq!(calls, 2);
+        assert_eq!(waits, [KEYCHAIN_RETRY_BACKOFF]);
+    }
+
+    #[test]
+    fn test_retry_transient_gives_up_and_skips_other_errors() {
+        let mut calls = 0;
+        let err = retry_transient(
+            2,
+            |_| {},
+            || -> Result<()> {
+                calls += 1;
+                Err(Error::KeychainBusy { status: -36 })
+            },
+        );
+        assert!(matches!(err, Err(Error::KeychainBusy { .. })));
+        assert_eq!(calls, 3);
+
+        // A lock error is surfaced on the first attempt
+        let mut calls = 0;
+        let err = retry_transient(
+            2,
+            |_| panic!("no retry expected"),
+            || -> Result<()> {
+                calls += 1;
+                Err(Error::KeychainLocked)
+            },
+        );
+        assert!(matches!(err, Err(Error::KeychainLocked)));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_nonstandard_admin_label() {
+        let store = MockStore::new();
+        for (name, kind) in [
+            ("openai:admin", KeyKind::Admin),
+            ("openai:billing", KeyKind::Admin),
+            ("openai:prod", KeyKind::Runtime),
+        ] {
+            store.set(name, "sk-test-value", kind, false).unwrap();
+        }
+        let flagged: Vec<String> = store
+            .list(true)
+            .unwrap()
+            .into_iter()
+            .filter(KeyEntry::has_nonstandard_admin_label)
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(flagged, ["openai:billing"]);
+    }
+
+    #[test]
+    fn test_is_nonstandard_admin_label_by_name_and_kind() {
+        assert!(is_nonstandard_admin_label("openai:billing", KeyKind::Admin));
+        assert!(!is_nonstandard_admin_label("openai:admin", KeyKind::Admin));
+        assert!(!is_nonstandard_admin_label(
+            "openai:billing",
+            KeyKind::Runtime
+        ));
+    }
+
+    #[test]
+    fn test_fingerprint_is_stable_and_short() {
+        // SHA-256("abc") starts with ba7816bf8f01cfea
+        assert_eq!(fingerprint("abc"), "sha256:ba7816bf8f01cfea");
+        assert_ne!(fingerprint("sk-a"), fingerprint("sk-b"));
+    }
+
     // -- Mask --
 
     #[test]
@@ -1708,6 +3102,821 @@ mod tests {
 
     // -- Exists --
 
+    // -- Search --
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("openai:*", "openai:prod"));
+        assert!(glob_match("*:prod", "anthropic:prod"));
+        assert!(glob_match("open?i:*", "openai:dev"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("openai:*", "anthropic:main"));
+        assert!(!glob_match("openai:prod", "openai:prod2"));
+        assert!(!glob_match("a*b", "axxbyy"));
+    }
+
+    #[test]
+    fn test_search_glob_filters_by_provider() {
+        let s = store();
+        s.set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        s.set("openai:dev", "sk-2", KeyKind::Runtime, false)
+            .unwrap();
+        s.set("anthropic:main", "sk-3", KeyKind::Runtime, false)
+            .unwrap();
+
+        let found = s.search("openai:*", false).unwrap();
+        let names: Vec<&str> = found.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["openai:dev", "openai:prod"]);
+    }
+
+    #[test]
+    fn test_search_substring_case_insensitive() {
+        let s = store();
+        s.set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        s.set("anthropic:main", "sk-2", KeyKind::Runtime, false)
+            .unwrap();
+
+        let found = s.search("PROD", false).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "openai:prod");
+    }
+
+    #[test]
+    fn test_search_respects_include_admin() {
+        let s = store();
+        s.set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        s.set("openai:admin", "sk-2", KeyKind::Admin, false)
+            .unwrap();
+
+        assert_eq!(s.search("openai:*", false).unwrap().len(), 1);
+        assert_eq!(s.search("openai:*", true).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_service_kind_roundtrip_and_list_exclusion() {
+        assert_eq!("service".parse::<KeyKind>(), Ok(KeyKind::Service));
+        assert_eq!(KeyKind::Service.to_string(), "service");
+        let json = serde_json::to_string(&KeyKind::Service).unwrap();
+        assert_eq!(json, r#""service""#);
+        assert_eq!(
+            serde_json::from_str::<KeyKind>(&json).unwrap(),
+            KeyKind::Service
+        );
+
+        let s = store();
+        s.set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        s.set("stripe:shared", "sk-2", KeyKind::Service, false)
+            .unwrap();
+        let names =
+            |all| -> Vec<String> { s.list(all).unwrap().into_iter().map(|e| e.name).collect() };
+        assert_eq!(names(false), ["openai:prod"]);
+        assert_eq!(names(true), ["openai:prod", "stripe:shared"]);
+    }
+
+    // -- Rename --
+
+    #[test]
+    fn test_rename_preserves_value_and_kind() {
+        let s = store();
+        s.set("openai:prd", "sk-admin-xyz", KeyKind::Admin, false)
+            .unwrap();
+        s.rename("openai:prd", "openai:prod", false).unwrap();
+
+        assert!(!s.exists("openai:prd").unwrap());
+        let (val, kind) = s.get("openai:prod").unwrap();
+        assert_eq!(&*val, "sk-admin-xyz");
+        assert_eq!(kind, KeyKind::Admin);
+    }
+
+    #[test]
+    fn test_rename_moves_metadata() {
+        let s = store();
+        s.set("openai:old", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        for update in [
+            MetadataUpdate::Description(Some("billing proxy".to_string())),
+            MetadataUpdate::Endpoint(Some("https://proxy.example.com".to_string())),
+            MetadataUpdate::Source(SOURCE_IMPORT.to_string()),
+            MetadataUpdate::Tags {
+                add: tags(&["ci"]),
+                remove: Vec::new(),
+            },
+        ] {
+            s.update_metadata("openai:old", &update).unwrap();
+        }
+        let before = s.get_with_metadata("openai:old").unwrap();
+
+        s.rename("openai:old", "openai:new", false).unwrap();
+        let after = s.get_with_metadata("openai:new").unwrap();
+        assert_eq!(after.description.as_deref(), Some("billing proxy"));
+        assert_eq!(
+            after.custom_endpoint.as_deref(),
+            Some("https://proxy.example.com")
+        );
+        assert_eq!(after.source.as_deref(), Some(SOURCE_IMPORT));
+        assert_eq!(after.tags, tags(&["ci"]));
+        assert_eq!(after.created_at, before.created_at);
+        assert_eq!(after.updated_at, before.updated_at);
+    }
+
+    #[test]
+    fn test_rename_keeps_expired_key_expired() {
+        let s = store();
+        s.set("openai:old", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        expire_in(&s, "openai:old", chrono::Duration::hours(-1));
+
+        s.rename("openai:old", "openai:new", false).unwrap();
+        assert!(matches!(
+            s.get("openai:new").unwrap_err(),
+            Error::KeyExpired { .. }
+        ));
+        assert!(
+            s.get_with_metadata("openai:new")
+                .unwrap()
+                .expires_at
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_rename_to_existing_rejected() {
+        let s = store();
+        s.set("openai:a", "sk-a", KeyKind::Runtime, false).unwrap();
+        s.set("openai:b", "sk-b", KeyKind::Runtime, false).unwrap();
+
+        let err = s.rename("openai:a", "openai:b", false).unwrap_err();
+        assert!(matches!(err, Error::KeyAlreadyExists { .. }));
+        // Both keys untouched
+        assert_eq!(&*s.get("openai:a").unwrap().0, "sk-a");
+        assert_eq!(&*s.get("openai:b").unwrap().0, "sk-b");
+    }
+
+    #[test]
+    fn test_rename_force_overwrites_existing() {
+        let s = store();
+        s.set("openai:a", "sk-a", KeyKind::Runtime, false).unwrap();
+        s.set("openai:b", "sk-b", KeyKind::Admin, false).unwrap();
+
+        s.rename("openai:a", "openai:b", true).unwrap();
+        assert!(!s.exists("openai:a").unwrap());
+        let (val, kind) = s.get("openai:b").unwrap();
+        assert_eq!(&*val, "sk-a");
+        assert_eq!(kind, KeyKind::Runtime);
+    }
+
+    #[test]
+    fn test_rename_force_drops_destination_metadata() {
+        let s = store();
+        s.set("openai:a", "sk-a", KeyKind::Runtime, false).unwrap();
+        s.update_metadata(
+            "openai:a",
+            &MetadataUpdate::Tags {
+                add: tags(&["dev"]),
+                remove: Vec::new(),
+            },
+        )
+        .unwrap();
+        s.set("openai:b", "sk-b", KeyKind::Runtime, false).unwrap();
+        for update in [
+            MetadataUpdate::Description(Some("old proxy".to_string())),
+            MetadataUpdate::Endpoint(Some("https://old.example.com".to_string())),
+            MetadataUpdate::Tags {
+                add: tags(&["prod"]),
+                remove: Vec::new(),
+            },
+        ] {
+            s.update_metadata("openai:b", &update).unwrap();
+        }
+        expire_in(&s, "openai:a", chrono::Duration::days(3));
+
+        s.rename("openai:a", "openai:b", true).unwrap();
+        let moved = s.get_with_metadata("openai:b").unwrap();
+        assert_eq!(moved.value, "sk-a");
+        assert_eq!(moved.description, None);
+        assert_eq!(moved.custom_endpoint, None);
+        assert_eq!(moved.tags, tags(&["dev"]));
+        assert!(moved.expires_at.is_some());
+    }
+
+    /// A `MockStore` whose `delete` and `set_with` of one name always fail.
+    struct FailingWrites(MockStore, &'static str);
+
+    impl KeyStore for FailingWrites {
+        fn set_with(
+            &self,
+            name: &str,
+            value: &str,
+            kind: KeyKind,
+            force: bool,
+            updates: &[MetadataUpdate],
+        ) -> Result<()> {
+            if name == self.1 {
+                return Err(Error::keychain("write failed".to_string()));
+            }
+            self.0.set_with(name, value, kind, force, updates)
+        }
+        fn set_entry(&self, name: &str, entry: &StoredEntry, force: bool) -> Result<()> {
+            self.0.set_entry(name, entry, force)
+        }
+        fn get(&self, name: &str) -> Result<(Zeroizing<String>, KeyKind)> {
+            self.0.get(name)
+        }
+        fn get_with_metadata(&self, name: &str) -> Result<StoredEntry> {
+            self.0.get_with_metadata(name)
+        }
+        fn delete(&self, name: &str) -> Result<()> {
+            if name == self.1 {
+                return Err(Error::keychain("write failed".to_string()));
+            }
+            self.0.delete(name)
+        }
+        fn list(&self, include_admin: bool) -> Result<Vec<KeyEntry>> {
+            self.0.list(include_admin)
+        }
+        fn exists(&self, name: &str) -> Result<bool> {
+            self.0.exists(name)
+        }
+        fn touch(&self, name: &str) -> Result<()> {
+            self.0.touch(name)
+        }
+        fn update_metadata(&self, name: &str, update: &MetadataUpdate) -> Result<()> {
+            self.0.update_metadata(name, update)
+        }
+    }
+
+    #[test]
+    fn test_rename_force_rollback_restores_replaced_entry() {
+        let s = FailingWrites(store(), "openai:a");
+        s.set("openai:a", "sk-a", KeyKind::Runtime, false).unwrap();
+        s.set("openai:b", "sk-b", KeyKind::Admin, false).unwrap();
+        s.update_metadata(
+            "openai:b",
+            &MetadataUpdate::Endpoint(Some("https://b.example.com".to_string())),
+        )
+        .unwrap();
+        let before = s.get_with_metadata("openai:b").unwrap();
+
+        assert!(s.rename("openai:a", "openai:b", true).is_err());
+        let after = s.get_with_metadata("openai:b").unwrap();
+        assert_eq!(after.value, "sk-b");
+        assert_eq!(after.kind, KeyKind::Admin);
+        assert_eq!(
+            after.custom_endpoint.as_deref(),
+            Some("https://b.example.com")
+        );
+        assert_eq!(after.created_at, before.created_at);
+        assert_eq!(&*s.get("openai:a").unwrap().0, "sk-a");
+    }
+
+    #[test]
+    fn test_rotate_preserves_kind_and_metadata() {
+        let s = store();
+        s.set("openai:admin", "sk-old", KeyKind::Admin, false)
+            .unwrap();
+        s.update_metadata(
+            "openai:admin",
+            &MetadataUpdate::Description(Some("billing".to_string())),
+        )
+        .unwrap();
+
+        s.rotate("openai:admin", "sk-new").unwrap();
+        let entry = s.get_with_metadata("openai:admin").unwrap();
+        assert_eq!(entry.value, "sk-new");
+        assert_eq!(entry.kind, KeyKind::Admin);
+        assert_eq!(entry.description.as_deref(), Some("billing"));
+        assert!(entry.last_rotated.is_some());
+    }
+
+    #[test]
+    fn test_rotate_rejects_empty_and_missing() {
+        let s = store();
+        s.set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        assert!(matches!(
+            s.rotate("openai:prod", ""),
+            Err(Error::EmptyValue)
+        ));
+        assert_eq!(&*s.get("openai:prod").unwrap().0, "sk-1");
+
+        assert!(matches!(
+            s.rotate("openai:missing", "sk-2"),
+            Err(Error::KeyNotFound { .. })
+        ));
+        assert!(!s.exists("openai:missing").unwrap());
+    }
+
+    fn expire_in(s: &MockStore, name: &str, offset: chrono::Duration) {
+        s.update_metadata(name, &MetadataUpdate::Expiry(Some(Utc::now() + offset)))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_get_refuses_expired_key() {
+        let s = store();
+        s.set("aws:session", "tok-1", KeyKind::Runtime, false)
+            .unwrap();
+        expire_in(&s, "aws:session", chrono::Duration::seconds(-1));
+
+        assert!(matches!(
+            s.get("aws:session"),
+            Err(Error::KeyExpired { ref name, .. }) if name == "aws:session"
+        ));
+        // Metadata reads and listing still see it
+        assert_eq!(s.get_with_metadata("aws:session").unwrap().value, "tok-1");
+        assert_eq!(s.list(false).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_get_allows_unexpired_and_almost_expired_keys() {
+        let s = store();
+        s.set("aws:soon", "tok-soon", KeyKind::Runtime, false)
+            .unwrap();
+        s.set("aws:later", "tok-later", KeyKind::Runtime, false)
+            .unwrap();
+        expire_in(&s, "aws:soon", chrono::Duration::minutes(5));
+        expire_in(&s, "aws:later", chrono::Duration::days(30));
+
+        assert_eq!(&*s.get("aws:soon").unwrap().0, "tok-soon");
+        assert_eq!(&*s.get("aws:later").unwrap().0, "tok-later");
+    }
+
+    #[test]
+    fn test_rotate_revives_expired_key() {
+        let s = store();
+        s.set("aws:session", "tok-1", KeyKind::Runtime, false)
+            .unwrap();
+        expire_in(&s, "aws:session", chrono::Duration::seconds(-1));
+
+        s.rotate("aws:session", "tok-2").unwrap();
+        assert_eq!(&*s.get("aws:session").unwrap().0, "tok-2");
+        assert!(
+            s.get_with_metadata("aws:session")
+                .unwrap()
+                .expires_at
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_set_kind_keeps_value_and_metadata() {
+        let s = store();
+        s.set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        s.update_metadata(
+            "openai:prod",
+            &MetadataUpdate::Description(Some("CI".to_string())),
+        )
+        .unwrap();
+
+        s.set_kind("openai:prod", KeyKind::Admin).unwrap();
+        let entry = s.get_with_metadata("openai:prod").unwrap();
+        assert_eq!(entry.value, "sk-1");
+        assert_eq!(entry.kind, KeyKind::Admin);
+        assert_eq!(entry.description.as_deref(), Some("CI"));
+        assert!(entry.last_rotated.is_none());
+
+        assert!(matches!(
+            s.set_kind("openai:missing", KeyKind::Admin),
+            Err(Error::KeyNotFound { .. })
+        ));
+    }
+
+    fn tags(list: &[&str]) -> Vec<String> {
+        list.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_validate_tag() {
+        for ok in ["prod", "dev-1", "2024", "a"] {
+            assert!(validate_tag(ok).is_ok(), "{ok}");
+        }
+        for bad in ["", "-prod", "Prod", "pro_d", "pro d", "ステージ"] {
+            assert!(validate_tag(bad).is_err(), "{bad}");
+        }
+        assert!(validate_tag(&"a".repeat(MAX_TAG_LEN)).is_ok());
+        assert!(validate_tag(&"a".repeat(MAX_TAG_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_update_tags_add_remove_idempotent() {
+        let s = store();
+        s.set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+
+        let after = s
+            .update_tags("openai:prod", &tags(&["prod", "ci"]), &[])
+            .unwrap();
+        assert_eq!(after, tags(&["ci", "prod"]));
+        // Adding again changes nothing
+        let again = s.update_tags("openai:prod", &tags(&["prod"]), &[]).unwrap();
+        assert_eq!(again, tags(&["ci", "prod"]));
+
+        let after = s.update_tags("openai:prod", &[], &tags(&["ci"])).unwrap();
+        assert_eq!(after, tags(&["prod"]));
+        // Removing an absent tag changes nothing
+        let again = s.update_tags("openai:prod", &[], &tags(&["ci"])).unwrap();
+        assert_eq!(again, tags(&["prod"]));
+
+        // The value is untouched
+        assert_eq!(&*s.get("openai:prod").unwrap().0, "sk-1");
+    }
+
+    #[test]
+    fn test_update_tags_rejects_invalid_before_writing() {
+        let s = store();
+        s.set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        let err = s
+            .update_tags("openai:prod", &tags(&["ok", "Not Ok"]), &[])
+            .unwrap_err();
+        assert!(matches!(err, Error::Usage(_)));
+        assert!(s.get_with_metadata("openai:prod").unwrap().tags.is_empty());
+
+        assert!(matches!(
+            s.update_tags("openai:missing", &tags(&["prod"]), &[]),
+            Err(Error::KeyNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_list_filter_by_tag() {
+        let s = store();
+        for name in ["openai:prod", "openai:dev", "anthropic:prod"] {
+            s.set(name, "sk-1", KeyKind::Runtime, false).unwrap();
+        }
+        s.update_tags("openai:prod", &tags(&["prod"]), &[]).unwrap();
+        s.update_tags("anthropic:prod", &tags(&["prod", "billing"]), &[])
+            .unwrap();
+
+        let tagged: Vec<_> = s
+            .list(false)
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.has_tag("prod"))
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(tagged, ["anthropic:prod", "openai:prod"]);
+        let entry = s.list(false).unwrap();
+        assert_eq!(entry[0].tags, tags(&["billing", "prod"]));
+    }
+
+    #[test]
+    fn test_rename_nonexistent() {
+        let s = store();
+        let err = s.rename("openai:missing", "openai:new", false).unwrap_err();
+        assert!(matches!(err, Error::KeyNotFound { .. }));
+    }
+
+    #[test]
+    fn test_rename_validates_both_names() {
+        let s = store();
+        s.set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+
+        let err = s.rename("openai:prod", "Bad Name", false).unwrap_err();
+        assert!(matches!(err, Error::InvalidKeyName { .. }));
+        let err = s.rename("bad", "openai:new", false).unwrap_err();
+        assert!(matches!(err, Error::InvalidKeyName { .. }));
+        assert!(s.exists("openai:prod").unwrap());
+    }
+
+    // -- import_batch --
+
+    fn import_entry(name: &str, value: &str, kind: KeyKind) -> ImportEntry {
+        ImportEntry {
+            name: name.to_string(),
+            value: value.to_string(),
+            kind,
+            source: None,
+        }
+    }
+
+    fn store_with_existing() -> MockStore {
+        let s = store();
+        s.set("openai:prod", "sk-old", KeyKind::Runtime, false)
+            .unwrap();
+        s
+    }
+
+    fn batch() -> Vec<ImportEntry> {
+        vec![
+            import_entry("openai:prod", "sk-new", KeyKind::Admin),
+            import_entry("anthropic:main", "sk-ant-1", KeyKind::Runtime),
+        ]
+    }
+
+    #[test]
+    fn test_import_merge_keeps_existing() {
+        let s = store_with_existing();
+        let report = s.import_batch(&batch(), ImportMode::Merge).unwrap();
+
+        assert_eq!(report.imported, vec!["anthropic:main"]);
+        assert_eq!(report.skipped, vec!["openai:prod"]);
+        assert!(report.replaced.is_empty());
+        assert_eq!(&*s.get("openai:prod").unwrap().0, "sk-old");
+    }
+
+    #[test]
+    fn test_import_replace_overwrites_value_and_kind() {
+        let s = store_with_existing();
+        let report = s.import_batch(&batch(), ImportMode::Replace).unwrap();
+
+        assert_eq!(report.imported, vec!["anthropic:main"]);
+        assert_eq!(report.replaced, vec!["openai:prod"]);
+        assert_eq!(
+            report.kind_changes,
+            vec![KindChange {
+                name: "openai:prod".to_string(),
+                from: Some(KeyKind::Runtime),
+                to: KeyKind::Admin,
+            }]
+        );
+        let (val, kind) = s.get("openai:prod").unwrap();
+        assert_eq!(&*val, "sk-new");
+        assert_eq!(kind, KeyKind::Admin);
+    }
+
+    #[test]
+    fn test_import_skip_reports_skipped() {
+        let s = store_with_existing();
+        let report = s.import_batch(&batch(), ImportMode::Skip).unwrap();
+        assert_eq!(report.skipped, vec!["openai:prod"]);
+        assert_eq!(report.imported.len(), 1);
+    }
+
+    #[test]
+    fn test_import_invalid_entry_aborts_before_writing() {
+        let s = store();
+        let entries = vec![
+            import_entry("openai:prod", "sk-1", KeyKind::Runtime),
+            import_entry("Bad Name", "sk-2", KeyKind::Runtime),
+        ];
+        let err = s.import_batch(&entries, ImportMode::Merge).unwrap_err();
+        assert!(matches!(err, Error::InvalidKeyName { .. }));
+        assert!(!s.exists("openai:prod").unwrap());
+    }
+
+    #[test]
+    fn test_import_failed_write_rolls_back_batch() {
+        let s = FailingWrites(store_with_existing(), "openai:dev");
+        s.0.update_metadata(
+            "openai:prod",
+            &MetadataUpdate::Description(Some("old".to_string())),
+        )
+        .unwrap();
+        let entries = vec![
+            import_entry("openai:prod", "sk-new", KeyKind::Admin),
+            import_entry("anthropic:main", "sk-ant-1", KeyKind::Runtime),
+            import_entry("openai:dev", "sk-dev", KeyKind::Runtime),
+        ];
+
+        let err = s.import_batch(&entries, ImportMode::Replace).unwrap_err();
+        assert!(matches!(err, Error::Keychain { .. }));
+        assert!(!s.exists("anthropic:main").unwrap());
+        let restored = s.get_with_metadata("openai:prod").unwrap();
+        assert_eq!(restored.value, "sk-old");
+        assert_eq!(restored.kind, KeyKind::Runtime);
+        assert_eq!(restored.description.as_deref(), Some("old"));
+    }
+
+    #[test]
+    fn test_import_entry_kind_defaults_to_runtime() {
+        let entry: ImportEntry =
+            serde_json::from_str(r#"{"name":"openai:prod","value":"sk-1"}"#).unwrap();
+        assert_eq!(entry.kind, KeyKind::Runtime);
+    }
+
+    #[test]
+    fn test_import_mode_from_str() {
+        assert_eq!("merge".parse::<ImportMode>().unwrap(), ImportMode::Merge);
+        assert_eq!(
+            "replace".parse::<ImportMode>().unwrap(),
+            ImportMode::Replace
+        );
+        assert_eq!("skip".parse::<ImportMode>().unwrap(), ImportMode::Skip);
+        assert!("overwrite".parse::<ImportMode>().is_err());
+    }
+
+    // -- with_locked --
+
+    #[test]
+    fn test_with_locked_rolls_back_on_error() {
+        let s = store();
+        s.set("openai:a", "sk-a", KeyKind::Runtime, false).unwrap();
+
+        let result: Result<()> = s.with_locked(|s| {
+            s.set("openai:b", "sk-b", KeyKind::Runtime, false)?;
+            s.delete("openai:a")?;
+            s.delete("openai:missing")
+        });
+        assert!(matches!(result, Err(Error::KeyNotFound { .. })));
+
+        // Both earlier steps undone
+        assert!(s.exists("openai:a").unwrap());
+        assert!(!s.exists("openai:b").unwrap());
+    }
+
+    #[test]
+    fn test_with_locked_commits_and_nests() {
+        let s = store();
+        let n = s
+            .with_locked(|s| {
+                s.set("openai:a", "sk-a", KeyKind::Runtime, false)?;
+                s.with_locked(|s| s.set("openai:b", "sk-b", KeyKind::Runtime, false))?;
+                Ok(s.list(true)?.len())
+            })
+            .unwrap();
+        assert_eq!(n, 2);
+        assert!(s.exists("openai:b").unwrap());
+    }
+
+    #[test]
+    fn test_with_locked_isolates_other_threads() {
+        let s = std::sync::Arc::new(store());
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+
+        let writer = {
+            let s = s.clone();
+            std::thread::spawn(move || {
+                s.with_locked(|s| {
+                    s.set("openai:a", "sk-a", KeyKind::Runtime, false)?;
+                    started_tx.send(()).unwrap();
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                    s.set("openai:b", "sk-b", KeyKind::Runtime, false)
+                })
+                .unwrap();
+            })
+        };
+
+        started_rx.recv().unwrap();
+        // Blocks until the transaction finishes: never sees only "openai:a"
+        assert_eq!(s.list(true).unwrap().len(), 2);
+        writer.join().unwrap();
+    }
+
+    // -- StoredEntry serialization --
+
+    #[test]
+    fn test_find_unlisted_reports_skipped_items() {
+        let items = vec![
+            (
+                "openai:prod".to_string(),
+                Ok(br#"{"value":"sk-1","kind":"runtime"}"#.to_vec()),
+            ),
+            ("openai:corrupt".to_string(), Ok(b"{not json".to_vec())),
+            ("openai:binary".to_string(), Ok(vec![0xff, 0xfe])),
+            (
+                "no-colon".to_string(),
+                Ok(br#"{"value":"x","kind":"runtime"}"#.to_vec()),
+            ),
+            (
+                "openai:gone".to_string(),
+                Err(Error::KeyNotFound {
+                    name: "openai:gone".to_string(),
+                }),
+            ),
+        ];
+        let listed = HashSet::from(["openai:prod".to_string()]);
+
+        let unlisted = find_unlisted(items, &listed);
+        let accounts: Vec<_> = unlisted.iter().map(|u| u.account.as_str()).collect();
+        assert_eq!(
+            accounts,
+            ["no-colon", "openai:binary", "openai:corrupt", "openai:gone"]
+        );
+        assert!(unlisted[0].reason.starts_with("invalid name"));
+        assert_eq!(unlisted[1].reason, "payload is not UTF-8");
+        assert_eq!(unlisted[2].reason, "payload is not a valid lkr entry");
+        assert!(unlisted[3].reason.starts_with("unreadable"));
+        assert!(!unlisted.iter().any(|u| u.reason.contains("not json")));
+    }
+
+    #[test]
+    fn test_stored_entry_legacy_json_deserializes() {
+        // Entries written before metadata fields existed: value + kind only
+        let stored: StoredEntry =
+            serde_json::from_str(r#"{"value":"sk-abc","kind":"runtime"}"#).unwrap();
+        assert_eq!(stored.kind, KeyKind::Runtime);
+        assert!(stored.last_accessed.is_none());
+        assert_eq!(stored.access_count, 0);
+    }
+
+    #[test]
+    fn test_set_stamps_created_and_overwrite_stamps_rotated() {
+        let s = store();
+        s.set("openai:prod", "sk-old", KeyKind::Runtime, false)
+            .unwrap();
+        let entry = &s.list(false).unwrap()[0];
+        let created = entry.created_at.expect("created_at recorded");
+        assert!(entry.last_rotated.is_none());
+
+        // Same value again is not a rotation
+        s.set("openai:prod", "sk-old", KeyKind::Runtime, true)
+            .unwrap();
+        assert!(s.list(false).unwrap()[0].last_rotated.is_none());
+
+        s.set("openai:prod", "sk-new", KeyKind::Runtime, true)
+            .unwrap();
+        let entry = &s.list(false).unwrap()[0];
+        assert_eq!(entry.created_at, Some(created));
+        assert!(entry.last_rotated.is_some());
+    }
+
+    #[test]
+    fn test_set_stamps_updated_even_without_rotation() {
+        let s = store();
+        s.set("openai:prod", "sk-1", KeyKind::Runtime, false)
+            .unwrap();
+        let first = s.get_with_metadata("openai:prod").unwrap();
+        assert_eq!(first.updated_at, first.created_at);
+
+        s.set("openai:prod", "sk-1", KeyKind::Service, true)
+            .unwrap();
+        let second = s.get_with_metadata("openai:prod").unwrap();
+        assert!(second.updated_at >= first.updated_at);
+        assert!(second.last_rotated.is_none());
+        assert_eq!(second.created_at, first.created_at);
+    }
+
+    #[test]
+    fn test_get_with_metadata_returns_full_entry() {
+        let s = store();
+        s.set("openai:prod", "sk-abc", KeyKind::Runtime, false)
+            .unwrap();
+        s.update_metadata(
+            "openai:prod",
+            &MetadataUpdate::Description(Some("CI key".to_string())),
+        )
+        .unwrap();
+
+        let entry = s.get_with_metadata("openai:prod").unwrap();
+        assert_eq!(entry.value, "sk-abc");
+        assert_eq!(entry.kind, KeyKind::Runtime);
+        assert_eq!(entry.description.as_deref(), Some("CI key"));
+        assert!(entry.created_at.is_some());
+        assert!(!format!("{:?}", entry).contains("sk-abc"));
+        assert!(matches!(
+            s.get_with_metadata("openai:none"),
+            Err(Error::KeyNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_touch_records_access_and_reads_do_not() {
+        let s = store();
+        s.set("openai:prod", "sk-abc", KeyKind::Runtime, false)
+            .unwrap();
+        s.get("openai:prod").unwrap();
+        assert_eq!(s.list(false).unwrap()[0].access_count, 0);
+
+        s.touch("openai:prod").unwrap();
+        s.touch("openai:prod").unwrap();
+        let entry = &s.list(false).unwrap()[0];
+        assert_eq!(entry.access_count, 2);
+        assert!(entry.last_accessed.is_some());
+
+        assert!(matches!(
+            s.touch("openai:missing"),
+            Err(Error::KeyNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_endpoint_survives_overwrite_and_clears() {
+        let s = store();
+        s.set("openai:corp", "sk-abc", KeyKind::Runtime, false)
+            .unwrap();
+        assert_eq!(s.endpoint("openai:corp").unwrap(), None);
+
+        s.set_endpoint("openai:corp", Some("https://proxy.example.com"))
+            .unwrap();
+        s.set("openai:corp", "sk-new", KeyKind::Runtime, true)
+            .unwrap();
+        assert_eq!(
+            s.endpoint("openai:corp").unwrap().as_deref(),
+            Some("https://proxy.example.com")
+        );
+
+        s.set_endpoint("openai:corp", None).unwrap();
+        assert_eq!(s.list(false).unwrap()[0].custom_endpoint, None);
+        assert!(matches!(
+            s.set_endpoint("openai:missing", None),
+            Err(Error::KeyNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_stored_entry_omits_unset_metadata() {
+        let json = serde_json::to_string(&StoredEntry::new("sk-abc", KeyKind::Admin)).unwrap();
+        assert_eq!(json, r#"{"value":"sk-abc","kind":"admin"}"#);
+    }
+
     #[test]
     fn test_exists() {
         let s = store();
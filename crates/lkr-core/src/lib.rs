@@ -1,23 +1,60 @@
 #![warn(clippy::undocumented_unsafe_blocks)]
 
 pub mod acl;
+pub mod backend;
+pub mod batch;
+pub mod checksum;
+pub mod crypto;
 pub mod custom_keychain;
+pub mod endpoint;
 pub mod error;
+pub mod estimate;
+pub mod file_store;
+pub mod instrumented;
 pub mod keymanager;
+pub mod provider_config;
 pub mod template;
 pub mod usage;
+pub mod validate;
 
-pub use error::{Error, Result};
+pub use backend::{BACKEND_ENV, Backend, default_store};
+pub use batch::{BatchEntry, BatchFormat, BatchResult, BatchStatus, parse_batch_file, set_batch};
+pub use checksum::{ChecksumRecord, ChecksumStore, Verification, sha256_hex};
+pub use endpoint::normalize_endpoint;
+pub use error::{Error, Result, json_error_summary};
+pub use estimate::{ModelPrice, PriceTable, estimate_cost};
+pub use file_store::FileStore;
+pub use instrumented::{InstrumentedStore, OpMetrics, StoreMetrics};
 pub use keymanager::{
-    KeyEntry, KeyKind, KeyStatus, KeyStore, KeychainStore, MigrateKeyResult, MigrateResult,
-    mask_value,
+    ADMIN_LABEL, ADMIN_LABEL_WARNING, ImportEntry, ImportMode, ImportReport, KEYCHAIN_RETRIES_ENV,
+    KeyEntry, KeyKind, KeyStatus, KeyStore, KeychainStore, KindChange, MAX_DESCRIPTION_LEN,
+    MAX_KEY_NAME_LEN, MAX_LABEL_LEN, MAX_PROVIDER_LEN, MAX_TAG_LEN, MetadataUpdate,
+    MigrateKeyResult, MigrateResult, RELAXED_NAMES_ENV, SOURCE_ENV, SOURCE_FILE, SOURCE_IMPORT,
+    SOURCE_INTERACTIVE, StoredEntry, UnlistedItem, find_unlisted, fingerprint, glob_match,
+    is_nonstandard_admin_label, mask_value, validate_key_name, validate_tag,
+};
+pub use provider_config::{
+    CostUnit, CustomUsageConfig, EndpointsConfig, ProviderConfig, parse_header,
+};
+pub use template::{
+    CheckResult, EnvMappingConflict, GenResult, OutputPerms, Resolution, ShellKind,
+    TEMPLATE_KINDS_ENV, TemplateFormat, TemplatePolicy, builtin_env_mapping_conflicts, check,
+    check_gitignore, env_mapping_conflicts, generate, generate_all, generate_append, generate_mcp,
+    generate_shell_profile, key_to_endpoint_env_var, key_to_env_var, known_env_vars, render_str,
+    render_template,
 };
-pub use template::{GenResult, Resolution, check_gitignore, generate, key_to_env_var};
 pub use usage::{
-    CostLineItem, CostReport, UsageCache, available_providers, fetch_cost, format_cost,
+    ALL_MODELS, CostLineItem, CostReport, CostReportDiff, FetchOptions, FetchProgress,
+    LineItemDiff, LruUsageCache, ModelSort, ModelUsage, USAGE_PROVIDERS, UsageCache,
+    UsageCachePolicy, available_providers, diff_reports, fetch_cost, fetch_cost_with_progress,
+    fetch_previous_cost, fetch_previous_cost_with_progress, format_cost,
 };
+pub use validate::{VALIDATE_TIMEOUT, Validity, can_validate, validate_key};
 pub use zeroize::Zeroizing;
 
 /// Keychain service name — shared across all frontends.
 /// NEVER change this value once keys are stored.
 pub const SERVICE_NAME: &str = "com.llm-key-ring";
+
+/// Scratch service `lkr self-test` writes to, apart from real keys.
+pub const SELF_TEST_SERVICE_NAME: &str = "com.llm-key-ring.selftest";
@@ -0,0 +1,397 @@
+//! Per-provider settings for usage API requests, read from
+//! `~/.config/lkr/endpoints.toml`:
+//!
+//! ```toml
+//! [endpoints.openai.extra_headers]
+//! X-Cost-Center = "eng-team"
+//! ```
+//!
+//! A provider lkr has no built-in client for can describe a cost endpoint
+//! under `usage` (see [`CustomUsageConfig`]).
+
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Settings for one provider.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProviderConfig {
+    /// Headers added to every usage API request (after the auth headers)
+    #[serde(default)]
+    pub extra_headers: BTreeMap<String, String>,
+    /// Cost endpoint for a provider without a built-in usage client
+    #[serde(default)]
+    pub usage: Option<CustomUsageConfig>,
+}
+
+/// A minimal cost endpoint, for OpenAI-compatible gateways and the like:
+///
+/// ```toml
+/// [endpoints.gateway.usage]
+/// url = "https://gw.example.com/v1/cost?from={start}&to={end}"
+/// auth_header = "Authorization"
+/// auth_scheme = "Bearer"
+/// cost_path = "$.data.total_cost"
+/// cost_unit = "dollars"
+/// ```
+///
+/// The response is reduced to a single line item: the number at
+/// `cost_path`. The admin key is `{provider}:admin`, as for the built-ins.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomUsageConfig {
+    /// Request URL; `{start}` and `{end}` become the period's first and last
+    /// day (`YYYY-MM-DD`)
+    pub url: String,
+    /// Header carrying the admin key
+    #[serde(default = "default_auth_header")]
+    pub auth_header: String,
+    /// Prefix before the key in `auth_header` (`""` sends the bare key)
+    #[serde(default = "default_auth_scheme")]
+    pub auth_scheme: String,
+    /// Where the cost is in the JSON response: `$`, then `.field` or `[index]`
+    pub cost_path: String,
+    #[serde(default)]
+    pub cost_unit: CostUnit,
+    /// Currency the endpoint reports in
+    #[serde(default = "default_currency")]
+    pub currency: String,
+}
+
+/// Unit of the number at [`CustomUsageConfig::cost_path`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CostUnit {
+    #[default]
+    Dollars,
+    Cents,
+}
+
+fn default_auth_header() -> String {
+    "Authorization".to_string()
+}
+
+fn default_auth_scheme() -> String {
+    "Bearer".to_string()
+}
+
+fn default_currency() -> String {
+    "usd".to_string()
+}
+
+/// One step of a [`CustomUsageConfig::cost_path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PathSegment {
+    Field(String),
+    Index(usize),
+}
+
+/// Parse a JSONPath subset: `$` followed by `.field` and `[index]` steps
+/// (e.g. `$.data[0].amount`). Filters and wildcards are not supported.
+pub(crate) fn parse_cost_path(path: &str) -> Result<Vec<PathSegment>> {
+    let invalid = |reason: &str| Error::Usage(format!("Invalid cost_path '{}': {}", path, reason));
+    let mut rest = path
+        .trim()
+        .strip_prefix('$')
+        .ok_or_else(|| invalid("must start with '$'"))?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return Err(invalid("empty field name"));
+            }
+            segments.push(PathSegment::Field(after[..end].to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let (index, after) = after
+                .split_once(']')
+                .ok_or_else(|| invalid("unclosed '['"))?;
+            let index = index
+                .trim()
+                .parse()
+                .map_err(|_| invalid("only numeric [index] steps are supported"))?;
+            segments.push(PathSegment::Index(index));
+            rest = after;
+        } else {
+            return Err(invalid("expected '.field' or '[index]'"));
+        }
+    }
+    Ok(segments)
+}
+
+impl CustomUsageConfig {
+    fn validate(&self, provider: &str) -> Result<()> {
+        if crate::usage::USAGE_PROVIDERS.contains(&provider) {
+            return Err(Error::Usage(format!(
+                "'{}' has a built-in usage client; `usage` is only for other providers",
+                provider
+            )));
+        }
+        crate::endpoint::normalize_endpoint(&self.url)?;
+        validate_header_name(&self.auth_header)?;
+        validate_header_value(&self.auth_header, &self.auth_scheme)?;
+        parse_cost_path(&self.cost_path)?;
+        Ok(())
+    }
+}
+
+/// All provider settings, keyed by provider name.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EndpointsConfig {
+    #[serde(default)]
+    pub endpoints: BTreeMap<String, ProviderConfig>,
+}
+
+impl EndpointsConfig {
+    /// `~/.config/lkr/endpoints.toml`
+    pub fn default_path() -> Result<PathBuf> {
+        let home = home::home_dir()
+            .ok_or_else(|| Error::Usage("Cannot determine home directory".to_string()))?;
+        Ok(home.join(".config").join("lkr").join("endpoints.toml"))
+    }
+
+    /// Load and validate `path`; a missing file is an empty config.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(Error::Usage(format!(
+                    "Cannot read endpoints config '{}': {}",
+                    path.display(),
+                    e
+                )));
+            }
+        };
+        let invalid = |reason: String| {
+            Error::Usage(format!(
+                "Invalid endpoints config '{}': {}",
+                path.display(),
+                reason
+            ))
+        };
+        let doc: toml_edit::DocumentMut = content.parse().map_err(|e| invalid(format!("{}", e)))?;
+        let config: Self = serde_json::from_value(toml_to_json(doc.as_item()))
+            .map_err(|e| invalid(e.to_string()))?;
+        for (name, provider) in &config.endpoints {
+            for (name, value) in &provider.extra_headers {
+                validate_header(name, value)?;
+            }
+            if let Some(usage) = &provider.usage {
+                usage.validate(name)?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Configured custom cost endpoint for `provider`, if any.
+    pub fn custom_usage(&self, provider: &str) -> Option<&CustomUsageConfig> {
+        self.endpoints.get(provider)?.usage.as_ref()
+    }
+
+    /// Configured extra headers for `provider` (empty if none).
+    pub fn extra_headers(&self, provider: &str) -> Vec<(String, String)> {
+        self.endpoints
+            .get(provider)
+            .map(|p| {
+                p.extra_headers
+                    .iter()
+                    .map(|(n, v)| (n.clone(), v.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// A TOML item as the equivalent JSON value, so the config structs can be
+/// deserialized with serde_json. Datetimes become strings.
+fn toml_to_json(item: &toml_edit::Item) -> serde_json::Value {
+    use serde_json::Value as Json;
+    use toml_edit::{Item, Value};
+
+    fn value(v: &Value) -> Json {
+        match v {
+            Value::String(s) => Json::from(s.value().as_str()),
+            Value::Integer(i) => Json::from(*i.value()),
+            Value::Float(f) => Json::from(*f.value()),
+            Value::Boolean(b) => Json::from(*b.value()),
+            Value::Datetime(d) => Json::from(d.value().to_string()),
+            Value::Array(a) => a.iter().map(value).collect(),
+            Value::InlineTable(t) => t.iter().map(|(k, v)| (k.to_string(), value(v))).collect(),
+        }
+    }
+
+    fn table(t: &toml_edit::Table) -> Json {
+        Json::Object(
+            t.iter()
+                .map(|(k, v)| (k.to_string(), toml_to_json(v)))
+                .collect(),
+        )
+    }
+
+    match item {
+        Item::None => Json::Null,
+        Item::Value(v) => value(v),
+        Item::Table(t) => table(t),
+        Item::ArrayOfTables(a) => a.iter().map(table).collect(),
+    }
+}
+
+/// Parse a `NAME:VALUE` header argument (`lkr usage --header`).
+pub fn parse_header(arg: &str) -> Result<(String, String)> {
+    let (name, value) = arg.split_once(':').ok_or_else(|| {
+        Error::Usage(format!(
+            "Invalid header '{}': expected NAME:VALUE (e.g. X-Cost-Center:eng-team)",
+            arg
+        ))
+    })?;
+    let (name, value) = (name.trim(), value.trim());
+    validate_header(name, value)?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Check a header against the HTTP field syntax (RFC 9110): the name is a
+/// non-empty `token`, the value has no control characters. Auth headers
+/// are reserved — lkr sets them from the admin key.
+pub fn validate_header(name: &str, value: &str) -> Result<()> {
+    validate_header_name(name)?;
+    if ["authorization", "x-api-key"].contains(&name.to_ascii_lowercase().as_str()) {
+        return Err(Error::Usage(format!(
+            "Header '{}' is reserved: lkr sends it from the stored admin key",
+            name
+        )));
+    }
+    validate_header_value(name, value)
+}
+
+fn validate_header_name(name: &str) -> Result<()> {
+    if name.is_empty() || !name.bytes().all(is_tchar) {
+        return Err(Error::Usage(format!(
+            "Invalid header name '{}': only letters, digits and !#$%&'*+-.^_`|~ are allowed",
+            name
+        )));
+    }
+    Ok(())
+}
+
+fn validate_header_value(name: &str, value: &str) -> Result<()> {
+    if value.bytes().any(|b| (b < 0x20 && b != b'\t') || b == 0x7f) {
+        return Err(Error::Usage(format!(
+            "Invalid value for header '{}': control characters are not allowed",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// `tchar` from RFC 9110 §5.6.2.
+fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("X-Cost-Center: eng-team").unwrap(),
+            ("X-Cost-Center".to_string(), "eng-team".to_string())
+        );
+        // Only the first colon separates name and value
+        assert_eq!(parse_header("X-Trace:a:b").unwrap().1, "a:b".to_string());
+        assert!(parse_header("no-colon").is_err());
+    }
+
+    #[test]
+    fn test_validate_header_rejects_invalid_names_and_values() {
+        assert!(validate_header("X Cost", "v").is_err());
+        assert!(validate_header("", "v").is_err());
+        assert!(validate_header("X-Ünicode", "v").is_err());
+        assert!(validate_header("X-Ok", "line\r\nInjected: 1").is_err());
+        assert!(validate_header("Authorization", "Bearer x").is_err());
+        assert!(validate_header("X-Request-ID", "tab\tok").is_ok());
+    }
+
+    #[test]
+    fn test_load_config() {
+        let dir = std::env::temp_dir().join(format!("lkr-endpoints-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("endpoints.toml");
+
+        assert!(EndpointsConfig::load(&path).unwrap().endpoints.is_empty());
+
+        std::fs::write(
+            &path,
+            "[endpoints.openai.extra_headers]\nX-Cost-Center = \"eng-team\"\n",
+        )
+        .unwrap();
+        let config = EndpointsConfig::load(&path).unwrap();
+        assert_eq!(
+            config.extra_headers("openai"),
+            vec![("X-Cost-Center".to_string(), "eng-team".to_string())]
+        );
+        assert!(config.extra_headers("anthropic").is_empty());
+
+        std::fs::write(
+            &path,
+            "[endpoints.openai.extra_headers]\n\"Bad Name\" = \"x\"\n",
+        )
+        .unwrap();
+        assert!(EndpointsConfig::load(&path).is_err());
+
+        std::fs::write(&path, "[endpoints.openai\n").unwrap();
+        assert!(EndpointsConfig::load(&path).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_custom_usage_config() {
+        let doc: toml_edit::DocumentMut = r#"
+            [endpoints.gateway.usage]
+            url = "https://gw.example.com/cost"
+            cost_path = "$.total"
+        "#
+        .parse()
+        .unwrap();
+        let config: EndpointsConfig = serde_json::from_value(toml_to_json(doc.as_item())).unwrap();
+        let usage = config.custom_usage("gateway").unwrap();
+        assert_eq!(usage.auth_header, "Authorization");
+        assert_eq!(usage.auth_scheme, "Bearer");
+        assert_eq!(usage.cost_unit, CostUnit::Dollars);
+        assert!(usage.validate("gateway").is_ok());
+        assert!(usage.validate("openai").is_err());
+        assert!(config.custom_usage("openai").is_none());
+
+        let insecure = CustomUsageConfig {
+            url: "http://gw.example.com/cost".to_string(),
+            ..usage.clone()
+        };
+        assert!(insecure.validate("gateway").is_err());
+    }
+
+    #[test]
+    fn test_parse_cost_path() {
+        assert_eq!(
+            parse_cost_path("$.data[0].amount").unwrap(),
+            [
+                PathSegment::Field("data".to_string()),
+                PathSegment::Index(0),
+                PathSegment::Field("amount".to_string()),
+            ]
+        );
+        assert!(parse_cost_path("$").unwrap().is_empty());
+        assert!(parse_cost_path("data.amount").is_err());
+        assert!(parse_cost_path("$..amount").is_err());
+        assert!(parse_cost_path("$.data[*]").is_err());
+        assert!(parse_cost_path("$.data[0").is_err());
+    }
+}
@@ -1,16 +1,18 @@
-use crate::error::{Error, Result};
-use crate::keymanager::KeyStore;
-use std::collections::BTreeMap;
+use crate::error::{Error, Result, json_error_summary};
+use crate::keymanager::{KeyKind, KeyStore};
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::io::Write;
-use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
 use std::path::Path;
+use zeroize::Zeroizing;
 
 // ---------------------------------------------------------------------------
 // Template types
 // ---------------------------------------------------------------------------
 
-/// A resolved placeholder — either matched to a Keychain key or left unresolved.
+/// A resolved placeholder — matched to a Keychain key, filled from the
+/// environment, or left unresolved.
 #[derive(Debug)]
 pub struct Resolution {
     /// Original variable or placeholder name (e.g. "OPENAI_API_KEY" or "{{lkr:openai:prod}}")
@@ -19,6 +21,28 @@ pub struct Resolution {
     pub key_name: Option<String>,
     /// Other keys for the same provider (for disambiguation warnings)
     pub alternatives: Vec<String>,
+    /// Variable the value came from instead of a key: a process env var
+    /// (env fallback) or a caller-supplied var (`gen --stdin-json`)
+    pub env_var: Option<String>,
+    /// MCP servers whose `env` receives the value (MCP templates only)
+    pub mcp_servers: Vec<String>,
+}
+
+impl Resolution {
+    fn unresolved(placeholder: String) -> Self {
+        Self {
+            placeholder,
+            key_name: None,
+            alternatives: vec![],
+            env_var: None,
+            mcp_servers: vec![],
+        }
+    }
+
+    /// Whether a value was filled in, from the Keychain or the environment.
+    pub fn is_resolved(&self) -> bool {
+        self.key_name.is_some() || self.env_var.is_some()
+    }
 }
 
 /// Result of template generation: the rendered content + resolution details.
@@ -28,6 +52,257 @@ pub struct GenResult {
     pub content: String,
     /// Details of each resolved/unresolved placeholder
     pub resolutions: Vec<Resolution>,
+    /// Lines appended to an existing output (`generate_append` only; 0 otherwise)
+    pub appended_count: usize,
+    /// Hex SHA-256 of the whole output file as written (empty if nothing was written)
+    pub output_checksum: String,
+}
+
+/// Result of `check`: the template's resolutions plus whether the existing
+/// output still matches them structurally.
+#[derive(Debug)]
+pub struct CheckResult {
+    /// Details of each resolved/unresolved placeholder, as `generate` would report them
+    pub resolutions: Vec<Resolution>,
+    /// 1-based line of the first structural difference, or `None` if up to date
+    pub mismatch_line: Option<usize>,
+}
+
+impl CheckResult {
+    pub fn is_up_to_date(&self) -> bool {
+        self.mismatch_line.is_none()
+    }
+}
+
+/// Environment variable listing the key kinds templates may resolve,
+/// comma-separated (e.g. `runtime,admin`). Unset means runtime and service.
+pub const TEMPLATE_KINDS_ENV: &str = "LKR_TEMPLATE_KINDS";
+
+/// Which key kinds a template may resolve.
+///
+/// The default allows `runtime` and `service`: admin keys stay out of
+/// generated files unless explicitly allowed.
+#[derive(Clone, PartialEq, Eq)]
+pub struct TemplatePolicy {
+    allowed_kinds: Vec<KeyKind>,
+    /// Fill placeholders no key matches from the process environment
+    env_fallback: EnvFallback,
+    /// Explicit values that win over keys, by variable or placeholder name
+    vars: BTreeMap<String, Zeroizing<String>>,
+    /// Fail instead of writing output with unresolved placeholders
+    strict: bool,
+    /// Re-serialize output through serde_json, failing if it is not valid JSON
+    strict_json: bool,
+    /// Mode and group given to written outputs
+    output_perms: OutputPerms,
+}
+
+impl std::fmt::Debug for TemplatePolicy {
+    // Var values may be secrets; only their names are shown
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TemplatePolicy")
+            .field("allowed_kinds", &self.allowed_kinds)
+            .field("env_fallback", &(self.env_fallback != EnvFallback::Off))
+            .field("vars", &self.vars.keys().collect::<Vec<_>>())
+            .field("strict", &self.strict)
+            .field("strict_json", &self.strict_json)
+            .field("output_perms", &self.output_perms)
+            .finish()
+    }
+}
+
+/// Where `--env-fallback` looks up values.
+#[derive(Clone, PartialEq, Eq)]
+enum EnvFallback {
+    Off,
+    Process,
+    /// Fixed values instead of the process environment, so tests need not
+    /// mutate it while other tests run
+    #[cfg(test)]
+    Fixed(BTreeMap<String, String>),
+}
+
+impl Default for TemplatePolicy {
+    fn default() -> Self {
+        Self::allowing(&[KeyKind::Runtime, KeyKind::Service])
+    }
+}
+
+impl TemplatePolicy {
+    pub fn allowing(kinds: &[KeyKind]) -> Self {
+        Self {
+            allowed_kinds: kinds.to_vec(),
+            env_fallback: EnvFallback::Off,
+            vars: BTreeMap::new(),
+            strict: false,
+            strict_json: false,
+            output_perms: OutputPerms::default(),
+        }
+    }
+
+    /// Also resolve placeholders no key matches from the same-named process
+    /// env var (`lkr gen --env-fallback`). Keys still take precedence.
+    pub fn with_env_fallback(mut self, enabled: bool) -> Self {
+        self.env_fallback = if enabled {
+            EnvFallback::Process
+        } else {
+            EnvFallback::Off
+        };
+        self
+    }
+
+    #[cfg(test)]
+    fn with_fixed_env_fallback(mut self, env: &[(&str, &str)]) -> Self {
+        let env = env.iter().map(|&(k, v)| (k.to_string(), v.to_string()));
+        self.env_fallback = EnvFallback::Fixed(env.collect());
+        self
+    }
+
+    /// Fill placeholders from `vars` before looking at keys. Names match a
+    /// `.env`/shell variable (`OPENAI_API_KEY`) or a JSON placeholder's key
+    /// name (`openai:prod`).
+    pub fn with_vars(mut self, vars: BTreeMap<String, Zeroizing<String>>) -> Self {
+        self.vars = vars;
+        self
+    }
+
+    /// Refuse to produce output while any placeholder is unresolved
+    /// (`lkr gen --strict`).
+    pub fn with_strict(mut self, enabled: bool) -> Self {
+        self.strict = enabled;
+        self
+    }
+
+    /// Parse the rendered output as JSON and pretty-print it, failing when
+    /// it does not parse (`lkr gen --strict-json`). Object keys come out
+    /// sorted.
+    pub fn with_strict_json(mut self, enabled: bool) -> Self {
+        self.strict_json = enabled;
+        self
+    }
+
+    /// Write outputs with `perms` instead of 0600 (`lkr gen --mode/--group`).
+    pub fn with_output_perms(mut self, perms: OutputPerms) -> Self {
+        self.output_perms = perms;
+        self
+    }
+
+    /// Policy from `LKR_TEMPLATE_KINDS`, or the default when unset or empty.
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(TEMPLATE_KINDS_ENV) {
+            Ok(value) if !value.trim().is_empty() => Self::parse(&value),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self> {
+        let kinds = value
+            .split(',')
+            .map(|k| {
+                k.trim().parse::<KeyKind>().map_err(|reason| {
+                    Error::template(format!("{}: {}", TEMPLATE_KINDS_ENV, reason))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::allowing(&kinds))
+    }
+
+    pub fn allows(&self, kind: KeyKind) -> bool {
+        self.allowed_kinds.contains(&kind)
+    }
+
+    /// The env fallback value for `var`, if enabled and set to a non-empty value.
+    fn fallback_value(&self, var: &str) -> Option<Zeroizing<String>> {
+        let value = match &self.env_fallback {
+            EnvFallback::Off => return None,
+            EnvFallback::Process => std::env::var(var).ok(),
+            #[cfg(test)]
+            EnvFallback::Fixed(env) => env.get(var).cloned(),
+        };
+        value.filter(|v| !v.is_empty()).map(Zeroizing::new)
+    }
+
+    /// The caller-supplied value for `name`, if any.
+    fn var_value(&self, name: &str) -> Option<Zeroizing<String>> {
+        self.vars.get(name).cloned()
+    }
+
+    /// In strict mode, an error naming every unresolved placeholder.
+    fn ensure_resolved(&self, resolutions: &[Resolution]) -> Result<()> {
+        if !self.strict {
+            return Ok(());
+        }
+        let unresolved: Vec<&str> = resolutions
+            .iter()
+            .filter(|r| !r.is_resolved())
+            .map(|r| r.placeholder.as_str())
+            .collect();
+        if unresolved.is_empty() {
+            return Ok(());
+        }
+        Err(Error::template(format!(
+            "Template has {} unresolved placeholder(s): {}. Use --allow-unresolved to proceed anyway.",
+            unresolved.len(),
+            unresolved.join(", ")
+        )))
+    }
+
+    /// With `strict_json`, replace `content` by its pretty-printed JSON.
+    /// Runs after resolution, so it checks the output that would be written.
+    fn normalize_json(&self, content: &mut String) -> Result<()> {
+        if !self.strict_json {
+            return Ok(());
+        }
+        let value: serde_json::Value = serde_json::from_str(content).map_err(|e| {
+            Error::template(format!(
+                "--strict-json: output is not valid JSON ({})",
+                json_error_summary(&e)
+            ))
+        })?;
+        let mut pretty = serde_json::to_string_pretty(&value)
+            .map_err(|e| Error::template(format!("--strict-json: {}", e)))?;
+        pretty.push('\n');
+        *content = pretty;
+        Ok(())
+    }
+
+    fn allowed_list(&self) -> String {
+        let names: Vec<String> = self.allowed_kinds.iter().map(|k| k.to_string()).collect();
+        names.join(", ")
+    }
+}
+
+/// Shell dialect of a profile template, which decides the guard syntax.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellKind {
+    /// `[ -z "$VAR" ]`
+    Bash,
+    /// `[[ -z "$VAR" ]]`
+    Zsh,
+}
+
+impl ShellKind {
+    /// Detect the shell from a profile template's file name
+    /// (e.g. `.zshrc.template`, `.bashrc.template`, `.bash_profile.template`).
+    pub fn from_template_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        let stem = name.strip_suffix(".template").unwrap_or(name);
+        if stem.starts_with(".zsh") || stem == ".zprofile" {
+            Some(Self::Zsh)
+        } else if stem.starts_with(".bash") {
+            Some(Self::Bash)
+        } else {
+            None
+        }
+    }
+
+    /// Condition that holds when `var` is unset or empty.
+    fn unset_guard(self, var: &str) -> String {
+        match self {
+            Self::Bash => format!("[ -z \"${var}\" ]"),
+            Self::Zsh => format!("[[ -z \"${var}\" ]]"),
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -76,6 +351,55 @@ pub fn key_to_env_var(key_name: &str) -> String {
     key_name.to_uppercase().replace(':', "_")
 }
 
+/// Env var carrying a key's custom endpoint (`lkr set --endpoint`):
+/// `OPENAI_API_KEY` → `OPENAI_BASE_URL`; unknown providers get a
+/// `_BASE_URL` suffix (e.g. `custom:dev` → `CUSTOM_DEV_BASE_URL`).
+pub fn key_to_endpoint_env_var(key_name: &str) -> String {
+    let env_var = key_to_env_var(key_name);
+    match env_var.strip_suffix("_API_KEY") {
+        Some(stem) => format!("{}_BASE_URL", stem),
+        None => format!("{}_BASE_URL", env_var),
+    }
+}
+
+/// An env var that more than one provider maps to, so `.env` auto-detection
+/// could resolve it to the wrong key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvMappingConflict {
+    pub env_var: String,
+    pub providers: Vec<String>,
+}
+
+/// Find env vars mapped to more than one distinct provider in `mappings`
+/// (`(env_var, provider)` pairs), sorted by env var name.
+pub fn env_mapping_conflicts(mappings: &[(&str, &str)]) -> Vec<EnvMappingConflict> {
+    let mut by_var: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for &(env_var, provider) in mappings {
+        let providers = by_var.entry(env_var).or_default();
+        if !providers.contains(&provider) {
+            providers.push(provider);
+        }
+    }
+    by_var
+        .into_iter()
+        .filter(|(_, providers)| providers.len() > 1)
+        .map(|(env_var, providers)| EnvMappingConflict {
+            env_var: env_var.to_string(),
+            providers: providers.into_iter().map(String::from).collect(),
+        })
+        .collect()
+}
+
+/// The built-in `(env var, provider)` pairs, e.g. `("OPENAI_API_KEY", "openai")`.
+pub fn known_env_vars() -> &'static [(&'static str, &'static str)] {
+    ENV_VAR_MAP
+}
+
+/// Conflicts in the env var mappings `.env` auto-detection actually uses.
+pub fn builtin_env_mapping_conflicts() -> Vec<EnvMappingConflict> {
+    env_mapping_conflicts(ENV_VAR_MAP)
+}
+
 // ---------------------------------------------------------------------------
 // Public API
 // ---------------------------------------------------------------------------
@@ -86,33 +410,275 @@ pub fn key_to_env_var(key_name: &str) -> String {
 /// - `.env.example` style: auto-detects provider from variable names
 /// - JSON with `{{lkr:provider:label}}` placeholders
 ///
-/// Admin keys are never resolved (security policy).
+/// Only key kinds allowed by `policy` are resolved (see
+/// [`TemplatePolicy::from_env`]: runtime by default; admin keys are never
+/// resolved unless explicitly allowed).
 pub fn generate(
     store: &impl KeyStore,
     template_path: &Path,
     output_path: &Path,
+    policy: &TemplatePolicy,
 ) -> Result<GenResult> {
-    let content = fs::read_to_string(template_path).map_err(|e| {
-        Error::Template(format!(
-            "Cannot read template '{}': {}",
-            template_path.display(),
-            e
-        ))
-    })?;
+    let content = read_template(template_path)?;
+    let mut result = render_for_path(store, template_path, &content, false, policy)?;
+    policy.ensure_resolved(&result.resolutions)?;
+    policy.normalize_json(&mut result.content)?;
 
-    // Detect format from content or extension
-    let result = if is_json_template(&content) {
-        generate_json(store, &content)?
-    } else {
-        generate_env(store, &content)?
+    // Atomic write: write to temp file, then rename
+    result.output_checksum = write_secure(output_path, &result.content, policy.output_perms)?;
+
+    Ok(result)
+}
+
+/// Render a template as `generate` would, without writing anything
+/// (`output_checksum` stays empty).
+pub fn render_template(
+    store: &impl KeyStore,
+    template_path: &Path,
+    policy: &TemplatePolicy,
+) -> Result<GenResult> {
+    let content = read_template(template_path)?;
+    render_for_path(store, template_path, &content, false, policy)
+}
+
+/// Template syntax for [`render_str`], where there is no path to detect
+/// it from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateFormat {
+    /// `.env.example` lines
+    Env,
+    /// `{{lkr:provider:label}}` placeholders
+    Json,
+    /// Shell profile with `export` lines
+    Shell(ShellKind),
+    /// MCP client config (`.mcp.json`): JSON placeholders, only in server `env` values
+    Mcp,
+}
+
+impl std::str::FromStr for TemplateFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "env" => Ok(Self::Env),
+            "json" => Ok(Self::Json),
+            "bash" => Ok(Self::Shell(ShellKind::Bash)),
+            "zsh" => Ok(Self::Shell(ShellKind::Zsh)),
+            "mcp" => Ok(Self::Mcp),
+            _ => Err(format!(
+                "Unknown template format '{}'. Must be 'env', 'json', 'bash', 'zsh' or 'mcp'.",
+                s
+            )),
+        }
+    }
+}
+
+/// Render template content that did not come from a file. With no
+/// `format`, it is detected from the content as for `.env`/JSON files.
+pub fn render_str(
+    store: &impl KeyStore,
+    content: &str,
+    format: Option<TemplateFormat>,
+    policy: &TemplatePolicy,
+) -> Result<GenResult> {
+    let mut result = match format {
+        None => render(store, content, false, policy),
+        Some(TemplateFormat::Env) => generate_env(store, content, false, policy),
+        Some(TemplateFormat::Json) => generate_json(store, content, false, policy),
+        Some(TemplateFormat::Shell(shell)) => generate_shell(store, content, shell, false, policy),
+        Some(TemplateFormat::Mcp) => render_mcp(store, content, false, policy),
+    }?;
+    policy.ensure_resolved(&result.resolutions)?;
+    policy.normalize_json(&mut result.content)?;
+    Ok(result)
+}
+
+/// Generate several `(template, output)` pairs as one unit.
+///
+/// Every template is read and resolved before any output is written, so a
+/// missing template or disallowed key leaves all outputs untouched.
+/// Results are returned in `jobs` order.
+pub fn generate_all(
+    store: &impl KeyStore,
+    jobs: &[(&Path, &Path)],
+    policy: &TemplatePolicy,
+) -> Result<Vec<GenResult>> {
+    let mut results = jobs
+        .iter()
+        .map(|&(template_path, _)| {
+            let content = read_template(template_path)?;
+            render_for_path(store, template_path, &content, false, policy)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for result in &mut results {
+        policy.ensure_resolved(&result.resolutions)?;
+        policy.normalize_json(&mut result.content)?;
+    }
+
+    for (&(_, output_path), result) in jobs.iter().zip(&mut results) {
+        result.output_checksum = write_secure(output_path, &result.content, policy.output_perms)?;
+    }
+    Ok(results)
+}
+
+/// Append to an existing `.env` output only the template variables it lacks.
+///
+/// Existing lines are left untouched. Only newly *resolved* variables are
+/// appended; new variables without a matching key are reported in
+/// `resolutions` but not written. `content` holds just the appended lines.
+/// The output is not rewritten when there is nothing to append, and is
+/// created if missing.
+pub fn generate_append(
+    store: &impl KeyStore,
+    template_path: &Path,
+    output_path: &Path,
+    policy: &TemplatePolicy,
+) -> Result<GenResult> {
+    let content = read_template(template_path)?;
+    if is_json_template(&content) {
+        return Err(Error::template(
+            "Append is only supported for .env-style templates.".to_string(),
+        ));
+    }
+
+    let existing = match fs::read_to_string(output_path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(Error::template(format!(
+                "Cannot read output '{}': {}",
+                output_path.display(),
+                e
+            )));
+        }
     };
+    let present: HashSet<&str> = existing.lines().filter_map(env_var_name).collect();
 
-    // Atomic write: write to temp file, then rename
-    write_secure(output_path, &result.content)?;
+    // generate_env emits one line per template line and one resolution per
+    // KEY=VALUE line, in order — so they can be walked side by side.
+    let rendered = generate_env(store, &content, false, policy)?;
+    let mut all_resolutions = rendered.resolutions.into_iter();
+    let mut appended = String::new();
+    let mut resolutions = Vec::new();
+    let mut appended_count = 0;
+    for line in rendered.content.lines() {
+        if env_var_name(line).is_none() {
+            continue;
+        }
+        let Some(resolution) = all_resolutions.next() else {
+            break;
+        };
+        if present.contains(resolution.placeholder.as_str()) {
+            continue;
+        }
+        if resolution.is_resolved() {
+            appended.push_str(line);
+            appended.push('\n');
+            appended_count += 1;
+        }
+        resolutions.push(resolution);
+    }
+
+    policy.ensure_resolved(&resolutions)?;
+
+    let mut output_checksum = String::new();
+    if appended_count > 0 {
+        let mut output = existing.clone();
+        if !output.is_empty() && !output.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push_str(&appended);
+        output_checksum = write_secure(output_path, &output, policy.output_perms)?;
+    }
+
+    Ok(GenResult {
+        content: appended,
+        resolutions,
+        appended_count,
+        output_checksum,
+    })
+}
 
+/// Generate a shell profile snippet (`.zshrc`, `.bashrc`) from a template.
+///
+/// Each resolved `VAR=...` or `export VAR=...` line becomes a guarded export
+/// that only sets the variable if it is not already set by a higher-priority
+/// source (e.g. a project `.envrc`):
+///
+/// ```text
+/// [[ -z "$OPENAI_API_KEY" ]] && export OPENAI_API_KEY='sk-...'
+/// ```
+///
+/// The guard syntax follows the shell detected from the template's file name.
+/// `generate` dispatches here automatically for profile templates.
+pub fn generate_shell_profile(
+    store: &impl KeyStore,
+    template_path: &Path,
+    output_path: &Path,
+) -> Result<GenResult> {
+    let shell = ShellKind::from_template_path(template_path).ok_or_else(|| {
+        Error::template(format!(
+            "Cannot detect shell from '{}' (expected e.g. .zshrc.template or .bashrc.template).",
+            template_path.display()
+        ))
+    })?;
+    let content = read_template(template_path)?;
+    let mut result = generate_shell(store, &content, shell, false, &TemplatePolicy::from_env()?)?;
+    result.output_checksum = write_secure(output_path, &result.content, OutputPerms::default())?;
     Ok(result)
 }
 
+/// Generate an MCP client config (`.mcp.json`) from a template
+/// (`lkr gen --format mcp`).
+///
+/// The template must already be a valid MCP config: a `mcpServers` object
+/// whose servers each have a `command` (or a `url`), and `{{lkr:...}}`
+/// placeholders only inside `mcpServers.<server>.env` values, never in
+/// server names, commands or args. Each resolution lists the servers that
+/// receive its key.
+pub fn generate_mcp(
+    store: &impl KeyStore,
+    template_path: &Path,
+    output_path: &Path,
+    policy: &TemplatePolicy,
+) -> Result<GenResult> {
+    let content = read_template(template_path)?;
+    let mut result = render_mcp(store, &content, false, policy)?;
+    policy.ensure_resolved(&result.resolutions)?;
+    policy.normalize_json(&mut result.content)?;
+    result.output_checksum = write_secure(output_path, &result.content, policy.output_perms)?;
+    Ok(result)
+}
+
+/// Check whether an existing output is what `generate` would produce now,
+/// ignoring the secret values themselves.
+///
+/// The template is rendered with every resolved value redacted, and the
+/// output must match everything else exactly: comments, unresolved lines,
+/// JSON structure, and which placeholders are resolved. Nothing is written.
+pub fn check(
+    store: &impl KeyStore,
+    template_path: &Path,
+    output_path: &Path,
+    policy: &TemplatePolicy,
+) -> Result<CheckResult> {
+    let content = read_template(template_path)?;
+    let skeleton = render_for_path(store, template_path, &content, true, policy)?;
+
+    let existing = fs::read_to_string(output_path).map_err(|e| {
+        Error::template(format!(
+            "Cannot read output '{}': {}",
+            output_path.display(),
+            e
+        ))
+    })?;
+
+    Ok(CheckResult {
+        mismatch_line: find_structural_mismatch(&skeleton.content, &existing),
+        resolutions: skeleton.resolutions,
+    })
+}
+
 /// Check if a path is covered by .gitignore (best-effort).
 /// Returns `None` if not in a git repository or git is unavailable.
 /// Returns `Some(true)` if gitignored, `Some(false)` if not.
@@ -129,6 +695,47 @@ pub fn check_gitignore(path: &Path) -> Option<bool> {
     Some(output.status.success())
 }
 
+fn read_template(template_path: &Path) -> Result<String> {
+    fs::read_to_string(template_path).map_err(|e| {
+        Error::template(format!(
+            "Cannot read template '{}': {}",
+            template_path.display(),
+            e
+        ))
+    })
+}
+
+/// Render a template, picking the format from its path first (shell
+/// profiles) and its content second.
+fn render_for_path(
+    store: &impl KeyStore,
+    template_path: &Path,
+    content: &str,
+    redact: bool,
+    policy: &TemplatePolicy,
+) -> Result<GenResult> {
+    match ShellKind::from_template_path(template_path) {
+        Some(shell) => generate_shell(store, content, shell, redact, policy),
+        None => render(store, content, redact, policy),
+    }
+}
+
+/// Render template content. With `redact`, resolved values are replaced by
+/// `redacted(original)` markers instead of the secret (used by `check`).
+fn render(
+    store: &impl KeyStore,
+    content: &str,
+    redact: bool,
+    policy: &TemplatePolicy,
+) -> Result<GenResult> {
+    // Detect format from content or extension
+    if is_json_template(content) {
+        generate_json(store, content, redact, policy)
+    } else {
+        generate_env(store, content, redact, policy)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // .env.example format
 // ---------------------------------------------------------------------------
@@ -140,10 +747,13 @@ pub fn check_gitignore(path: &Path) -> Option<bool> {
 /// 2. Searching Keychain for any key with that provider
 ///
 /// Lines without `=` or starting with `#` are passed through.
-fn generate_env(store: &impl KeyStore, content: &str) -> Result<GenResult> {
-    // Get available keys (runtime only — admin keys excluded)
-    let entries = store.list(false)?;
-    let provider_map = build_provider_map(&entries);
+fn generate_env(
+    store: &impl KeyStore,
+    content: &str,
+    redact: bool,
+    policy: &TemplatePolicy,
+) -> Result<GenResult> {
+    let provider_map = build_provider_map(&allowed_entries(store, policy)?);
 
     let mut output = String::new();
     let mut resolutions = Vec::new();
@@ -162,25 +772,23 @@ fn generate_env(store: &impl KeyStore, content: &str) -> Result<GenResult> {
         if let Some(eq_pos) = trimmed.find('=') {
             let var_name = trimmed[..eq_pos].trim();
 
-            // Try to resolve from Keychain
-            if let Some((key_name, value, alternatives)) =
-                resolve_env_var(store, var_name, &provider_map)
+            // Try to resolve from Keychain (then the environment, if enabled)
+            if let Some((resolution, value)) = resolve_line(store, var_name, &provider_map, policy)
             {
-                output.push_str(&format!("{}={}\n", var_name, *value));
-                resolutions.push(Resolution {
-                    placeholder: var_name.to_string(),
-                    key_name: Some(key_name),
-                    alternatives,
-                });
+                let (original, comment) = split_inline_comment(&line.trim_start()[eq_pos + 1..]);
+                let comment = comment.unwrap_or("");
+                if redact {
+                    // What an unresolved render keeps: the value as-is
+                    output.push_str(&format!("{}={}{}\n", var_name, redacted(original), comment));
+                } else {
+                    output.push_str(&format!("{}={}{}\n", var_name, *value, comment));
+                }
+                resolutions.push(resolution);
             } else {
                 // Keep original line (unresolved)
                 output.push_str(line);
                 output.push('\n');
-                resolutions.push(Resolution {
-                    placeholder: var_name.to_string(),
-                    key_name: None,
-                    alternatives: vec![],
-                });
+                resolutions.push(Resolution::unresolved(var_name.to_string()));
             }
         } else {
             // Not a key=value line, pass through
@@ -192,9 +800,124 @@ fn generate_env(store: &impl KeyStore, content: &str) -> Result<GenResult> {
     Ok(GenResult {
         content: output,
         resolutions,
+        appended_count: 0,
+        output_checksum: String::new(),
     })
 }
 
+/// Split the part of a `.env` line after `=` into the value and an inline
+/// comment: `placeholder # set by lkr` → (`placeholder`, ` # set by lkr`).
+/// The comment keeps its leading whitespace so the line can be rebuilt
+/// as written. A `#` only starts a comment after whitespace and outside
+/// quotes, so `sk-abc#1` and `"a # b"` are values.
+fn split_inline_comment(rest: &str) -> (&str, Option<&str>) {
+    let mut quote = None;
+    let mut prev_space = false;
+    for (i, c) in rest.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') if prev_space => {
+                let value = rest[..i].trim_end();
+                return (value, Some(&rest[value.len()..]));
+            }
+            _ => {}
+        }
+        prev_space = c.is_whitespace();
+    }
+    (rest, None)
+}
+
+// ---------------------------------------------------------------------------
+// Shell profile format
+// ---------------------------------------------------------------------------
+
+/// Generate from a shell profile template.
+///
+/// Resolution works as for `.env` templates; resolved lines are rewritten as
+/// guarded, single-quoted exports. Everything else (aliases, functions,
+/// unresolved assignments) passes through unchanged.
+fn generate_shell(
+    store: &impl KeyStore,
+    content: &str,
+    shell: ShellKind,
+    redact: bool,
+    policy: &TemplatePolicy,
+) -> Result<GenResult> {
+    let provider_map = build_provider_map(&allowed_entries(store, policy)?);
+
+    let mut output = String::new();
+    let mut resolutions = Vec::new();
+
+    for line in content.lines() {
+        let Some(var_name) = env_var_name(line).filter(|n| is_shell_identifier(n)) else {
+            output.push_str(line);
+            output.push('\n');
+            continue;
+        };
+
+        if let Some((resolution, value)) = resolve_line(store, var_name, &provider_map, policy) {
+            let quoted = if redact {
+                let original = line.split_once('=').map_or("", |(_, v)| v.trim());
+                redacted(original)
+            } else {
+                value.replace('\'', "'\\''")
+            };
+            output.push_str(&format!(
+                "{} && export {}='{}'\n",
+                shell.unset_guard(var_name),
+                var_name,
+                quoted
+            ));
+            resolutions.push(resolution);
+        } else {
+            output.push_str(line);
+            output.push('\n');
+            resolutions.push(Resolution::unresolved(var_name.to_string()));
+        }
+    }
+
+    Ok(GenResult {
+        content: output,
+        resolutions,
+        appended_count: 0,
+        output_checksum: String::new(),
+    })
+}
+
+/// A valid shell variable name: letters, digits, `_`, not starting with a digit.
+fn is_shell_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Variable name of a `KEY=VALUE` line (an optional `export ` prefix is
+/// ignored). None for comments, blank lines, and lines without `=`.
+fn env_var_name(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+    let (name, _) = trimmed.split_once('=')?;
+    Some(name.trim_start_matches("export ").trim())
+}
+
+/// Keys whose kind the policy allows in templates (admin and service keys
+/// are only listed at all when allowed).
+fn allowed_entries(
+    store: &impl KeyStore,
+    policy: &TemplatePolicy,
+) -> Result<Vec<crate::keymanager::KeyEntry>> {
+    let hidden_allowed = policy.allows(KeyKind::Admin) || policy.allows(KeyKind::Service);
+    let mut entries = store.list(hidden_allowed)?;
+    entries.retain(|e| e.kind.is_some_and(|k| policy.allows(k)));
+    Ok(entries)
+}
+
 /// Build a map of provider → (first matching key name, all key names for this provider).
 /// Entries are sorted alphabetically, so the first key per provider is deterministic.
 fn build_provider_map(
@@ -214,11 +937,45 @@ fn build_provider_map(
     map
 }
 
+/// Resolve a `.env`/shell variable from the Keychain, falling back to the
+/// same-named process env var when the policy allows it.
+fn resolve_line(
+    store: &impl KeyStore,
+    var_name: &str,
+    provider_map: &BTreeMap<String, (String, Vec<String>)>,
+    policy: &TemplatePolicy,
+) -> Option<(Resolution, Zeroizing<String>)> {
+    if let Some(value) = policy.var_value(var_name) {
+        let resolution = Resolution {
+            env_var: Some(var_name.to_string()),
+            ..Resolution::unresolved(var_name.to_string())
+        };
+        return Some((resolution, value));
+    }
+    if let Some((key_name, value, alternatives)) = resolve_env_var(store, var_name, provider_map) {
+        let resolution = Resolution {
+            key_name: Some(key_name),
+            alternatives,
+            ..Resolution::unresolved(var_name.to_string())
+        };
+        return Some((resolution, value));
+    }
+    let value = policy.fallback_value(var_name)?;
+    let resolution = Resolution {
+        env_var: Some(var_name.to_string()),
+        ..Resolution::unresolved(var_name.to_string())
+    };
+    Some((resolution, value))
+}
+
 /// Try to resolve an env var name to a Keychain key.
 /// Returns (key_name, decrypted_value, alternatives) if found.
 ///
 /// Uses exact env var name matching (not prefix) to avoid over-broad substitution.
 /// e.g. `AWS_REGION` will NOT be matched even if `aws:*` key exists.
+///
+/// A provider's base URL variable (e.g. `OPENAI_BASE_URL`) resolves to the
+/// custom endpoint of the key chosen for that provider, if it has one.
 fn resolve_env_var(
     store: &impl KeyStore,
     var_name: &str,
@@ -236,6 +993,19 @@ fn resolve_env_var(
         }
     }
 
+    for &(_, provider) in ENV_VAR_MAP {
+        if let Some((key_name, alternatives)) = provider_map.get(provider)
+            && var_upper == key_to_endpoint_env_var(key_name)
+            && let Ok(Some(endpoint)) = store.endpoint(key_name)
+        {
+            return Some((
+                key_name.clone(),
+                zeroize::Zeroizing::new(endpoint),
+                alternatives.clone(),
+            ));
+        }
+    }
+
     None
 }
 
@@ -244,7 +1014,12 @@ fn resolve_env_var(
 // ---------------------------------------------------------------------------
 
 /// Generate from JSON template with {{lkr:provider:label}} placeholders.
-fn generate_json(store: &impl KeyStore, content: &str) -> Result<GenResult> {
+fn generate_json(
+    store: &impl KeyStore,
+    content: &str,
+    redact: bool,
+    policy: &TemplatePolicy,
+) -> Result<GenResult> {
     let mut output = content.to_string();
     let mut resolutions = Vec::new();
 
@@ -255,7 +1030,7 @@ fn generate_json(store: &impl KeyStore, content: &str) -> Result<GenResult> {
         let end = match output[start..].find("}}") {
             Some(pos) => start + pos + 2,
             None => {
-                return Err(Error::Template(format!(
+                return Err(Error::template(format!(
                     "Unclosed placeholder starting at position {}",
                     start
                 )));
@@ -267,45 +1042,193 @@ fn generate_json(store: &impl KeyStore, content: &str) -> Result<GenResult> {
         // Extract key name: {{lkr:openai:prod}} → openai:prod
         let key_name = placeholder[6..placeholder.len() - 2].to_string();
 
-        match store.get(&key_name) {
-            Ok((value, kind)) => {
-                // Security: never resolve admin keys in templates
-                if kind == crate::keymanager::KeyKind::Admin {
-                    return Err(Error::Template(format!(
-                        "Admin key '{}' cannot be used in templates. Only runtime keys are allowed.",
-                        key_name
-                    )));
+        let (value, resolution) = if let Some(value) = policy.var_value(&key_name) {
+            let resolution = Resolution {
+                env_var: Some(key_name.clone()),
+                ..Resolution::unresolved(placeholder.clone())
+            };
+            (value, resolution)
+        } else {
+            match store.get(&key_name) {
+                Ok((value, kind)) => {
+                    // Security: only kinds on the allowlist (runtime by default)
+                    if !policy.allows(kind) {
+                        return Err(Error::template(format!(
+                            "Key '{}' is a {} key and cannot be used in templates. Allowed kinds: {}.",
+                            key_name,
+                            kind,
+                            policy.allowed_list()
+                        )));
+                    }
+                    // JSON placeholders are explicit; no alternatives to report
+                    let resolution = Resolution {
+                        key_name: Some(key_name),
+                        ..Resolution::unresolved(placeholder.clone())
+                    };
+                    (value, resolution)
                 }
-                // Escape special JSON characters in the value to prevent
-                // broken JSON output if a key contains ", \, or control chars.
-                let escaped = escape_json_value(&value);
-                output = format!("{}{}{}", &output[..start], escaped, &output[end..]);
-                resolutions.push(Resolution {
-                    placeholder,
-                    key_name: Some(key_name),
-                    alternatives: vec![], // JSON placeholders are explicit; no ambiguity
-                });
-                // Don't advance search_from past end — replacement may be shorter
-                search_from = start + escaped.len();
-            }
-            Err(Error::KeyNotFound { .. }) => {
-                resolutions.push(Resolution {
-                    placeholder,
-                    key_name: None,
-                    alternatives: vec![],
-                });
-                search_from = end;
+                Err(Error::KeyNotFound { .. }) => {
+                    // Env values have no kind, so the allowlist does not apply
+                    let env_var = key_to_env_var(&key_name);
+                    let Some(value) = policy.fallback_value(&env_var) else {
+                        resolutions.push(Resolution::unresolved(placeholder));
+                        search_from = end;
+                        continue;
+                    };
+                    let resolution = Resolution {
+                        env_var: Some(env_var),
+                        ..Resolution::unresolved(placeholder.clone())
+                    };
+                    (value, resolution)
+                }
+                Err(e) => return Err(e),
             }
-            Err(e) => return Err(e),
-        }
+        };
+
+        // Escape special JSON characters in the value to prevent
+        // broken JSON output if a key contains ", \, or control chars.
+        let escaped = if redact {
+            redacted(&placeholder)
+        } else {
+            escape_json_value(&value)
+        };
+        output = format!("{}{}{}", &output[..start], escaped, &output[end..]);
+        resolutions.push(resolution);
+        // Don't advance search_from past end — replacement may be shorter
+        search_from = start + escaped.len();
     }
 
     Ok(GenResult {
         content: output,
         resolutions,
+        appended_count: 0,
+        output_checksum: String::new(),
     })
 }
 
+// ---------------------------------------------------------------------------
+// MCP config format
+// ---------------------------------------------------------------------------
+
+/// Validate an MCP template, then render it as a JSON template with each
+/// resolution tagged with the servers it feeds.
+fn render_mcp(
+    store: &impl KeyStore,
+    content: &str,
+    redact: bool,
+    policy: &TemplatePolicy,
+) -> Result<GenResult> {
+    let servers = mcp_placeholder_servers(content)?;
+    let mut result = generate_json(store, content, redact, policy)?;
+    for resolution in &mut result.resolutions {
+        if let Some(names) = servers.get(&resolution.placeholder) {
+            resolution.mcp_servers = names.clone();
+        }
+    }
+    Ok(result)
+}
+
+/// Check `content` is a well-formed MCP config with placeholders only in
+/// server `env` values. Returns, for each placeholder, the servers using it.
+fn mcp_placeholder_servers(content: &str) -> Result<BTreeMap<String, Vec<String>>> {
+    let invalid = |reason: String| Error::template(format!("Invalid MCP config: {}", reason));
+    let has_placeholder = |value: &serde_json::Value| value.to_string().contains("{{lkr:");
+
+    let doc: serde_json::Value =
+        serde_json::from_str(content).map_err(|e| invalid(format!("not valid JSON ({})", e)))?;
+    let Some(top) = doc.as_object() else {
+        return Err(invalid("the top level must be an object".to_string()));
+    };
+    let Some(servers) = top.get("mcpServers").and_then(|s| s.as_object()) else {
+        return Err(invalid("missing \"mcpServers\" object".to_string()));
+    };
+    if let Some((field, _)) = top
+        .iter()
+        .find(|(field, value)| *field != "mcpServers" && has_placeholder(value))
+    {
+        return Err(invalid(format!(
+            "placeholder in \"{}\"; keys may only be injected through server env values",
+            field
+        )));
+    }
+
+    let mut found: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, server) in servers {
+        if name.contains("{{lkr:") {
+            return Err(invalid(format!(
+                "placeholder in server name \"{}\"; keys may only be injected through env values",
+                name
+            )));
+        }
+        let Some(server) = server.as_object() else {
+            return Err(invalid(format!("server \"{}\" must be an object", name)));
+        };
+        let is_string = |field: &str| server.get(field).is_some_and(|v| v.is_string());
+        if !is_string("command") && !is_string("url") {
+            return Err(invalid(format!(
+                "server \"{}\" needs a \"command\" (or a \"url\" for a remote server)",
+                name
+            )));
+        }
+        if let Some(args) = server.get("args")
+            && !args
+                .as_array()
+                .is_some_and(|a| a.iter().all(|arg| arg.is_string()))
+        {
+            return Err(invalid(format!(
+                "\"args\" of server \"{}\" must be an array of strings",
+                name
+            )));
+        }
+        if let Some((field, _)) = server
+            .iter()
+            .find(|(field, value)| *field != "env" && has_placeholder(value))
+        {
+            return Err(invalid(format!(
+                "placeholder in mcpServers.{}.{}; keys may only be injected through env values",
+                name, field
+            )));
+        }
+
+        let Some(env) = server.get("env") else {
+            continue;
+        };
+        let Some(env) = env.as_object() else {
+            return Err(invalid(format!(
+                "\"env\" of server \"{}\" must be an object",
+                name
+            )));
+        };
+        for (var, value) in env {
+            let Some(value) = value.as_str() else {
+                return Err(invalid(format!(
+                    "mcpServers.{}.env.{} must be a string",
+                    name, var
+                )));
+            };
+            if var.contains("{{lkr:") {
+                return Err(invalid(format!(
+                    "placeholder in env var name \"{}\" of server \"{}\"",
+                    var, name
+                )));
+            }
+            let mut rest = value;
+            while let Some(start) = rest.find("{{lkr:") {
+                let Some(len) = rest[start..].find("}}") else {
+                    break;
+                };
+                let placeholder = &rest[start..start + len + 2];
+                let names = found.entry(placeholder.to_string()).or_default();
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+                rest = &rest[start + len + 2..];
+            }
+        }
+    }
+    Ok(found)
+}
+
 /// Detect if content looks like a JSON template (contains {{lkr:...}}).
 fn is_json_template(content: &str) -> bool {
     content.contains("{{lkr:")
@@ -329,16 +1252,129 @@ fn escape_json_value(s: &str) -> String {
             _ => out.push(c),
         }
     }
-    out
-}
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Structural comparison (gen --check)
+// ---------------------------------------------------------------------------
+
+/// Delimits a redacted value in a skeleton render. A control character, so
+/// it cannot collide with printable template text.
+const REDACTION_MARK: char = '\u{1}';
+
+/// Wrap the text an unresolved render would have kept in place of a secret.
+fn redacted(original: &str) -> String {
+    format!("{REDACTION_MARK}{original}{REDACTION_MARK}")
+}
+
+/// Compare an existing output against a redacted skeleton.
+///
+/// Literal segments must match exactly. Each redacted segment matches any
+/// single-line value except an unresolved placeholder. Returns the 1-based line of the first difference.
+fn find_structural_mismatch(skeleton: &str, actual: &str) -> Option<usize> {
+    let line_at = |offset: usize| actual[..offset].matches('\n').count() + 1;
+    let parts: Vec<&str> = skeleton.split(REDACTION_MARK).collect();
+
+    // Even parts are literals, odd parts are the originals of redacted values
+    let mut pos = 0;
+    for (i, literal) in parts.iter().enumerate().step_by(2) {
+        if i > 0 {
+            let original = parts[i - 1];
+            let rest = &actual[pos..];
+            let line_end = rest.find('\n').unwrap_or(rest.len());
+            // If the next literal is not on this line, take the whole line and
+            // let the literal comparison below pinpoint the difference.
+            let value_len = rest
+                .find(literal)
+                .filter(|&n| !literal.is_empty() && n <= line_end)
+                .unwrap_or(line_end);
+            let value = &rest[..value_len];
+            // An unresolved placeholder where a secret is expected means the
+            // key resolves now but did not when the output was generated.
+            if value == original || value.starts_with("{{lkr:") {
+                return Some(line_at(pos));
+            }
+            pos += value_len;
+        }
+
+        let rest = &actual[pos..];
+        if !rest.starts_with(literal) {
+            let common = rest
+                .char_indices()
+                .zip(literal.chars())
+                .find(|((_, a), b)| a != b)
+                .map_or(rest.len().min(literal.len()), |((n, _), _)| n);
+            return Some(line_at(pos + common));
+        }
+        pos += literal.len();
+    }
+
+    if pos != actual.len() {
+        return Some(line_at(pos));
+    }
+    None
+}
+
+// ---------------------------------------------------------------------------
+// Secure file writing
+// ---------------------------------------------------------------------------
+
+/// Permissions for files `lkr gen` writes. The default is 0600 in the
+/// owner's group; anything else is validated by [`OutputPerms::new`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputPerms {
+    mode: u32,
+    gid: Option<u32>,
+}
+
+impl Default for OutputPerms {
+    fn default() -> Self {
+        Self {
+            mode: 0o600,
+            gid: None,
+        }
+    }
+}
+
+impl OutputPerms {
+    /// `mode` may only hold permission bits, must leave the owner able to
+    /// read, and may never be executable. Access for others is refused
+    /// unless `allow_world`. `gid` hands the file to that group.
+    pub fn new(mode: u32, gid: Option<u32>, allow_world: bool) -> Result<Self> {
+        let reject = |reason: &str| Err(Error::Usage(format!("Mode {:04o} {}.", mode, reason)));
+        if mode & !0o777 != 0 {
+            return reject("has bits beyond rwx (setuid/setgid/sticky are not allowed)");
+        }
+        if mode & 0o111 != 0 {
+            return reject("is executable; generated secrets never need to be");
+        }
+        if mode & 0o400 == 0 {
+            return reject("does not let the owner read the file");
+        }
+        if mode & 0o007 != 0 && !allow_world {
+            return Err(Error::Usage(format!(
+                "Mode {:04o} makes the secrets readable by every user. Pass --i-understand to allow it.",
+                mode
+            )));
+        }
+        Ok(Self { mode, gid })
+    }
+
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
 
-// ---------------------------------------------------------------------------
-// Secure file writing
-// ---------------------------------------------------------------------------
+    pub fn gid(&self) -> Option<u32> {
+        self.gid
+    }
+}
 
-/// Write content to file with 0600 permissions (owner read/write only).
-/// Uses temp file + rename for atomicity.
-fn write_secure(path: &Path, content: &str) -> Result<()> {
+/// Write content to file with `perms` (0600, owner read/write only, by
+/// default). The temp file is chowned and chmodded before it is renamed
+/// into place, so the output never appears with other permissions. Returns
+/// the content's checksum.
+fn write_secure(path: &Path, content: &str, perms: OutputPerms) -> Result<String> {
     let parent = path.parent().unwrap_or(Path::new("."));
 
     // Write to temp file first
@@ -350,20 +1386,40 @@ fn write_secure(path: &Path, content: &str) -> Result<()> {
         .truncate(true)
         .mode(0o600)
         .open(&tmp_path)
-        .map_err(|e| Error::Template(format!("Cannot write to '{}': {}", tmp_path.display(), e)))?;
+        .map_err(|source| {
+            Error::template_with(format!("Cannot write to '{}'", tmp_path.display()), source)
+        })?;
 
     file.write_all(content.as_bytes())
-        .map_err(|e| Error::Template(format!("Write failed: {}", e)))?;
+        .map_err(|source| Error::template_with("Write failed".to_string(), source))?;
     file.flush()
-        .map_err(|e| Error::Template(format!("Flush failed: {}", e)))?;
+        .map_err(|source| Error::template_with("Flush failed".to_string(), source))?;
+    if let Err(source) = apply_perms(&file, perms) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(Error::template_with(
+            format!("Cannot set permissions on '{}'", tmp_path.display()),
+            source,
+        ));
+    }
 
     // Atomic rename
-    fs::rename(&tmp_path, path).map_err(|e| {
+    fs::rename(&tmp_path, path).map_err(|source| {
         // Clean up temp file on failure
         let _ = fs::remove_file(&tmp_path);
-        Error::Template(format!("Cannot rename to '{}': {}", path.display(), e))
+        Error::template_with(format!("Cannot rename to '{}'", path.display()), source)
     })?;
 
+    Ok(crate::checksum::sha256_hex(content.as_bytes()))
+}
+
+fn apply_perms(file: &fs::File, perms: OutputPerms) -> std::io::Result<()> {
+    if let Some(gid) = perms.gid {
+        std::os::unix::fs::fchown(file, None, Some(gid))?;
+    }
+    // Set explicitly: the mode given at open is reduced by the umask
+    if perms.mode != 0o600 {
+        file.set_permissions(fs::Permissions::from_mode(perms.mode))?;
+    }
     Ok(())
 }
 
@@ -408,7 +1464,7 @@ OPENAI_API_KEY=your-key-here
 ANTHROPIC_API_KEY=change-me
 DATABASE_URL=postgres://localhost/mydb
 ";
-        let result = generate_env(&store, template).unwrap();
+        let result = generate_env(&store, template, false, &TemplatePolicy::default()).unwrap();
 
         assert!(
             result
@@ -437,16 +1493,40 @@ DATABASE_URL=postgres://localhost/mydb
     fn test_env_preserves_comments_and_blanks() {
         let store = setup_store();
         let template = "# Comment\n\n# Another\nFOO=bar\n";
-        let result = generate_env(&store, template).unwrap();
+        let result = generate_env(&store, template, false, &TemplatePolicy::default()).unwrap();
 
         assert_eq!(result.content, "# Comment\n\n# Another\nFOO=bar\n");
     }
 
+    #[test]
+    fn test_env_keeps_inline_comment_on_resolved_line() {
+        let store = setup_store();
+        let template = "OPENAI_API_KEY=placeholder # set by lkr\nANTHROPIC_API_KEY=x\n";
+        let result = generate_env(&store, template, false, &TemplatePolicy::default()).unwrap();
+        assert_eq!(
+            result.content,
+            "OPENAI_API_KEY=sk-test-openai-key-12345678 # set by lkr\n\
+             ANTHROPIC_API_KEY=sk-ant-test-key-87654321\n"
+        );
+    }
+
+    #[test]
+    fn test_split_inline_comment() {
+        assert_eq!(
+            split_inline_comment("placeholder  # note"),
+            ("placeholder", Some("  # note"))
+        );
+        assert_eq!(split_inline_comment("sk-abc#1"), ("sk-abc#1", None));
+        assert_eq!(split_inline_comment(r#""a # b""#), (r#""a # b""#, None));
+        assert_eq!(split_inline_comment(" # only"), ("", Some(" # only")));
+        assert_eq!(split_inline_comment("x"), ("x", None));
+    }
+
     #[test]
     fn test_env_unresolved_kept_as_is() {
         let store = setup_store();
         let template = "UNKNOWN_KEY=placeholder\n";
-        let result = generate_env(&store, template).unwrap();
+        let result = generate_env(&store, template, false, &TemplatePolicy::default()).unwrap();
 
         assert_eq!(result.content, "UNKNOWN_KEY=placeholder\n");
         assert!(result.resolutions[0].key_name.is_none());
@@ -464,7 +1544,7 @@ AWS_REGION=us-east-1
 AWS_API_KEY=your-key-here
 AWS_DEFAULT_REGION=ap-northeast-1
 ";
-        let result = generate_env(&store, template).unwrap();
+        let result = generate_env(&store, template, false, &TemplatePolicy::default()).unwrap();
 
         // AWS_REGION and AWS_DEFAULT_REGION must be kept as-is
         assert!(result.content.contains("AWS_REGION=us-east-1"));
@@ -487,7 +1567,7 @@ AWS_DEFAULT_REGION=ap-northeast-1
     }
   }
 }"#;
-        let result = generate_json(&store, template).unwrap();
+        let result = generate_json(&store, template, false, &TemplatePolicy::default()).unwrap();
 
         assert!(
             result
@@ -506,7 +1586,7 @@ AWS_DEFAULT_REGION=ap-northeast-1
     fn test_json_multiple_placeholders() {
         let store = setup_store();
         let template = r#"{"a": "{{lkr:openai:prod}}", "b": "{{lkr:anthropic:main}}"}"#;
-        let result = generate_json(&store, template).unwrap();
+        let result = generate_json(&store, template, false, &TemplatePolicy::default()).unwrap();
 
         assert!(result.content.contains("sk-test-openai-key-12345678"));
         assert!(result.content.contains("sk-ant-test-key-87654321"));
@@ -517,7 +1597,7 @@ AWS_DEFAULT_REGION=ap-northeast-1
     fn test_json_unresolved_placeholder_kept() {
         let store = setup_store();
         let template = r#"{"key": "{{lkr:unknown:key}}"}"#;
-        let result = generate_json(&store, template).unwrap();
+        let result = generate_json(&store, template, false, &TemplatePolicy::default()).unwrap();
 
         assert!(result.content.contains("{{lkr:unknown:key}}"));
         assert!(result.resolutions[0].key_name.is_none());
@@ -527,8 +1607,8 @@ AWS_DEFAULT_REGION=ap-northeast-1
     fn test_json_unclosed_placeholder_error() {
         let store = setup_store();
         let template = r#"{"key": "{{lkr:openai:prod"}"#;
-        let err = generate_json(&store, template).unwrap_err();
-        assert!(matches!(err, Error::Template(_)));
+        let err = generate_json(&store, template, false, &TemplatePolicy::default()).unwrap_err();
+        assert!(matches!(err, Error::Template { .. }));
     }
 
     #[test]
@@ -538,8 +1618,214 @@ AWS_DEFAULT_REGION=ap-northeast-1
             .set("openai:admin", "sk-admin-secret", KeyKind::Admin, false)
             .unwrap();
         let template = r#"{"key": "{{lkr:openai:admin}}"}"#;
-        let err = generate_json(&store, template).unwrap_err();
-        assert!(matches!(err, Error::Template(_)));
+        let err = generate_json(&store, template, false, &TemplatePolicy::default()).unwrap_err();
+        assert!(matches!(err, Error::Template { .. }));
+    }
+
+    #[test]
+    fn test_env_fallback_fills_keychain_miss() {
+        let store = setup_store();
+        let policy = TemplatePolicy::default().with_fixed_env_fallback(&[
+            ("LKR_TEST_FALLBACK_TOKEN", "from-env-1"),
+            ("LKRTEST_FALLBACK", "from-env-2"),
+        ]);
+
+        let result = generate_env(&store, "LKR_TEST_FALLBACK_TOKEN=x\n", false, &policy).unwrap();
+        assert_eq!(result.content, "LKR_TEST_FALLBACK_TOKEN=from-env-1\n");
+        let resolution = &result.resolutions[0];
+        assert!(resolution.is_resolved() && resolution.key_name.is_none());
+        assert_eq!(
+            resolution.env_var.as_deref(),
+            Some("LKR_TEST_FALLBACK_TOKEN")
+        );
+
+        let json = r#"{"a": "{{lkr:lkrtest:fallback}}"}"#;
+        let result = generate_json(&store, json, false, &policy).unwrap();
+        assert_eq!(result.content, r#"{"a": "from-env-2"}"#);
+        assert_eq!(
+            result.resolutions[0].env_var.as_deref(),
+            Some("LKRTEST_FALLBACK")
+        );
+
+        // Off by default
+        let result = generate_json(&store, json, false, &TemplatePolicy::default()).unwrap();
+        assert!(!result.resolutions[0].is_resolved());
+    }
+
+    #[test]
+    fn test_env_fallback_keychain_wins() {
+        let store = MockStore::new();
+        store
+            .set("lkrtest:both", "from-keychain", KeyKind::Runtime, false)
+            .unwrap();
+        let policy =
+            TemplatePolicy::default().with_fixed_env_fallback(&[("LKRTEST_BOTH", "from-env")]);
+
+        let result =
+            generate_json(&store, r#"{"a": "{{lkr:lkrtest:both}}"}"#, false, &policy).unwrap();
+        assert_eq!(result.content, r#"{"a": "from-keychain"}"#);
+        assert_eq!(
+            result.resolutions[0].key_name.as_deref(),
+            Some("lkrtest:both")
+        );
+        assert!(result.resolutions[0].env_var.is_none());
+    }
+
+    #[test]
+    fn test_vars_override_keys() {
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-from-keychain", KeyKind::Runtime, false)
+            .unwrap();
+        let vars = BTreeMap::from([
+            (
+                "OPENAI_API_KEY".to_string(),
+                Zeroizing::new("sk-var".to_string()),
+            ),
+            (
+                "openai:prod".to_string(),
+                Zeroizing::new("sk-json-var".to_string()),
+            ),
+        ]);
+        let policy = TemplatePolicy::default().with_vars(vars);
+        assert!(!format!("{:?}", policy).contains("sk-var"));
+
+        let result = render_str(&store, "OPENAI_API_KEY=x\n", None, &policy).unwrap();
+        assert_eq!(result.content, "OPENAI_API_KEY=sk-var\n");
+        assert_eq!(
+            result.resolutions[0].env_var.as_deref(),
+            Some("OPENAI_API_KEY")
+        );
+
+        let json = r#"{"a": "{{lkr:openai:prod}}"}"#;
+        let result = render_str(&store, json, Some(TemplateFormat::Json), &policy).unwrap();
+        assert_eq!(result.content, r#"{"a": "sk-json-var"}"#);
+        assert!(result.resolutions[0].key_name.is_none());
+    }
+
+    #[test]
+    fn test_strict_rejects_unresolved_without_writing() {
+        let store = setup_store();
+        let dir = append_fixture(
+            "strict",
+            "OPENAI_API_KEY=x\nUNKNOWN_KEY=placeholder\nANOTHER_KEY=\n",
+            None,
+        );
+        let template_path = dir.join(".env.example");
+        let output_path = dir.join(".env");
+        let policy = TemplatePolicy::default().with_strict(true);
+
+        let err = generate(&store, &template_path, &output_path, &policy).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Template error: Template has 2 unresolved placeholder(s): UNKNOWN_KEY, ANOTHER_KEY. \
+             Use --allow-unresolved to proceed anyway."
+        );
+        assert!(!output_path.exists());
+
+        fs::write(&template_path, "OPENAI_API_KEY=x\n").unwrap();
+        generate(&store, &template_path, &output_path, &policy).unwrap();
+        assert!(output_path.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_strict_json_normalizes_whitespace() {
+        let store = setup_store();
+        let policy = TemplatePolicy::default().with_strict_json(true);
+        let json = "{ \"b\" :1,\n\n   \"a\":   \"{{lkr:openai:prod}}\" }";
+        let result = render_str(&store, json, Some(TemplateFormat::Json), &policy).unwrap();
+        assert_eq!(
+            result.content,
+            "{\n  \"a\": \"sk-test-openai-key-12345678\",\n  \"b\": 1\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_strict_json_rejects_invalid_output() {
+        let store = setup_store();
+        let policy = TemplatePolicy::default().with_strict_json(true);
+        let json = r#"{"a": "{{lkr:openai:prod}}",}"#;
+        let err = render_str(&store, json, Some(TemplateFormat::Json), &policy).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("--strict-json: output is not valid JSON"));
+        assert!(!message.contains("sk-test"));
+    }
+
+    #[test]
+    fn test_template_format_from_str() {
+        assert_eq!("env".parse(), Ok(TemplateFormat::Env));
+        assert_eq!("zsh".parse(), Ok(TemplateFormat::Shell(ShellKind::Zsh)));
+        assert!("yaml".parse::<TemplateFormat>().is_err());
+    }
+
+    #[test]
+    fn test_policy_runtime_resolves_admin_rejected_by_default() {
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-runtime", KeyKind::Runtime, false)
+            .unwrap();
+        store
+            .set("anthropic:admin", "sk-admin", KeyKind::Admin, false)
+            .unwrap();
+        let policy = TemplatePolicy::default();
+
+        let json = r#"{"a": "{{lkr:openai:prod}}"}"#;
+        let result = generate_json(&store, json, false, &policy).unwrap();
+        assert!(result.content.contains("sk-runtime"));
+
+        let json = r#"{"a": "{{lkr:anthropic:admin}}"}"#;
+        let err = generate_json(&store, json, false, &policy).unwrap_err();
+        assert!(err.to_string().contains("Allowed kinds: runtime"));
+
+        // .env: the admin key is not a candidate at all
+        let result = generate_env(&store, "ANTHROPIC_API_KEY=x\n", false, &policy).unwrap();
+        assert_eq!(result.content, "ANTHROPIC_API_KEY=x\n");
+    }
+
+    #[test]
+    fn test_policy_service_key_resolves_by_default() {
+        let store = MockStore::new();
+        store
+            .set("openai:shared", "sk-service", KeyKind::Service, false)
+            .unwrap();
+        let policy = TemplatePolicy::default();
+
+        let json = r#"{"a": "{{lkr:openai:shared}}"}"#;
+        let result = generate_json(&store, json, false, &policy).unwrap();
+        assert_eq!(result.content, r#"{"a": "sk-service"}"#);
+
+        let result = generate_env(&store, "OPENAI_API_KEY=x\n", false, &policy).unwrap();
+        assert_eq!(result.content, "OPENAI_API_KEY=sk-service\n");
+
+        // Not when the policy is narrowed to runtime
+        let runtime_only = TemplatePolicy::allowing(&[KeyKind::Runtime]);
+        assert!(generate_json(&store, json, false, &runtime_only).is_err());
+    }
+
+    #[test]
+    fn test_policy_configured_kind_resolves() {
+        let store = MockStore::new();
+        store
+            .set("anthropic:admin", "sk-admin", KeyKind::Admin, false)
+            .unwrap();
+        let policy = TemplatePolicy::allowing(&[KeyKind::Runtime, KeyKind::Admin]);
+
+        let json = r#"{"a": "{{lkr:anthropic:admin}}"}"#;
+        let result = generate_json(&store, json, false, &policy).unwrap();
+        assert!(result.content.contains("sk-admin"));
+
+        let result = generate_env(&store, "ANTHROPIC_API_KEY=x\n", false, &policy).unwrap();
+        assert_eq!(result.content, "ANTHROPIC_API_KEY=sk-admin\n");
+    }
+
+    #[test]
+    fn test_policy_parse() {
+        assert_eq!(
+            TemplatePolicy::parse("runtime, admin").unwrap(),
+            TemplatePolicy::allowing(&[KeyKind::Runtime, KeyKind::Admin])
+        );
+        assert!(TemplatePolicy::parse("runtime,bogus").is_err());
     }
 
     #[test]
@@ -555,7 +1841,7 @@ AWS_DEFAULT_REGION=ap-northeast-1
             )
             .unwrap();
         let template = r#"{"key": "{{lkr:test:special}}"}"#;
-        let result = generate_json(&store, template).unwrap();
+        let result = generate_json(&store, template, false, &TemplatePolicy::default()).unwrap();
 
         // The output must be valid JSON — quotes and backslashes escaped
         assert!(
@@ -574,6 +1860,67 @@ AWS_DEFAULT_REGION=ap-northeast-1
         assert!(!is_json_template("OPENAI_API_KEY=value"));
     }
 
+    #[test]
+    fn test_env_resolves_base_url_from_custom_endpoint() {
+        let store = setup_store();
+        store
+            .set_endpoint("openai:prod", Some("https://proxy.example.com"))
+            .unwrap();
+        let template =
+            "OPENAI_API_KEY=\nOPENAI_BASE_URL=https://api.openai.com/v1\nANTHROPIC_BASE_URL=\n";
+        let result = generate_env(&store, template, false, &TemplatePolicy::default()).unwrap();
+        assert!(
+            result
+                .content
+                .contains("OPENAI_BASE_URL=https://proxy.example.com\n")
+        );
+        // No endpoint stored for anthropic: left as in the template
+        assert!(result.content.contains("ANTHROPIC_BASE_URL=\n"));
+    }
+
+    #[test]
+    fn test_key_to_endpoint_env_var() {
+        assert_eq!(key_to_endpoint_env_var("openai:corp"), "OPENAI_BASE_URL");
+        assert_eq!(
+            key_to_endpoint_env_var("azure-openai:prod"),
+            "AZURE_OPENAI_BASE_URL"
+        );
+        assert_eq!(key_to_endpoint_env_var("custom:dev"), "CUSTOM_DEV_BASE_URL");
+    }
+
+    // -- env mapping conflicts --
+
+    #[test]
+    fn test_env_mapping_conflicts_reported() {
+        let conflicts = env_mapping_conflicts(&[
+            ("OPENAI_API_KEY", "openai"),
+            ("LLM_API_KEY", "openai"),
+            ("LLM_API_KEY", "anthropic"),
+            ("ANTHROPIC_API_KEY", "anthropic"),
+        ]);
+        assert_eq!(
+            conflicts,
+            vec![EnvMappingConflict {
+                env_var: "LLM_API_KEY".to_string(),
+                providers: vec!["openai".to_string(), "anthropic".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_env_mapping_clean_passes() {
+        // Repeating the same pair is redundant, not ambiguous
+        assert!(
+            env_mapping_conflicts(&[
+                ("OPENAI_API_KEY", "openai"),
+                ("OPENAI_API_KEY", "openai"),
+                ("ANTHROPIC_API_KEY", "anthropic"),
+            ])
+            .is_empty()
+        );
+        assert!(builtin_env_mapping_conflicts().is_empty());
+    }
+
     // -- key_to_env_var --
 
     #[test]
@@ -604,6 +1951,419 @@ AWS_DEFAULT_REGION=ap-northeast-1
         );
     }
 
+    // -- Structural check --
+
+    /// Render for real and as a skeleton, then compare the two.
+    fn check_content(store: &MockStore, template: &str, existing: &str) -> Option<usize> {
+        let skeleton = render(store, template, true, &TemplatePolicy::default()).unwrap();
+        find_structural_mismatch(&skeleton.content, existing)
+    }
+
+    #[test]
+    fn test_check_env_up_to_date_ignores_values() {
+        let store = setup_store();
+        let template =
+            "# config\nOPENAI_API_KEY=your-key-here\nDATABASE_URL=postgres://localhost\n";
+        let current = render(&store, template, false, &TemplatePolicy::default())
+            .unwrap()
+            .content;
+        assert_eq!(check_content(&store, template, &current), None);
+
+        // A rotated key changes the value, not the structure
+        let rotated = current.replace("sk-test-openai-key-12345678", "sk-rotated-999");
+        assert_eq!(check_content(&store, template, &rotated), None);
+    }
+
+    #[test]
+    fn test_check_env_out_of_date() {
+        let store = setup_store();
+        let old_template = "OPENAI_API_KEY=x\nDATABASE_URL=postgres://localhost\n";
+        let existing = render(&store, old_template, false, &TemplatePolicy::default())
+            .unwrap()
+            .content;
+
+        // Template gained a line
+        let template = "OPENAI_API_KEY=x\nANTHROPIC_API_KEY=x\nDATABASE_URL=postgres://localhost\n";
+        assert_eq!(check_content(&store, template, &existing), Some(2));
+
+        // Non-secret content changed
+        let template = "OPENAI_API_KEY=x\nDATABASE_URL=postgres://prod\n";
+        assert_eq!(check_content(&store, template, &existing), Some(2));
+    }
+
+    #[test]
+    fn test_check_env_detects_newly_resolved_key() {
+        let store = MockStore::new();
+        let template = "OPENAI_API_KEY=your-key-here\n";
+        // Generated before any key was stored: line kept as-is
+        let existing = render(&store, template, false, &TemplatePolicy::default())
+            .unwrap()
+            .content;
+
+        store
+            .set("openai:prod", "sk-new-key", KeyKind::Runtime, false)
+            .unwrap();
+        assert_eq!(check_content(&store, template, &existing), Some(1));
+    }
+
+    #[test]
+    fn test_check_json_up_to_date_and_out_of_date() {
+        let store = setup_store();
+        let template = r#"{"a": "{{lkr:openai:prod}}", "b": "{{lkr:missing:key}}"}"#;
+        let current = render(&store, template, false, &TemplatePolicy::default())
+            .unwrap()
+            .content;
+        assert_eq!(check_content(&store, template, &current), None);
+
+        let template = r#"{"a": "{{lkr:openai:prod}}", "b": "{{lkr:anthropic:main}}"}"#;
+        assert_eq!(check_content(&store, template, &current), Some(1));
+    }
+
+    #[test]
+    fn test_check_reads_files_without_writing() {
+        let store = setup_store();
+        let dir = std::env::temp_dir().join(format!("lkr-test-check-{}", std::process::id()));
+        let _ = fs::create_dir_all(&dir);
+        let template_path = dir.join(".env.example");
+        let output_path = dir.join(".env");
+        fs::write(&template_path, "OPENAI_API_KEY=x\n").unwrap();
+
+        generate(
+            &store,
+            &template_path,
+            &output_path,
+            &TemplatePolicy::default(),
+        )
+        .unwrap();
+        let result = check(
+            &store,
+            &template_path,
+            &output_path,
+            &TemplatePolicy::default(),
+        )
+        .unwrap();
+        assert!(result.is_up_to_date());
+        assert_eq!(result.resolutions.len(), 1);
+
+        fs::write(&template_path, "OPENAI_API_KEY=x\nEXTRA=1\n").unwrap();
+        let result = check(
+            &store,
+            &template_path,
+            &output_path,
+            &TemplatePolicy::default(),
+        )
+        .unwrap();
+        assert_eq!(result.mismatch_line, Some(2));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    // -- generate_all --
+
+    #[test]
+    fn test_generate_all_writes_every_output() {
+        let store = setup_store();
+        let dir = append_fixture("gen-all", "OPENAI_API_KEY=\n", None);
+        fs::write(
+            dir.join("mcp.json.template"),
+            r#"{"key": "{{lkr:anthropic:main}}"}"#,
+        )
+        .unwrap();
+        let (env_out, json_out) = (dir.join(".env"), dir.join("mcp.json"));
+        let results = generate_all(
+            &store,
+            &[
+                (&dir.join(".env.example"), &env_out),
+                (&dir.join("mcp.json.template"), &json_out),
+            ],
+            &TemplatePolicy::default(),
+        )
+        .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(env_out.exists() && json_out.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_generate_all_writes_nothing_if_any_template_fails() {
+        let store = setup_store();
+        let dir = append_fixture("gen-all-fail", "OPENAI_API_KEY=\n", None);
+        let env_out = dir.join(".env");
+        let result = generate_all(
+            &store,
+            &[
+                (&dir.join(".env.example"), &env_out),
+                (&dir.join("missing.template"), &dir.join("missing")),
+            ],
+            &TemplatePolicy::default(),
+        );
+
+        assert!(result.is_err());
+        assert!(!env_out.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // -- Append --
+
+    fn append_fixture(test: &str, template: &str, existing: Option<&str>) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("lkr-test-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(".env.example"), template).unwrap();
+        if let Some(existing) = existing {
+            fs::write(dir.join(".env"), existing).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_append_adds_only_missing_resolved_vars() {
+        let store = setup_store();
+        let dir = append_fixture(
+            "append-new",
+            "OPENAI_API_KEY=x\nANTHROPIC_API_KEY=x\nGROQ_API_KEY=x\n",
+            Some("# mine\nOPENAI_API_KEY=sk-hand-edited"),
+        );
+
+        let result = generate_append(
+            &store,
+            &dir.join(".env.example"),
+            &dir.join(".env"),
+            &TemplatePolicy::default(),
+        )
+        .unwrap();
+        assert_eq!(result.appended_count, 1);
+        // GROQ has no key: reported, not appended
+        assert_eq!(result.resolutions.len(), 2);
+
+        let output = fs::read_to_string(dir.join(".env")).unwrap();
+        assert_eq!(
+            output,
+            "# mine\nOPENAI_API_KEY=sk-hand-edited\nANTHROPIC_API_KEY=sk-ant-test-key-87654321\n"
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_append_nothing_new_leaves_file_untouched() {
+        let store = setup_store();
+        let existing = "export OPENAI_API_KEY=sk-keep\n";
+        let dir = append_fixture("append-none", "OPENAI_API_KEY=x\n", Some(existing));
+
+        let result = generate_append(
+            &store,
+            &dir.join(".env.example"),
+            &dir.join(".env"),
+            &TemplatePolicy::default(),
+        )
+        .unwrap();
+        assert_eq!(result.appended_count, 0);
+        assert_eq!(fs::read_to_string(dir.join(".env")).unwrap(), existing);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_append_rejects_json_template() {
+        let store = setup_store();
+        let dir = append_fixture("append-json", r#"{"k": "{{lkr:openai:prod}}"}"#, None);
+        let err = generate_append(
+            &store,
+            &dir.join(".env.example"),
+            &dir.join(".env"),
+            &TemplatePolicy::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::Template { .. }));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    // -- Shell profiles --
+
+    #[test]
+    fn test_shell_kind_from_template_path() {
+        let kind = |p: &str| ShellKind::from_template_path(Path::new(p));
+        assert_eq!(kind(".zshrc.template"), Some(ShellKind::Zsh));
+        assert_eq!(kind("dotfiles/.zprofile.template"), Some(ShellKind::Zsh));
+        assert_eq!(kind(".bashrc.template"), Some(ShellKind::Bash));
+        assert_eq!(kind(".bash_profile.template"), Some(ShellKind::Bash));
+        assert_eq!(kind(".env.example"), None);
+    }
+
+    #[test]
+    fn test_shell_guards_resolved_exports() {
+        let store = setup_store();
+        let template = "\
+# keys
+export OPENAI_API_KEY=your-key-here
+ANTHROPIC_API_KEY=x
+export PATH=\"$HOME/bin:$PATH\"
+alias ll='ls -l'
+";
+        let zsh = generate_shell(
+            &store,
+            template,
+            ShellKind::Zsh,
+            false,
+            &TemplatePolicy::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            zsh.content,
+            "\
+# keys
+[[ -z \"$OPENAI_API_KEY\" ]] && export OPENAI_API_KEY='sk-test-openai-key-12345678'
+[[ -z \"$ANTHROPIC_API_KEY\" ]] && export ANTHROPIC_API_KEY='sk-ant-test-key-87654321'
+export PATH=\"$HOME/bin:$PATH\"
+alias ll='ls -l'
+"
+        );
+        // PATH is an unresolved assignment; the alias is not an assignment
+        assert_eq!(zsh.resolutions.len(), 3);
+
+        let bash = generate_shell(
+            &store,
+            template,
+            ShellKind::Bash,
+            false,
+            &TemplatePolicy::default(),
+        )
+        .unwrap();
+        assert!(bash.content.contains(
+            "[ -z \"$OPENAI_API_KEY\" ] && export OPENAI_API_KEY='sk-test-openai-key-12345678'"
+        ));
+    }
+
+    #[test]
+    fn test_shell_escapes_single_quotes() {
+        let store = MockStore::new();
+        store
+            .set("openai:prod", "sk-it's", KeyKind::Runtime, false)
+            .unwrap();
+        let result = generate_shell(
+            &store,
+            "OPENAI_API_KEY=x\n",
+            ShellKind::Bash,
+            false,
+            &TemplatePolicy::default(),
+        )
+        .unwrap();
+        assert!(
+            result
+                .content
+                .ends_with("export OPENAI_API_KEY='sk-it'\\''s'\n")
+        );
+    }
+
+    #[test]
+    fn test_generate_dispatches_shell_profile_and_checks() {
+        let store = setup_store();
+        let dir = std::env::temp_dir().join(format!("lkr-test-shell-{}", std::process::id()));
+        let _ = fs::create_dir_all(&dir);
+        let template_path = dir.join(".zshrc.template");
+        let output_path = dir.join(".zshrc.lkr");
+        fs::write(&template_path, "export OPENAI_API_KEY=x\n").unwrap();
+
+        generate(
+            &store,
+            &template_path,
+            &output_path,
+            &TemplatePolicy::default(),
+        )
+        .unwrap();
+        let output = fs::read_to_string(&output_path).unwrap();
+        assert!(output.starts_with("[[ -z \"$OPENAI_API_KEY\" ]] && export"));
+        assert!(
+            check(
+                &store,
+                &template_path,
+                &output_path,
+                &TemplatePolicy::default()
+            )
+            .unwrap()
+            .is_up_to_date()
+        );
+
+        // Not a profile template: explicit entry point refuses
+        let err = generate_shell_profile(&store, &dir.join(".env.example"), &output_path);
+        assert!(matches!(err, Err(Error::Template { .. })));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    // -- MCP format --
+
+    #[test]
+    fn test_mcp_reports_servers_per_key() {
+        let store = setup_store();
+        let template = r#"{"mcpServers": {
+            "search": {"command": "npx", "args": ["-y", "search-mcp"],
+                       "env": {"OPENAI_API_KEY": "{{lkr:openai:prod}}"}},
+            "writer": {"command": "writer-mcp",
+                       "env": {"OPENAI_API_KEY": "{{lkr:openai:prod}}",
+                               "ANTHROPIC_API_KEY": "{{lkr:anthropic:main}}"}},
+            "remote": {"url": "https://mcp.example.com/sse"}
+        }}"#;
+        let result = render_str(
+            &store,
+            template,
+            Some(TemplateFormat::Mcp),
+            &TemplatePolicy::default(),
+        )
+        .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&result.content).unwrap();
+        assert_eq!(
+            output["mcpServers"]["writer"]["env"]["ANTHROPIC_API_KEY"],
+            "sk-ant-test-key-87654321"
+        );
+        let servers: BTreeMap<_, _> = result
+            .resolutions
+            .iter()
+            .map(|r| (r.key_name.as_deref().unwrap(), r.mcp_servers.clone()))
+            .collect();
+        assert_eq!(servers["openai:prod"], ["search", "writer"]);
+        assert_eq!(servers["anthropic:main"], ["writer"]);
+    }
+
+    #[test]
+    fn test_mcp_rejects_invalid_structure_and_stray_placeholders() {
+        let store = setup_store();
+        let cases = [
+            (r#"{"servers": {}}"#, "missing \"mcpServers\""),
+            (
+                r#"{"mcpServers": {"a": {"args": []}}}"#,
+                "needs a \"command\"",
+            ),
+            (
+                r#"{"mcpServers": {"a": {"command": "x", "args": "-y"}}}"#,
+                "must be an array of strings",
+            ),
+            (
+                r#"{"mcpServers": {"a": {"command": "{{lkr:openai:prod}}"}}}"#,
+                "placeholder in mcpServers.a.command",
+            ),
+            (
+                r#"{"mcpServers": {"a": {"command": "x", "args": ["--key={{lkr:openai:prod}}"]}}}"#,
+                "placeholder in mcpServers.a.args",
+            ),
+            (
+                r#"{"mcpServers": {"{{lkr:openai:prod}}": {"command": "x"}}}"#,
+                "placeholder in server name",
+            ),
+            ("{{lkr:openai:prod}}", "not valid JSON"),
+        ];
+        for (template, expected) in cases {
+            let err = render_str(
+                &store,
+                template,
+                Some(TemplateFormat::Mcp),
+                &TemplatePolicy::default(),
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains(expected), "{}: {}", template, err);
+        }
+    }
+
     // -- Secure writing --
 
     #[test]
@@ -612,14 +2372,45 @@ AWS_DEFAULT_REGION=ap-northeast-1
         let _ = fs::create_dir_all(&dir);
         let path = dir.join("test-output.env");
 
-        write_secure(&path, "SECRET=value\n").unwrap();
+        write_secure(&path, "SECRET=value\n", OutputPerms::default()).unwrap();
 
         let metadata = fs::metadata(&path).unwrap();
-        use std::os::unix::fs::PermissionsExt;
         assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
 
         // Clean up
         let _ = fs::remove_file(&path);
         let _ = fs::remove_dir(&dir);
     }
+
+    #[test]
+    fn test_write_secure_applies_output_perms() {
+        use std::os::unix::fs::MetadataExt;
+        let dir = std::env::temp_dir().join(format!("lkr-test-perms-{}", std::process::id()));
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("service.env");
+        // Our own group is the one chown is always allowed to set
+        let gid = fs::metadata(&dir).unwrap().gid();
+
+        let perms = OutputPerms::new(0o640, Some(gid), false).unwrap();
+        write_secure(&path, "SECRET=value\n", perms).unwrap();
+        let metadata = fs::metadata(&path).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o640);
+        assert_eq!(metadata.gid(), gid);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_output_perms_rejects_unsafe_modes() {
+        assert!(OutputPerms::new(0o640, None, false).is_ok());
+        assert!(OutputPerms::new(0o400, None, false).is_ok());
+        for mode in [0o644, 0o604] {
+            let err = OutputPerms::new(mode, None, false).unwrap_err();
+            assert!(err.to_string().contains("--i-understand"));
+            assert!(OutputPerms::new(mode, None, true).is_ok());
+        }
+        for mode in [0o4600, 0o700, 0o650, 0o060, 0o1000] {
+            assert!(OutputPerms::new(mode, None, true).is_err(), "{:o}", mode);
+        }
+    }
 }
@@ -1,8 +1,11 @@
 use crate::error::{Error, Result};
 use crate::keymanager::KeyStore;
+use crate::provider_config::{CostUnit, CustomUsageConfig, PathSegment};
 use chrono::{Datelike, NaiveDate, Utc};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::mpsc::Sender;
 use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
@@ -18,8 +21,111 @@ pub struct CostReport {
     pub period_end: String,
     /// Total cost in cents (USD)
     pub total_cost_cents: f64,
+    /// Lowercase ISO code shared by every line item (see [`CostLineItem::currency`])
     pub currency: String,
     pub line_items: Vec<CostLineItem>,
+    /// Per-model breakdown, filled in for `lkr usage --by-model`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<ModelUsage>,
+}
+
+/// Label for cost the provider doesn't attribute to a model.
+pub const ALL_MODELS: &str = "(all models)";
+
+/// One model's share of a [`CostReport`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelUsage {
+    pub name: String,
+    /// Cost in cents (USD)
+    pub cost_cents: f64,
+    /// Token counts (None: not reported by the provider's cost API)
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+}
+
+/// Order of `lkr usage --by-model` rows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ModelSort {
+    #[default]
+    Cost,
+    /// Most tokens first; models without token data keep cost order, last
+    Tokens,
+}
+
+impl std::str::FromStr for ModelSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "cost" => Ok(Self::Cost),
+            "tokens" => Ok(Self::Tokens),
+            other => Err(format!(
+                "Unknown sort '{}' (expected cost or tokens)",
+                other
+            )),
+        }
+    }
+}
+
+impl CostReport {
+    /// Group line items by model. OpenAI items look like `gpt-4o, input`;
+    /// anything without a recognisable model (e.g. Anthropic's generic
+    /// "Claude API") is reported as [`ALL_MODELS`].
+    pub fn model_breakdown(&self, sort: ModelSort) -> Vec<ModelUsage> {
+        let mut models: Vec<ModelUsage> = Vec::new();
+        for item in &self.line_items {
+            let name = model_of(&item.description);
+            match models.iter_mut().find(|m| m.name == name) {
+                Some(m) => {
+                    m.cost_cents += item.cost_cents;
+                    m.input_tokens = add_tokens(m.input_tokens, item.input_tokens);
+                    m.output_tokens = add_tokens(m.output_tokens, item.output_tokens);
+                }
+                None => models.push(ModelUsage {
+                    name: name.to_string(),
+                    cost_cents: item.cost_cents,
+                    input_tokens: item.input_tokens,
+                    output_tokens: item.output_tokens,
+                }),
+            }
+        }
+        if models.is_empty() && self.total_cost_cents != 0.0 {
+            models.push(ModelUsage {
+                name: ALL_MODELS.to_string(),
+                cost_cents: self.total_cost_cents,
+                input_tokens: None,
+                output_tokens: None,
+            });
+        }
+
+        models.sort_by(|a, b| {
+            b.cost_cents
+                .partial_cmp(&a.cost_cents)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        if sort == ModelSort::Tokens {
+            let tokens = |m: &ModelUsage| match (m.input_tokens, m.output_tokens) {
+                (None, None) => None,
+                (i, o) => Some(i.unwrap_or(0) + o.unwrap_or(0)),
+            };
+            // Stable: ties (and models without token data) stay in cost order
+            models.sort_by_key(|m| std::cmp::Reverse(tokens(m)));
+        }
+        models
+    }
+}
+
+/// Model a line item belongs to: the part before `, ` or ` - `.
+fn model_of(description: &str) -> &str {
+    if description == ANTHROPIC_DEFAULT_LINE_ITEM {
+        return ALL_MODELS;
+    }
+    let end = [", ", " - "]
+        .iter()
+        .filter_map(|sep| description.find(sep))
+        .min()
+        .unwrap_or(description.len());
+    description[..end].trim()
 }
 
 /// A single line item (e.g. "GPT-4o" or "Claude API").
@@ -28,6 +134,108 @@ pub struct CostLineItem {
     pub description: String,
     /// Cost in cents (USD)
     pub cost_cents: f64,
+    /// Token counts (None: not reported by the provider's cost API)
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    /// Lowercase ISO code the provider reported the cost in (e.g. "usd")
+    pub currency: String,
+}
+
+impl CostLineItem {
+    /// Whether the provider reported token counts for this item.
+    pub fn has_tokens(&self) -> bool {
+        self.input_tokens.is_some() || self.output_tokens.is_some()
+    }
+}
+
+/// Sum two optional token counts; None only when both are.
+fn add_tokens(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+    }
+}
+
+/// Period-over-period comparison of two cost reports (`lkr usage --compare-previous`).
+#[derive(Debug, Clone, Serialize)]
+pub struct CostReportDiff {
+    pub current: CostReport,
+    pub previous: CostReport,
+    /// Current total minus previous total, in cents
+    pub delta_cents: f64,
+    /// Change relative to the previous total, in percent
+    pub delta_pct: f64,
+    pub line_item_diffs: Vec<LineItemDiff>,
+}
+
+/// Per-line-item comparison. Items missing from one period count as 0 there.
+#[derive(Debug, Clone, Serialize)]
+pub struct LineItemDiff {
+    pub description: String,
+    pub current_cents: f64,
+    pub previous_cents: f64,
+    pub delta_pct: f64,
+}
+
+/// Progress events emitted by `fetch_cost_with_progress`, in order.
+///
+/// A cache hit goes straight from `Started` to `Done`. `RateLimited` may
+/// come between `RequestSent` and `ResponseReceived`, once per retry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum FetchProgress {
+    Started {
+        provider: String,
+    },
+    RequestSent,
+    /// HTTP 429: the request is sent again after `retry_in_secs`
+    RateLimited {
+        retry_in_secs: u64,
+    },
+    ResponseReceived,
+    Parsing,
+    Done,
+}
+
+/// Per-call settings for cost fetches.
+#[derive(Debug, Clone, Default)]
+pub struct FetchOptions {
+    /// Skip the cache and always call the API
+    pub refresh: bool,
+    /// Neither read nor write the cache (`--no-cache`): unlike `refresh`,
+    /// the fresh report is not stored either
+    pub no_cache: bool,
+    /// Headers added to each API request after the auth headers (validated
+    /// with `provider_config::validate_header`)
+    pub extra_headers: Vec<(String, String)>,
+    /// Fold line items cheaper than this (in cents) into "Other"; `None`
+    /// folds nothing. Applied to the returned report only: the cache keeps
+    /// every line item, so any threshold can be served from it.
+    pub min_cost_cents: Option<f64>,
+    /// Group cost by project instead of line item (`--breakdown-by-key`;
+    /// OpenAI only, where API keys are scoped to projects)
+    pub by_project: bool,
+    /// Cost endpoint from `endpoints.toml`, for a provider without a
+    /// built-in client
+    pub custom_usage: Option<CustomUsageConfig>,
+}
+
+impl FetchOptions {
+    /// Cache slot for `provider`: project breakdowns are cached apart from
+    /// the default line-item report.
+    fn cache_key(&self, provider: &str) -> String {
+        if self.by_project {
+            format!("{}:by-project", provider)
+        } else {
+            provider.to_string()
+        }
+    }
+}
+
+/// Report a progress event. The receiver may be gone (e.g. `fetch_cost`'s
+/// discarded channel, or a closed GUI window) — that is not an error.
+fn emit(progress: &Sender<FetchProgress>, event: FetchProgress) {
+    let _ = progress.send(event);
 }
 
 // ---------------------------------------------------------------------------
@@ -40,32 +248,66 @@ struct CacheEntry {
     fetched_at: Instant,
 }
 
+/// Size and lifetime limits for a [`UsageCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsageCachePolicy {
+    /// Least-recently-used entries are evicted beyond this many
+    pub max_entries: usize,
+    pub ttl: Duration,
+}
+
+impl Default for UsageCachePolicy {
+    fn default() -> Self {
+        Self {
+            max_entries: 16,
+            ttl: Duration::from_secs(3600),
+        }
+    }
+}
+
 /// Thread-safe response cache. Lives for the process lifetime.
+///
+/// Entries are kept in least- to most-recently-used order, so eviction
+/// drops the front.
 pub struct UsageCache {
-    entries: Mutex<HashMap<String, CacheEntry>>,
-    ttl: Duration,
+    entries: Mutex<IndexMap<String, CacheEntry>>,
+    policy: UsageCachePolicy,
 }
 
+/// A [`UsageCache`] with an entry limit (see [`UsageCache::new_with_policy`]).
+pub type LruUsageCache = UsageCache;
+
 impl UsageCache {
+    /// TTL-only cache with no entry limit.
     pub fn new(ttl: Duration) -> Self {
-        Self {
-            entries: Mutex::new(HashMap::new()),
+        Self::new_with_policy(UsageCachePolicy {
+            max_entries: usize::MAX,
             ttl,
+        })
+    }
+
+    pub fn new_with_policy(policy: UsageCachePolicy) -> Self {
+        Self {
+            entries: Mutex::new(IndexMap::new()),
+            policy,
         }
     }
 
     fn get(&self, provider: &str) -> Option<CostReport> {
-        let entries = self.entries.lock().ok()?;
-        let entry = entries.get(provider)?;
-        if entry.fetched_at.elapsed() < self.ttl {
-            Some(entry.report.clone())
-        } else {
-            None
+        let mut entries = self.entries.lock().ok()?;
+        let index = entries.get_index_of(provider)?;
+        if entries[index].fetched_at.elapsed() >= self.policy.ttl {
+            return None;
         }
+        // A hit makes the entry most recently used
+        let last = entries.len() - 1;
+        entries.move_index(index, last);
+        Some(entries[last].report.clone())
     }
 
     fn set(&self, provider: &str, report: CostReport) {
         if let Ok(mut entries) = self.entries.lock() {
+            entries.shift_remove(provider);
             entries.insert(
                 provider.to_string(),
                 CacheEntry {
@@ -73,6 +315,9 @@ impl UsageCache {
                     fetched_at: Instant::now(),
                 },
             );
+            while entries.len() > self.policy.max_entries {
+                entries.shift_remove_index(0);
+            }
         }
     }
 }
@@ -95,26 +340,168 @@ pub async fn fetch_cost(
     store: &impl KeyStore,
     provider: &str,
     cache: &UsageCache,
-    refresh: bool,
+    opts: &FetchOptions,
+) -> Result<CostReport> {
+    let (progress, _) = std::sync::mpsc::channel();
+    fetch_cost_with_progress(store, provider, cache, opts, progress).await
+}
+
+/// `fetch_cost`, reporting each stage on `progress` (for spinners and
+/// progress bars). No `Done` is sent if the fetch fails.
+pub async fn fetch_cost_with_progress(
+    store: &impl KeyStore,
+    provider: &str,
+    cache: &UsageCache,
+    opts: &FetchOptions,
+    progress: Sender<FetchProgress>,
 ) -> Result<CostReport> {
-    // Check cache first (unless --refresh)
-    if !refresh && let Some(cached) = cache.get(provider) {
-        return Ok(cached);
+    emit(
+        &progress,
+        FetchProgress::Started {
+            provider: provider.to_string(),
+        },
+    );
+
+    // Check cache first (unless --refresh / --no-cache)
+    if !opts.refresh
+        && !opts.no_cache
+        && let Some(cached) = cache.get(&opts.cache_key(provider))
+    {
+        emit(&progress, FetchProgress::Done);
+        return Ok(fold_report(cached, opts.min_cost_cents));
     }
 
-    let report = match provider {
-        "openai" => fetch_openai_cost(store).await?,
-        "anthropic" => fetch_anthropic_cost(store).await?,
-        other => {
-            return Err(Error::Usage(format!(
-                "Unknown provider '{}'. Supported: openai, anthropic",
+    let (start, end) = current_billing_period();
+    let report = fetch_cost_for_period(store, provider, start, end, opts, &progress).await?;
+
+    if !opts.no_cache {
+        cache.set(&opts.cache_key(provider), report.clone());
+    }
+    emit(&progress, FetchProgress::Done);
+    Ok(fold_report(report, opts.min_cost_cents))
+}
+
+/// Fetch the cost report for the previous (full) calendar month.
+///
+/// Not cached — only used for `lkr usage --compare-previous`.
+pub async fn fetch_previous_cost(
+    store: &impl KeyStore,
+    provider: &str,
+    opts: &FetchOptions,
+) -> Result<CostReport> {
+    let (progress, _) = std::sync::mpsc::channel();
+    fetch_previous_cost_with_progress(store, provider, opts, progress).await
+}
+
+/// `fetch_previous_cost`, reporting each stage on `progress`.
+pub async fn fetch_previous_cost_with_progress(
+    store: &impl KeyStore,
+    provider: &str,
+    opts: &FetchOptions,
+    progress: Sender<FetchProgress>,
+) -> Result<CostReport> {
+    let (start, end) = previous_billing_period();
+    let report = fetch_cost_for_period(store, provider, start, end, opts, &progress).await?;
+    Ok(fold_report(report, opts.min_cost_cents))
+}
+
+/// Providers whose usage/cost `lkr usage` can fetch (with an admin key).
+pub const USAGE_PROVIDERS: &[&str] = &["openai", "anthropic"];
+
+/// Dispatch a cost fetch for `provider` over `[start, end]` (inclusive dates).
+async fn fetch_cost_for_period(
+    store: &impl KeyStore,
+    provider: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    opts: &FetchOptions,
+    progress: &Sender<FetchProgress>,
+) -> Result<CostReport> {
+    if opts.by_project && provider != "openai" {
+        return Err(Error::Usage(format!(
+            "--breakdown-by-key is only supported for openai, not '{}'",
+            provider
+        )));
+    }
+    match provider {
+        "openai" => fetch_openai_cost(store, start, end, opts, progress).await,
+        "anthropic" => fetch_anthropic_cost(store, start, end, opts, progress).await,
+        other => match &opts.custom_usage {
+            Some(custom) => {
+                fetch_custom_cost(store, other, custom, start, end, opts, progress).await
+            }
+            None => Err(Error::Usage(format!(
+                "Unknown provider '{}'. Supported: openai, anthropic \
+                 (or describe a `usage` endpoint in endpoints.toml)",
                 other
-            )));
+            ))),
+        },
+    }
+}
+
+/// Compare two cost reports for the same provider.
+///
+/// Line items are matched by description. Items present in only one period
+/// are reported with 0 for the other. Order follows `current` (cost
+/// descending), followed by items that only appear in `previous`.
+pub fn diff_reports(current: &CostReport, previous: &CostReport) -> CostReportDiff {
+    let previous_costs: HashMap<&str, f64> = previous
+        .line_items
+        .iter()
+        .map(|i| (i.description.as_str(), i.cost_cents))
+        .collect();
+
+    let mut line_item_diffs: Vec<LineItemDiff> = current
+        .line_items
+        .iter()
+        .map(|item| {
+            let previous_cents = previous_costs
+                .get(item.description.as_str())
+                .copied()
+                .unwrap_or(0.0);
+            LineItemDiff {
+                description: item.description.clone(),
+                current_cents: item.cost_cents,
+                previous_cents,
+                delta_pct: pct_change(item.cost_cents, previous_cents),
+            }
+        })
+        .collect();
+
+    for item in &previous.line_items {
+        if !current
+            .line_items
+            .iter()
+            .any(|c| c.description == item.description)
+        {
+            line_item_diffs.push(LineItemDiff {
+                description: item.description.clone(),
+                current_cents: 0.0,
+                previous_cents: item.cost_cents,
+                delta_pct: pct_change(0.0, item.cost_cents),
+            });
         }
-    };
+    }
+
+    CostReportDiff {
+        current: current.clone(),
+        previous: previous.clone(),
+        delta_cents: current.total_cost_cents - previous.total_cost_cents,
+        delta_pct: pct_change(current.total_cost_cents, previous.total_cost_cents),
+        line_item_diffs,
+    }
+}
 
-    cache.set(provider, report.clone());
-    Ok(report)
+/// Percent change from `previous` to `current`.
+///
+/// A zero baseline has no meaningful ratio: reports 0% when both are zero
+/// and +100% for anything new (keeps the value finite for JSON output).
+fn pct_change(current: f64, previous: f64) -> f64 {
+    if previous == 0.0 {
+        if current == 0.0 { 0.0 } else { 100.0 }
+    } else {
+        (current - previous) / previous * 100.0
+    }
 }
 
 /// List providers that have admin keys registered.
@@ -123,7 +510,7 @@ pub async fn fetch_cost(
 /// silently treating all errors as "key not found").
 pub fn available_providers(store: &impl KeyStore) -> Result<Vec<String>> {
     let mut providers = Vec::new();
-    for provider in &["openai", "anthropic"] {
+    for provider in USAGE_PROVIDERS {
         let admin_key = format!("{}:admin", provider);
         match store.get(&admin_key) {
             Ok(_) => providers.push(provider.to_string()),
@@ -145,6 +532,14 @@ fn current_billing_period() -> (NaiveDate, NaiveDate) {
     (start, today)
 }
 
+/// Returns (start, end) of the previous calendar month.
+fn previous_billing_period() -> (NaiveDate, NaiveDate) {
+    let (current_start, _) = current_billing_period();
+    let end = current_start.pred_opt().unwrap_or(current_start);
+    let start = NaiveDate::from_ymd_opt(end.year(), end.month(), 1).unwrap_or(end);
+    (start, end)
+}
+
 // ---------------------------------------------------------------------------
 // OpenAI
 // ---------------------------------------------------------------------------
@@ -165,10 +560,27 @@ struct OpenAiCostResult {
     amount: OpenAiAmount,
     #[serde(default)]
     line_item: Option<String>,
+    #[serde(default)]
+    project_id: Option<String>,
+    #[serde(default)]
+    aggregated_by: Option<OpenAiAggregatedBy>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiAggregatedBy {
+    #[serde(default)]
+    token_usage: Option<OpenAiTokenUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiTokenUsage {
+    #[serde(default)]
+    input_tokens: Option<u64>,
+    #[serde(default)]
+    output_tokens: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 struct OpenAiAmount {
     value: f64,
     #[serde(default = "default_usd")]
@@ -179,10 +591,111 @@ fn default_usd() -> String {
     "usd".to_string()
 }
 
+/// The one currency all `currencies` share, lowercased ("usd" when there
+/// are none). Amounts in different currencies can't be summed, so a mix is
+/// an error rather than a silently wrong total.
+fn single_currency<'a>(
+    provider: &str,
+    currencies: impl IntoIterator<Item = &'a str>,
+) -> Result<String> {
+    let mut seen: Vec<String> = Vec::new();
+    for currency in currencies {
+        let currency = currency.to_lowercase();
+        if !seen.contains(&currency) {
+            seen.push(currency);
+        }
+    }
+    match seen.len() {
+        0 => Ok(default_usd()),
+        1 => Ok(seen.remove(0)),
+        _ => {
+            seen.sort();
+            Err(Error::Usage(format!(
+                "{} reported costs in more than one currency ({}). \
+                 lkr does not convert between currencies, so it cannot total them.",
+                provider,
+                seen.join(", ")
+            )))
+        }
+    }
+}
+
+/// Line item for costs without (or folded out of) a description.
+const OTHER_LINE_ITEM: &str = "Other";
+
+/// Line item for costs not attributed to a project (`by_project`).
+const NO_PROJECT_LINE_ITEM: &str = "(no project)";
+
+/// Sum OpenAI cost results across daily buckets into line items, keyed by
+/// line item or, with `by_project`, by project. Fails if the results are
+/// not all in one currency.
+fn aggregate_openai_costs(
+    body: &OpenAiCostsResponse,
+    opts: &FetchOptions,
+) -> Result<Vec<CostLineItem>> {
+    let currency = single_currency(
+        "OpenAI",
+        body.data
+            .iter()
+            .flat_map(|b| &b.results)
+            .map(|r| r.amount.currency.as_str()),
+    )?;
+    let mut line_item_costs: HashMap<String, CostLineItem> = HashMap::new();
+    for bucket in &body.data {
+        for result in &bucket.results {
+            let desc = if opts.by_project {
+                result
+                    .project_id
+                    .clone()
+                    .unwrap_or_else(|| NO_PROJECT_LINE_ITEM.to_string())
+            } else {
+                result
+                    .line_item
+                    .clone()
+                    .unwrap_or_else(|| OTHER_LINE_ITEM.to_string())
+            };
+            let tokens = result
+                .aggregated_by
+                .as_ref()
+                .and_then(|a| a.token_usage.as_ref());
+            let item = line_item_costs
+                .entry(desc.clone())
+                .or_insert_with(|| CostLineItem {
+                    description: desc,
+                    cost_cents: 0.0,
+                    input_tokens: None,
+                    output_tokens: None,
+                    currency: currency.clone(),
+                });
+            // OpenAI returns float USD — convert to cents
+            item.cost_cents += result.amount.value * 100.0;
+            item.input_tokens = add_tokens(item.input_tokens, tokens.and_then(|t| t.input_tokens));
+            item.output_tokens =
+                add_tokens(item.output_tokens, tokens.and_then(|t| t.output_tokens));
+        }
+    }
+
+    let mut items: Vec<_> = line_item_costs
+        .into_values()
+        .map(|item| CostLineItem {
+            cost_cents: item.cost_cents.round(),
+            ..item
+        })
+        .collect();
+    sort_by_cost_desc(&mut items);
+    Ok(items)
+}
+
 /// Fetch cost from OpenAI `/v1/organization/costs`.
-async fn fetch_openai_cost(store: &impl KeyStore) -> Result<CostReport> {
+async fn fetch_openai_cost(
+    store: &impl KeyStore,
+    start: NaiveDate,
+    end: NaiveDate,
+    opts: &FetchOptions,
+    progress: &Sender<FetchProgress>,
+) -> Result<CostReport> {
     let admin_key = get_admin_key(store, "openai")?;
-    let (start, end) = current_billing_period();
+    let base = admin_api_base(store, "openai", "https://api.openai.com")?;
 
     let start_ts = start.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp();
     let end_ts = end
@@ -193,69 +706,56 @@ async fn fetch_openai_cost(store: &impl KeyStore) -> Result<CostReport> {
         .and_utc()
         .timestamp();
 
+    let group_by = if opts.by_project {
+        "project_id"
+    } else {
+        "line_item"
+    };
     let url = format!(
-        "https://api.openai.com/v1/organization/costs?\
-         start_time={}&end_time={}&bucket_width=1d&limit=31&group_by=line_item",
-        start_ts, end_ts
+        "{}/v1/organization/costs?\
+         start_time={}&end_time={}&bucket_width=1d&limit=31&group_by={}",
+        base, start_ts, end_ts, group_by
     );
 
     let client = http_client();
-    let resp = client
+    emit(progress, FetchProgress::RequestSent);
+    let request = client
         .get(&url)
-        .header("Authorization", format!("Bearer {}", *admin_key))
-        .send()
-        .await
-        .map_err(|e| Error::Usage(request_failed_msg("OpenAI", &e)))?;
+        .header("Authorization", format!("Bearer {}", *admin_key));
+    let request = with_extra_headers(request, &opts.extra_headers);
+    let resp = send_with_retry(request, "OpenAI", progress).await?;
+    emit(progress, FetchProgress::ResponseReceived);
 
     // admin_key is Zeroizing<String>; explicit drop zeroes memory before response parsing
     drop(admin_key);
 
-    let resp = check_response(
-        resp,
-        "OpenAI admin key is invalid or expired. \
-         Create a new one at: https://platform.openai.com/settings/organization/admin-keys",
-    )
+    let resp = check_response(resp, |_| {
+        Error::Usage(
+            "OpenAI admin key is invalid or expired. \
+             Create a new one at: https://platform.openai.com/settings/organization/admin-keys"
+                .to_string(),
+        )
+    })
     .await?;
 
+    emit(progress, FetchProgress::Parsing);
     let body: OpenAiCostsResponse = resp
         .json()
         .await
         .map_err(|e| Error::Usage(format!("Failed to parse OpenAI response: {}", e)))?;
 
-    // Aggregate across all daily buckets
-    let mut line_item_costs: HashMap<String, f64> = HashMap::new();
-    for bucket in &body.data {
-        for result in &bucket.results {
-            let desc = result
-                .line_item
-                .clone()
-                .unwrap_or_else(|| "Other".to_string());
-            // OpenAI returns float USD — convert to cents
-            *line_item_costs.entry(desc).or_default() += result.amount.value * 100.0;
-        }
-    }
-
-    let line_items: Vec<CostLineItem> = {
-        let mut items: Vec<_> = line_item_costs
-            .into_iter()
-            .map(|(description, cost_cents)| CostLineItem {
-                description,
-                cost_cents: cost_cents.round(),
-            })
-            .collect();
-        sort_by_cost_desc(&mut items);
-        items
-    };
-
+    let line_items = aggregate_openai_costs(&body, opts)?;
     let total_cost_cents = line_items.iter().map(|i| i.cost_cents).sum();
+    let currency = single_currency("OpenAI", line_items.iter().map(|i| i.currency.as_str()))?;
 
     Ok(CostReport {
         provider: "openai".to_string(),
         period_start: start.to_string(),
         period_end: end.to_string(),
         total_cost_cents,
-        currency: "usd".to_string(),
+        currency,
         line_items,
+        models: Vec::new(),
     })
 }
 
@@ -270,7 +770,6 @@ struct AnthropicCostResponse {
 }
 
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 struct AnthropicCostResult {
     #[serde(default)]
     description: Option<String>,
@@ -280,44 +779,50 @@ struct AnthropicCostResult {
     currency: String,
 }
 
+/// Line item for Anthropic cost results without a description.
+const ANTHROPIC_DEFAULT_LINE_ITEM: &str = "Claude API";
+
 /// Fetch cost from Anthropic `/v1/organizations/cost_report`.
-async fn fetch_anthropic_cost(store: &impl KeyStore) -> Result<CostReport> {
+async fn fetch_anthropic_cost(
+    store: &impl KeyStore,
+    start: NaiveDate,
+    end: NaiveDate,
+    opts: &FetchOptions,
+    progress: &Sender<FetchProgress>,
+) -> Result<CostReport> {
     let admin_key = get_admin_key(store, "anthropic")?;
-    let (start, end) = current_billing_period();
+    let base = admin_api_base(store, "anthropic", "https://api.anthropic.com")?;
 
     let start_iso = format!("{}T00:00:00Z", start);
     let end_iso = format!("{}T00:00:00Z", end.succ_opt().unwrap_or(end));
 
     let url = format!(
-        "https://api.anthropic.com/v1/organizations/cost_report?\
+        "{}/v1/organizations/cost_report?\
          starting_at={}&ending_at={}&group_by[]=description",
-        start_iso, end_iso
+        base, start_iso, end_iso
     );
 
     let client = http_client();
-    let resp = client
+    emit(progress, FetchProgress::RequestSent);
+    let request = client
         .get(&url)
         .header("x-api-key", &*admin_key)
-        .header("anthropic-version", "2023-06-01")
-        .send()
-        .await
-        .map_err(|e| Error::Usage(request_failed_msg("Anthropic", &e)))?;
+        .header("anthropic-version", "2023-06-01");
+    let request = with_extra_headers(request, &opts.extra_headers);
+    let resp = send_with_retry(request, "Anthropic", progress).await?;
+    emit(progress, FetchProgress::ResponseReceived);
 
     drop(admin_key);
 
-    let resp = check_response(
-        resp,
-        "Anthropic admin key is invalid or requires an Organization account.\n  \
-         Individual accounts cannot use the Usage API.\n  \
-         View your usage at: https://console.anthropic.com/settings/billing",
-    )
-    .await?;
+    let resp = check_response(resp, anthropic_auth_error).await?;
 
+    emit(progress, FetchProgress::Parsing);
     let body: AnthropicCostResponse = resp
         .json()
         .await
         .map_err(|e| Error::Usage(format!("Failed to parse Anthropic response: {}", e)))?;
 
+    let currency = single_currency("Anthropic", body.data.iter().map(|r| r.currency.as_str()))?;
     let line_items: Vec<CostLineItem> = {
         let mut items: Vec<_> = body
             .data
@@ -326,8 +831,11 @@ async fn fetch_anthropic_cost(store: &impl KeyStore) -> Result<CostReport> {
                 description: r
                     .description
                     .clone()
-                    .unwrap_or_else(|| "Claude API".to_string()),
+                    .unwrap_or_else(|| ANTHROPIC_DEFAULT_LINE_ITEM.to_string()),
                 cost_cents: r.amount.parse::<f64>().unwrap_or(0.0),
+                input_tokens: None,
+                output_tokens: None,
+                currency: currency.clone(),
             })
             .collect();
         sort_by_cost_desc(&mut items);
@@ -341,8 +849,135 @@ async fn fetch_anthropic_cost(store: &impl KeyStore) -> Result<CostReport> {
         period_start: start.to_string(),
         period_end: end.to_string(),
         total_cost_cents,
-        currency: "usd".to_string(),
+        currency,
         line_items,
+        models: Vec::new(),
+    })
+}
+
+/// Fetch from a [`CustomUsageConfig`] endpoint. Best effort: the response
+/// is only expected to hold a cost number at `cost_path`.
+async fn fetch_custom_cost(
+    store: &impl KeyStore,
+    provider: &str,
+    custom: &CustomUsageConfig,
+    start: NaiveDate,
+    end: NaiveDate,
+    opts: &FetchOptions,
+    progress: &Sender<FetchProgress>,
+) -> Result<CostReport> {
+    let admin_key = get_admin_key(store, provider)?;
+    let url = custom
+        .url
+        .replace("{start}", &start.to_string())
+        .replace("{end}", &end.to_string());
+    let auth = zeroize::Zeroizing::new(if custom.auth_scheme.is_empty() {
+        admin_key.to_string()
+    } else {
+        format!("{} {}", custom.auth_scheme, &*admin_key)
+    });
+
+    let request = custom_cost_request(&http_client(), custom, &url, &auth, &opts.extra_headers)?;
+    emit(progress, FetchProgress::RequestSent);
+    let resp = send_with_retry(request, provider, progress).await?;
+    emit(progress, FetchProgress::ResponseReceived);
+
+    drop(auth);
+    drop(admin_key);
+
+    let resp = check_response(resp, |_| {
+        Error::Usage(format!(
+            "{} rejected the admin key. Re-register with `lkr set {}:admin --kind admin`.",
+            provider, provider
+        ))
+    })
+    .await?;
+
+    emit(progress, FetchProgress::Parsing);
+    let body: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| Error::Usage(format!("Failed to parse {} response: {}", provider, e)))?;
+    custom_cost_report(provider, custom, &body, start, end)
+}
+
+/// The GET for a custom cost endpoint: the admin key in `auth_header`, then
+/// `extra_headers`. An extra header may not replace the configured auth
+/// header, which the built-in reserved names don't cover.
+fn custom_cost_request(
+    client: &reqwest::Client,
+    custom: &CustomUsageConfig,
+    url: &str,
+    auth: &str,
+    extra_headers: &[(String, String)],
+) -> Result<reqwest::RequestBuilder> {
+    if let Some((name, _)) = extra_headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(&custom.auth_header))
+    {
+        return Err(Error::Usage(format!(
+            "Header '{}' is reserved: lkr sends it from the stored admin key",
+            name
+        )));
+    }
+    let request = client.get(url).header(custom.auth_header.as_str(), auth);
+    Ok(with_extra_headers(request, extra_headers))
+}
+
+/// Reduce a custom endpoint's response to a one-line-item report. The
+/// value at `cost_path` may be a number or a numeric string.
+fn custom_cost_report(
+    provider: &str,
+    custom: &CustomUsageConfig,
+    body: &serde_json::Value,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<CostReport> {
+    let segments = crate::provider_config::parse_cost_path(&custom.cost_path)?;
+    let value = segments
+        .iter()
+        .try_fold(body, |value, segment| match segment {
+            PathSegment::Field(name) => value.get(name),
+            PathSegment::Index(i) => value.get(i),
+        })
+        .ok_or_else(|| {
+            Error::Usage(format!(
+                "{} response has nothing at '{}'",
+                provider, custom.cost_path
+            ))
+        })?;
+    let cost = match value {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+    .filter(|c| c.is_finite())
+    .ok_or_else(|| {
+        Error::Usage(format!(
+            "{} response value at '{}' is not a number",
+            provider, custom.cost_path
+        ))
+    })?;
+    let cost_cents = match custom.cost_unit {
+        CostUnit::Dollars => cost * 100.0,
+        CostUnit::Cents => cost,
+    };
+    let currency = custom.currency.to_lowercase();
+
+    Ok(CostReport {
+        provider: provider.to_string(),
+        period_start: start.to_string(),
+        period_end: end.to_string(),
+        total_cost_cents: cost_cents,
+        currency: currency.clone(),
+        line_items: vec![CostLineItem {
+            description: "Total".to_string(),
+            cost_cents,
+            input_tokens: None,
+            output_tokens: None,
+            currency,
+        }],
+        models: Vec::new(),
     })
 }
 
@@ -356,7 +991,7 @@ async fn fetch_anthropic_cost(store: &impl KeyStore) -> Result<CostReport> {
 /// rustls-native-roots feature, building a client reads the OS trust store,
 /// so a fresh client per provider would repeat that read unnecessarily when
 /// `lkr usage` queries more than one provider in a single invocation.
-fn http_client() -> reqwest::Client {
+pub(crate) fn http_client() -> reqwest::Client {
     static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
     CLIENT
         .get_or_init(|| {
@@ -372,10 +1007,16 @@ fn http_client() -> reqwest::Client {
 /// (`reqwest::Error::is_connect()` — covers TCP refusal/timeout, DNS
 /// resolution, and TLS handshake failures alike, not TLS specifically),
 /// appends a hint pointing at the possible causes.
-fn request_failed_msg(provider: &str, e: &reqwest::Error) -> String {
+pub(crate) fn request_failed_msg(provider: &str, e: &reqwest::Error) -> String {
     request_failed_msg_from_parts(provider, e, e.is_connect())
 }
 
+/// `Error::Network` for a request that failed before any response, keeping
+/// the reqwest error as its source.
+pub(crate) fn request_failed(provider: &str, source: reqwest::Error) -> Error {
+    Error::network(request_failed_msg(provider, &source), source)
+}
+
 /// Pure formatting logic behind [`request_failed_msg`], split out so the
 /// hint-vs-no-hint branch can be tested with deterministic inputs instead of
 /// a live `reqwest::Error`.
@@ -394,14 +1035,86 @@ fn request_failed_msg_from_parts(
     msg
 }
 
-/// Check HTTP response status, returning a typed error for auth failures.
+/// Retries after an HTTP 429 before giving up with `Error::RateLimited`.
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+
+/// Upper bound on a single `Retry-After` wait, whatever the server asks for.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Send a request, retrying on HTTP 429 as directed by `Retry-After`.
+///
+/// Any other response (success or not) is returned as-is for
+/// `check_response`. Each retry is reported on `progress` as
+/// `RateLimited`; printing it is up to the caller.
+async fn send_with_retry(
+    request: reqwest::RequestBuilder,
+    provider: &str,
+    progress: &Sender<FetchProgress>,
+) -> Result<reqwest::Response> {
+    let mut retries = 0;
+    loop {
+        // GET requests have no streaming body, so cloning cannot fail
+        let attempt = request
+            .try_clone()
+            .ok_or_else(|| Error::Usage(format!("{provider} request cannot be retried")))?;
+        let resp = attempt
+            .send()
+            .await
+            .map_err(|e| request_failed(provider, e))?;
+        if resp.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Ok(resp);
+        }
+        if retries == MAX_RATE_LIMIT_RETRIES {
+            return Err(Error::RateLimited {
+                provider: provider.to_string(),
+                retries,
+            });
+        }
+        retries += 1;
+
+        let delay = resp
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| parse_retry_after(v, Utc::now()))
+            // No usable header: back off 1s, 2s, 4s
+            .unwrap_or(Duration::from_secs(1 << (retries - 1)))
+            .min(MAX_RETRY_DELAY);
+        emit(
+            progress,
+            FetchProgress::RateLimited {
+                retry_in_secs: delay.as_secs(),
+            },
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Parse a `Retry-After` value: delay in seconds, or an HTTP date
+/// (a date in the past means no wait).
+fn parse_retry_after(value: &str, now: chrono::DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Check HTTP response status. Auth failures (401/403) are mapped by
+/// `auth_error`, which receives the response body.
 async fn check_response(
     resp: reqwest::Response,
-    auth_error_msg: &str,
+    auth_error: impl FnOnce(&str) -> Error,
 ) -> Result<reqwest::Response> {
     let status = resp.status().as_u16();
     if status == 401 || status == 403 {
-        return Err(Error::Usage(auth_error_msg.to_string()));
+        let body = resp.text().await.unwrap_or_default();
+        return Err(auth_error(&body));
     }
     if !resp.status().is_success() {
         let body = resp.text().await.unwrap_or_default();
@@ -410,6 +1123,40 @@ async fn check_response(
     Ok(resp)
 }
 
+/// Anthropic error envelope: `{"type": "error", "error": {"type", "message"}}`.
+#[derive(Debug, Deserialize)]
+struct AnthropicErrorBody {
+    error: AnthropicErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicErrorDetail {
+    #[serde(default)]
+    message: String,
+}
+
+/// Tell "this account type has no Usage API" (individual accounts) apart
+/// from a bad admin key, using the error message in the response body.
+fn anthropic_auth_error(body: &str) -> Error {
+    let message = serde_json::from_str::<AnthropicErrorBody>(body)
+        .map(|b| b.error.message.to_lowercase())
+        .unwrap_or_default();
+    if message.contains("organization") || message.contains("individual") {
+        return Error::AccountTypeUnsupported {
+            provider: "Anthropic".to_string(),
+            hint: "The Usage API requires an Organization account. \
+                   View your usage at: https://console.anthropic.com/settings/billing"
+                .to_string(),
+        };
+    }
+    Error::Usage(
+        "Anthropic admin key is invalid or expired.\n  \
+         Create one at https://console.anthropic.com/settings/admin-keys, then run \
+         `lkr set anthropic:admin --kind admin --force`."
+            .to_string(),
+    )
+}
+
 /// Sort cost line items by cost descending.
 fn sort_by_cost_desc(items: &mut [CostLineItem]) {
     items.sort_by(|a, b| {
@@ -419,6 +1166,40 @@ fn sort_by_cost_desc(items: &mut [CostLineItem]) {
     });
 }
 
+/// `report` with its small line items folded (see [`fold_small_items`]).
+fn fold_report(mut report: CostReport, min_cents: Option<f64>) -> CostReport {
+    report.line_items = fold_small_items(report.line_items, min_cents);
+    report
+}
+
+/// Merge sorted line items cheaper than `min_cents` into a single "Other"
+/// item (together with any existing "Other"), kept last. Totals are
+/// unchanged; with no threshold the items are returned as-is.
+fn fold_small_items(items: Vec<CostLineItem>, min_cents: Option<f64>) -> Vec<CostLineItem> {
+    let Some(min_cents) = min_cents else {
+        return items;
+    };
+    let (mut kept, folded): (Vec<_>, Vec<_>) = items
+        .into_iter()
+        .partition(|i| i.cost_cents >= min_cents && i.description != OTHER_LINE_ITEM);
+    if let Some(first) = folded.first() {
+        // Items of one report share a currency
+        let currency = first.currency.clone();
+        kept.push(CostLineItem {
+            description: OTHER_LINE_ITEM.to_string(),
+            currency,
+            cost_cents: folded.iter().map(|i| i.cost_cents).sum(),
+            input_tokens: folded
+                .iter()
+                .fold(None, |sum, i| add_tokens(sum, i.input_tokens)),
+            output_tokens: folded
+                .iter()
+                .fold(None, |sum, i| add_tokens(sum, i.output_tokens)),
+        });
+    }
+    kept
+}
+
 /// Retrieve the admin key for a provider from KeyStore.
 fn get_admin_key(store: &impl KeyStore, provider: &str) -> Result<zeroize::Zeroizing<String>> {
     let key_name = format!("{}:admin", provider);
@@ -439,6 +1220,23 @@ fn get_admin_key(store: &impl KeyStore, provider: &str) -> Result<zeroize::Zeroi
     }
 }
 
+/// Append `headers` to a request that already carries its auth headers.
+fn with_extra_headers(
+    request: reqwest::RequestBuilder,
+    headers: &[(String, String)],
+) -> reqwest::RequestBuilder {
+    headers.iter().fold(request, |request, (name, value)| {
+        request.header(name, value)
+    })
+}
+
+/// Base URL for `provider`'s usage API: the admin key's custom endpoint
+/// (`lkr set --endpoint`) if one is stored, otherwise `default`.
+fn admin_api_base(store: &impl KeyStore, provider: &str, default: &str) -> Result<String> {
+    let endpoint = store.endpoint(&format!("{}:admin", provider))?;
+    Ok(crate::endpoint::api_base(endpoint.as_deref(), default))
+}
+
 /// Format cents as dollar string (e.g. 1350.0 → "$13.50").
 pub fn format_cost(cents: f64) -> String {
     if !cents.is_finite() {
@@ -531,12 +1329,31 @@ mod tests {
             total_cost_cents: 1350.0,
             currency: "usd".to_string(),
             line_items: vec![],
+            models: Vec::new(),
         };
         cache.set("openai", report);
         assert!(cache.get("openai").is_some());
         assert!(cache.get("anthropic").is_none());
     }
 
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = UsageCache::new_with_policy(UsageCachePolicy {
+            max_entries: 2,
+            ..Default::default()
+        });
+        let report = || report_with(&[("GPT-4o", 100.0)]);
+        cache.set("openai", report());
+        cache.set("anthropic", report());
+        // Touch openai so anthropic becomes the eviction candidate
+        assert!(cache.get("openai").is_some());
+        cache.set("google", report());
+
+        assert!(cache.get("anthropic").is_none());
+        assert!(cache.get("openai").is_some());
+        assert!(cache.get("google").is_some());
+    }
+
     #[test]
     fn test_cache_expiry() {
         let cache = UsageCache::new(Duration::from_millis(1));
@@ -547,6 +1364,7 @@ mod tests {
             total_cost_cents: 0.0,
             currency: "usd".to_string(),
             line_items: vec![],
+            models: Vec::new(),
         };
         cache.set("openai", report);
         std::thread::sleep(Duration::from_millis(10));
@@ -557,7 +1375,7 @@ mod tests {
     async fn test_fetch_cost_unknown_provider() {
         let store = MockStore::new();
         let cache = UsageCache::default();
-        let err = fetch_cost(&store, "unknown", &cache, false)
+        let err = fetch_cost(&store, "unknown", &cache, &FetchOptions::default())
             .await
             .unwrap_err();
         assert!(matches!(err, Error::Usage(_)));
@@ -567,10 +1385,76 @@ mod tests {
     async fn test_fetch_cost_missing_admin_key() {
         let store = MockStore::new();
         let cache = UsageCache::default();
-        let err = fetch_cost(&store, "openai", &cache, false)
+        let err = fetch_cost(&store, "openai", &cache, &FetchOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::AdminKeyRequired { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_with_progress_cache_hit() {
+        let store = MockStore::new();
+        let cache = UsageCache::default();
+        cache.set("openai", report_with(&[("GPT-4o", 100.0)]));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        fetch_cost_with_progress(&store, "openai", &cache, &FetchOptions::default(), tx)
+            .await
+            .unwrap();
+
+        let events: Vec<_> = rx.iter().collect();
+        assert_eq!(
+            events,
+            vec![
+                FetchProgress::Started {
+                    provider: "openai".to_string()
+                },
+                FetchProgress::Done,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_keeps_unfolded_report() {
+        let store = MockStore::new();
+        let cache = UsageCache::default();
+        cache.set(
+            "openai",
+            report_with(&[("GPT-4o", 1200.0), ("Embeddings", 40.0)]),
+        );
+
+        let folding = FetchOptions {
+            min_cost_cents: Some(50.0),
+            ..Default::default()
+        };
+        let folded = fetch_cost(&store, "openai", &cache, &folding)
+            .await
+            .unwrap();
+        let descriptions: Vec<_> = folded.line_items.iter().map(|i| &i.description).collect();
+        assert_eq!(descriptions, ["GPT-4o", "Other"]);
+
+        // A later call without a threshold still sees every item
+        let full = fetch_cost(&store, "openai", &cache, &FetchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(full.line_items.len(), 2);
+        assert_eq!(full.line_items[1].description, "Embeddings");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_with_progress_error_has_no_done() {
+        let store = MockStore::new();
+        let cache = UsageCache::default();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let err = fetch_cost_with_progress(&store, "openai", &cache, &FetchOptions::default(), tx)
             .await
             .unwrap_err();
         assert!(matches!(err, Error::AdminKeyRequired { .. }));
+
+        let events: Vec<_> = rx.iter().collect();
+        assert_eq!(events.len(), 1);
+        assert!(!events.contains(&FetchProgress::Done));
     }
 
     #[test]
@@ -580,6 +1464,118 @@ mod tests {
         assert!(end >= start);
     }
 
+    #[test]
+    fn test_previous_billing_period() {
+        let (cur_start, _) = current_billing_period();
+        let (start, end) = previous_billing_period();
+        assert_eq!(start.day(), 1);
+        assert_eq!(end.succ_opt().unwrap(), cur_start);
+    }
+
+    // -- diff_reports --
+
+    fn report_with(items: &[(&str, f64)]) -> CostReport {
+        let line_items: Vec<CostLineItem> = items
+            .iter()
+            .map(|&(description, cost_cents)| CostLineItem {
+                description: description.to_string(),
+                cost_cents,
+                input_tokens: None,
+                output_tokens: None,
+                currency: "usd".to_string(),
+            })
+            .collect();
+        CostReport {
+            provider: "openai".to_string(),
+            period_start: "2026-02-01".to_string(),
+            period_end: "2026-02-28".to_string(),
+            total_cost_cents: line_items.iter().map(|i| i.cost_cents).sum(),
+            currency: "usd".to_string(),
+            line_items,
+            models: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_fold_small_items_into_other() {
+        let items = report_with(&[
+            ("GPT-4o", 1200.0),
+            ("Other", 30.0),
+            ("Embeddings", 40.0),
+            ("Moderation", 2.0),
+        ])
+        .line_items;
+        let total: f64 = items.iter().map(|i| i.cost_cents).sum();
+
+        let folded = fold_small_items(items.clone(), Some(50.0));
+        let descriptions: Vec<_> = folded.iter().map(|i| i.description.as_str()).collect();
+        assert_eq!(descriptions, ["GPT-4o", "Other"]);
+        assert_eq!(folded[1].cost_cents, 72.0);
+        assert_eq!(folded.iter().map(|i| i.cost_cents).sum::<f64>(), total);
+
+        // Default: no folding
+        assert_eq!(fold_small_items(items, None).len(), 4);
+    }
+
+    #[test]
+    fn test_diff_reports_totals() {
+        let current = report_with(&[("GPT-4o", 300.0)]);
+        let previous = report_with(&[("GPT-4o", 200.0)]);
+        let diff = diff_reports(&current, &previous);
+
+        assert_eq!(diff.delta_cents, 100.0);
+        assert_eq!(diff.delta_pct, 50.0);
+        assert_eq!(diff.line_item_diffs.len(), 1);
+        assert_eq!(diff.line_item_diffs[0].delta_pct, 50.0);
+    }
+
+    #[test]
+    fn test_diff_reports_item_only_in_current() {
+        let current = report_with(&[("GPT-4o", 300.0), ("o1", 50.0)]);
+        let previous = report_with(&[("GPT-4o", 300.0)]);
+        let diff = diff_reports(&current, &previous);
+
+        let new_item = diff
+            .line_item_diffs
+            .iter()
+            .find(|d| d.description == "o1")
+            .unwrap();
+        assert_eq!(new_item.current_cents, 50.0);
+        assert_eq!(new_item.previous_cents, 0.0);
+        assert_eq!(new_item.delta_pct, 100.0);
+    }
+
+    #[test]
+    fn test_diff_reports_item_only_in_previous() {
+        let current = report_with(&[("GPT-4o", 300.0)]);
+        let previous = report_with(&[("GPT-4o", 300.0), ("DALL-E", 80.0)]);
+        let diff = diff_reports(&current, &previous);
+
+        assert_eq!(diff.line_item_diffs.len(), 2);
+        // Previous-only items come after current items
+        let gone = &diff.line_item_diffs[1];
+        assert_eq!(gone.description, "DALL-E");
+        assert_eq!(gone.current_cents, 0.0);
+        assert_eq!(gone.previous_cents, 80.0);
+        assert_eq!(gone.delta_pct, -100.0);
+    }
+
+    #[test]
+    fn test_diff_reports_both_empty() {
+        let diff = diff_reports(&report_with(&[]), &report_with(&[]));
+        assert_eq!(diff.delta_cents, 0.0);
+        assert_eq!(diff.delta_pct, 0.0);
+        assert!(diff.line_item_diffs.is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_serializes() {
+        let diff = diff_reports(&report_with(&[("GPT-4o", 1.0)]), &report_with(&[]));
+        let json = serde_json::to_value(&diff).unwrap();
+        assert!(json["line_item_diffs"].is_array());
+        assert!(json["delta_pct"].is_number());
+    }
+
     // -- request_failed_msg_from_parts: pure logic, deterministic inputs --
 
     #[test]
@@ -599,6 +1595,355 @@ mod tests {
         assert_eq!(msg, "Anthropic API request failed: invalid response");
     }
 
+    // -- Anthropic auth errors --
+
+    #[test]
+    fn test_anthropic_org_account_error_maps_to_account_type() {
+        let body = r#"{"type":"error","error":{"type":"permission_error","message":"This endpoint is only available to organizations. Individual accounts are not supported."}}"#;
+        let err = anthropic_auth_error(body);
+        assert!(matches!(err, Error::AccountTypeUnsupported { .. }));
+        assert!(
+            err.to_string()
+                .contains("console.anthropic.com/settings/billing")
+        );
+    }
+
+    #[test]
+    fn test_anthropic_bad_key_error_suggests_reregistering() {
+        let body = r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#;
+        let err = anthropic_auth_error(body);
+        assert!(matches!(err, Error::Usage(_)));
+        assert!(err.to_string().contains("lkr set anthropic:admin"));
+        // Unparseable body: assume the key
+        assert!(matches!(anthropic_auth_error("<html>"), Error::Usage(_)));
+    }
+
+    // -- 429 retry --
+
+    #[test]
+    fn test_parse_retry_after_seconds_and_http_date() {
+        let now = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(parse_retry_after("7", now), Some(Duration::from_secs(7)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        // A date in the past: retry immediately
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    /// Serve one canned response per connection, in order, on a loopback
+    /// port. Returns the base URL.
+    fn mock_server(responses: Vec<&'static str>) -> String {
+        use std::io::{BufRead, BufReader, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                // Drain the request headers
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                    line.clear();
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        format!("http://{addr}/")
+    }
+
+    const RATE_LIMITED: &str = "HTTP/1.1 429 Too Many Requests\r\n\
+        Retry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}";
+
+    fn gateway_config(url: &str) -> CustomUsageConfig {
+        serde_json::from_value(serde_json::json!({
+            "url": url,
+            "cost_path": "$.data[0].total_cost",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_custom_cost_report_from_sample_response() {
+        let (start, end) = (
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 3, 15).unwrap(),
+        );
+        let config = gateway_config("https://gw.example.com/cost");
+        let body = serde_json::json!({"data": [{"total_cost": 12.5}]});
+        let report = custom_cost_report("gateway", &config, &body, start, end).unwrap();
+        assert_eq!(report.provider, "gateway");
+        assert_eq!(report.period_start, "2026-03-01");
+        assert_eq!(report.total_cost_cents, 1250.0);
+        assert_eq!(report.currency, "usd");
+        assert_eq!(report.line_items.len(), 1);
+        assert_eq!(report.line_items[0].cost_cents, 1250.0);
+
+        // Numeric strings and cents are accepted
+        let cents = CustomUsageConfig {
+            cost_unit: CostUnit::Cents,
+            ..config.clone()
+        };
+        let body = serde_json::json!({"data": [{"total_cost": "980"}]});
+        let report = custom_cost_report("gateway", &cents, &body, start, end).unwrap();
+        assert_eq!(report.total_cost_cents, 980.0);
+
+        let missing = serde_json::json!({"data": []});
+        assert!(custom_cost_report("gateway", &config, &missing, start, end).is_err());
+        let not_a_number = serde_json::json!({"data": [{"total_cost": null}]});
+        assert!(custom_cost_report("gateway", &config, &not_a_number, start, end).is_err());
+    }
+
+    const GATEWAY_COST: &str = "HTTP/1.1 200 OK\r\nContent-Length: 29\r\n\
+        Connection: close\r\n\r\n{\"data\":[{\"total_cost\":3.5}]}";
+
+    #[tokio::test]
+    async fn test_fetch_custom_usage_endpoint() {
+        let url = mock_server(vec![GATEWAY_COST]);
+        let store = MockStore::new();
+        store
+            .set("gateway:admin", "gw-admin-1", KeyKind::Admin, false)
+            .unwrap();
+        let opts = FetchOptions {
+            no_cache: true,
+            custom_usage: Some(gateway_config(&url)),
+            ..Default::default()
+        };
+        let report = fetch_cost(&store, "gateway", &UsageCache::default(), &opts)
+            .await
+            .unwrap();
+        assert_eq!(report.total_cost_cents, 350.0);
+
+        // Unconfigured providers are still rejected
+        let err = fetch_cost(
+            &store,
+            "gateway",
+            &UsageCache::default(),
+            &FetchOptions::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("Unknown provider"));
+    }
+
+    #[tokio::test]
+    async fn test_send_with_retry_succeeds_after_two_429s() {
+        let url = mock_server(vec![RATE_LIMITED, RATE_LIMITED, OK]);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let resp = send_with_retry(http_client().get(&url), "Test", &tx)
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), 200);
+        drop(tx);
+        let events: Vec<_> = rx.iter().collect();
+        assert_eq!(
+            events,
+            vec![FetchProgress::RateLimited { retry_in_secs: 0 }; 2]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_with_retry_gives_up_after_max_retries() {
+        let url = mock_server(vec![RATE_LIMITED; 4]);
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let err = send_with_retry(http_client().get(&url), "Test", &tx)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::RateLimited { retries: 3, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_no_cache_fetch_leaves_cache_empty() {
+        const EMPTY_COSTS: &str = "HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\
+            Connection: close\r\n\r\n{\"data\":[]}";
+        let url = mock_server(vec![EMPTY_COSTS, EMPTY_COSTS]);
+        let store = MockStore::new();
+        store
+            .set("openai:admin", "sk-admin-1", KeyKind::Admin, false)
+            .unwrap();
+        store.set_endpoint("openai:admin", Some(&url)).unwrap();
+        let cache = UsageCache::default();
+
+        let opts = FetchOptions {
+            no_cache: true,
+            ..Default::default()
+        };
+        fetch_cost(&store, "openai", &cache, &opts).await.unwrap();
+        assert!(cache.get("openai").is_none());
+
+        // Without the flag the same fetch is cached
+        fetch_cost(&store, "openai", &cache, &FetchOptions::default())
+            .await
+            .unwrap();
+        assert!(cache.get("openai").is_some());
+    }
+
+    #[test]
+    fn test_openai_costs_by_project() {
+        let body: OpenAiCostsResponse = serde_json::from_str(
+            r#"{"data": [
+                {"results": [
+                    {"amount": {"value": 1.0}, "project_id": "proj_web"},
+                    {"amount": {"value": 0.5}, "project_id": "proj_batch"}
+                ]},
+                {"results": [
+                    {"amount": {"value": 2.0}, "project_id": "proj_web"},
+                    {"amount": {"value": 0.1}, "project_id": null}
+                ]}
+            ]}"#,
+        )
+        .unwrap();
+        let opts = FetchOptions {
+            by_project: true,
+            ..Default::default()
+        };
+        let items: Vec<_> = aggregate_openai_costs(&body, &opts)
+            .unwrap()
+            .into_iter()
+            .map(|i| (i.description, i.cost_cents))
+            .collect();
+        assert_eq!(
+            items,
+            [
+                ("proj_web".to_string(), 300.0),
+                ("proj_batch".to_string(), 50.0),
+                (NO_PROJECT_LINE_ITEM.to_string(), 10.0),
+            ]
+        );
+        assert_eq!(opts.cache_key("openai"), "openai:by-project");
+    }
+
+    #[test]
+    fn test_openai_costs_single_currency_sums() {
+        let body: OpenAiCostsResponse = serde_json::from_str(
+            r#"{"data": [
+                {"results": [{"amount": {"value": 1.0, "currency": "usd"}, "line_item": "a"}]},
+                {"results": [
+                    {"amount": {"value": 2.0, "currency": "USD"}, "line_item": "a"},
+                    {"amount": {"value": 0.5}, "line_item": "b"}
+                ]}
+            ]}"#,
+        )
+        .unwrap();
+        let items = aggregate_openai_costs(&body, &FetchOptions::default()).unwrap();
+        let summed: Vec<_> = items
+            .iter()
+            .map(|i| (i.description.as_str(), i.cost_cents, i.currency.as_str()))
+            .collect();
+        assert_eq!(summed, [("a", 300.0, "usd"), ("b", 50.0, "usd")]);
+    }
+
+    #[test]
+    fn test_mixed_currencies_are_rejected() {
+        let body: OpenAiCostsResponse = serde_json::from_str(
+            r#"{"data": [{"results": [
+                {"amount": {"value": 1.0, "currency": "usd"}, "line_item": "a"},
+                {"amount": {"value": 1.0, "currency": "eur"}, "line_item": "a"}
+            ]}]}"#,
+        )
+        .unwrap();
+        let err = aggregate_openai_costs(&body, &FetchOptions::default()).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("more than one currency (eur, usd)")
+        );
+
+        assert_eq!(single_currency("Anthropic", []).unwrap(), "usd");
+        assert!(single_currency("Anthropic", ["usd", "jpy"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_openai_token_usage_fills_line_items() {
+        let body = r#"{"data": [
+            {"results": [{"amount": {"value": 0.5}, "line_item": "gpt-4o, input",
+                "aggregated_by": {"token_usage": {"input_tokens": 1000, "output_tokens": 0}}}]},
+            {"results": [
+                {"amount": {"value": 0.25}, "line_item": "gpt-4o, input",
+                    "aggregated_by": {"token_usage": {"input_tokens": 500, "output_tokens": 0}}},
+                {"amount": {"value": 0.1}, "line_item": "web search"}
+            ]}
+        ]}"#;
+        let response: &'static str = Box::leak(
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .into_boxed_str(),
+        );
+        let url = mock_server(vec![response]);
+        let store = MockStore::new();
+        store
+            .set("openai:admin", "sk-admin-1", KeyKind::Admin, false)
+            .unwrap();
+        store.set_endpoint("openai:admin", Some(&url)).unwrap();
+
+        let report = fetch_cost(
+            &store,
+            "openai",
+            &UsageCache::default(),
+            &FetchOptions::default(),
+        )
+        .await
+        .unwrap();
+        let input = &report.line_items[0];
+        assert_eq!(input.description, "gpt-4o, input");
+        assert_eq!(input.cost_cents, 75.0);
+        assert_eq!(
+            (input.input_tokens, input.output_tokens),
+            (Some(1500), Some(0))
+        );
+        let search = &report.line_items[1];
+        assert!(!search.has_tokens());
+    }
+
+    #[test]
+    fn test_extra_headers_follow_auth_header() {
+        let request = http_client()
+            .get("https://example.com/")
+            .header("x-api-key", "sk-admin");
+        let headers = vec![
+            ("X-Cost-Center".to_string(), "eng-team".to_string()),
+            ("X-Request-ID".to_string(), "abc".to_string()),
+        ];
+        let request = with_extra_headers(request, &headers).build().unwrap();
+        assert_eq!(request.headers()["x-api-key"], "sk-admin");
+        assert_eq!(request.headers()["x-cost-center"], "eng-team");
+        assert_eq!(request.headers()["x-request-id"], "abc");
+    }
+
+    #[test]
+    fn test_custom_cost_request_sends_extra_headers() {
+        let custom = CustomUsageConfig {
+            url: "https://gw.example.com/cost".to_string(),
+            auth_header: "X-Gateway-Key".to_string(),
+            auth_scheme: String::new(),
+            cost_path: "$.total".to_string(),
+            cost_unit: CostUnit::Dollars,
+            currency: "usd".to_string(),
+        };
+        let headers = vec![("X-Cost-Center".to_string(), "eng-team".to_string())];
+        let request = custom_cost_request(&http_client(), &custom, &custom.url, "gw-key", &headers)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()["x-gateway-key"], "gw-key");
+        assert_eq!(request.headers()["x-cost-center"], "eng-team");
+
+        let clash = vec![("x-gateway-key".to_string(), "other".to_string())];
+        assert!(
+            custom_cost_request(&http_client(), &custom, &custom.url, "gw-key", &clash).is_err()
+        );
+    }
+
     // -- request_failed_msg: wiring against a real reqwest::Error --
     // Assertions here stay loose (substring only) since reqwest's Display
     // text for a live error isn't something this crate should hard-code;
@@ -627,4 +1972,45 @@ mod tests {
         let msg = request_failed_msg("Test", &err);
         assert!(!msg.contains("Hint"));
     }
+
+    // -- model_breakdown --
+
+    #[test]
+    fn test_model_breakdown_groups_line_items() {
+        let report = report_with(&[
+            ("gpt-4o, input", 500.0),
+            ("gpt-4o-mini, input", 100.0),
+            ("gpt-4o, output", 900.0),
+            ("Claude API", 40.0),
+        ]);
+        let models = report.model_breakdown(ModelSort::Cost);
+        let rows: Vec<_> = models
+            .iter()
+            .map(|m| (m.name.as_str(), m.cost_cents))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("gpt-4o", 1400.0),
+                ("gpt-4o-mini", 100.0),
+                (ALL_MODELS, 40.0)
+            ]
+        );
+        // Cost APIs carry no token counts
+        assert!(models.iter().all(|m| m.input_tokens.is_none()));
+        // Without token data, --sort tokens keeps cost order
+        assert_eq!(report.model_breakdown(ModelSort::Tokens), models);
+    }
+
+    #[test]
+    fn test_model_breakdown_without_line_items() {
+        let mut report = report_with(&[]);
+        assert!(report.model_breakdown(ModelSort::Cost).is_empty());
+        report.total_cost_cents = 250.0;
+        let models = report.model_breakdown(ModelSort::Cost);
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].name, ALL_MODELS);
+        assert_eq!("tokens".parse::<ModelSort>(), Ok(ModelSort::Tokens));
+        assert!("name".parse::<ModelSort>().is_err());
+    }
 }
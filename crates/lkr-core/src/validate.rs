@@ -0,0 +1,141 @@
+//! Check an API key against its provider before storing it (`lkr set --test`).
+
+use crate::error::{Error, Result};
+use crate::usage::{http_client, request_failed};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Upper bound on a validation request, connection included.
+pub const VALIDATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome of validating a key against its provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Validity {
+    /// The provider accepted the key.
+    Valid,
+    /// The provider rejected the key (401/403): revoked, expired, or mistyped.
+    Invalid,
+}
+
+impl std::fmt::Display for Validity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Validity::Valid => write!(f, "VALID"),
+            Validity::Invalid => write!(f, "INVALID"),
+        }
+    }
+}
+
+/// A cheap authenticated endpoint that lists models — no tokens are spent.
+struct Endpoint {
+    display: &'static str,
+    default_base: &'static str,
+}
+
+/// Path of the model listing under the API base.
+const MODELS_PATH: &str = "/v1/models";
+
+fn endpoint_for(provider: &str) -> Option<Endpoint> {
+    match provider {
+        "openai" => Some(Endpoint {
+            display: "OpenAI",
+            default_base: "https://api.openai.com",
+        }),
+        "anthropic" => Some(Endpoint {
+            display: "Anthropic",
+            default_base: "https://api.anthropic.com",
+        }),
+        _ => None,
+    }
+}
+
+/// Whether `provider` has a validation endpoint.
+pub fn can_validate(provider: &str) -> bool {
+    endpoint_for(provider).is_some()
+}
+
+/// Validate `value` as an API key for `provider`, against `custom_endpoint`
+/// instead of the public API when one is given.
+///
+/// Network failures, timeouts, and unexpected statuses are errors rather
+/// than `Invalid`: they say nothing about the key itself.
+pub async fn validate_key(
+    provider: &str,
+    value: &str,
+    custom_endpoint: Option<&str>,
+) -> Result<Validity> {
+    let endpoint = endpoint_for(provider).ok_or_else(|| {
+        Error::Usage(format!(
+            "Key validation is not supported for provider '{}' (supported: openai, anthropic).",
+            provider
+        ))
+    })?;
+
+    let url = format!(
+        "{}{}",
+        crate::endpoint::api_base(custom_endpoint, endpoint.default_base),
+        MODELS_PATH
+    );
+    let request = http_client().get(&url).timeout(VALIDATE_TIMEOUT);
+    let request = match provider {
+        "anthropic" => request
+            .header("x-api-key", value)
+            .header("anthropic-version", "2023-06-01"),
+        _ => request.header("Authorization", format!("Bearer {}", value)),
+    };
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| request_failed(endpoint.display, e))?;
+
+    let status = resp.status().as_u16();
+    match validity_from_status(status) {
+        Some(validity) => Ok(validity),
+        None => Err(Error::HttpError {
+            status,
+            body: resp.text().await.unwrap_or_default(),
+        }),
+    }
+}
+
+/// Map an HTTP status to a verdict; `None` means the status is inconclusive.
+fn validity_from_status(status: u16) -> Option<Validity> {
+    match status {
+        200..=299 => Some(Validity::Valid),
+        401 | 403 => Some(Validity::Invalid),
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validity_from_status() {
+        assert_eq!(validity_from_status(200), Some(Validity::Valid));
+        assert_eq!(validity_from_status(401), Some(Validity::Invalid));
+        assert_eq!(validity_from_status(403), Some(Validity::Invalid));
+        // Rate limits and outages say nothing about the key
+        assert_eq!(validity_from_status(429), None);
+        assert_eq!(validity_from_status(500), None);
+    }
+
+    #[test]
+    fn test_can_validate() {
+        assert!(can_validate("openai"));
+        assert!(can_validate("anthropic"));
+        assert!(!can_validate("custom"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_unsupported_provider_errors_without_request() {
+        let err = validate_key("custom", "x", None).await.unwrap_err();
+        assert!(matches!(err, Error::Usage(_)));
+    }
+}
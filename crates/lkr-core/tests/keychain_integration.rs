@@ -176,6 +176,30 @@ fn test_list_keys() {
     cleanup_keychain(&path);
 }
 
+#[test]
+fn test_search_glob_returns_only_matching_provider() {
+    let (path, mut kc) = create_test_keychain();
+    let _ = custom_keychain::unlock(&mut kc, TEST_PASSWORD);
+
+    let store = KeychainStore::new_v3(kc);
+
+    store
+        .set("openai:prod", "sk-openai-1", KeyKind::Runtime, false)
+        .unwrap();
+    store
+        .set("openai:dev", "sk-openai-2", KeyKind::Runtime, false)
+        .unwrap();
+    store
+        .set("anthropic:main", "sk-ant-1", KeyKind::Runtime, false)
+        .unwrap();
+
+    let entries = store.search("openai:*", false).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e.provider == "openai"));
+
+    cleanup_keychain(&path);
+}
+
 #[test]
 fn test_admin_key_storage() {
     let (path, mut kc) = create_test_keychain();